@@ -1,10 +1,11 @@
 #![allow(dead_code)]
 #![allow(unreachable_patterns)]
 
+use std::process::exit;
 use std::sync::Arc;
 
 use clap::Parser;
-use log::debug;
+use log::{debug, error, info};
 
 use bernardo::*;
 
@@ -27,7 +28,19 @@ fn main() {
     // Parsing arguments
     debug!("{:?}", args.paths());
     let (start_dir, files) = args.paths();
-    let fsf = RealFS::new(start_dir).to_fsf();
+    let fsf = RealFS::new(start_dir).with_fsync(config_ref.global.fsync_on_save).to_fsf();
+    let use_session = !args.no_session;
+
+    if args.clean_undo_history {
+        match clean_up_undo_histories(&fsf, &config_ref.global) {
+            Ok(dropped) => info!("dropped {} undo histories", dropped),
+            Err(e) => {
+                error!("failed cleaning up undo histories: {:?}", e);
+                exit(1);
+            }
+        }
+        return;
+    }
 
     // Initializing Bernardo TUI
     App::init()
@@ -36,9 +49,14 @@ fn main() {
             let tree_sitter = Arc::new(TreeSitterWrapper::new(LanguageSet::full()));
             let navcomp_loader = Arc::new(Box::new(RealNavCompLoader::new()) as Box<dyn NavCompLoader>);
 
-            let providers = Providers::new(config_ref, fsf, clipboard, theme, tree_sitter, navcomp_loader, vec![]);
+            let git_op = CliGitProvider::discover(fsf.root_path_buf());
+
+            let mut providers = Providers::new(config_ref, fsf, clipboard, theme, tree_sitter, navcomp_loader, vec![]);
+            if let Some(git) = git_op {
+                providers = providers.with_git(Arc::new(Box::new(git) as Box<dyn GitProvider>));
+            }
 
-            run_gladius(providers, input, output, files);
+            run_gladius(providers, input, output, files, use_session);
         })
         .expect("Expected the app to work")
 }
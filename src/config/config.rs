@@ -22,6 +22,10 @@ pub struct KeyboardConfig {
     pub global: Global,
     #[serde(default)]
     pub editor: Editor,
+    #[serde(default)]
+    pub diff: Diff,
+    #[serde(default)]
+    pub task: Task,
 }
 
 #[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
@@ -31,6 +35,28 @@ pub struct Global {
     pub new_buffer: Key,
     pub browse_buffers: Key,
     pub everything_bar: Key,
+    // returns focus from embedded terminal, one of the few keys that are NOT passed to the shell
+    #[serde(default = "Global::default_terminal_escape")]
+    pub terminal_escape: Key,
+    // scroll embedded terminal through lines that went off screen, a page at a time
+    #[serde(default = "Global::default_terminal_scroll_up")]
+    pub terminal_scroll_up: Key,
+    #[serde(default = "Global::default_terminal_scroll_down")]
+    pub terminal_scroll_down: Key,
+}
+
+impl Global {
+    fn default_terminal_escape() -> Key {
+        Keycode::Char('q').to_key().with_alt()
+    }
+
+    fn default_terminal_scroll_up() -> Key {
+        Keycode::PageUp.to_key().with_shift()
+    }
+
+    fn default_terminal_scroll_down() -> Key {
+        Keycode::PageDown.to_key().with_shift()
+    }
 }
 
 impl Default for Global {
@@ -43,6 +69,9 @@ impl Default for Global {
             // This is the most important feature of them all.
             // In order to support it EVERYWHERE it will need to be converted to InputEvent
             everything_bar: Keycode::Char('e').to_key().with_ctrl(),
+            terminal_escape: Self::default_terminal_escape(),
+            terminal_scroll_up: Self::default_terminal_scroll_up(),
+            terminal_scroll_down: Self::default_terminal_scroll_down(),
         }
     }
 }
@@ -59,6 +88,177 @@ pub struct Editor {
     pub request_completions: Key,
 
     pub reformat: Key,
+
+    #[serde(default = "Editor::default_next_hunk")]
+    pub next_hunk: Key,
+    #[serde(default = "Editor::default_prev_hunk")]
+    pub prev_hunk: Key,
+    #[serde(default = "Editor::default_toggle_blame")]
+    pub toggle_blame: Key,
+    #[serde(default = "Editor::default_undo_tree")]
+    pub undo_tree: Key,
+    #[serde(default = "Editor::default_jump_to_matching_bracket")]
+    pub jump_to_matching_bracket: Key,
+    #[serde(default = "Editor::default_toggle_comment")]
+    pub toggle_comment: Key,
+    #[serde(default = "Editor::default_toggle_block_comment")]
+    pub toggle_block_comment: Key,
+    #[serde(default = "Editor::default_move_lines_up")]
+    pub move_lines_up: Key,
+    #[serde(default = "Editor::default_move_lines_down")]
+    pub move_lines_down: Key,
+    #[serde(default = "Editor::default_duplicate_lines")]
+    pub duplicate_lines: Key,
+    #[serde(default = "Editor::default_delete_lines")]
+    pub delete_lines: Key,
+    #[serde(default = "Editor::default_join_lines")]
+    pub join_lines: Key,
+    #[serde(default = "Editor::default_insert_line_above")]
+    pub insert_line_above: Key,
+    #[serde(default = "Editor::default_insert_line_below")]
+    pub insert_line_below: Key,
+    #[serde(default = "Editor::default_add_next_occurrence")]
+    pub add_next_occurrence: Key,
+    #[serde(default = "Editor::default_skip_occurrence")]
+    pub skip_occurrence: Key,
+    #[serde(default = "Editor::default_select_all_occurrences")]
+    pub select_all_occurrences: Key,
+    // column (block) selection, like Shift+arrows select text
+    #[serde(default = "Editor::default_column_select_up")]
+    pub column_select_up: Key,
+    #[serde(default = "Editor::default_column_select_down")]
+    pub column_select_down: Key,
+    #[serde(default = "Editor::default_column_select_left")]
+    pub column_select_left: Key,
+    #[serde(default = "Editor::default_column_select_right")]
+    pub column_select_right: Key,
+    // no default keys, these are in context bar
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sort_lines: Option<Key>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unique_lines: Option<Key>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reverse_lines: Option<Key>,
+}
+
+impl Editor {
+    fn default_next_hunk() -> Key {
+        Keycode::F(8).to_key()
+    }
+
+    fn default_prev_hunk() -> Key {
+        Keycode::F(7).to_key()
+    }
+
+    fn default_toggle_blame() -> Key {
+        Keycode::F(6).to_key()
+    }
+
+    fn default_undo_tree() -> Key {
+        Keycode::F(9).to_key()
+    }
+
+    fn default_jump_to_matching_bracket() -> Key {
+        Keycode::Char('m').to_key().with_alt()
+    }
+
+    fn default_toggle_comment() -> Key {
+        Keycode::Char('/').to_key().with_alt()
+    }
+
+    fn default_toggle_block_comment() -> Key {
+        Keycode::Char('b').to_key().with_alt()
+    }
+
+    fn default_move_lines_up() -> Key {
+        Keycode::ArrowUp.to_key().with_ctrl().with_shift()
+    }
+
+    fn default_move_lines_down() -> Key {
+        Keycode::ArrowDown.to_key().with_ctrl().with_shift()
+    }
+
+    fn default_duplicate_lines() -> Key {
+        Keycode::Char('d').to_key().with_alt()
+    }
+
+    fn default_delete_lines() -> Key {
+        Keycode::Char('k').to_key().with_alt()
+    }
+
+    fn default_join_lines() -> Key {
+        Keycode::Char('j').to_key().with_alt()
+    }
+
+    fn default_insert_line_above() -> Key {
+        Keycode::Char('i').to_key().with_alt()
+    }
+
+    fn default_insert_line_below() -> Key {
+        Keycode::Char('o').to_key().with_alt()
+    }
+
+    fn default_add_next_occurrence() -> Key {
+        Keycode::Char('n').to_key().with_alt()
+    }
+
+    fn default_skip_occurrence() -> Key {
+        Keycode::Char('s').to_key().with_alt()
+    }
+
+    fn default_select_all_occurrences() -> Key {
+        Keycode::Char('a').to_key().with_alt()
+    }
+
+    fn default_column_select_up() -> Key {
+        Keycode::ArrowUp.to_key().with_alt().with_shift()
+    }
+
+    fn default_column_select_down() -> Key {
+        Keycode::ArrowDown.to_key().with_alt().with_shift()
+    }
+
+    fn default_column_select_left() -> Key {
+        Keycode::ArrowLeft.to_key().with_alt().with_shift()
+    }
+
+    fn default_column_select_right() -> Key {
+        Keycode::ArrowRight.to_key().with_alt().with_shift()
+    }
+
+    /*
+    Direction column selection is extended in by key, if any.
+     */
+    pub fn column_select_arrow(&self, key: Key) -> Option<Arrow> {
+        let bindings = [
+            (self.column_select_up, Arrow::Up),
+            (self.column_select_down, Arrow::Down),
+            (self.column_select_left, Arrow::Left),
+            (self.column_select_right, Arrow::Right),
+        ];
+
+        bindings.into_iter().find(|(bound, _)| *bound == key).map(|(_, arrow)| arrow)
+    }
+
+    /*
+    Line operation bound to key, if any.
+     */
+    pub fn line_edit_msg(&self, key: Key) -> Option<CommonEditMsg> {
+        let bindings = [
+            (Some(self.move_lines_up), CommonEditMsg::MoveLinesUp),
+            (Some(self.move_lines_down), CommonEditMsg::MoveLinesDown),
+            (Some(self.duplicate_lines), CommonEditMsg::DuplicateLines),
+            (Some(self.delete_lines), CommonEditMsg::DeleteLines),
+            (Some(self.join_lines), CommonEditMsg::JoinLines),
+            (Some(self.insert_line_above), CommonEditMsg::InsertLineAbove),
+            (Some(self.insert_line_below), CommonEditMsg::InsertLineBelow),
+            (self.sort_lines, CommonEditMsg::SortLines),
+            (self.unique_lines, CommonEditMsg::UniqueLines),
+            (self.reverse_lines, CommonEditMsg::ReverseLines),
+        ];
+
+        bindings.into_iter().find(|(bound, _)| *bound == Some(key)).map(|(_, msg)| msg)
+    }
 }
 
 impl Default for Editor {
@@ -74,6 +274,60 @@ impl Default for Editor {
             // I know it's stupid, but at this point I am out of keys on under my left hand
             //  normal people will use context options anyway
             reformat: Keycode::Char('g').to_key().with_ctrl(),
+            next_hunk: Self::default_next_hunk(),
+            prev_hunk: Self::default_prev_hunk(),
+            toggle_blame: Self::default_toggle_blame(),
+            undo_tree: Self::default_undo_tree(),
+            jump_to_matching_bracket: Self::default_jump_to_matching_bracket(),
+            toggle_comment: Self::default_toggle_comment(),
+            toggle_block_comment: Self::default_toggle_block_comment(),
+            move_lines_up: Self::default_move_lines_up(),
+            move_lines_down: Self::default_move_lines_down(),
+            duplicate_lines: Self::default_duplicate_lines(),
+            delete_lines: Self::default_delete_lines(),
+            join_lines: Self::default_join_lines(),
+            insert_line_above: Self::default_insert_line_above(),
+            insert_line_below: Self::default_insert_line_below(),
+            add_next_occurrence: Self::default_add_next_occurrence(),
+            skip_occurrence: Self::default_skip_occurrence(),
+            select_all_occurrences: Self::default_select_all_occurrences(),
+            column_select_up: Self::default_column_select_up(),
+            column_select_down: Self::default_column_select_down(),
+            column_select_left: Self::default_column_select_left(),
+            column_select_right: Self::default_column_select_right(),
+            sort_lines: None,
+            unique_lines: None,
+            reverse_lines: None,
+        }
+    }
+}
+
+// side-by-side diff, next/previous difference are shared with editor's next/previous hunk
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Diff {
+    // copies difference under cursor from focused side to the other one
+    pub copy_hunk: Key,
+}
+
+impl Default for Diff {
+    fn default() -> Self {
+        Diff {
+            copy_hunk: Keycode::F(5).to_key(),
+        }
+    }
+}
+
+// output of a user defined task
+#[derive(Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct Task {
+    // kills the task if it still runs
+    pub stop: Key,
+}
+
+impl Default for Task {
+    fn default() -> Self {
+        Task {
+            stop: Keycode::Char('c').to_key().with_ctrl(),
         }
     }
 }
@@ -0,0 +1,446 @@
+use std::collections::HashMap;
+
+use log::{debug, warn};
+use regex::Regex;
+
+use crate::*;
+
+/*
+Settings from .editorconfig files (see https://editorconfig.org). They are looked for in directory of
+the file and all its parents, up to root of filesystem (so workspace root) or a file with "root = true".
+Nearer files override farther ones, later sections of a file override earlier ones, and "unset" drops
+whatever was set before.
+
+Unknown keys and values are ignored, so is "end_of_line = cr", as buffers don't do CR-only files.
+ */
+pub const EDITORCONFIG_FILE_NAME: &str = ".editorconfig";
+
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct EditorConfigOptions {
+    pub indent_style: Option<IndentStyle>,
+    // columns per level of indentation
+    pub indent_size: Option<usize>,
+    // columns per tab char
+    pub tab_width: Option<usize>,
+    pub end_of_line: Option<LineEnding>,
+    pub charset: Option<Encoding>,
+    pub trim_trailing_whitespace: Option<bool>,
+    // false is taken for "don't care", files are not stripped of their final newline
+    pub insert_final_newline: Option<bool>,
+    pub max_line_length: Option<usize>,
+}
+
+impl EditorConfigOptions {
+    /*
+    Reads all .editorconfig files that apply to file under path. Ones that fail to read or parse are
+    skipped.
+     */
+    pub fn for_file(path: &SPath) -> EditorConfigOptions {
+        let mut files: Vec<(SPath, EditorConfigFile)> = Vec::new();
+
+        for dir in path.ancestors_and_self().skip(1) {
+            let config_path = match dir.descendant_checked(EDITORCONFIG_FILE_NAME) {
+                Some(config_path) if config_path.is_file() => config_path,
+                _ => continue,
+            };
+
+            let file = match config_path.read_entire_file_to_string() {
+                Ok(text) => EditorConfigFile::parse(&text),
+                Err(e) => {
+                    warn!("failed to read {}: {}", config_path, e);
+                    continue;
+                }
+            };
+
+            let root = file.root;
+            files.push((dir, file));
+            if root {
+                break;
+            }
+        }
+
+        let mut properties: HashMap<String, String> = HashMap::new();
+        for (dir, file) in files.iter().rev() {
+            let relative = relative_to(path, dir);
+            file.apply(&relative, &mut properties);
+        }
+
+        debug!("editorconfig of {}: {:?}", path, properties);
+        EditorConfigOptions::from_properties(&properties)
+    }
+
+    fn from_properties(properties: &HashMap<String, String>) -> EditorConfigOptions {
+        let get = |key: &str| properties.get(key).map(|value| value.as_str());
+        let number = |key: &str| get(key).and_then(|value| value.parse::<usize>().ok()).filter(|n| *n > 0);
+        let boolean = |key: &str| match get(key) {
+            Some("true") => Some(true),
+            Some("false") => Some(false),
+            _ => None,
+        };
+
+        let tab_width = number("tab_width");
+        let indent_size = match get("indent_size") {
+            Some("tab") => tab_width,
+            _ => number("indent_size"),
+        };
+
+        EditorConfigOptions {
+            indent_style: match get("indent_style") {
+                Some("tab") => Some(IndentStyle::Tabs),
+                Some("space") => Some(IndentStyle::Spaces),
+                _ => None,
+            },
+            indent_size,
+            // defaults to indent_size, as the spec says
+            tab_width: tab_width.or(indent_size),
+            end_of_line: match get("end_of_line") {
+                Some("lf") => Some(LineEnding::Lf),
+                Some("crlf") => Some(LineEnding::CrLf),
+                _ => None,
+            },
+            charset: match get("charset") {
+                Some("utf-8") => Some(Encoding::Utf8),
+                Some("utf-8-bom") => Some(Encoding::Utf8Bom),
+                Some("utf-16le") => Some(Encoding::Utf16Le),
+                Some("utf-16be") => Some(Encoding::Utf16Be),
+                Some("latin1") => Some(Encoding::Latin1),
+                _ => None,
+            },
+            trim_trailing_whitespace: boolean("trim_trailing_whitespace"),
+            insert_final_newline: boolean("insert_final_newline"),
+            max_line_length: number("max_line_length"),
+        }
+    }
+
+    /*
+    Buffers have one width for both indentation and tabs. Tab char is what indents with tabs, so it's
+    width wins then, otherwise it's indent_size.
+     */
+    pub fn indent_width(&self) -> Option<usize> {
+        match self.indent_style {
+            Some(IndentStyle::Tabs) => self.tab_width.or(self.indent_size),
+            _ => self.indent_size.or(self.tab_width),
+        }
+    }
+
+    /*
+    .editorconfig is about this very file, so it overrides every layer of settings.
+     */
+    pub fn override_language_options(&self, options: ResolvedLanguageOptions) -> ResolvedLanguageOptions {
+        ResolvedLanguageOptions {
+            tab_width: self.indent_width().unwrap_or(options.tab_width),
+            indent_style: self.indent_style.unwrap_or(options.indent_style),
+            ..options
+        }
+    }
+}
+
+/*
+Path of file relative to directory, with '/' separators, as globs are written.
+ */
+fn relative_to(path: &SPath, dir: &SPath) -> String {
+    let path = path.relative_path();
+    let relative = path.strip_prefix(dir.relative_path()).unwrap_or(&path);
+
+    let segments: Vec<String> = relative.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+    segments.join("/")
+}
+
+// glob (None if it failed to parse) and properties, in order of appearance
+type Section = (Option<Glob>, Vec<(String, String)>);
+
+struct EditorConfigFile {
+    root: bool,
+    sections: Vec<Section>,
+}
+
+impl EditorConfigFile {
+    fn parse(text: &str) -> EditorConfigFile {
+        let mut root = false;
+        let mut sections: Vec<Section> = Vec::new();
+
+        for line in text.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') || line.starts_with(';') {
+                continue;
+            }
+
+            if line.starts_with('[') && line.ends_with(']') {
+                let pattern = &line[1..line.len() - 1];
+                let glob = Glob::new(pattern);
+                if glob.is_none() {
+                    warn!("failed to parse section [{}] of .editorconfig, skipping it", pattern);
+                }
+                sections.push((glob, Vec::new()));
+                continue;
+            }
+
+            let (key, value) = match line.split_once('=') {
+                Some((key, value)) => (key.trim().to_lowercase(), value.trim().to_lowercase()),
+                None => {
+                    debug!("ignoring line [{}] of .editorconfig", line);
+                    continue;
+                }
+            };
+
+            match sections.last_mut() {
+                Some((_, properties)) => properties.push((key, value)),
+                // preamble
+                None => {
+                    if key == "root" {
+                        root = value == "true";
+                    }
+                }
+            }
+        }
+
+        EditorConfigFile { root, sections }
+    }
+
+    fn apply(&self, relative_path: &str, properties: &mut HashMap<String, String>) {
+        for (glob, section) in self.sections.iter() {
+            let glob = match glob {
+                Some(glob) if glob.matches(relative_path) => glob,
+                _ => continue,
+            };
+            debug!("{} matches {:?}", relative_path, glob.regex);
+
+            for (key, value) in section.iter() {
+                if value == "unset" {
+                    properties.remove(key);
+                } else {
+                    properties.insert(key.clone(), value.clone());
+                }
+            }
+        }
+    }
+}
+
+/*
+Section name of .editorconfig, as a regex. Numeric ranges {n..m} can't be expressed in it, so they
+are captured and checked after match.
+ */
+struct Glob {
+    regex: Regex,
+    ranges: Vec<(i64, i64)>,
+}
+
+impl Glob {
+    fn new(pattern: &str) -> Option<Glob> {
+        let mut regex = String::from("^");
+        // patterns without '/' match file name in any directory
+        let pattern = if pattern.contains('/') {
+            pattern.strip_prefix('/').unwrap_or(pattern)
+        } else {
+            regex += "(?:.*/)?";
+            pattern
+        };
+
+        let mut ranges: Vec<(i64, i64)> = Vec::new();
+        glob_to_regex(pattern, &mut regex, &mut ranges);
+        regex += "$";
+
+        Regex::new(&regex).ok().map(|regex| Glob { regex, ranges })
+    }
+
+    fn matches(&self, path: &str) -> bool {
+        let captures = match self.regex.captures(path) {
+            Some(captures) => captures,
+            None => return false,
+        };
+
+        self.ranges.iter().enumerate().all(|(idx, (begin, end))| {
+            captures
+                .get(idx + 1)
+                .and_then(|number| number.as_str().parse::<i64>().ok())
+                .map(|number| *begin <= number && number <= *end)
+                .unwrap_or(false)
+        })
+    }
+}
+
+fn glob_to_regex(pattern: &str, regex: &mut String, ranges: &mut Vec<(i64, i64)>) {
+    let chars: Vec<char> = pattern.chars().collect();
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        match chars[idx] {
+            '\\' if idx + 1 < chars.len() => {
+                *regex += &regex::escape(&chars[idx + 1].to_string());
+                idx += 1;
+            }
+            // "a/**/b" matches "a/b" too
+            '/' if chars[idx + 1..].starts_with(&['*', '*', '/']) => {
+                *regex += "(?:/|/.*/)";
+                idx += 3;
+            }
+            '*' if chars.get(idx + 1) == Some(&'*') => {
+                *regex += ".*";
+                idx += 1;
+            }
+            '*' => *regex += "[^/]*",
+            '?' => *regex += "[^/]",
+            '[' => match chars[idx + 1..].iter().position(|c| *c == ']') {
+                Some(len) => {
+                    let class: String = chars[idx + 1..idx + 1 + len].iter().collect();
+                    let (negated, class) = match class.strip_prefix('!') {
+                        Some(rest) => (true, rest.to_string()),
+                        None => (false, class),
+                    };
+                    let class: String = class.split('-').map(regex::escape).collect::<Vec<String>>().join("-");
+
+                    *regex += if negated { "[^" } else { "[" };
+                    *regex += &class;
+                    *regex += "]";
+                    idx += len + 1;
+                }
+                None => *regex += "\\[",
+            },
+            '{' => match closing_brace(&chars, idx) {
+                Some(end) => {
+                    let inner: String = chars[idx + 1..end].iter().collect();
+                    brace_to_regex(&inner, regex, ranges);
+                    idx = end;
+                }
+                None => *regex += "\\{",
+            },
+            c => *regex += &regex::escape(&c.to_string()),
+        }
+        idx += 1;
+    }
+}
+
+fn closing_brace(chars: &[char], open_idx: usize) -> Option<usize> {
+    let mut depth = 0;
+    for (idx, c) in chars.iter().enumerate().skip(open_idx) {
+        match c {
+            '{' => depth += 1,
+            '}' => {
+                depth -= 1;
+                if depth == 0 {
+                    return Some(idx);
+                }
+            }
+            _ => {}
+        }
+    }
+    None
+}
+
+/*
+Contents of {}: a numeric range, alternatives separated by commas (that can hold globs themselves), or
+just a literal.
+ */
+fn brace_to_regex(inner: &str, regex: &mut String, ranges: &mut Vec<(i64, i64)>) {
+    if let Some((begin, end)) = inner.split_once("..") {
+        if let (Ok(begin), Ok(end)) = (begin.parse::<i64>(), end.parse::<i64>()) {
+            *regex += "([+-]?[0-9]+)";
+            ranges.push((begin.min(end), begin.max(end)));
+            return;
+        }
+    }
+
+    let mut alternatives: Vec<&str> = Vec::new();
+    let mut depth = 0;
+    let mut begin = 0;
+    for (idx, c) in inner.char_indices() {
+        match c {
+            '{' => depth += 1,
+            '}' => depth -= 1,
+            ',' if depth == 0 => {
+                alternatives.push(&inner[begin..idx]);
+                begin = idx + 1;
+            }
+            _ => {}
+        }
+    }
+    alternatives.push(&inner[begin..]);
+
+    if alternatives.len() == 1 {
+        *regex += &regex::escape(&format!("{{{}}}", inner));
+        return;
+    }
+
+    *regex += "(?:";
+    for (idx, alternative) in alternatives.iter().enumerate() {
+        if idx > 0 {
+            *regex += "|";
+        }
+        glob_to_regex(alternative, regex, ranges);
+    }
+    *regex += ")";
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn matches(pattern: &str, path: &str) -> bool {
+        Glob::new(pattern).unwrap().matches(path)
+    }
+
+    #[test]
+    fn test_globs() {
+        assert!(matches("*", "main.rs"));
+        assert!(matches("*", "src/main.rs"));
+        assert!(matches("*.rs", "src/main.rs"));
+        assert!(!matches("*.rs", "src/main.rsx"));
+        assert!(matches("src/*.rs", "src/main.rs"));
+        assert!(!matches("src/*.rs", "src/a/main.rs"));
+        assert!(!matches("src/*.rs", "lib/src/main.rs"));
+        assert!(matches("/src/**.rs", "src/a/main.rs"));
+        assert!(matches("src/**/main.rs", "src/main.rs"));
+        assert!(matches("src/**/main.rs", "src/a/b/main.rs"));
+        assert!(matches("ma?n.rs", "main.rs"));
+        assert!(matches("[abc].rs", "b.rs"));
+        assert!(!matches("[!abc].rs", "b.rs"));
+        assert!(matches("[a-c].rs", "b.rs"));
+        assert!(matches("*.{rs,toml}", "Cargo.toml"));
+        assert!(matches("{*.rs,Makefile}", "src/Makefile"));
+        assert!(!matches("*.{rs,toml}", "Cargo.lock"));
+        assert!(matches("file{1..10}.txt", "file7.txt"));
+        assert!(!matches("file{1..10}.txt", "file11.txt"));
+        assert!(matches("{single}.txt", "{single}.txt"));
+        assert!(matches("a\\*.txt", "a*.txt"));
+        assert!(!matches("a\\*.txt", "ab.txt"));
+    }
+
+    #[test]
+    fn test_layers() {
+        let fsf = MockFS::new("/tmp")
+            .with_file("ignored/.editorconfig", "[*]\nindent_size = 8\n")
+            .with_file(
+                "ignored/ws/.editorconfig",
+                "root = true\n\n[*]\nindent_style = space\nindent_size = 4\ncharset = utf-8\nend_of_line = lf\n\n[*.{md,txt}]\ntrim_trailing_whitespace = false\n",
+            )
+            .with_file(
+                "ignored/ws/src/.editorconfig",
+                "# comment\n[*.RS]\nindent_style = tab\n\n[*.rs]\nIndent_Style = Tab\ntab_width = 2\nmax_line_length = 100\ntrim_trailing_whitespace = true\ninsert_final_newline = true\n\n[main.rs]\ncharset = unset\n",
+            )
+            .with_file("ignored/ws/src/main.rs", "")
+            .with_file("ignored/ws/src/notes.txt", "")
+            .to_fsf();
+
+        let main_rs = EditorConfigOptions::for_file(&spath!(fsf, "ignored", "ws", "src", "main.rs").unwrap());
+        assert_eq!(
+            main_rs,
+            EditorConfigOptions {
+                indent_style: Some(IndentStyle::Tabs),
+                indent_size: Some(4),
+                tab_width: Some(2),
+                end_of_line: Some(LineEnding::Lf),
+                charset: None,
+                trim_trailing_whitespace: Some(true),
+                insert_final_newline: Some(true),
+                max_line_length: Some(100),
+            }
+        );
+        assert_eq!(main_rs.indent_width(), Some(2));
+
+        let notes = EditorConfigOptions::for_file(&spath!(fsf, "ignored", "ws", "src", "notes.txt").unwrap());
+        assert_eq!(notes.indent_style, Some(IndentStyle::Spaces));
+        assert_eq!(notes.indent_width(), Some(4));
+        assert_eq!(notes.charset, Some(Encoding::Utf8));
+        assert_eq!(notes.trim_trailing_whitespace, Some(false));
+    }
+}
@@ -1,15 +1,108 @@
 use std::path::PathBuf;
+use std::time::Duration;
 
 use log::debug;
 use serde::{Deserialize, Serialize};
 use which;
 
+use crate::*;
+
 #[derive(Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
 pub struct GlobalEditorOptions {
     pub rust_lsp_path: Option<PathBuf>,
+
+    // defaults for all languages, can be overridden per workspace (see WorkspaceSettings)
+    #[serde(default, skip_serializing_if = "LanguageOptions::is_default")]
+    pub language_options: LanguageOptions,
+
+    // per buffer, oldest undo steps are forgotten above it. None means EditHistory::DEFAULT_LIMIT_BYTES.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub undo_memory_limit_bytes: Option<usize>,
+
+    // undo history of a single file kept between sessions (see UndoHistoryStore), 0 turns it off.
+    // None means UndoHistoryStore::DEFAULT_FILE_LIMIT_BYTES.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistent_undo_file_limit_bytes: Option<usize>,
+
+    // all kept histories together, the ones saved longest ago are dropped first (by --clean-undo-history)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistent_undo_total_limit_bytes: Option<usize>,
+
+    // histories of files not saved for that long are dropped
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub persistent_undo_max_age_days: Option<u64>,
+
+    // how often modified buffers are written to swap files (see SwapWriter), 0 turns it off.
+    // None means SwapWriter::DEFAULT_INTERVAL_MS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub swap_interval_ms: Option<u64>,
+
+    // how often files of open buffers are checked for changes made by other programs, 0 turns it off.
+    // None means DEFAULT_FILE_CHECK_INTERVAL_MS.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub file_check_interval_ms: Option<u64>,
+
+    // files bigger than that open in large file mode (see BufferState::set_large_file), 0 turns it off.
+    // None means BufferState::DEFAULT_LARGE_FILE_THRESHOLD_BYTES.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub large_file_threshold_bytes: Option<u64>,
+
+    // copy of previous version of file, made when buffer is saved over it
+    #[serde(default, skip_serializing_if = "BackupMode::is_default")]
+    pub backup: BackupMode,
+
+    // whether saves wait until files reach the disk, it's slower but they survive a crash right after
+    #[serde(default)]
+    pub fsync_on_save: bool,
 }
 
 impl GlobalEditorOptions {
+    pub fn get_undo_memory_limit_bytes(&self) -> usize {
+        self.undo_memory_limit_bytes.unwrap_or(EditHistory::DEFAULT_LIMIT_BYTES)
+    }
+
+    pub fn get_persistent_undo_file_limit_bytes(&self) -> usize {
+        self.persistent_undo_file_limit_bytes
+            .unwrap_or(UndoHistoryStore::DEFAULT_FILE_LIMIT_BYTES)
+    }
+
+    pub fn get_persistent_undo_total_limit_bytes(&self) -> usize {
+        self.persistent_undo_total_limit_bytes
+            .unwrap_or(UndoHistoryStore::DEFAULT_TOTAL_LIMIT_BYTES)
+    }
+
+    pub fn get_persistent_undo_max_age(&self) -> Duration {
+        let days = self.persistent_undo_max_age_days.unwrap_or(UndoHistoryStore::DEFAULT_MAX_AGE_DAYS);
+        Duration::from_secs(days.saturating_mul(24 * 60 * 60))
+    }
+
+    // None if swap files are off
+    pub fn get_swap_interval(&self) -> Option<Duration> {
+        match self.swap_interval_ms.unwrap_or(SwapWriter::DEFAULT_INTERVAL_MS) {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        }
+    }
+
+    // None if checking files for external changes is off
+    pub fn get_file_check_interval(&self) -> Option<Duration> {
+        match self.file_check_interval_ms.unwrap_or(DEFAULT_FILE_CHECK_INTERVAL_MS) {
+            0 => None,
+            ms => Some(Duration::from_millis(ms)),
+        }
+    }
+
+    // None if large file mode is off
+    pub fn get_large_file_threshold_bytes(&self) -> Option<u64> {
+        match self
+            .large_file_threshold_bytes
+            .unwrap_or(BufferState::DEFAULT_LARGE_FILE_THRESHOLD_BYTES)
+        {
+            0 => None,
+            bytes => Some(bytes),
+        }
+    }
+
     pub fn get_rust_lsp_path(&self) -> Option<PathBuf> {
         self.rust_lsp_path.as_ref().map(|c| c.clone()).or_else(|| {
             debug!("discovering location of rust_analyzer");
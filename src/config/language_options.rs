@@ -0,0 +1,79 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Clone, Copy, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum IndentStyle {
+    #[default]
+    Spaces,
+    Tabs,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub enum FormatterChoice {
+    // whatever navcomp (LSP) of given language offers
+    #[default]
+    Navcomp,
+    // reformat command is ignored
+    Disabled,
+    // buffer contents is piped through stdin of command, and stdout is taken as a result
+    Command {
+        command: String,
+        args: Vec<String>,
+    },
+}
+
+/*
+Every field here is optional, because these settings are layered: workspace language specific ones
+override workspace defaults, which override global config, which overrides hardcoded defaults.
+None means "I don't care, ask the next layer".
+ */
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LanguageOptions {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tab_width: Option<usize>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub indent_style: Option<IndentStyle>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub formatter: Option<FormatterChoice>,
+}
+
+impl LanguageOptions {
+    /*
+    Fills all empty fields of self with values from other.
+     */
+    pub fn or(self, other: &LanguageOptions) -> LanguageOptions {
+        LanguageOptions {
+            tab_width: self.tab_width.or(other.tab_width),
+            indent_style: self.indent_style.or(other.indent_style),
+            formatter: self.formatter.or_else(|| other.formatter.clone()),
+        }
+    }
+
+    pub fn resolve(self) -> ResolvedLanguageOptions {
+        let default = ResolvedLanguageOptions::default();
+        ResolvedLanguageOptions {
+            tab_width: self.tab_width.filter(|tw| *tw > 0).unwrap_or(default.tab_width),
+            indent_style: self.indent_style.unwrap_or(default.indent_style),
+            formatter: self.formatter.unwrap_or(default.formatter),
+        }
+    }
+}
+
+/*
+This is what's left after all layers of settings got applied, no more "maybes".
+ */
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ResolvedLanguageOptions {
+    pub tab_width: usize,
+    pub indent_style: IndentStyle,
+    pub formatter: FormatterChoice,
+}
+
+impl Default for ResolvedLanguageOptions {
+    fn default() -> Self {
+        ResolvedLanguageOptions {
+            tab_width: 4,
+            indent_style: IndentStyle::Spaces,
+            formatter: FormatterChoice::Navcomp,
+        }
+    }
+}
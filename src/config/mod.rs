@@ -1,9 +1,15 @@
 mod config;
 pub use config::{Config, ConfigRef};
 
+mod editorconfig;
+pub use editorconfig::{EditorConfigOptions, EDITORCONFIG_FILE_NAME};
+
 mod global_editor_options;
 pub use global_editor_options::GlobalEditorOptions;
 
+mod language_options;
+pub use language_options::{FormatterChoice, IndentStyle, LanguageOptions, ResolvedLanguageOptions};
+
 mod load_error;
 pub use load_error::ConfigLoadError;
 
@@ -11,4 +17,4 @@ mod save_error;
 pub use save_error::SaveError;
 
 mod theme;
-pub use theme::{CursorsSettings, Theme, UiTheme};
+pub use theme::{CursorsSettings, GitTheme, Theme, UiTheme};
@@ -94,6 +94,24 @@ pub struct UiTheme {
     pub label_warning: TextStyle,
     pub label_error: TextStyle,
     pub label_type_annotation: TextStyle,
+
+    // added later, hence the default, so old theme files still load
+    #[serde(default)]
+    pub git: GitTheme,
+    // column past max_line_length of .editorconfig
+    #[serde(default = "default_ruler_background")]
+    pub ruler_background: Color,
+    // bracket next to the cursor and the one it pairs with
+    #[serde(default = "default_matching_bracket_background")]
+    pub matching_bracket_background: Color,
+}
+
+fn default_ruler_background() -> Color {
+    *RULER_BACKGROUND
+}
+
+fn default_matching_bracket_background() -> Color {
+    *MATCHING_BRACKET_BACKGROUND
 }
 
 lazy_static! {
@@ -116,6 +134,23 @@ lazy_static! {
     static ref KETCHUP_COLOR: Color = ron::from_str("\"#B10B0B\"").unwrap();
     static ref BLACK_COLOR: Color = ron::from_str("\"#000000\"").unwrap();
     static ref GREY_COLOR: Color = ron::from_str("\"#999999\"").unwrap();
+    static ref GIT_ADDED_COLOR: Color = ron::from_str("\"#98971A\"").unwrap();
+    static ref GIT_MODIFIED_COLOR: Color = ron::from_str("\"#458588\"").unwrap();
+    static ref GIT_DELETED_COLOR: Color = ron::from_str("\"#CC241D\"").unwrap();
+    static ref GIT_STAGED_COLOR: Color = ron::from_str("\"#B8BB26\"").unwrap();
+    static ref GIT_UNTRACKED_COLOR: Color = ron::from_str("\"#D79921\"").unwrap();
+    static ref GIT_IGNORED_COLOR: Color = ron::from_str("\"#504945\"").unwrap();
+    static ref DIFF_ADDED_BACKGROUND: Color = ron::from_str("\"#32361A\"").unwrap();
+    static ref DIFF_REMOVED_BACKGROUND: Color = ron::from_str("\"#3C1F1E\"").unwrap();
+    static ref DIFF_CHANGED_BACKGROUND: Color = ron::from_str("\"#1F3434\"").unwrap();
+    static ref DIFF_WORD_BACKGROUND: Color = ron::from_str("\"#2E5859\"").unwrap();
+    static ref DIFF_FILLER_BACKGROUND: Color = ron::from_str("\"#1D2021\"").unwrap();
+    static ref CONFLICT_MARKER_BACKGROUND: Color = ron::from_str("\"#504945\"").unwrap();
+    static ref CONFLICT_OURS_BACKGROUND: Color = ron::from_str("\"#2B3328\"").unwrap();
+    static ref CONFLICT_BASE_BACKGROUND: Color = ron::from_str("\"#32302F\"").unwrap();
+    static ref CONFLICT_THEIRS_BACKGROUND: Color = ron::from_str("\"#25303B\"").unwrap();
+    static ref RULER_BACKGROUND: Color = ron::from_str("\"#32302F\"").unwrap();
+    static ref MATCHING_BRACKET_BACKGROUND: Color = ron::from_str("\"#665C54\"").unwrap();
 }
 
 impl Default for UiTheme {
@@ -168,6 +203,72 @@ impl Default for UiTheme {
                 background: *GREY_COLOR,
                 effect: Default::default(),
             },
+            git: GitTheme::default(),
+            ruler_background: *RULER_BACKGROUND,
+            matching_bracket_background: *MATCHING_BRACKET_BACKGROUND,
+        }
+    }
+}
+
+// missing fields fall back to defaults, so new ones can be added without breaking theme files
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+#[serde(default)]
+pub struct GitTheme {
+    // gutter
+    pub added: Color,
+    pub modified: Color,
+    pub deleted: Color,
+
+    // file tree (modified files use "modified")
+    pub staged: Color,
+    pub untracked: Color,
+    pub ignored: Color,
+
+    // side-by-side diff backgrounds. Left side is "old", so lines only there are "removed".
+    pub diff_added_background: Color,
+    pub diff_removed_background: Color,
+    pub diff_changed_background: Color,
+    // changed part of a changed line
+    pub diff_word_background: Color,
+    // padding lines, so both sides stay aligned
+    pub diff_filler_background: Color,
+
+    // merge conflict regions
+    pub conflict_marker_background: Color,
+    pub conflict_ours_background: Color,
+    pub conflict_base_background: Color,
+    pub conflict_theirs_background: Color,
+}
+
+impl GitTheme {
+    pub fn file_status_color(&self, status: GitFileStatus) -> Color {
+        match status {
+            GitFileStatus::Modified => self.modified,
+            GitFileStatus::Staged => self.staged,
+            GitFileStatus::Untracked => self.untracked,
+            GitFileStatus::Ignored => self.ignored,
+        }
+    }
+}
+
+impl Default for GitTheme {
+    fn default() -> Self {
+        GitTheme {
+            added: *GIT_ADDED_COLOR,
+            modified: *GIT_MODIFIED_COLOR,
+            deleted: *GIT_DELETED_COLOR,
+            staged: *GIT_STAGED_COLOR,
+            untracked: *GIT_UNTRACKED_COLOR,
+            ignored: *GIT_IGNORED_COLOR,
+            diff_added_background: *DIFF_ADDED_BACKGROUND,
+            diff_removed_background: *DIFF_REMOVED_BACKGROUND,
+            diff_changed_background: *DIFF_CHANGED_BACKGROUND,
+            diff_word_background: *DIFF_WORD_BACKGROUND,
+            diff_filler_background: *DIFF_FILLER_BACKGROUND,
+            conflict_marker_background: *CONFLICT_MARKER_BACKGROUND,
+            conflict_ours_background: *CONFLICT_OURS_BACKGROUND,
+            conflict_base_background: *CONFLICT_BASE_BACKGROUND,
+            conflict_theirs_background: *CONFLICT_THEIRS_BACKGROUND,
         }
     }
 }
@@ -24,6 +24,7 @@ use std::cmp::Ordering;
 use std::ops::Range;
 
 use log::{error, warn};
+use serde::{Deserialize, Serialize};
 
 use crate::*;
 
@@ -35,7 +36,7 @@ pub enum CursorStatus {
     UnderCursor,
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 /*
    Describes a selection of text.
    Invariant: anchor is at begin OR end, never in between.
@@ -103,7 +104,7 @@ pub fn default_word_determinant(buffer: &dyn TextBuffer, first_idx: usize, curre
     return_value
 }
 
-#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct Cursor {
     // selection. Invariant: anchor is either at begin or end of selection, never inside.
@@ -217,29 +218,27 @@ impl Cursor {
                        [  ]
                    So shift initially engaged at middle position, then user moved left while holding
                    it, then decided to go right. In this scenario, selection shrinks.
+
+                   Jumps (word, line, home) can also take the moving end past the other one, then
+                   selection flips to the other side of it.
                 */
 
                 debug_assert!(old_pos == sel.b || old_pos == sel.e);
 
-                if sel.b == old_pos {
-                    if new_pos != sel.e {
-                        self.s = Some(Selection::new(new_pos, sel.e));
-                    } else {
-                        self.s = None;
-                    }
-
+                let other_end = if sel.b == old_pos {
+                    sel.e
+                } else if sel.e == old_pos {
+                    sel.b
+                } else {
+                    error!("invariant that selection begins or ends with anchor broken. Not crashing, but fix it.");
                     return;
-                }
-                if sel.e == old_pos {
-                    if sel.b != new_pos {
-                        self.s = Some(Selection::new(sel.b, new_pos));
-                    } else {
-                        self.s = None;
-                    }
+                };
 
-                    return;
+                if new_pos != other_end {
+                    self.s = Some(Selection::new(usize::min(new_pos, other_end), usize::max(new_pos, other_end)));
+                } else {
+                    self.s = None;
                 }
-                error!("invariant that selection begins or ends with anchor broken. Not crashing, but fix it.");
             }
         }
     }
@@ -293,7 +292,7 @@ impl Cursor {
         } else {
             self.a = new_pos;
             if selecting {
-                self.update_select(new_pos, old_pos);
+                self.update_select(old_pos, new_pos);
             } else {
                 self.clear_selection();
             }
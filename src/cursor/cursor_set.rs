@@ -3,6 +3,7 @@ use std::collections::HashMap;
 use std::slice::{Iter, IterMut};
 
 use log::{error, warn};
+use serde::{Deserialize, Serialize};
 
 use crate::*;
 
@@ -21,7 +22,7 @@ use super::cursor::default_word_determinant;
 // - (maybe) add "supercursor", which is always the first or the last, depending on which direction
 //   they were moved. it would help with anchoring.
 
-#[derive(Clone, Debug, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
 #[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
 pub struct CursorSet {
     set: Vec<Cursor>,
@@ -633,19 +634,26 @@ impl CursorSet {
     }
 
     /*
-    Adds cursor, returns true if such cursor did not exist.
+    Adds cursor, returns true if it didn't collide with existing ones: share an anchor, overlap a
+    selection, or have anchor on the other side.
      */
     pub fn add_cursor(&mut self, cursor: Cursor) -> bool {
-        debug_assert!(self.are_simple());
-        if self.get_cursor_status_for_char(cursor.a) == CursorStatus::None {
-            self.set.push(cursor);
-            self.set.sort();
-
-            debug_assert!(self.check_invariant());
-            true
-        } else {
-            false
+        let collides = self.set.iter().any(|c| {
+            c.a == cursor.a
+                || (c.get_begin() < cursor.get_end() && cursor.get_begin() < c.get_end())
+                || (c.anchor_left() && cursor.anchor_right())
+                || (c.anchor_right() && cursor.anchor_left())
+        });
+
+        if collides {
+            return false;
         }
+
+        self.set.push(cursor);
+        self.set.sort();
+
+        debug_assert!(self.check_invariant());
+        true
     }
 
     pub fn len(&self) -> usize {
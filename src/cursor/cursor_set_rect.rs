@@ -1,8 +1,12 @@
-use log::error;
+use std::cmp::min;
+use std::ops::Range;
+
+use log::{debug, error};
+use unicode_width::UnicodeWidthChar;
 
 use crate::*;
 
-fn cursor_to_xy(c: &Cursor, buffer: &dyn TextBuffer) -> XY {
+fn cursor_to_xy(c: &Cursor, buffer: &dyn TextBuffer, lines: &Range<usize>) -> XY {
     let y = buffer.char_to_line(c.a).unwrap_or_else(|| {
         error!("failed translating cursor to XY (1), most likely wrong buffer provided. c: {:?}", c);
         0
@@ -16,27 +20,174 @@ fn cursor_to_xy(c: &Cursor, buffer: &dyn TextBuffer) -> XY {
         0
     });
 
-    if x > u16::MAX as usize || y > u16::MAX as usize {
-        error!("failed translating cursor to XY (3), x/y too big. c: {:?} x: {} y: {}", c, x, y);
-        XY::ZERO
-    } else {
-        XY::new(x as u16, y as u16)
+    // outside of lines shown, it's as far as it can be shown
+    if x > u16::MAX as usize || !lines.contains(&y) {
+        debug!("cursor beyond what can be shown. c: {:?} x: {} y: {} lines: {:?}", c, x, y, lines);
     }
+    let y = y.clamp(lines.start, lines.end.saturating_sub(1)) - lines.start;
+    XY::new(min(x, u16::MAX as usize) as u16, min(y, u16::MAX as usize) as u16)
 }
 
-pub fn cursor_set_to_rect(cs: &CursorSet, buffer: &dyn TextBuffer) -> Rect {
+/*
+Rect containing all cursors, in coordinates of lines range shown (first of lines is y = 0).
+ */
+pub fn cursor_set_to_rect(cs: &CursorSet, buffer: &dyn TextBuffer, lines: Range<usize>) -> Rect {
     if cs.set().is_empty() {
         error!("asked for cursor_rect on an empty cursor set, returning 0,0");
         return Rect::ZERO;
     }
 
-    let first_cursor_as_xy = cursor_to_xy(&cs.set()[0], buffer);
+    let first_cursor_as_xy = cursor_to_xy(&cs.set()[0], buffer, &lines);
     let mut result = Rect::new(first_cursor_as_xy, XY::ZERO);
 
     for i in 1..cs.set().len() {
-        let cursor_as_xy = cursor_to_xy(&cs.set()[i], buffer);
+        let cursor_as_xy = cursor_to_xy(&cs.set()[i], buffer, &lines);
         result.expand_to_contain(cursor_as_xy);
     }
 
     result
 }
+
+fn char_width(c: char) -> usize {
+    UnicodeWidthChar::width(c).unwrap_or(0)
+}
+
+// index of last char of line that is not a newline, plus one
+fn line_end(buffer: &dyn TextBuffer, line_idx: usize) -> Option<usize> {
+    if line_idx + 1 < buffer.len_lines() {
+        buffer.line_to_char(line_idx + 1).map(|next_begin| next_begin - 1)
+    } else if line_idx < buffer.len_lines() {
+        Some(buffer.len_chars())
+    } else {
+        None
+    }
+}
+
+/*
+Display column of char_idx within its line, wide characters count as two.
+ */
+pub fn char_to_display_column(buffer: &dyn TextBuffer, char_idx: usize) -> Option<usize> {
+    let line_begin = buffer.line_to_char(buffer.char_to_line(char_idx)?)?;
+    Some((line_begin..char_idx).filter_map(|idx| buffer.char_at(idx)).map(char_width).sum())
+}
+
+/*
+First char of line that begins at display column or later, end of line if there is none. So a wide
+character is "at" the column it begins at, not the one it ends at.
+ */
+pub fn display_column_to_char(buffer: &dyn TextBuffer, line_idx: usize, column: usize) -> Option<usize> {
+    let line_begin = buffer.line_to_char(line_idx)?;
+    let line_end = line_end(buffer, line_idx)?;
+
+    let mut current_column = 0;
+    for idx in line_begin..line_end {
+        if current_column >= column {
+            return Some(idx);
+        }
+        current_column += char_width(buffer.char_at(idx)?);
+    }
+
+    Some(line_end)
+}
+
+/*
+Corner of column (block) selection. It's in buffer coordinates, not screen ones, so unlike XY it's
+not limited to u16.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct BlockCorner {
+    // display column, wide characters count as two
+    pub column: usize,
+    pub line: usize,
+}
+
+impl BlockCorner {
+    pub fn new(column: usize, line: usize) -> Self {
+        BlockCorner { column, line }
+    }
+}
+
+/*
+Cursor set of a column (block) selection spanned between anchor and head. Every line gets one cursor, selecting characters that begin within [left, right) columns. Lines
+too short to reach the block (and all lines, if block has zero width) get a simple cursor instead,
+at the block's left edge or end of line. Carets are on the side the head is.
+ */
+pub fn block_to_cursor_set(buffer: &dyn TextBuffer, anchor: BlockCorner, head: BlockCorner) -> Option<CursorSet> {
+    let left = anchor.column.min(head.column);
+    let right = anchor.column.max(head.column);
+    let top = anchor.line.min(head.line);
+    let bottom = anchor.line.max(head.line).min(buffer.len_lines().saturating_sub(1));
+    let caret_right = head.column >= anchor.column;
+
+    if top > bottom {
+        return None;
+    }
+
+    let mut cursors: Vec<Cursor> = Vec::with_capacity(bottom - top + 1);
+    for line_idx in top..=bottom {
+        let begin = display_column_to_char(buffer, line_idx, left)?;
+        let end = display_column_to_char(buffer, line_idx, right)?;
+
+        let cursor = if begin < end {
+            Cursor::new(if caret_right { end } else { begin }).with_selection(Selection::new(begin, end))
+        } else {
+            Cursor::new(begin)
+        };
+        cursors.push(cursor);
+    }
+
+    let mut cursor_set = CursorSet::single();
+    *cursor_set.set_mut() = cursors;
+    Some(cursor_set)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn selections(cs: &CursorSet) -> Vec<(usize, Option<(usize, usize)>)> {
+        cs.iter().map(|c| (c.a, c.s.map(|s| (s.b, s.e)))).collect()
+    }
+
+    #[test]
+    fn block_over_short_lines_and_wide_chars() {
+        let buffer = ropey::Rope::from_str("abcdef\nab\n日本語x\nabcdef");
+
+        assert_eq!(char_to_display_column(&buffer, 12), Some(4));
+        assert_eq!(display_column_to_char(&buffer, 2, 3), Some(12));
+        assert_eq!(display_column_to_char(&buffer, 1, 4), Some(9));
+
+        let cs = block_to_cursor_set(&buffer, BlockCorner::new(1, 0), BlockCorner::new(4, 3)).unwrap();
+        assert_eq!(
+            selections(&cs),
+            vec![
+                (4, Some((1, 4))),
+                (9, Some((8, 9))),
+                // "本" begins at column 2, "語" at column 4
+                (12, Some((11, 12))),
+                (19, Some((16, 19))),
+            ]
+        );
+
+        let cs = block_to_cursor_set(&buffer, BlockCorner::new(4, 3), BlockCorner::new(2, 2)).unwrap();
+        assert_eq!(selections(&cs), vec![(11, Some((11, 12))), (17, Some((17, 19)))]);
+
+        // second line is too short to reach the block
+        let cs = block_to_cursor_set(&buffer, BlockCorner::new(3, 0), BlockCorner::new(5, 1)).unwrap();
+        assert_eq!(selections(&cs), vec![(5, Some((3, 5))), (9, None)]);
+    }
+
+    #[test]
+    fn block_beyond_u16_lines() {
+        let buffer = ropey::Rope::from_str(&"ab\n".repeat(70_000));
+
+        let cs = block_to_cursor_set(&buffer, BlockCorner::new(0, 69_998), BlockCorner::new(1, 69_999)).unwrap();
+        assert_eq!(
+            selections(&cs),
+            vec![
+                (69_998 * 3 + 1, Some((69_998 * 3, 69_998 * 3 + 1))),
+                (69_999 * 3 + 1, Some((69_999 * 3, 69_999 * 3 + 1)))
+            ]
+        );
+    }
+}
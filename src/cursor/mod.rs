@@ -7,7 +7,7 @@ pub use cursor_set::CursorSet;
 mod cursor_set_fuzz;
 
 mod cursor_set_rect;
-pub use cursor_set_rect::cursor_set_to_rect;
+pub use cursor_set_rect::{block_to_cursor_set, char_to_display_column, cursor_set_to_rect, display_column_to_char, BlockCorner};
 
 mod constants;
 pub use constants::{NEWLINE_WIDTH, ZERO_CURSOR};
@@ -173,6 +173,23 @@ fn arrow_up_2() {
     assert_eq!(apply_sel("li[ne1\nline2\nli)ne3", f), "[line1\nline2\nli)ne3");
 }
 
+#[test]
+fn jumps_over_other_end_of_selection() {
+    let up: fn(&mut CursorSet, &dyn TextBuffer) = |c: &mut CursorSet, b: &dyn TextBuffer| {
+        c.move_vertically_by(b, -1, true);
+    };
+    let down: fn(&mut CursorSet, &dyn TextBuffer) = |c: &mut CursorSet, b: &dyn TextBuffer| {
+        c.move_vertically_by(b, 1, true);
+    };
+    let home: fn(&mut CursorSet, &dyn TextBuffer) = |c: &mut CursorSet, b: &dyn TextBuffer| {
+        c.move_home(b, true);
+    };
+
+    assert_eq!(apply_sel("lin(e1\nli]ne2", up), "li[n)e1\nline2");
+    assert_eq!(apply_sel("li[n)e1\nline2", down), "lin(e1\nli]ne2");
+    assert_eq!(apply_sel("te(x]t", home), "[te)xt");
+}
+
 #[test]
 fn arrow_down_1() {
     let f: fn(&mut CursorSet, &dyn TextBuffer) = |c: &mut CursorSet, b: &dyn TextBuffer| {
@@ -297,3 +314,17 @@ fn multiple_cursors_word_begin_with_selection() {
         assert_eq!(apply_sel(progress[i], f), progress[i + 1], "i: {}", i);
     }
 }
+
+#[test]
+fn add_cursor_with_selection() {
+    let (_, mut cs) = text_to_buffer_cursors("a[bc) abc abc");
+
+    // overlapping, then on the other side
+    assert!(!cs.add_cursor(Cursor::new(2).with_selection(Selection::new(2, 5))));
+    assert!(!cs.add_cursor(Cursor::new(7).with_selection(Selection::new(4, 7))));
+
+    assert!(cs.add_cursor(Cursor::new(8).with_selection(Selection::new(8, 11))));
+    assert!(cs.add_cursor(Cursor::new(4).with_selection(Selection::new(4, 7))));
+    assert!(cs.check_invariant());
+    assert_eq!(cs.iter().map(|c| c.a).collect::<Vec<_>>(), vec![1, 4, 8]);
+}
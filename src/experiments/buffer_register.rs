@@ -1,8 +1,7 @@
 use std::collections::{HashMap, HashSet};
-use std::str::from_utf8;
 use std::sync::{Arc, RwLock};
 
-use log::error;
+use log::{debug, error, warn};
 
 use crate::*;
 
@@ -38,10 +37,17 @@ impl BufferRegister {
             .flatten()
     }
 
+    pub fn buffers(&self) -> impl Iterator<Item = &BufferSharedRef> + '_ {
+        self.buffers.values()
+    }
+
     pub fn open_new_file(&mut self, providers: &Providers) -> BufferSharedRef {
         let doc_id = DocumentIdentifier::new_unique();
 
-        let buffer_state = BufferState::full(Some(providers.tree_sitter().clone()), doc_id.clone());
+        let options = providers.language_options(None);
+        let buffer_state = BufferState::full(Some(providers.tree_sitter().clone()), doc_id.clone())
+            .with_indentation(options.tab_width, options.indent_style)
+            .with_history_limit(providers.config().global.get_undo_memory_limit_bytes());
 
         let bsr = BufferSharedRef::new_from_buffer(buffer_state);
 
@@ -59,31 +65,75 @@ impl BufferRegister {
                 opened: false,
             }
         } else {
-            let buffer_bytes: Vec<u8> = match providers.fsf().blocking_read_entire_file(&path) {
-                Ok(bytes) => bytes,
+            // taken before reading, so a change made in between is noticed later rather than missed
+            let stamp = match path.stamp() {
+                Ok(stamp) => Some(stamp),
                 Err(e) => {
-                    error!("failed to read {}, because {}", &path, e);
-                    return OpenResult {
-                        buffer_shared_ref: Err(e),
-                        opened: false,
-                    };
+                    warn!("failed to get stamp of {}, its changes on disk will not be noticed: {}", path, e);
+                    None
                 }
             };
 
-            let buffer_str = match from_utf8(&buffer_bytes) {
-                Ok(s) => s,
-                Err(e) => {
-                    error!("failed loading file {}, because utf8 error {}", &path, e);
-                    return OpenResult {
-                        buffer_shared_ref: Err(ReadError::Utf8Error(e)),
-                        opened: false,
-                    };
-                }
+            let doc_id = DocumentIdentifier::new_unique().with_file_path(path.clone());
+
+            let editorconfig = EditorConfigOptions::for_file(path);
+            let options = editorconfig.override_language_options(providers.language_options(filename_to_language(path)));
+            let buffer_state = BufferState::full(Some(providers.tree_sitter().clone()), doc_id.clone())
+                .with_indentation(options.tab_width, options.indent_style)
+                .with_history_limit(providers.config().global.get_undo_memory_limit_bytes());
+
+            // size is known from the stamp, so large file is never read whole
+            let large_file = match (providers.config().global.get_large_file_threshold_bytes(), stamp) {
+                (Some(threshold), Some(stamp)) => stamp.len > threshold,
+                _ => false,
             };
 
-            let doc_id = DocumentIdentifier::new_unique().with_file_path(path.clone());
+            let mut buffer_state = if large_file {
+                debug!("opening {} ({} bytes) in large file mode", path, stamp.map(|s| s.len).unwrap_or(0));
+                // large_file first, so text is never parsed
+                // charset is not applied here, going through the whole file again is what we try to avoid
+                let (file_format, rope) = match FileFormat::decode_to_rope(|| path.open_reader()) {
+                    Ok(decoded) => decoded,
+                    Err(e) => return decoding_failed(path, e),
+                };
+                buffer_state
+                    .with_large_file(true)
+                    .with_text_from_rope(rope, None)
+                    .with_file_format(file_format)
+            } else {
+                let buffer_bytes: Vec<u8> = match providers.fsf().blocking_read_entire_file(&path) {
+                    Ok(bytes) => bytes,
+                    Err(e) => {
+                        error!("failed to read {}, because {}", &path, e);
+                        return OpenResult {
+                            buffer_shared_ref: Err(e),
+                            opened: false,
+                        };
+                    }
+                };
+
+                let decoded = match editorconfig.charset {
+                    Some(encoding) => FileFormat::decode_as(&buffer_bytes, encoding),
+                    None => FileFormat::decode(&buffer_bytes),
+                };
+                let (file_format, buffer_str) = match decoded {
+                    Ok(decoded) => decoded,
+                    Err(e) => return decoding_failed(path, e),
+                };
+                buffer_state.with_text(buffer_str).with_file_format(file_format)
+            };
+            buffer_state.set_disk_stamp(stamp);
+
+            // undo history from previous session, if the file is as we left it. Large files have none, as
+            // checking that would go through the whole text.
+            if !buffer_state.is_large_file() {
+                if let Some(history) = load_undo_history(providers, path, buffer_state.text().rope()) {
+                    buffer_state.set_history(history);
+                }
+            }
 
-            let buffer_state = BufferState::full(Some(providers.tree_sitter().clone()), doc_id.clone()).with_text(buffer_str);
+            // end_of_line is applied on save, see clean_up_for_save
+            buffer_state.set_editorconfig(editorconfig);
 
             let bsr = BufferSharedRef::new_from_buffer(buffer_state);
 
@@ -99,6 +149,16 @@ impl BufferRegister {
     }
 }
 
+// broken chars are not replaced, as saving would write the replacements back. Large files are read
+// while decoded, so it covers failing reads of them too.
+fn decoding_failed(path: &SPath, e: ReadError) -> OpenResult {
+    error!("failed to decode {}, because {}", path, e);
+    OpenResult {
+        buffer_shared_ref: Err(e),
+        opened: false,
+    }
+}
+
 impl HasInvariant for BufferRegister {
     fn check_invariant(&self) -> bool {
         // no two references to the same file
@@ -1,11 +1,19 @@
-use regex::Regex;
+use std::borrow::Cow;
 
-use crate::*;
+use regex::Regex;
+use regex_syntax::hir::{Class, Hir, HirKind};
+use ropey::Rope;
 
 /*
 I wasted hours trying to do regex search on the
  */
 
+/*
+Search goes line by line, borrowing the line from rope when it's in one chunk, so neither large nor small
+files are copied whole. That only works if pattern can't match a newline - if it can (a literal \n, \s,
+[^x], (?s). and such), we fall back to searching the whole text at once.
+ */
+
 #[derive(Copy, Clone, Debug, Eq, PartialEq)]
 pub enum FindError {
     RegexPatternFail,
@@ -17,55 +25,169 @@ pub enum FindError {
 
 /*
 This is an iterator of consecutive NON-OVERLAPPING matches, meaning not necessarily ALL matches.
+Items are ranges of chars.
  */
-pub struct RegexMatches {
-    all_bytes: String,
+pub struct RegexMatches<'a> {
+    rope: &'a Rope,
     regex: Regex,
-    byte_pos: usize,
+    search: Search,
+}
+
+enum Search {
+    Lines { line_idx: usize, byte_pos: usize },
+    WholeText { all_bytes: String, byte_pos: usize },
 }
 
 /*
 This will work with both regexes and simple strings.
  */
-pub fn regex_find<'a>(pattern: &'a str, rope: &'a dyn TextBuffer, start_pos_chars: Option<usize>) -> Result<RegexMatches, FindError> {
+pub fn regex_find<'a>(pattern: &str, rope: &'a Rope, start_pos_chars: Option<usize>) -> Result<RegexMatches<'a>, FindError> {
     if pattern.is_empty() {
         return Err(FindError::EmptyPattern);
     }
 
-    let mut all_bytes = String::new();
-    for chunk in rope.chunks() {
-        all_bytes += chunk;
-    }
-
     let regex = Regex::new(pattern).map_err(|_| FindError::RegexPatternFail)?;
 
-    let byte_pos: usize = match start_pos_chars {
-        None => 0,
-        Some(char_pos) => match rope.char_to_byte(char_pos) {
-            None => {
-                return Err(FindError::CharToByteFail);
-            }
-            Some(byte_pos) => byte_pos,
-        },
+    let char_pos = start_pos_chars.unwrap_or(0);
+    let byte_pos = rope.try_char_to_byte(char_pos).map_err(|_| FindError::CharToByteFail)?;
+
+    let search = if can_match_newline(pattern) {
+        let mut all_bytes = String::new();
+        for chunk in rope.chunks() {
+            all_bytes += chunk;
+        }
+
+        Search::WholeText { all_bytes, byte_pos }
+    } else {
+        let line_idx = rope.char_to_line(char_pos);
+        Search::Lines {
+            line_idx,
+            byte_pos: byte_pos - rope.line_to_byte(line_idx),
+        }
     };
 
-    Ok(RegexMatches {
-        all_bytes,
-        regex,
-        byte_pos,
-    })
+    Ok(RegexMatches { rope, regex, search })
+}
+
+impl<'a> RegexMatches<'a> {
+    fn next_in_lines(&mut self) -> Option<(usize, usize)> {
+        let (line_idx, byte_pos) = match &mut self.search {
+            Search::Lines { line_idx, byte_pos } => (line_idx, byte_pos),
+            Search::WholeText { .. } => return None,
+        };
+
+        while let Some(line) = self.rope.get_line(*line_idx) {
+            let line: Cow<str> = match line.as_str() {
+                Some(s) => Cow::Borrowed(s),
+                None => Cow::Owned(line.to_string()),
+            };
+            let line = line.strip_suffix('\n').unwrap_or(&line);
+
+            if *byte_pos <= line.len() {
+                if let Some(m) = self.regex.find_at(line, *byte_pos) {
+                    *byte_pos = after_match(line, &m);
+
+                    let line_begin = self.rope.line_to_char(*line_idx);
+                    let begin = line_begin + line[..m.start()].chars().count();
+                    let end = begin + line[m.start()..m.end()].chars().count();
+                    return Some((begin, end));
+                }
+            }
+
+            *line_idx += 1;
+            *byte_pos = 0;
+        }
+
+        None
+    }
+}
+
+/*
+Whether any part of pattern matches '\n'. Answers true when in doubt, whole text search is always right.
+ */
+fn can_match_newline(pattern: &str) -> bool {
+    fn visit(hir: &Hir) -> bool {
+        match hir.kind() {
+            HirKind::Empty | HirKind::Look(_) => false,
+            HirKind::Literal(literal) => literal.0.contains(&b'\n'),
+            HirKind::Class(Class::Unicode(class)) => class.ranges().iter().any(|r| r.start() <= '\n' && '\n' <= r.end()),
+            HirKind::Class(Class::Bytes(class)) => class.ranges().iter().any(|r| r.start() <= b'\n' && b'\n' <= r.end()),
+            HirKind::Repetition(repetition) => visit(&repetition.sub),
+            HirKind::Capture(capture) => visit(&capture.sub),
+            HirKind::Concat(hirs) | HirKind::Alternation(hirs) => hirs.iter().any(visit),
+        }
+    }
+
+    regex_syntax::Parser::new().parse(pattern).map(|hir| visit(&hir)).unwrap_or(true)
+}
+
+/*
+Where to look for the next match. Empty match would be found again and again, so we skip a char after it.
+ */
+fn after_match(text: &str, m: &regex::Match) -> usize {
+    if m.start() == m.end() {
+        m.end() + text[m.end()..].chars().next().map(|c| c.len_utf8()).unwrap_or(1)
+    } else {
+        m.end()
+    }
 }
 
-impl<'a> Iterator for RegexMatches {
+impl<'a> Iterator for RegexMatches<'a> {
     type Item = (usize, usize);
 
     fn next(&mut self) -> Option<Self::Item> {
-        match self.regex.find_at(&self.all_bytes, self.byte_pos) {
-            Some(m) => {
-                self.byte_pos = m.end();
-                Some((m.start(), m.end()))
+        match &mut self.search {
+            Search::Lines { .. } => self.next_in_lines(),
+            Search::WholeText { all_bytes, byte_pos } => {
+                if *byte_pos > all_bytes.len() {
+                    return None;
+                }
+
+                let m = self.regex.find_at(all_bytes, *byte_pos)?;
+                *byte_pos = after_match(all_bytes, &m);
+
+                Some((self.rope.byte_to_char(m.start()), self.rope.byte_to_char(m.end())))
             }
-            None => None,
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all(pattern: &str, text: &str, start: usize) -> Vec<(usize, usize)> {
+        regex_find(pattern, &Rope::from_str(text), Some(start)).unwrap().collect()
+    }
+
+    #[test]
+    fn test_matches_are_in_chars() {
+        assert_eq!(all("ść", "gęść\nść", 0), vec![(2, 4), (5, 7)]);
+        assert_eq!(all("ść", "gęść\nść", 3), vec![(5, 7)]);
+        assert_eq!(all("ś.$", "gęść\nść\n", 0), vec![(2, 4), (5, 7)]);
+        assert_eq!(all("ć\\nś", "gęść\nść", 0), vec![(3, 6)]);
+        assert_eq!(all("x", "gęść\nść", 0), vec![]);
+        assert_eq!(all("x*", "ęx", 0), vec![(0, 0), (1, 2), (2, 2)]);
+    }
+
+    #[test]
+    fn test_matches_across_lines() {
+        assert_eq!(all("ć\\sś", "gęść\nść", 0), vec![(3, 6)]);
+        assert_eq!(all("ć[^x]ś", "gęść\nść", 0), vec![(3, 6)]);
+        assert_eq!(all("(?s)ś.*ć", "gęść\nść", 0), vec![(2, 7)]);
+        assert_eq!(all("ś.*ć", "gęść\nść", 0), vec![(2, 4), (5, 7)]);
+    }
+
+    #[test]
+    fn test_can_match_newline() {
+        assert!(can_match_newline("a\nb"));
+        assert!(can_match_newline("a\\nb"));
+        assert!(can_match_newline("\\s+"));
+        assert!(can_match_newline("[^a]"));
+        assert!(can_match_newline("(?s)a.b"));
+        assert!(can_match_newline("\\W"));
+        assert!(!can_match_newline("a.b"));
+        assert!(!can_match_newline("^\\w+$"));
+        assert!(!can_match_newline("[ \\t]+"));
+    }
+}
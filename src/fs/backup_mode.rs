@@ -0,0 +1,58 @@
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+
+/*
+Where a copy of previous version of file goes, before save overwrites it. Only saves of buffers make
+backups, not the files editor keeps for itself (sessions, swap files etc.).
+ */
+#[derive(Clone, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum BackupMode {
+    #[default]
+    None,
+    // next to the file, with "~" appended to its name
+    Tilde,
+    // all in one directory (relative to workspace root, unless absolute), named after whole path of
+    // file with separators replaced by '%', so files of the same name don't overwrite each other's backups
+    Dir(PathBuf),
+}
+
+impl BackupMode {
+    pub fn is_default(&self) -> bool {
+        *self == BackupMode::None
+    }
+
+    /*
+    Path of backup of file under path (both relative to root of filesystem), None if there are no backups.
+     */
+    pub fn backup_path(&self, path: &Path) -> Option<PathBuf> {
+        match self {
+            BackupMode::None => None,
+            BackupMode::Tilde => {
+                let mut file_name = path.file_name()?.to_os_string();
+                file_name.push("~");
+                Some(path.with_file_name(file_name))
+            }
+            BackupMode::Dir(dir) => {
+                let name: Vec<String> = path.components().map(|c| c.as_os_str().to_string_lossy().to_string()).collect();
+                Some(dir.join(format!("{}~", name.join("%"))))
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_backup_paths() {
+        let path = Path::new("src/main.rs");
+        assert_eq!(BackupMode::None.backup_path(path), None);
+        assert_eq!(BackupMode::Tilde.backup_path(path), Some(PathBuf::from("src/main.rs~")));
+        assert_eq!(
+            BackupMode::Dir(PathBuf::from(".backups")).backup_path(path),
+            Some(PathBuf::from(".backups/src%main.rs~"))
+        );
+    }
+}
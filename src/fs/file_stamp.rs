@@ -0,0 +1,14 @@
+use std::time::SystemTime;
+
+/*
+Identifies a version of file on disk, so we can tell it was changed (by someone else) since we last
+read or wrote it. It's only ever compared for equality, fields are whatever filesystem can offer:
+RealFS uses modification time, size and inode, MockFS has neither times nor inodes, so it puts hash of
+contents in place of the latter.
+ */
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct FileStamp {
+    pub modified: Option<SystemTime>,
+    pub len: u64,
+    pub inode: u64,
+}
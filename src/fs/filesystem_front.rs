@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::io::Read;
 use std::path::{Path, PathBuf};
 
 use streaming_iterator::StreamingIterator;
@@ -12,6 +13,11 @@ pub trait FilesystemFront: Debug + Send + Sync {
 
     fn blocking_read_entire_file(&self, path: &Path) -> Result<Vec<u8>, ReadError>;
 
+    /*
+    For files too big to be read at once, see FileFormat::decode_to_rope.
+     */
+    fn blocking_open_reader(&self, path: &Path) -> Result<Box<dyn Read>, ReadError>;
+
     /*
     Blocking.
      */
@@ -27,6 +33,11 @@ pub trait FilesystemFront: Debug + Send + Sync {
 
     fn hash_seed(&self) -> usize;
 
+    /*
+    Current version of file, to tell if it changed since. Fails if there's no file.
+     */
+    fn blocking_stamp(&self, path: &Path) -> Result<FileStamp, ReadError>;
+
     fn blocking_list(&self, path: &Path) -> Result<Vec<DirEntry>, ListError>;
 
     fn exists(&self, path: &Path) -> bool;
@@ -40,5 +51,12 @@ pub trait FilesystemFront: Debug + Send + Sync {
 
     fn blocking_overwrite_with_bytes(&self, path: &Path, s: &[u8], must_exist: bool) -> Result<usize, WriteError>;
 
+    /*
+    Creates dir with all missing parents, succeeds if it's already there.
+     */
+    fn blocking_create_dir_all(&self, path: &Path) -> Result<(), WriteError>;
+
+    fn blocking_remove_file(&self, path: &Path) -> Result<(), WriteError>;
+
     fn to_fsf(self) -> FsfRef;
 }
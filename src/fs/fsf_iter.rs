@@ -1,14 +1,17 @@
 use std::collections::VecDeque;
+use std::path::PathBuf;
 
 use log::error;
 
 use crate::*;
 
 /*
-Recursively iterates over all items under root, in DFS pattern, siblings sorted lexicographically
+Recursively iterates over all items under root, in DFS pattern, siblings sorted lexicographically.
+Excluded dirs (relative to root) are neither listed nor descended into.
  */
 pub struct RecursiveFsIter {
     stack: VecDeque<Box<dyn Iterator<Item = SPath>>>,
+    excluded_dirs: Vec<PathBuf>,
 }
 
 impl RecursiveFsIter {
@@ -26,8 +29,22 @@ impl RecursiveFsIter {
 
         RecursiveFsIter {
             stack: VecDeque::from([first_iter]),
+            excluded_dirs: Vec::new(),
         }
     }
+
+    pub fn with_excluded_dirs(self, excluded_dirs: Vec<PathBuf>) -> Self {
+        Self { excluded_dirs, ..self }
+    }
+
+    fn is_excluded(&self, item: &SPath) -> bool {
+        if self.excluded_dirs.is_empty() {
+            return false;
+        }
+
+        let relative_path = item.relative_path();
+        self.excluded_dirs.iter().any(|dir| relative_path.starts_with(dir))
+    }
 }
 
 impl Iterator for RecursiveFsIter {
@@ -40,6 +57,10 @@ impl Iterator for RecursiveFsIter {
 
         while let Some(iter) = self.stack.front_mut() {
             if let Some(item) = iter.next() {
+                if self.is_excluded(&item) {
+                    continue;
+                }
+
                 if item.is_dir() {
                     match item.blocking_list() {
                         Ok(mut children) => {
@@ -83,4 +104,20 @@ mod tests {
         assert_eq!(iter.next(), Some(spath!(m, "folder1", "folder3", "moulder.txt").unwrap()));
         assert_eq!(iter.next(), None);
     }
+
+    #[test]
+    fn test_excluded_dirs_are_skipped() {
+        let m = MockFS::new("/tmp")
+            .with_file("folder1/folder2/file1.txt", "some text")
+            .with_file("folder1/folder3/moulder.txt", "truth is out there")
+            .with_file("target/debug/build.log", "compiling")
+            .to_fsf();
+
+        let mut iter = RecursiveFsIter::new(m.root()).with_excluded_dirs(vec![PathBuf::from("folder1/folder2"), PathBuf::from("target")]);
+
+        assert_eq!(iter.next(), Some(spath!(m, "folder1").unwrap()));
+        assert_eq!(iter.next(), Some(spath!(m, "folder1", "folder3").unwrap()));
+        assert_eq!(iter.next(), Some(spath!(m, "folder1", "folder3", "moulder.txt").unwrap()));
+        assert_eq!(iter.next(), None);
+    }
 }
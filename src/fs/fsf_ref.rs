@@ -1,6 +1,7 @@
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::hash::{Hash, Hasher};
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 
@@ -103,10 +104,80 @@ impl FsfRef {
     }
 
     pub fn overwrite_with_str(&self, spath: &SPath, s: &str, must_exist: bool) -> Result<usize, WriteError> {
+        self.overwrite_with_bytes(spath, s.as_bytes(), must_exist)
+    }
+
+    pub fn overwrite_with_bytes(&self, spath: &SPath, bytes: &[u8], must_exist: bool) -> Result<usize, WriteError> {
+        let path = spath.relative_path();
+        self.fs.fs.blocking_overwrite_with_bytes(&path, bytes, must_exist)
+    }
+
+    /*
+    Copies file to its backup (see BackupMode) before it's overwritten. Returns whether a copy was made,
+    there's nothing to copy if file doesn't exist yet.
+     */
+    pub fn backup(&self, spath: &SPath, mode: &BackupMode) -> Result<bool, WriteError> {
+        let path = spath.relative_path();
+        let backup_path = unpack_or!(mode.backup_path(&path), Ok(false));
+        if !self.fs.fs.is_file(&path) {
+            return Ok(false);
+        }
+
+        let to_backup_error = |e: String| WriteError::BackupError(format!("{}: {}", backup_path.to_string_lossy(), e));
+
+        let bytes = self
+            .fs
+            .fs
+            .blocking_read_entire_file(&path)
+            .map_err(|e| to_backup_error(e.to_string()))?;
+        if let Some(dir) = backup_path.parent() {
+            self.fs
+                .fs
+                .blocking_create_dir_all(dir)
+                .map_err(|e| to_backup_error(e.to_string()))?;
+        }
+        self.fs
+            .fs
+            .blocking_overwrite_with_bytes(&backup_path, &bytes, false)
+            .map_err(|e| to_backup_error(e.to_string()))?;
+
+        // backups in workspace show up in its listings
+        if backup_path.is_relative() {
+            for ancestor in backup_path.ancestors().filter(|a| !a.as_os_str().is_empty()) {
+                if let Some(sp) = self.descendant_unchecked(ancestor) {
+                    self.drop_cache_of_parent(&sp);
+                }
+            }
+        }
+
+        Ok(true)
+    }
+
+    pub fn create_dir_all(&self, spath: &SPath) -> Result<(), WriteError> {
+        let path = spath.relative_path();
+        self.fs.fs.blocking_create_dir_all(&path)?;
+        self.drop_cache_of_parent(spath);
+        Ok(())
+    }
+
+    pub fn remove_file(&self, spath: &SPath) -> Result<(), WriteError> {
         let path = spath.relative_path();
-        let bytes: Vec<u8> = s.bytes().collect();
+        self.fs.fs.blocking_remove_file(&path)?;
+        self.drop_cache_of_parent(spath);
+        Ok(())
+    }
 
-        self.fs.fs.blocking_overwrite_with_bytes(&path, &bytes, must_exist)
+    // listing of dir is cached, so it has to be dropped when we add or remove an item
+    fn drop_cache_of_parent(&self, spath: &SPath) {
+        let parent = unpack_or!(spath.parent(), ());
+        match self.fs.caches.try_write() {
+            Ok(mut cache) => {
+                cache.remove(&parent);
+            }
+            Err(e) => {
+                error!("failed dropping cache, because {:?}", e);
+            }
+        }
     }
 
     pub fn blocking_list(&self, spath: &SPath) -> Result<Vec<SPath>, ListError> {
@@ -141,6 +212,16 @@ impl FsfRef {
         self.fs.fs.blocking_read_entire_file(&path)
     }
 
+    pub fn blocking_open_reader(&self, spath: &SPath) -> Result<Box<dyn Read>, ReadError> {
+        let path = spath.relative_path();
+        self.fs.fs.blocking_open_reader(&path)
+    }
+
+    pub fn stamp(&self, spath: &SPath) -> Result<FileStamp, ReadError> {
+        let path = spath.relative_path();
+        self.fs.fs.blocking_stamp(&path)
+    }
+
     pub fn is_dir(&self, spath: &SPath) -> bool {
         let path = spath.relative_path();
         self.fs.fs.is_dir(&path)
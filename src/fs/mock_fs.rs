@@ -1,6 +1,9 @@
+use std::collections::hash_map::DefaultHasher;
 use std::collections::HashMap;
 use std::fmt::{Debug, Formatter};
 use std::fs;
+use std::hash::{Hash, Hasher};
+use std::io::{Cursor, Read};
 use std::path::{Component, Path, PathBuf};
 use std::sync::RwLock;
 
@@ -117,6 +120,16 @@ impl Record {
             .unwrap_or(false)
     }
 
+    fn remove(&mut self, path: &Path) -> Option<Record> {
+        let components: Vec<Component> = path.components().collect();
+        let (last, parent) = components.split_last()?;
+
+        match self.get_mut(parent, false)? {
+            Record::File(_) => None,
+            Record::Dir(items) => items.remove(&PathBuf::new().join(last)),
+        }
+    }
+
     fn list(&self) -> Option<Vec<PathBuf>> {
         match self {
             Record::File(_) => None,
@@ -160,6 +173,8 @@ impl Record {
 pub struct MockFS {
     root_path: PathBuf,
     root_dir: RwLock<Record>,
+    // writes to these paths fail with given errors, leaving files as they were
+    write_errors: HashMap<PathBuf, WriteError>,
 }
 
 impl MockFS {
@@ -167,6 +182,7 @@ impl MockFS {
         MockFS {
             root_path: root_path.into(),
             root_dir: RwLock::new(Record::Dir(HashMap::default())),
+            write_errors: HashMap::default(),
         }
     }
 
@@ -176,6 +192,14 @@ impl MockFS {
         self
     }
 
+    /*
+    Simulates failure of writes to path (like full disk, or missing permissions).
+     */
+    pub fn with_write_error<P: AsRef<Path>>(mut self, path: P, error: WriteError) -> Self {
+        self.write_errors.insert(path.as_ref().to_path_buf(), error);
+        self
+    }
+
     pub fn with_dir<P: AsRef<Path>>(self, path: P) -> Self {
         self.add_dir(path.as_ref())
             .unwrap_or_else(|_| error!("failed creating dir in mockfs"));
@@ -199,6 +223,11 @@ impl MockFS {
     }
 
     pub fn blocking_overwrite_with_bytes(&self, path: &Path, bytes: &[u8], must_exist: bool) -> Result<usize, WriteError> {
+        if let Some(error) = self.write_errors.get(path) {
+            debug!("simulating failed write to [{:?}]: {}", path, error);
+            return Err(error.clone());
+        }
+
         let comp: Vec<_> = path.components().collect();
 
         if let Some(record) = self.root_dir.try_read().unwrap().get(&comp) {
@@ -230,6 +259,7 @@ impl MockFS {
         Ok(MockFS {
             root_path: path.as_ref().to_path_buf(),
             root_dir: RwLock::new(root),
+            write_errors: HashMap::default(),
         })
     }
 }
@@ -257,6 +287,11 @@ impl FilesystemFront for MockFS {
         }
     }
 
+    fn blocking_open_reader(&self, path: &Path) -> Result<Box<dyn Read>, ReadError> {
+        let bytes = self.blocking_read_entire_file(path)?;
+        Ok(Box::new(Cursor::new(bytes)))
+    }
+
     fn is_dir(&self, path: &Path) -> bool {
         let comp: Vec<_> = path.components().collect();
         self.root_dir.read().unwrap().get(&comp).map(|r| r.is_dir()).unwrap_or(false)
@@ -271,6 +306,19 @@ impl FilesystemFront for MockFS {
         2
     }
 
+    fn blocking_stamp(&self, path: &Path) -> Result<FileStamp, ReadError> {
+        let contents = self.blocking_read_entire_file(path)?;
+
+        let mut hasher = DefaultHasher::new();
+        contents.hash(&mut hasher);
+
+        Ok(FileStamp {
+            modified: None,
+            len: contents.len() as u64,
+            inode: hasher.finish(),
+        })
+    }
+
     fn blocking_list(&self, path: &Path) -> Result<Vec<DirEntry>, ListError> {
         if !self.exists(path) {
             return Err(ListError::PathNotFound);
@@ -329,6 +377,29 @@ impl FilesystemFront for MockFS {
         self.blocking_overwrite_with_bytes(path, s, must_exist)
     }
 
+    fn blocking_create_dir_all(&self, path: &Path) -> Result<(), WriteError> {
+        let comp: Vec<_> = path.components().collect();
+
+        match self.root_dir.try_write().unwrap().get_mut(&comp, true) {
+            Some(record) if record.is_dir() => Ok(()),
+            _ => Err(WriteError::UnmappedError(format!("{:?} is not a dir", path))),
+        }
+    }
+
+    fn blocking_remove_file(&self, path: &Path) -> Result<(), WriteError> {
+        let comp: Vec<_> = path.components().collect();
+        let mut root_dir = self.root_dir.try_write().unwrap();
+
+        match root_dir.get(&comp) {
+            None => Err(WriteError::FileNotFound),
+            Some(record) if record.is_dir() => Err(WriteError::NotAFile),
+            Some(_) => {
+                root_dir.remove(path);
+                Ok(())
+            }
+        }
+    }
+
     fn to_fsf(self) -> FsfRef {
         FsfRef::new(self)
     }
@@ -406,4 +477,93 @@ mod tests {
 
         assert_eq!(read_content, new_content)
     }
+
+    #[test]
+    fn mock_create_dir_and_remove_file() {
+        let mockfs = MockFS::new("/tmp").with_file("folder1/file1.txt", "some text").to_fsf();
+
+        let dir = mockfs.descendant_unchecked("folder2/nested").unwrap();
+        assert!(dir.create_dir_all().is_ok());
+        assert!(dir.create_dir_all().is_ok());
+        assert!(dir.is_dir());
+
+        let file = mockfs.descendant_checked("folder1/file1.txt").unwrap();
+        assert_eq!(
+            mockfs.root().descendant_unchecked("folder1").unwrap().remove_file(),
+            Err(WriteError::NotAFile)
+        );
+        assert!(file.remove_file().is_ok());
+        assert!(!file.exists());
+        assert_eq!(file.remove_file(), Err(WriteError::FileNotFound));
+    }
+
+    #[test]
+    fn mock_stamp_follows_contents() {
+        let mockfs = MockFS::new("/tmp").with_file("file.txt", "some text").to_fsf();
+        let file = mockfs.descendant_checked("file.txt").unwrap();
+
+        let stamp = file.stamp().unwrap();
+        assert_eq!(file.stamp(), Ok(stamp));
+
+        file.overwrite_with_str("other text", true).unwrap();
+        assert_ne!(file.stamp().unwrap(), stamp);
+
+        file.overwrite_with_str("some text", true).unwrap();
+        assert_eq!(file.stamp(), Ok(stamp));
+
+        assert_eq!(mockfs.root().stamp(), Err(ReadError::NotAFilePath));
+        assert_eq!(
+            mockfs.descendant_unchecked("gone.txt").unwrap().stamp(),
+            Err(ReadError::FileNotFound)
+        );
+    }
+
+    #[test]
+    fn simulated_write_errors() {
+        let mockfs = MockFS::new("/tmp")
+            .with_file("full.txt", "some text")
+            .with_write_error("full.txt", WriteError::DiskFull)
+            .with_file("main.rs", "fn main() {}")
+            .with_write_error("main.rs~", WriteError::PermissionDenied)
+            .to_fsf();
+
+        let full = mockfs.descendant_checked("full.txt").unwrap();
+        assert_eq!(full.overwrite_with_str("other text", false), Err(WriteError::DiskFull));
+        assert_eq!(full.read_entire_file_to_string().unwrap(), "some text");
+
+        // failing backup is a failure of its own
+        let main = mockfs.descendant_checked("main.rs").unwrap();
+        assert!(matches!(main.backup(&BackupMode::Tilde), Err(WriteError::BackupError(_))));
+        assert_eq!(main.backup(&BackupMode::None), Ok(false));
+    }
+
+    #[test]
+    fn backups() {
+        let mockfs = MockFS::new("/tmp").with_file("src/main.rs", "fn main() {}").to_fsf();
+        let main = mockfs.descendant_checked("src/main.rs").unwrap();
+
+        assert_eq!(main.backup(&BackupMode::Tilde), Ok(true));
+        assert_eq!(
+            mockfs
+                .descendant_checked("src/main.rs~")
+                .unwrap()
+                .read_entire_file_to_string()
+                .unwrap(),
+            "fn main() {}"
+        );
+
+        assert_eq!(main.backup(&BackupMode::Dir(PathBuf::from(".backups"))), Ok(true));
+        assert_eq!(
+            mockfs
+                .descendant_checked(".backups/src%main.rs~")
+                .unwrap()
+                .read_entire_file_to_string()
+                .unwrap(),
+            "fn main() {}"
+        );
+
+        // nothing to back up yet
+        let new = mockfs.descendant_unchecked("src/new.rs").unwrap();
+        assert_eq!(new.backup(&BackupMode::Tilde), Ok(false));
+    }
 }
@@ -1,9 +1,15 @@
+mod backup_mode;
+pub use backup_mode::BackupMode;
+
 mod dir_entry;
 pub use dir_entry::DirEntry;
 
 // TODO(XXX): remove
 mod file_attrs;
 
+mod file_stamp;
+pub use file_stamp::FileStamp;
+
 mod filesystem_front;
 pub use filesystem_front::FilesystemFront;
 
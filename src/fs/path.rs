@@ -2,6 +2,7 @@ use std::borrow::Cow;
 use std::cmp::Ordering;
 use std::fmt::{Debug, Display, Formatter};
 use std::hash::{Hash, Hasher};
+use std::io::Read;
 use std::path::{Path, PathBuf};
 use std::sync::Arc;
 
@@ -123,6 +124,11 @@ impl SPath {
         fsf.blocking_read_entire_file(self)
     }
 
+    pub fn open_reader(&self) -> Result<Box<dyn Read>, ReadError> {
+        let fsf = self.fsf();
+        fsf.blocking_open_reader(self)
+    }
+
     pub fn read_entire_file_to_item<T: DeserializeOwned>(&self) -> Result<T, ReadError> {
         let bytes = self.read_entire_file()?;
         ron::de::from_bytes(&bytes).map_err(|e| e.into())
@@ -133,6 +139,15 @@ impl SPath {
         Ok(String::from_utf8(bytes)?)
     }
 
+    /*
+    Text as it would be kept in buffer, with format it was stored in. See FileFormat.
+     */
+    pub fn read_entire_file_to_text(&self) -> Result<(String, FileFormat), ReadError> {
+        let bytes = self.read_entire_file()?;
+        let (format, text) = FileFormat::decode(&bytes)?;
+        Ok((text, format))
+    }
+
     pub fn read_entire_file_to_rope(&self) -> Result<Rope, ReadError> {
         let bytes = self.read_entire_file()?;
         Ok(ropey::Rope::from_reader(&*bytes)?)
@@ -238,6 +253,11 @@ impl SPath {
         fsf.exists(self)
     }
 
+    pub fn stamp(&self) -> Result<FileStamp, ReadError> {
+        let fsf = self.fsf();
+        fsf.stamp(self)
+    }
+
     pub fn overwrite_with_stream(&self, stream: &mut dyn StreamingIterator<Item = [u8]>, must_exist: bool) -> Result<usize, WriteError> {
         let fsf = self.fsf();
         fsf.overwrite_with_stream(self, stream, must_exist)
@@ -249,11 +269,31 @@ impl SPath {
         fsf.overwrite_with_str(self, ss, must_exist)
     }
 
+    pub fn overwrite_with_bytes(&self, bytes: &[u8], must_exist: bool) -> Result<usize, WriteError> {
+        let fsf = self.fsf();
+        fsf.overwrite_with_bytes(self, bytes, must_exist)
+    }
+
+    pub fn backup(&self, mode: &BackupMode) -> Result<bool, WriteError> {
+        let fsf = self.fsf();
+        fsf.backup(self, mode)
+    }
+
     pub fn overwrite_with_ron<T: Serialize>(&self, item: &T, must_exist: bool) -> Result<usize, WriteOrSerError> {
         let ron_item = ron::ser::to_string_pretty::<T>(item, ron::ser::PrettyConfig::default())?;
         self.overwrite_with_str(&ron_item, must_exist).map_err(|e| e.into())
     }
 
+    pub fn create_dir_all(&self) -> Result<(), WriteError> {
+        let fsf = self.fsf();
+        fsf.create_dir_all(self)
+    }
+
+    pub fn remove_file(&self) -> Result<(), WriteError> {
+        let fsf = self.fsf();
+        fsf.remove_file(self)
+    }
+
     pub fn blocking_list(&self) -> Result<Vec<SPath>, ListError> {
         let fsf = self.fsf();
         fsf.blocking_list(self)
@@ -10,6 +10,8 @@ pub enum ReadError {
     // TODO separate?
     DeError(String),
     Utf8Error(std::str::Utf8Error),
+    // odd number of bytes or unpaired surrogates
+    Utf16Error,
     UnmappedError(String),
 }
 
@@ -1,19 +1,184 @@
 use std::fmt::{Debug, Formatter};
-use std::io::Write;
+use std::fs::{File, Metadata, OpenOptions};
+use std::io::{BufWriter, ErrorKind, Read, Write};
 use std::path::{Path, PathBuf};
 
-use log::{error, warn};
+use log::{debug, error, warn};
 use streaming_iterator::StreamingIterator;
 
 use crate::*;
 
 pub struct RealFS {
     root_path: PathBuf,
+    // whether writes wait until data reaches the disk
+    fsync: bool,
 }
 
 impl RealFS {
     pub fn new(root_path: PathBuf) -> RealFS {
-        RealFS { root_path }
+        RealFS { root_path, fsync: false }
+    }
+
+    pub fn with_fsync(self, fsync: bool) -> Self {
+        RealFS { fsync, ..self }
+    }
+
+    /*
+    New contents go to a temporary file in the same directory, that then replaces the file with rename,
+    so a failed or interrupted write never leaves it half written. Permissions and owner of previous
+    version are kept. If path is a symlink, its target is replaced (or created), not the link.
+
+    Rename would break hard links, so hard linked files are written in place. So are files in
+    directories we can't create files in, as there's no other way.
+     */
+    fn overwrite(
+        &self,
+        path: &Path,
+        must_exist: bool,
+        write: &mut dyn FnMut(&mut File) -> std::io::Result<usize>,
+    ) -> Result<usize, WriteError> {
+        let target = follow_symlinks(self.root_path.join(path))?;
+
+        let existing = match std::fs::metadata(&target) {
+            Ok(metadata) => Some(metadata),
+            Err(e) if e.kind() == ErrorKind::NotFound => None,
+            Err(e) => return Err(e.into()),
+        };
+        match &existing {
+            None if must_exist => return Err(WriteError::FileNotFound),
+            Some(metadata) if !metadata.is_file() => return Err(WriteError::NotAFile),
+            Some(metadata) if is_hard_linked(metadata) => {
+                debug!("{:?} is hard linked, overwriting it in place", target);
+                return self.overwrite_in_place(&target, write);
+            }
+            _ => {}
+        }
+
+        let dir = target.parent().unwrap_or(Path::new("."));
+        let file_name = target
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let temp_path = dir.join(format!(".{}.{}.tmp", file_name, std::process::id()));
+
+        let mut temp = match File::create(&temp_path) {
+            Ok(temp) => temp,
+            Err(e) if e.kind() == ErrorKind::PermissionDenied && existing.is_some() => {
+                warn!("can't create files next to {:?}, overwriting it in place", target);
+                return self.overwrite_in_place(&target, write);
+            }
+            Err(e) => return Err(WriteError::from_io_or(e, WriteError::TempFileError)),
+        };
+
+        let result = self.replace_with_temp(&mut temp, &temp_path, &target, existing.as_ref(), write);
+        if result.is_err() {
+            if let Err(e) = std::fs::remove_file(&temp_path) {
+                warn!("failed to remove temporary file {:?}: {}", temp_path, e);
+            }
+        }
+        result
+    }
+
+    fn replace_with_temp(
+        &self,
+        temp: &mut File,
+        temp_path: &Path,
+        target: &Path,
+        existing: Option<&Metadata>,
+        write: &mut dyn FnMut(&mut File) -> std::io::Result<usize>,
+    ) -> Result<usize, WriteError> {
+        let bytes_written = write(temp).map_err(|e| WriteError::from_io_or(e, WriteError::TempFileError))?;
+        if self.fsync {
+            temp.sync_all().map_err(|e| WriteError::from_io_or(e, WriteError::SyncError))?;
+        }
+
+        if let Some(metadata) = existing {
+            // owner first, changing it can drop some permission bits
+            #[cfg(unix)]
+            keep_owner(temp, metadata, target);
+
+            std::fs::set_permissions(temp_path, metadata.permissions())
+                .map_err(|e| WriteError::from_io_or(e, WriteError::TempFileError))?;
+        }
+
+        std::fs::rename(temp_path, target).map_err(|e| WriteError::from_io_or(e, WriteError::RenameError))?;
+
+        if self.fsync {
+            // so the rename itself is not lost
+            let dir = target.parent().unwrap_or(Path::new("."));
+            File::open(dir)
+                .and_then(|dir| dir.sync_all())
+                .map_err(|e| WriteError::from_io_or(e, WriteError::SyncError))?;
+        }
+
+        Ok(bytes_written)
+    }
+
+    fn overwrite_in_place(&self, target: &Path, write: &mut dyn FnMut(&mut File) -> std::io::Result<usize>) -> Result<usize, WriteError> {
+        let mut file = OpenOptions::new().write(true).truncate(true).open(target)?;
+        let bytes_written = write(&mut file)?;
+        if self.fsync {
+            file.sync_all().map_err(|e| WriteError::from_io_or(e, WriteError::SyncError))?;
+        }
+        Ok(bytes_written)
+    }
+}
+
+// links are followed at most this many times, like in Linux
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/*
+Unlike canonicalize, this doesn't fail on a dangling link, it returns the path the link points to, so
+writing creates the target instead of replacing the link.
+ */
+fn follow_symlinks(mut path: PathBuf) -> Result<PathBuf, WriteError> {
+    for _ in 0..MAX_SYMLINK_HOPS {
+        match std::fs::symlink_metadata(&path) {
+            Ok(metadata) if metadata.file_type().is_symlink() => {
+                let link = std::fs::read_link(&path)?;
+                path = match path.parent() {
+                    Some(parent) => parent.join(link),
+                    None => link,
+                };
+            }
+            Ok(_) => return Ok(path),
+            Err(e) if e.kind() == ErrorKind::NotFound => return Ok(path),
+            Err(e) => return Err(e.into()),
+        }
+    }
+
+    Err(WriteError::UnmappedError(format!(
+        "too many levels of symbolic links in {:?}",
+        path
+    )))
+}
+
+#[cfg(unix)]
+fn is_hard_linked(metadata: &Metadata) -> bool {
+    std::os::unix::fs::MetadataExt::nlink(metadata) > 1
+}
+
+#[cfg(not(unix))]
+fn is_hard_linked(_metadata: &Metadata) -> bool {
+    false
+}
+
+/*
+Only root can give files away, so mostly this works when file already belongs to us (and then there's
+nothing to do), or to a group we are in.
+ */
+#[cfg(unix)]
+fn keep_owner(temp: &File, metadata: &Metadata, target: &Path) {
+    use std::os::unix::fs::MetadataExt;
+
+    let (uid, gid) = (metadata.uid(), metadata.gid());
+    match temp.metadata() {
+        Ok(temp_metadata) if temp_metadata.uid() == uid && temp_metadata.gid() == gid => {}
+        _ => {
+            if let Err(e) = std::os::unix::fs::fchown(temp, Some(uid), Some(gid)) {
+                warn!("failed to keep owner of {:?}: {}", target, e);
+            }
+        }
     }
 }
 
@@ -33,6 +198,12 @@ impl FilesystemFront for RealFS {
         std::fs::read(&full_path).map_err(|e| e.into())
     }
 
+    fn blocking_open_reader(&self, path: &Path) -> Result<Box<dyn Read>, ReadError> {
+        let full_path = self.root_path.join(path);
+        let file = File::open(&full_path)?;
+        Ok(Box::new(file))
+    }
+
     fn is_dir(&self, path: &Path) -> bool {
         let full_path = self.root_path.join(path);
         full_path.is_dir()
@@ -47,6 +218,25 @@ impl FilesystemFront for RealFS {
         1
     }
 
+    fn blocking_stamp(&self, path: &Path) -> Result<FileStamp, ReadError> {
+        let full_path = self.root_path.join(path);
+        let metadata = std::fs::metadata(&full_path)?;
+        if !metadata.is_file() {
+            return Err(ReadError::NotAFilePath);
+        }
+
+        #[cfg(unix)]
+        let inode = std::os::unix::fs::MetadataExt::ino(&metadata);
+        #[cfg(not(unix))]
+        let inode = 0;
+
+        Ok(FileStamp {
+            modified: metadata.modified().ok(),
+            len: metadata.len(),
+            inode,
+        })
+    }
+
     fn blocking_list(&self, path: &Path) -> Result<Vec<DirEntry>, ListError> {
         let full_path = self.root_path.join(path);
         let readdir = std::fs::read_dir(&full_path)?;
@@ -71,7 +261,8 @@ impl FilesystemFront for RealFS {
     }
 
     fn exists(&self, path: &Path) -> bool {
-        path.exists()
+        let full_path = self.root_path.join(path);
+        full_path.exists()
     }
 
     fn blocking_overwrite_with_stream(
@@ -80,38 +271,116 @@ impl FilesystemFront for RealFS {
         stream: &mut dyn StreamingIterator<Item = [u8]>,
         must_exist: bool,
     ) -> Result<usize, WriteError> {
-        if must_exist {
-            if path.exists() {
-                return Err(WriteError::FileNotFound);
+        self.overwrite(path, must_exist, &mut |file| {
+            let mut writer = BufWriter::new(file);
+            let mut bytes_written: usize = 0;
+            while let Some(bytes) = stream.next() {
+                writer.write_all(bytes)?;
+                bytes_written += bytes.len();
             }
-        }
 
-        let mut file = std::fs::File::open(path)?;
+            writer.flush()?;
+            Ok(bytes_written)
+        })
+    }
 
-        let mut bytes_written: usize = 0;
-        while let Some(bytes) = stream.next() {
-            let num_bytes = file.write(bytes)?;
-            if num_bytes != bytes.len() {
-                error!("unexpected number of bytes written");
-                break;
-            }
-            bytes_written += num_bytes;
-        }
+    fn blocking_overwrite_with_bytes(&self, path: &Path, s: &[u8], must_exist: bool) -> Result<usize, WriteError> {
+        self.overwrite(path, must_exist, &mut |file| {
+            file.write_all(s)?;
+            Ok(s.len())
+        })
+    }
 
-        file.flush()?;
-        Ok(bytes_written)
+    fn blocking_create_dir_all(&self, path: &Path) -> Result<(), WriteError> {
+        let full_path = self.root_path.join(path);
+        std::fs::create_dir_all(&full_path).map_err(|e| e.into())
     }
 
-    fn blocking_overwrite_with_bytes(&self, path: &Path, s: &[u8], must_exist: bool) -> Result<usize, WriteError> {
-        if must_exist && path.exists() {
+    fn blocking_remove_file(&self, path: &Path) -> Result<(), WriteError> {
+        let full_path = self.root_path.join(path);
+        if !full_path.exists() {
             return Err(WriteError::FileNotFound);
         }
+        if !full_path.is_file() {
+            return Err(WriteError::NotAFile);
+        }
 
-        std::fs::write(path, s)?;
-        Ok(s.len())
+        std::fs::remove_file(&full_path).map_err(|e| e.into())
     }
 
     fn to_fsf(self) -> FsfRef {
         FsfRef::new(self)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn scratch_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("bernardo_real_fs_{}_{}", name, std::process::id()));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn overwrite_replaces_file_and_leaves_nothing_behind() {
+        let dir = scratch_dir("overwrite");
+        let fs = RealFS::new(dir.clone()).with_fsync(true);
+
+        assert_eq!(
+            fs.blocking_overwrite_with_bytes(Path::new("file.txt"), b"abc", true),
+            Err(WriteError::FileNotFound)
+        );
+        assert_eq!(fs.blocking_overwrite_with_bytes(Path::new("file.txt"), b"abc", false), Ok(3));
+        assert!(fs.exists(Path::new("file.txt")));
+        assert_eq!(fs.blocking_overwrite_with_bytes(Path::new("file.txt"), b"xy", true), Ok(2));
+        assert_eq!(std::fs::read(dir.join("file.txt")).unwrap(), b"xy".to_vec());
+
+        assert_eq!(
+            fs.blocking_overwrite_with_bytes(Path::new(""), b"xy", false),
+            Err(WriteError::NotAFile)
+        );
+
+        assert_eq!(fs.blocking_list(Path::new("")).unwrap(), vec![DirEntry::new("file.txt")]);
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn overwrite_keeps_permissions_and_symlinks() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = scratch_dir("permissions");
+        let fs = RealFS::new(dir.clone());
+
+        std::fs::write(dir.join("script.sh"), "echo a").unwrap();
+        std::fs::set_permissions(dir.join("script.sh"), std::fs::Permissions::from_mode(0o750)).unwrap();
+        std::os::unix::fs::symlink("script.sh", dir.join("link.sh")).unwrap();
+
+        assert_eq!(fs.blocking_overwrite_with_bytes(Path::new("link.sh"), b"echo b", true), Ok(6));
+
+        assert!(std::fs::symlink_metadata(dir.join("link.sh")).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read(dir.join("script.sh")).unwrap(), b"echo b".to_vec());
+        let mode = std::fs::metadata(dir.join("script.sh")).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o750);
+
+        // dangling link gets its target created, link stays
+        std::os::unix::fs::symlink("new.sh", dir.join("dangling.sh")).unwrap();
+        assert_eq!(
+            fs.blocking_overwrite_with_bytes(Path::new("dangling.sh"), b"echo d", true),
+            Err(WriteError::FileNotFound)
+        );
+        assert_eq!(fs.blocking_overwrite_with_bytes(Path::new("dangling.sh"), b"echo d", false), Ok(6));
+        assert!(std::fs::symlink_metadata(dir.join("dangling.sh")).unwrap().file_type().is_symlink());
+        assert_eq!(std::fs::read(dir.join("new.sh")).unwrap(), b"echo d".to_vec());
+
+        // hard links stay linked
+        std::fs::hard_link(dir.join("script.sh"), dir.join("hard.sh")).unwrap();
+        assert_eq!(fs.blocking_overwrite_with_bytes(Path::new("hard.sh"), b"echo c", true), Ok(6));
+        assert_eq!(std::fs::read(dir.join("script.sh")).unwrap(), b"echo c".to_vec());
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
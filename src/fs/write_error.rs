@@ -1,15 +1,56 @@
-use std::io::Error;
+use std::fmt::{Display, Formatter};
+use std::io::{Error, ErrorKind};
 
-#[derive(Debug, PartialEq, Eq)]
+#[derive(Clone, Debug, PartialEq, Eq)]
 pub enum WriteError {
     NotAFile,
     FileNotFound,
+    PermissionDenied,
+    DiskFull,
+    // copy of previous version of file could not be made, file was left as it was
+    BackupError(String),
+    // new contents could not be written next to the file, file was left as it was
+    TempFileError(String),
+    // new contents were written, but could not replace the file, file was left as it was
+    RenameError(String),
+    // file was replaced, but it's not known if it reached the disk
+    SyncError(String),
     UnmappedError(String),
 }
 
+impl WriteError {
+    /*
+    Errors that have variants of their own are mapped to them, the rest become the one given.
+     */
+    pub fn from_io_or(e: Error, other: fn(String) -> WriteError) -> WriteError {
+        match e.kind() {
+            ErrorKind::NotFound => WriteError::FileNotFound,
+            ErrorKind::PermissionDenied => WriteError::PermissionDenied,
+            ErrorKind::StorageFull => WriteError::DiskFull,
+            _ => other(e.to_string()),
+        }
+    }
+}
+
+impl Display for WriteError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            WriteError::NotAFile => write!(f, "not a file"),
+            WriteError::FileNotFound => write!(f, "file not found"),
+            WriteError::PermissionDenied => write!(f, "permission denied"),
+            WriteError::DiskFull => write!(f, "no space left on disk"),
+            WriteError::BackupError(e) => write!(f, "failed to make backup: {}", e),
+            WriteError::TempFileError(e) => write!(f, "failed to write temporary file: {}", e),
+            WriteError::RenameError(e) => write!(f, "failed to replace file: {}", e),
+            WriteError::SyncError(e) => write!(f, "failed to sync file to disk: {}", e),
+            WriteError::UnmappedError(e) => write!(f, "{}", e),
+        }
+    }
+}
+
 impl From<std::io::Error> for WriteError {
     fn from(e: Error) -> Self {
-        WriteError::UnmappedError(e.to_string())
+        WriteError::from_io_or(e, WriteError::UnmappedError)
     }
 }
 
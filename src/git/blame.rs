@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use log::warn;
+
+use crate::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlameCommit {
+    pub hash: String,
+    pub author: String,
+    // seconds since epoch
+    pub author_time: i64,
+    pub summary: String,
+}
+
+impl BlameCommit {
+    // git reports lines that are changed in working tree as commited by a commit of zeroes
+    pub fn is_uncommitted(&self) -> bool {
+        self.hash.chars().all(|c| c == '0')
+    }
+
+    pub fn short_hash(&self) -> &str {
+        &self.hash[..self.hash.len().min(8)]
+    }
+
+    pub fn label_text(&self) -> String {
+        if self.is_uncommitted() {
+            return "not committed yet".to_string();
+        }
+
+        format!("{}, {} ({})", self.author, format_date(self.author_time), self.short_hash())
+    }
+}
+
+/*
+One entry per line of blamed file. Commit info is shared between lines, so it's indexed.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct BlameLine {
+    pub commit_idx: usize,
+    pub text: String,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Blame {
+    pub commits: Vec<BlameCommit>,
+    pub lines: Vec<BlameLine>,
+}
+
+impl Blame {
+    pub fn commit_for_line(&self, line_idx: usize) -> Option<&BlameCommit> {
+        self.lines.get(line_idx).and_then(|l| self.commits.get(l.commit_idx))
+    }
+
+    /*
+    Text of the file as it was blamed, lines of porcelain output carry it.
+     */
+    pub fn text(&self) -> String {
+        let mut result = String::new();
+        for line in self.lines.iter() {
+            result.push_str(&line.text);
+            result.push('\n');
+        }
+        result
+    }
+
+    /*
+    Parses output of "git blame --porcelain". Each line is preceded by a header "<hash> <orig line> <final line> [<group size>]",
+    first occurrence of a commit is followed by its details (author, summary etc.), then comes the line itself,
+    prefixed with a tab.
+     */
+    pub fn from_porcelain(output: &str) -> Result<Blame, GitError> {
+        let mut commits: Vec<BlameCommit> = Vec::new();
+        let mut commit_idxs: HashMap<String, usize> = HashMap::new();
+        let mut lines: Vec<BlameLine> = Vec::new();
+        let mut current: Option<usize> = None;
+
+        for line in output.lines() {
+            if let Some(text) = line.strip_prefix('\t') {
+                let commit_idx = unpack_or_e!(
+                    current.take(),
+                    Err(GitError::UnexpectedOutput("blame line without header".to_string())),
+                    "blame line without header"
+                );
+                lines.push(BlameLine {
+                    commit_idx,
+                    text: text.to_string(),
+                });
+                continue;
+            }
+
+            match current {
+                None => {
+                    let hash = unpack_or_e!(
+                        line.split(' ').next().filter(|h| h.len() == 40),
+                        Err(GitError::UnexpectedOutput(line.to_string())),
+                        "unexpected blame header [{}]",
+                        line
+                    );
+
+                    let idx = *commit_idxs.entry(hash.to_string()).or_insert_with(|| {
+                        commits.push(BlameCommit {
+                            hash: hash.to_string(),
+                            author: String::new(),
+                            author_time: 0,
+                            summary: String::new(),
+                        });
+                        commits.len() - 1
+                    });
+                    current = Some(idx);
+                }
+                Some(idx) => {
+                    let (key, value) = line.split_once(' ').unwrap_or((line, ""));
+                    let commit = &mut commits[idx];
+                    match key {
+                        "author" => commit.author = value.to_string(),
+                        "author-time" => {
+                            commit.author_time = value.parse().unwrap_or_else(|_| {
+                                warn!("failed to parse author-time [{}]", value);
+                                0
+                            })
+                        }
+                        "summary" => commit.summary = value.to_string(),
+                        _ => {}
+                    }
+                }
+            }
+        }
+
+        Ok(Blame { commits, lines })
+    }
+}
+
+/*
+YYYY-MM-DD in UTC. It's Howard Hinnant's days_from_civil reversed, I don't want a whole date crate for
+one label.
+ */
+pub fn format_date(seconds_since_epoch: i64) -> String {
+    let days = seconds_since_epoch.div_euclid(86400);
+    let z = days + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+
+    format!("{:04}-{:02}-{:02}", year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_porcelain() {
+        let output = "\
+1111111111111111111111111111111111111111 1 1 2
+author Alice
+author-mail <alice@example.com>
+author-time 1700000000
+author-tz +0100
+summary First commit
+filename src/main.rs
+\tfn main() {
+1111111111111111111111111111111111111111 2 2
+\t    let a = 1;
+0000000000000000000000000000000000000000 3 3 1
+author Not Committed Yet
+author-time 1800000000
+summary Version of src/main.rs from src/main.rs
+filename src/main.rs
+\t}
+";
+        let blame = Blame::from_porcelain(output).unwrap();
+
+        assert_eq!(blame.lines.len(), 3);
+        assert_eq!(blame.commits.len(), 2);
+        assert_eq!(blame.text(), "fn main() {\n    let a = 1;\n}\n");
+
+        let first = blame.commit_for_line(1).unwrap();
+        assert_eq!(first.author, "Alice");
+        assert_eq!(first.summary, "First commit");
+        assert_eq!(first.label_text(), "Alice, 2023-11-14 (11111111)");
+
+        assert!(blame.commit_for_line(2).unwrap().is_uncommitted());
+        assert_eq!(blame.commit_for_line(3), None);
+    }
+
+    #[test]
+    fn test_format_date() {
+        assert_eq!(format_date(0), "1970-01-01");
+        assert_eq!(format_date(951782400), "2000-02-29");
+        assert_eq!(format_date(1709251199), "2024-02-29");
+    }
+}
@@ -0,0 +1,90 @@
+use log::{debug, error};
+use similar::{DiffOp, TextDiff};
+
+use crate::*;
+
+/*
+Per-editor blame labels. Blame is run once (on toggle, and again after save), but it describes the
+file as it was on disk, so whenever buffer changes, lines are re-mapped through a diff between blamed
+text and current buffer. Lines that differ from blamed ones get no label.
+ */
+pub struct BlameAnnotations {
+    blame: Blame,
+    // current line idx -> blamed line idx
+    line_map: Vec<Option<usize>>,
+    labels: Vec<Label>,
+    computed_for_version: Option<u64>,
+}
+
+impl BlameAnnotations {
+    pub fn load(git: &GitProviderRef, path: &SPath) -> Result<Self, GitError> {
+        let blame = git.blame(path)?;
+        debug!("blame for {}: {} lines", path, blame.lines.len());
+
+        Ok(BlameAnnotations {
+            blame,
+            line_map: Vec::new(),
+            labels: Vec::new(),
+            computed_for_version: None,
+        })
+    }
+
+    pub fn update(&mut self, buffer: &BufferState) {
+        if self.computed_for_version == Some(buffer.content_version()) {
+            return;
+        }
+        self.computed_for_version = Some(buffer.content_version());
+
+        self.line_map = map_lines(&self.blame.text(), &buffer.to_string(), buffer.len_lines());
+        self.labels.clear();
+
+        for (line_idx, blamed_idx_op) in self.line_map.iter().enumerate() {
+            if let Some(commit) = blamed_idx_op.and_then(|idx| self.blame.commit_for_line(idx)) {
+                self.labels.push(Label::new(
+                    LabelPos::LineAfter { line_no_1b: line_idx + 1 },
+                    LabelStyle::TypeAnnotation,
+                    Box::new(format!("  {}", commit.label_text())),
+                ));
+            }
+        }
+    }
+
+    pub fn labels(&self) -> impl Iterator<Item = &Label> {
+        self.labels.iter()
+    }
+
+    pub fn commit_at(&self, line_idx: usize) -> Option<&BlameCommit> {
+        let blamed_idx = self.line_map.get(line_idx).copied().flatten()?;
+        self.blame.commit_for_line(blamed_idx).filter(|c| !c.is_uncommitted())
+    }
+}
+
+fn map_lines(old: &str, new: &str, new_lines_count: usize) -> Vec<Option<usize>> {
+    let mut result: Vec<Option<usize>> = vec![None; new_lines_count];
+
+    for op in TextDiff::from_lines(old, new).ops() {
+        if let DiffOp::Equal { old_index, new_index, len } = *op {
+            for i in 0..len {
+                match result.get_mut(new_index + i) {
+                    Some(slot) => *slot = Some(old_index + i),
+                    None => error!("diff points beyond buffer, line {}", new_index + i),
+                }
+            }
+        }
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_map_lines() {
+        let old = "a\nb\nc\nd\n";
+        let new = "x\na\nc\nd2\n";
+
+        assert_eq!(map_lines(old, new, 5), vec![None, Some(0), Some(2), None, None]);
+    }
+}
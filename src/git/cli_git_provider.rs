@@ -0,0 +1,123 @@
+use std::path::{Path, PathBuf};
+use std::process::{Command, Output, Stdio};
+
+use log::{debug, warn};
+
+use crate::*;
+
+/*
+Talks to the local git binary. Workspace does not have to be the root of repository, so we remember
+the "prefix" (path from repository root to workspace root) to build paths git understands.
+ */
+#[derive(Debug)]
+pub struct CliGitProvider {
+    workspace_root: PathBuf,
+    prefix: PathBuf,
+}
+
+impl CliGitProvider {
+    /*
+    Returns None if git is not available or workspace is not within a git repository.
+     */
+    pub fn discover(workspace_root: &Path) -> Option<Self> {
+        let output = match Self::run_in(workspace_root, &["rev-parse", "--show-prefix"]) {
+            Ok(output) => output,
+            Err(e) => {
+                debug!("git not available: {}", e);
+                return None;
+            }
+        };
+
+        if !output.status.success() {
+            debug!("{:?} is not in a git repository", workspace_root);
+            return None;
+        }
+
+        let prefix = unpack_or_e!(String::from_utf8(output.stdout).ok(), None, "non-utf8 git prefix");
+
+        Some(CliGitProvider {
+            workspace_root: workspace_root.to_path_buf(),
+            prefix: PathBuf::from(prefix.trim()),
+        })
+    }
+
+    fn run_in(dir: &Path, args: &[&str]) -> Result<Output, GitError> {
+        let output = Command::new("git").arg("-C").arg(dir).args(args).stdin(Stdio::null()).output()?;
+        Ok(output)
+    }
+
+    fn repo_path(&self, path: &SPath) -> String {
+        // git wants forward slashes, no matter the platform
+        let path = self.prefix.join(path.relative_path());
+        path.components()
+            .map(|c| c.as_os_str().to_string_lossy())
+            .collect::<Vec<_>>()
+            .join("/")
+    }
+}
+
+impl GitProvider for CliGitProvider {
+    fn head_version(&self, path: &SPath) -> Result<Option<String>, GitError> {
+        let object = format!("HEAD:{}", self.repo_path(path));
+        let output = Self::run_in(&self.workspace_root, &["show", &object])?;
+
+        if !output.status.success() {
+            // that's most likely untracked file or repository without commits, not an error
+            debug!("no HEAD version of {}: {}", path, String::from_utf8_lossy(&output.stderr).trim());
+            return Ok(None);
+        }
+
+        match String::from_utf8(output.stdout) {
+            Ok(s) => Ok(Some(s)),
+            Err(_) => {
+                warn!("HEAD version of {} is not valid utf8", path);
+                Err(GitError::NonUtf8Output)
+            }
+        }
+    }
+
+    fn status(&self) -> Result<GitStatus, GitError> {
+        // "." limits the output to workspace
+        let output = Self::run_in(
+            &self.workspace_root,
+            &[
+                "status",
+                "--porcelain=v1",
+                "-z",
+                "--ignored=matching",
+                "--untracked-files=normal",
+                "--",
+                ".",
+            ],
+        )?;
+
+        if !output.status.success() {
+            return Err(GitError::CommandFailed(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+
+        let stdout = String::from_utf8(output.stdout).map_err(|_| GitError::NonUtf8Output)?;
+        Ok(GitStatus::from_porcelain(&stdout, &self.prefix))
+    }
+
+    fn blame(&self, path: &SPath) -> Result<Blame, GitError> {
+        let repo_path = self.repo_path(path);
+        let output = Self::run_in(&self.workspace_root, &["blame", "--porcelain", "--", &repo_path])?;
+
+        if !output.status.success() {
+            return Err(GitError::CommandFailed(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+
+        let stdout = String::from_utf8(output.stdout).map_err(|_| GitError::NonUtf8Output)?;
+        Blame::from_porcelain(&stdout)
+    }
+
+    fn show_commit(&self, hash: &str) -> Result<String, GitError> {
+        let output = Self::run_in(&self.workspace_root, &["show", "--no-color", hash])?;
+
+        if !output.status.success() {
+            return Err(GitError::CommandFailed(String::from_utf8_lossy(&output.stderr).trim().to_string()));
+        }
+
+        String::from_utf8(output.stdout).map_err(|_| GitError::NonUtf8Output)
+    }
+}
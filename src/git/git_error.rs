@@ -0,0 +1,26 @@
+use std::fmt::{Display, Formatter};
+
+#[derive(Debug)]
+pub enum GitError {
+    IoError(std::io::Error),
+    CommandFailed(String),
+    NonUtf8Output,
+    UnexpectedOutput(String),
+}
+
+impl Display for GitError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            GitError::IoError(e) => write!(f, "failed to run git: {}", e),
+            GitError::CommandFailed(stderr) => write!(f, "git failed: {}", stderr),
+            GitError::NonUtf8Output => write!(f, "git returned non-utf8 output"),
+            GitError::UnexpectedOutput(line) => write!(f, "unexpected git output: {}", line),
+        }
+    }
+}
+
+impl From<std::io::Error> for GitError {
+    fn from(e: std::io::Error) -> Self {
+        GitError::IoError(e)
+    }
+}
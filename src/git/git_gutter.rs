@@ -0,0 +1,183 @@
+use std::ops::Range;
+
+use log::{debug, error};
+
+use crate::*;
+
+/*
+Per-editor state of "what changed since HEAD". Base (HEAD version) is loaded once per path, and
+re-loaded only on invalidate_base (after save, as HEAD might have moved) - we don't want to spawn git on
+every keystroke. Hunks are recomputed whenever buffer contents change.
+ */
+#[derive(Debug, Default)]
+pub struct GitGutter {
+    loaded_for: Option<SPath>,
+    base: Option<String>,
+    hunks: Vec<DiffHunk>,
+    computed_for_version: Option<u64>,
+    lines_count: usize,
+}
+
+impl GitGutter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn invalidate_base(&mut self) {
+        self.loaded_for = None;
+        self.computed_for_version = None;
+    }
+
+    pub fn update(&mut self, git: Option<&GitProviderRef>, buffer: &BufferState) {
+        let git = match git {
+            Some(git) => git,
+            None => {
+                self.hunks.clear();
+                return;
+            }
+        };
+
+        let path = match buffer.get_path() {
+            Some(path) => path,
+            None => {
+                self.loaded_for = None;
+                self.base = None;
+                self.hunks.clear();
+                return;
+            }
+        };
+
+        if self.loaded_for.as_ref() != Some(path) {
+            self.base = match git.head_version(path) {
+                Ok(base) => base,
+                Err(e) => {
+                    error!("failed to get HEAD version of {}: {}", path, e);
+                    None
+                }
+            };
+            self.loaded_for = Some(path.clone());
+            self.computed_for_version = None;
+        }
+
+        if self.computed_for_version == Some(buffer.content_version()) {
+            return;
+        }
+
+        self.computed_for_version = Some(buffer.content_version());
+        self.lines_count = buffer.len_lines();
+        self.hunks = match self.base.as_ref() {
+            Some(base) => compute_hunks(base, &buffer.to_string()),
+            None => Vec::new(),
+        };
+
+        debug!("git gutter for {}: {} hunks", path, self.hunks.len());
+    }
+
+    pub fn hunks(&self) -> &[DiffHunk] {
+        &self.hunks
+    }
+
+    pub fn hunk_idx_at(&self, line_idx: usize) -> Option<usize> {
+        self.hunks.iter().position(|h| h.touches_line(line_idx, self.lines_count))
+    }
+
+    pub fn hunk_kind_at(&self, line_idx: usize) -> Option<HunkKind> {
+        self.hunk_idx_at(line_idx).map(|idx| self.hunks[idx].kind)
+    }
+
+    /*
+    Line where next hunk (after line_idx) begins. Wraps around.
+     */
+    pub fn next_hunk_line(&self, line_idx: usize) -> Option<usize> {
+        let current = self.hunk_idx_at(line_idx);
+        self.hunks
+            .iter()
+            .enumerate()
+            .find(|(idx, h)| Some(*idx) != current && h.new_lines.start > line_idx)
+            .map(|(_, h)| h)
+            .or_else(|| self.hunks.first())
+            .map(|h| self.marker_line(h))
+    }
+
+    /*
+    Line where previous hunk (before line_idx) begins. Wraps around.
+     */
+    pub fn prev_hunk_line(&self, line_idx: usize) -> Option<usize> {
+        let current = self.hunk_idx_at(line_idx);
+        self.hunks
+            .iter()
+            .enumerate()
+            .rev()
+            .find(|(idx, h)| Some(*idx) != current && h.new_lines.start < line_idx)
+            .map(|(_, h)| h)
+            .or_else(|| self.hunks.last())
+            .map(|h| self.marker_line(h))
+    }
+
+    fn marker_line(&self, hunk: &DiffHunk) -> usize {
+        hunk.new_lines.start.min(self.lines_count.saturating_sub(1))
+    }
+
+    /*
+    Returns char range of buffer to substitute and text from HEAD to put there, so that hunk touching
+    line_idx is reverted.
+     */
+    pub fn revert_at(&self, line_idx: usize, buffer: &BufferState) -> Option<(Range<usize>, String)> {
+        let hunk = &self.hunks[self.hunk_idx_at(line_idx)?];
+        let base = self.base.as_ref()?;
+
+        let line_start = |line: usize| -> usize {
+            if line >= buffer.len_lines() {
+                buffer.len_chars()
+            } else {
+                buffer.line_to_char(line).unwrap_or(buffer.len_chars())
+            }
+        };
+
+        let char_range = line_start(hunk.new_lines.start)..line_start(hunk.new_lines.end);
+        let old_text: String = base
+            .split_inclusive('\n')
+            .skip(hunk.old_lines.start)
+            .take(hunk.old_lines.len())
+            .collect();
+
+        Some((char_range, old_text))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn buffer_with(text: &str) -> BufferState {
+        let fsf = MockFS::new("/tmp").with_file("a.txt", "").to_fsf();
+        let path = fsf.descendant_checked("a.txt").unwrap();
+        let mut buffer = BufferState::full(None, DocumentIdentifier::new_unique()).with_text(text);
+        buffer.set_file_path(Some(path));
+        buffer
+    }
+
+    #[test]
+    fn test_navigation_and_revert() {
+        let git: GitProviderRef = std::sync::Arc::new(Box::new(MockGitProvider::new().with_head_version("a.txt", "a\nb\nc\nd\n")));
+        let buffer = buffer_with("a\nB\nc\nd\nx\n");
+
+        let mut gutter = GitGutter::new();
+        gutter.update(Some(&git), &buffer);
+
+        assert_eq!(gutter.hunks().len(), 2);
+        assert_eq!(gutter.hunk_kind_at(0), None);
+        assert_eq!(gutter.hunk_kind_at(1), Some(HunkKind::Modified));
+        assert_eq!(gutter.hunk_kind_at(4), Some(HunkKind::Added));
+
+        assert_eq!(gutter.next_hunk_line(0), Some(1));
+        assert_eq!(gutter.next_hunk_line(1), Some(4));
+        assert_eq!(gutter.next_hunk_line(4), Some(1));
+        assert_eq!(gutter.prev_hunk_line(4), Some(1));
+        assert_eq!(gutter.prev_hunk_line(1), Some(4));
+
+        assert_eq!(gutter.revert_at(1, &buffer), Some((2..4, "b\n".to_string())));
+        assert_eq!(gutter.revert_at(4, &buffer), Some((8..10, "".to_string())));
+        assert_eq!(gutter.revert_at(0, &buffer), None);
+    }
+}
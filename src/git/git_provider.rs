@@ -0,0 +1,28 @@
+use std::fmt::Debug;
+use std::sync::Arc;
+
+use crate::*;
+
+pub trait GitProvider: Debug + Send + Sync {
+    /*
+    Contents of the file in HEAD. Ok(None) means the file is not tracked (or there is no HEAD yet).
+     */
+    fn head_version(&self, path: &SPath) -> Result<Option<String>, GitError>;
+
+    /*
+    Status of files within workspace, ignored ones included.
+     */
+    fn status(&self) -> Result<GitStatus, GitError>;
+
+    /*
+    Blame of the file as it is on disk (uncommitted lines included).
+     */
+    fn blame(&self, path: &SPath) -> Result<Blame, GitError>;
+
+    /*
+    Full commit message and diff, as "git show" prints it.
+     */
+    fn show_commit(&self, hash: &str) -> Result<String, GitError>;
+}
+
+pub type GitProviderRef = Arc<Box<dyn GitProvider>>;
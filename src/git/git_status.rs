@@ -0,0 +1,193 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, RwLock};
+
+use log::warn;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+pub enum GitFileStatus {
+    // changed in working tree (staged or not)
+    Modified,
+    // all changes are in index
+    Staged,
+    Untracked,
+    Ignored,
+}
+
+/*
+What "git status" said last time we asked. Paths are relative to workspace root (same as
+SPath::relative_path), files outside of workspace are skipped.
+Git reports untracked and ignored directories as a whole, so lookups check ancestors too.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct GitStatus {
+    entries: HashMap<PathBuf, GitFileStatus>,
+}
+
+pub type GitStatusRef = Arc<RwLock<GitStatus>>;
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum GitFileFilter {
+    ChangedOnly,
+    TrackedOnly,
+}
+
+impl GitStatus {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_entry<P: AsRef<Path>>(mut self, path: P, status: GitFileStatus) -> Self {
+        self.entries.insert(path.as_ref().to_path_buf(), status);
+        self
+    }
+
+    /*
+    Parses output of "git status --porcelain=v1 -z". Paths there are relative to repository root, so
+    prefix (path from repository root to workspace root) is stripped.
+     */
+    pub fn from_porcelain(output: &str, prefix: &Path) -> Self {
+        let mut entries: HashMap<PathBuf, GitFileStatus> = HashMap::new();
+        let mut records = output.split('\0');
+
+        while let Some(record) = records.next() {
+            if record.len() < 4 {
+                if !record.is_empty() {
+                    warn!("unexpected git status record [{}]", record);
+                }
+                continue;
+            }
+
+            let (code, path) = record.split_at(3);
+            let mut code_chars = code.chars();
+            let index = code_chars.next().unwrap_or(' ');
+            let worktree = code_chars.next().unwrap_or(' ');
+
+            // renames and copies are followed by original path, which we don't care about
+            if index == 'R' || index == 'C' {
+                records.next();
+            }
+
+            let status = match (index, worktree) {
+                ('?', '?') => GitFileStatus::Untracked,
+                ('!', '!') => GitFileStatus::Ignored,
+                (_, ' ') => GitFileStatus::Staged,
+                _ => GitFileStatus::Modified,
+            };
+
+            // directories come with trailing slash, PathBuf gets rid of it
+            let path = PathBuf::from(path);
+            if let Ok(relative) = path.strip_prefix(prefix) {
+                entries.insert(relative.to_path_buf(), status);
+            }
+        }
+
+        GitStatus { entries }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /*
+    Status of a file, or of its closest ancestor reported by git. None means "clean".
+     */
+    pub fn file_status(&self, relative_path: &Path) -> Option<GitFileStatus> {
+        relative_path.ancestors().find_map(|p| self.entries.get(p).copied())
+    }
+
+    /*
+    Directory is considered modified if it contains any changes, untracked and ignored directories
+    are reported by git as a whole.
+     */
+    pub fn dir_status(&self, relative_path: &Path) -> Option<GitFileStatus> {
+        if let Some(status) = self.file_status(relative_path) {
+            return Some(status);
+        }
+
+        let mut result: Option<GitFileStatus> = None;
+        for (path, status) in self.entries.iter() {
+            if !path.starts_with(relative_path) {
+                continue;
+            }
+
+            match status {
+                GitFileStatus::Modified => return Some(GitFileStatus::Modified),
+                GitFileStatus::Staged | GitFileStatus::Untracked => {
+                    result = Some(GitFileStatus::Modified);
+                }
+                GitFileStatus::Ignored => {}
+            }
+        }
+        result
+    }
+
+    pub fn is_changed(&self, relative_path: &Path) -> bool {
+        matches!(
+            self.file_status(relative_path),
+            Some(GitFileStatus::Modified | GitFileStatus::Staged | GitFileStatus::Untracked)
+        )
+    }
+
+    pub fn is_tracked(&self, relative_path: &Path) -> bool {
+        !matches!(
+            self.file_status(relative_path),
+            Some(GitFileStatus::Untracked | GitFileStatus::Ignored)
+        )
+    }
+
+    pub fn is_ignored(&self, relative_path: &Path) -> bool {
+        self.file_status(relative_path) == Some(GitFileStatus::Ignored)
+    }
+
+    pub fn matches(&self, filter: GitFileFilter, relative_path: &Path) -> bool {
+        match filter {
+            GitFileFilter::ChangedOnly => self.is_changed(relative_path),
+            GitFileFilter::TrackedOnly => self.is_tracked(relative_path),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_from_porcelain() {
+        let output =
+            " M ws/src/main.rs\0M  ws/staged.rs\0MM ws/both.rs\0R  ws/new.rs\0ws/old.rs\0?? ws/untracked/\0!! ws/target/\0 M outside.rs\0";
+        let status = GitStatus::from_porcelain(output, Path::new("ws"));
+
+        assert_eq!(status.file_status(Path::new("src/main.rs")), Some(GitFileStatus::Modified));
+        assert_eq!(status.file_status(Path::new("staged.rs")), Some(GitFileStatus::Staged));
+        assert_eq!(status.file_status(Path::new("both.rs")), Some(GitFileStatus::Modified));
+        assert_eq!(status.file_status(Path::new("new.rs")), Some(GitFileStatus::Staged));
+        assert_eq!(status.file_status(Path::new("old.rs")), None);
+        assert_eq!(status.file_status(Path::new("untracked/a/b.rs")), Some(GitFileStatus::Untracked));
+        assert_eq!(status.file_status(Path::new("target/debug")), Some(GitFileStatus::Ignored));
+        assert_eq!(status.file_status(Path::new("outside.rs")), None);
+        assert_eq!(status.file_status(Path::new("clean.rs")), None);
+    }
+
+    #[test]
+    fn test_filters() {
+        let status = GitStatus::new()
+            .with_entry("src/main.rs", GitFileStatus::Modified)
+            .with_entry("docs", GitFileStatus::Untracked)
+            .with_entry("target", GitFileStatus::Ignored);
+
+        assert!(status.matches(GitFileFilter::ChangedOnly, Path::new("src/main.rs")));
+        assert!(status.matches(GitFileFilter::ChangedOnly, Path::new("docs/readme.md")));
+        assert!(!status.matches(GitFileFilter::ChangedOnly, Path::new("src/lib.rs")));
+        assert!(!status.matches(GitFileFilter::ChangedOnly, Path::new("target/x")));
+
+        assert!(status.matches(GitFileFilter::TrackedOnly, Path::new("src/lib.rs")));
+        assert!(status.matches(GitFileFilter::TrackedOnly, Path::new("src/main.rs")));
+        assert!(!status.matches(GitFileFilter::TrackedOnly, Path::new("docs/readme.md")));
+        assert!(!status.matches(GitFileFilter::TrackedOnly, Path::new("target/x")));
+
+        assert_eq!(status.dir_status(Path::new("src")), Some(GitFileStatus::Modified));
+        assert_eq!(status.dir_status(Path::new("")), Some(GitFileStatus::Modified));
+        assert_eq!(status.dir_status(Path::new("target")), Some(GitFileStatus::Ignored));
+    }
+}
@@ -0,0 +1,143 @@
+use std::ops::Range;
+
+use similar::{DiffOp, TextDiff};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum HunkKind {
+    Added,
+    Modified,
+    Deleted,
+}
+
+/*
+Line ranges are 0-based, end exclusive. For Deleted hunks new_lines is empty, and its start is the line
+before which the lines were removed.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct DiffHunk {
+    pub kind: HunkKind,
+    pub old_lines: Range<usize>,
+    pub new_lines: Range<usize>,
+}
+
+impl DiffHunk {
+    /*
+    Whether the hunk should be "visible" at given line of new text. Deleted hunks are attached to the line
+    that follows them (or the last line, if they were at the end of file).
+     */
+    pub fn touches_line(&self, line_idx: usize, new_lines_count: usize) -> bool {
+        match self.kind {
+            HunkKind::Added | HunkKind::Modified => self.new_lines.contains(&line_idx),
+            HunkKind::Deleted => {
+                let marker_line = if self.new_lines.start >= new_lines_count {
+                    new_lines_count.saturating_sub(1)
+                } else {
+                    self.new_lines.start
+                };
+                marker_line == line_idx
+            }
+        }
+    }
+}
+
+pub fn compute_hunks(old: &str, new: &str) -> Vec<DiffHunk> {
+    let diff = TextDiff::from_lines(old, new);
+    let mut result: Vec<DiffHunk> = Vec::new();
+
+    for op in diff.ops() {
+        let hunk = match *op {
+            DiffOp::Equal { .. } => continue,
+            DiffOp::Delete {
+                old_index,
+                old_len,
+                new_index,
+            } => DiffHunk {
+                kind: HunkKind::Deleted,
+                old_lines: old_index..old_index + old_len,
+                new_lines: new_index..new_index,
+            },
+            DiffOp::Insert {
+                old_index,
+                new_index,
+                new_len,
+            } => DiffHunk {
+                kind: HunkKind::Added,
+                old_lines: old_index..old_index,
+                new_lines: new_index..new_index + new_len,
+            },
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => DiffHunk {
+                kind: HunkKind::Modified,
+                old_lines: old_index..old_index + old_len,
+                new_lines: new_index..new_index + new_len,
+            },
+        };
+
+        // "delete followed by insert" in the same place is really a modification
+        if let Some(last) = result.last_mut() {
+            if last.old_lines.end == hunk.old_lines.start && last.new_lines.end == hunk.new_lines.start {
+                last.kind = HunkKind::Modified;
+                last.old_lines.end = hunk.old_lines.end;
+                last.new_lines.end = hunk.new_lines.end;
+                continue;
+            }
+        }
+
+        result.push(hunk);
+    }
+
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_changes() {
+        assert_eq!(compute_hunks("a\nb\n", "a\nb\n"), vec![]);
+    }
+
+    #[test]
+    fn test_added_modified_deleted() {
+        let old = "a\nb\nc\nd\ne\n";
+        let new = "a\nx\nb\nC\ne\n";
+
+        assert_eq!(
+            compute_hunks(old, new),
+            vec![
+                DiffHunk {
+                    kind: HunkKind::Added,
+                    old_lines: 1..1,
+                    new_lines: 1..2,
+                },
+                DiffHunk {
+                    kind: HunkKind::Modified,
+                    old_lines: 2..4,
+                    new_lines: 3..4,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_deleted_at_end() {
+        let hunks = compute_hunks("a\nb\nc\n", "a\n");
+        assert_eq!(
+            hunks,
+            vec![DiffHunk {
+                kind: HunkKind::Deleted,
+                old_lines: 1..3,
+                new_lines: 1..1,
+            }]
+        );
+
+        // rope counts the empty line after last newline, so "a\n" has 2 lines
+        assert!(hunks[0].touches_line(1, 2));
+        assert!(!hunks[0].touches_line(0, 2));
+    }
+}
@@ -0,0 +1,314 @@
+use std::ops::Range;
+
+use log::{debug, warn};
+
+use crate::*;
+
+const OURS_MARKER: &str = "<<<<<<<";
+const BASE_MARKER: &str = "|||||||";
+const SEPARATOR_MARKER: &str = "=======";
+const THEIRS_MARKER: &str = ">>>>>>>";
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConflictPart {
+    // lines with <<<<<<<, |||||||, ======= and >>>>>>>
+    Marker,
+    Ours,
+    // only present in diff3 style conflicts
+    Base,
+    Theirs,
+}
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum ConflictResolution {
+    Ours,
+    Theirs,
+    // ours followed by theirs
+    Both,
+}
+
+/*
+Line indices (0-based) of markers of a single conflict region.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct MergeConflict {
+    pub ours_marker: usize,
+    pub base_marker: Option<usize>,
+    pub separator: usize,
+    pub theirs_marker: usize,
+}
+
+impl MergeConflict {
+    pub fn lines(&self) -> Range<usize> {
+        self.ours_marker..self.theirs_marker + 1
+    }
+
+    pub fn ours_lines(&self) -> Range<usize> {
+        self.ours_marker + 1..self.base_marker.unwrap_or(self.separator)
+    }
+
+    pub fn theirs_lines(&self) -> Range<usize> {
+        self.separator + 1..self.theirs_marker
+    }
+
+    pub fn part_at(&self, line_idx: usize) -> Option<ConflictPart> {
+        if !self.lines().contains(&line_idx) {
+            return None;
+        }
+
+        if line_idx == self.ours_marker
+            || Some(line_idx) == self.base_marker
+            || line_idx == self.separator
+            || line_idx == self.theirs_marker
+        {
+            Some(ConflictPart::Marker)
+        } else if self.ours_lines().contains(&line_idx) {
+            Some(ConflictPart::Ours)
+        } else if line_idx < self.separator {
+            Some(ConflictPart::Base)
+        } else {
+            Some(ConflictPart::Theirs)
+        }
+    }
+}
+
+/*
+Finds conflict regions left by git merge/rebase. Unterminated or malformed regions are ignored, so half
+typed markers don't light up the whole file.
+ */
+pub fn find_conflicts(text: &str) -> Vec<MergeConflict> {
+    let mut result: Vec<MergeConflict> = Vec::new();
+
+    let mut ours_marker: Option<usize> = None;
+    let mut base_marker: Option<usize> = None;
+    let mut separator: Option<usize> = None;
+
+    for (line_idx, line) in text.lines().enumerate() {
+        if line.starts_with(OURS_MARKER) {
+            if ours_marker.is_some() {
+                warn!("nested conflict marker at line {}, starting over", line_idx);
+            }
+            ours_marker = Some(line_idx);
+            base_marker = None;
+            separator = None;
+        } else if ours_marker.is_none() {
+            continue;
+        } else if line.starts_with(BASE_MARKER) && separator.is_none() && base_marker.is_none() {
+            base_marker = Some(line_idx);
+        } else if line.starts_with(SEPARATOR_MARKER) && separator.is_none() {
+            separator = Some(line_idx);
+        } else if line.starts_with(THEIRS_MARKER) {
+            if let (Some(ours_marker), Some(separator)) = (ours_marker, separator) {
+                result.push(MergeConflict {
+                    ours_marker,
+                    base_marker,
+                    separator,
+                    theirs_marker: line_idx,
+                });
+            }
+            ours_marker = None;
+            base_marker = None;
+            separator = None;
+        }
+    }
+
+    result
+}
+
+/*
+Returns true iff any line starts with "<<<<<<<". Goes over bytes, so it's cheap enough to run on every edit.
+ */
+fn has_ours_marker<'a>(chunks: impl Iterator<Item = &'a str>) -> bool {
+    // number of '<' since line begin, None if line began with something else
+    let mut matched: Option<usize> = Some(0);
+
+    for byte in chunks.flat_map(|chunk| chunk.bytes()) {
+        matched = match (byte, matched) {
+            (b'\n', _) => Some(0),
+            (b'<', Some(count)) => Some(count + 1),
+            _ => None,
+        };
+
+        if matched == Some(OURS_MARKER.len()) {
+            return true;
+        }
+    }
+
+    false
+}
+
+/*
+Per-editor list of conflicts, recomputed whenever buffer contents change. Unlike git gutter, this needs
+no git at all, markers are just text.
+ */
+#[derive(Debug, Default)]
+pub struct MergeConflicts {
+    conflicts: Vec<MergeConflict>,
+    computed_for_version: Option<u64>,
+}
+
+impl MergeConflicts {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update(&mut self, buffer: &BufferState) {
+        if self.computed_for_version == Some(buffer.content_version()) {
+            return;
+        }
+
+        self.computed_for_version = Some(buffer.content_version());
+        // copying whole buffer on every edit is not worth it for files without conflicts (so almost all)
+        self.conflicts = if has_ours_marker(buffer.chunks()) {
+            find_conflicts(&buffer.to_string())
+        } else {
+            Vec::new()
+        };
+
+        if !self.conflicts.is_empty() {
+            debug!("found {} merge conflicts", self.conflicts.len());
+        }
+    }
+
+    pub fn conflicts(&self) -> &[MergeConflict] {
+        &self.conflicts
+    }
+
+    pub fn conflict_idx_at(&self, line_idx: usize) -> Option<usize> {
+        self.conflicts.iter().position(|c| c.lines().contains(&line_idx))
+    }
+
+    pub fn part_at(&self, line_idx: usize) -> Option<ConflictPart> {
+        self.conflicts.iter().find_map(|c| c.part_at(line_idx))
+    }
+
+    /*
+    First line of next conflict (after the one containing line_idx). Wraps around.
+     */
+    pub fn next_conflict_line(&self, line_idx: usize) -> Option<usize> {
+        self.conflicts
+            .iter()
+            .find(|c| c.ours_marker > line_idx)
+            .or_else(|| self.conflicts.first())
+            .map(|c| c.ours_marker)
+    }
+
+    /*
+    Returns char range of buffer to substitute and text to put there, so that conflict touching line_idx
+    is resolved.
+     */
+    pub fn resolve_at(&self, line_idx: usize, resolution: ConflictResolution, buffer: &BufferState) -> Option<(Range<usize>, String)> {
+        let conflict = &self.conflicts[self.conflict_idx_at(line_idx)?];
+
+        let line_start = |line: usize| -> usize {
+            if line >= buffer.len_lines() {
+                buffer.len_chars()
+            } else {
+                buffer.line_to_char(line).unwrap_or(buffer.len_chars())
+            }
+        };
+        let lines_text = |lines: Range<usize>| -> String {
+            let (begin, end) = (line_start(lines.start), line_start(lines.end));
+            buffer.chars().skip(begin).take(end - begin).collect()
+        };
+
+        let with_what = match resolution {
+            ConflictResolution::Ours => lines_text(conflict.ours_lines()),
+            ConflictResolution::Theirs => lines_text(conflict.theirs_lines()),
+            ConflictResolution::Both => lines_text(conflict.ours_lines()) + &lines_text(conflict.theirs_lines()),
+        };
+
+        let lines = conflict.lines();
+        Some((line_start(lines.start)..line_start(lines.end), with_what))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TEXT: &str = "\
+a
+<<<<<<< HEAD
+ours
+=======
+theirs 1
+theirs 2
+>>>>>>> feature
+b
+<<<<<<< HEAD
+x
+||||||| base
+y
+=======
+z
+>>>>>>> feature
+";
+
+    #[test]
+    fn test_find_conflicts() {
+        let conflicts = find_conflicts(TEXT);
+
+        assert_eq!(
+            conflicts,
+            vec![
+                MergeConflict {
+                    ours_marker: 1,
+                    base_marker: None,
+                    separator: 3,
+                    theirs_marker: 6,
+                },
+                MergeConflict {
+                    ours_marker: 8,
+                    base_marker: Some(10),
+                    separator: 12,
+                    theirs_marker: 14,
+                },
+            ]
+        );
+
+        assert_eq!(conflicts[0].part_at(0), None);
+        assert_eq!(conflicts[0].part_at(1), Some(ConflictPart::Marker));
+        assert_eq!(conflicts[0].part_at(2), Some(ConflictPart::Ours));
+        assert_eq!(conflicts[0].part_at(5), Some(ConflictPart::Theirs));
+        assert_eq!(conflicts[1].part_at(11), Some(ConflictPart::Base));
+
+        // unterminated region is not a conflict
+        assert!(find_conflicts("<<<<<<< HEAD\na\n=======\nb\n").is_empty());
+    }
+
+    #[test]
+    fn test_has_ours_marker() {
+        assert!(has_ours_marker([TEXT].into_iter()));
+        // marker split between chunks
+        assert!(has_ours_marker(["a\n<<<<", "<<< HEAD\n"].into_iter()));
+        assert!(!has_ours_marker(["a <<<<<<< b\n<<<<<<\n"].into_iter()));
+        assert!(!has_ours_marker(std::iter::empty()));
+    }
+
+    #[test]
+    fn test_resolve() {
+        let buffer = BufferState::full(None, DocumentIdentifier::new_unique()).with_text(TEXT);
+        let mut conflicts = MergeConflicts::new();
+        conflicts.update(&buffer);
+
+        assert_eq!(conflicts.next_conflict_line(0), Some(1));
+        assert_eq!(conflicts.next_conflict_line(2), Some(8));
+        assert_eq!(conflicts.next_conflict_line(9), Some(1));
+
+        assert_eq!(conflicts.resolve_at(0, ConflictResolution::Ours, &buffer), None);
+        assert_eq!(
+            conflicts.resolve_at(3, ConflictResolution::Ours, &buffer),
+            Some((2..62, "ours\n".to_string()))
+        );
+        assert_eq!(
+            conflicts.resolve_at(3, ConflictResolution::Theirs, &buffer),
+            Some((2..62, "theirs 1\ntheirs 2\n".to_string()))
+        );
+        // base is dropped
+        assert_eq!(
+            conflicts.resolve_at(14, ConflictResolution::Both, &buffer),
+            Some((64..120, "x\nz\n".to_string()))
+        );
+    }
+}
@@ -0,0 +1,31 @@
+/*
+Git integration. All git access goes through GitProvider, so it can be mocked in tests (same way FS is).
+Real implementation just calls local git binary, no network is ever involved.
+ */
+
+mod blame;
+pub use blame::{format_date, Blame, BlameCommit, BlameLine};
+
+mod blame_annotations;
+pub use blame_annotations::BlameAnnotations;
+
+mod cli_git_provider;
+pub use cli_git_provider::CliGitProvider;
+
+mod git_error;
+pub use git_error::GitError;
+
+mod git_gutter;
+pub use git_gutter::GitGutter;
+
+mod git_provider;
+pub use git_provider::{GitProvider, GitProviderRef};
+
+mod git_status;
+pub use git_status::{GitFileFilter, GitFileStatus, GitStatus, GitStatusRef};
+
+mod line_diff;
+pub use line_diff::{compute_hunks, DiffHunk, HunkKind};
+
+mod merge_conflicts;
+pub use merge_conflicts::{find_conflicts, ConflictPart, ConflictResolution, MergeConflict, MergeConflicts};
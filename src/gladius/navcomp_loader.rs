@@ -4,6 +4,7 @@ pub trait NavCompLoader: Send + Sync + 'static {
     fn load_handler(
         &self,
         config: &ConfigRef,
+        workspace_settings: &WorkspaceSettings,
         project_scope: &ProjectScope,
         navcomp_tick_sender: NavCompTickSender,
     ) -> Result<Box<dyn Handler>, HandlerLoadError>;
@@ -5,6 +5,8 @@ of key components like EditorView or CodeResultsView
 
 use std::sync::{Arc, RwLock};
 
+use log::error;
+
 use crate::*;
 
 // do not share via Arc, we want to be able to "overload" providers in tests or exotic cases
@@ -22,6 +24,16 @@ pub struct Providers {
     buffer_register: BufferRegisterRef,
 
     todo_labels_providers: Vec<LabelsProviderRef>,
+
+    // defaults until workspace is loaded
+    workspace_settings: WorkspaceSettingsRef,
+
+    // None if we're not in a git repository (or git is not installed)
+    git: Option<GitProviderRef>,
+    // shared, refreshed on save
+    git_status: GitStatusRef,
+
+    undo_history_writer: UndoHistoryWriterRef,
 }
 
 impl Providers {
@@ -48,6 +60,23 @@ impl Providers {
             navcomp_group: Arc::new(RwLock::new(NavCompGroup::new())),
             buffer_register: Arc::new(RwLock::new(BufferRegister::new())),
             todo_labels_providers,
+            workspace_settings: WorkspaceSettingsRef::default(),
+            git: None,
+            git_status: GitStatusRef::default(),
+            undo_history_writer: UndoHistoryWriterRef::default(),
+        }
+    }
+
+    pub fn with_git(self, git: GitProviderRef) -> Self {
+        let res = Providers { git: Some(git), ..self };
+        res.refresh_git_status();
+        res
+    }
+
+    pub fn with_workspace_settings(self, workspace_settings: WorkspaceSettingsRef) -> Self {
+        Providers {
+            workspace_settings,
+            ..self
         }
     }
 
@@ -87,7 +116,51 @@ impl Providers {
         &self.navcomp_group
     }
 
+    pub fn undo_history_writer(&self) -> &UndoHistoryWriterRef {
+        &self.undo_history_writer
+    }
+
     pub fn todo_label_providers(&self) -> impl Iterator<Item = &LabelsProviderRef> {
         self.todo_labels_providers.iter()
     }
+
+    pub fn git(&self) -> Option<&GitProviderRef> {
+        self.git.as_ref()
+    }
+
+    pub fn git_status(&self) -> &GitStatusRef {
+        &self.git_status
+    }
+
+    /*
+    Asks git for status of workspace files again. Blocking, but "git status" is fast enough to do it on
+    save.
+     */
+    pub fn refresh_git_status(&self) {
+        let git = unpack_or!(self.git.as_ref(), (), "no git, not refreshing status");
+
+        let new_status = match git.status() {
+            Ok(status) => status,
+            Err(e) => {
+                error!("failed to get git status: {}", e);
+                return;
+            }
+        };
+
+        match self.git_status.write() {
+            Ok(mut lock) => *lock = new_status,
+            Err(e) => error!("failed to lock git status: {}", e),
+        }
+    }
+
+    pub fn workspace_settings(&self) -> &WorkspaceSettingsRef {
+        &self.workspace_settings
+    }
+
+    /*
+    Language options with all layers (workspace, global config, defaults) applied.
+     */
+    pub fn language_options(&self, lang_id_op: Option<LangId>) -> ResolvedLanguageOptions {
+        self.workspace_settings.language_options(&self.config, lang_id_op)
+    }
 }
@@ -139,7 +139,7 @@ pub fn run_gladius<I: Input, O: FinalOutput>(providers: Providers, input: I, mut
                         }
 
                         match ie {
-                            InputEvent::KeyInput(key) if key.as_focus_update().is_some() && key.modifiers.alt => {
+                            InputEvent::KeyInput(key) if key.as_focus_update().is_some() && key.modifiers.alt && !key.modifiers.shift => {
                                 ie = InputEvent::FocusUpdate(key.as_focus_update().unwrap());
                             },
                             InputEvent::KeyInput(key) if key == providers.config().keyboard_config.global.everything_bar => {
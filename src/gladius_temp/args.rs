@@ -17,6 +17,14 @@ pub struct Args {
     #[clap(long = "record")]
     pub recording: bool,
 
+    // don't reopen what was open on last exit, and don't save what's open now
+    #[clap(long = "no-session")]
+    pub no_session: bool,
+
+    // drops undo histories kept between sessions that can't be used anymore or don't fit total limit, and exits
+    #[clap(long = "clean-undo-history")]
+    pub clean_undo_history: bool,
+
     pub paths: Vec<PathBuf>,
 }
 
@@ -0,0 +1,70 @@
+use crate::*;
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/brackets_test_1").with_files(["src/main.rs"]).build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| f
+        .get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines()
+        .any(|line| line.contents.text.starts_with("fn main()"))));
+
+    full_setup
+}
+
+fn main_rs(f: &FullSetup) -> String {
+    f.fsf()
+        .descendant_checked("src/main.rs")
+        .unwrap()
+        .read_entire_file_to_string()
+        .unwrap()
+}
+
+fn cursor_lines(f: &FullSetup) -> Vec<String> {
+    f.get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines_with_coded_cursors()
+        .map(|line| line.contents.text)
+        .collect()
+}
+
+#[test]
+fn typed_pairs_get_closed_and_stepped_over() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(Keycode::End.to_key()));
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.type_in("foo(\"a"));
+    assert!(full_setup.wait_for(|f| cursor_lines(f) == vec!["    foo(\"a#\")⏎".to_string()]));
+
+    assert!(full_setup.type_in("\");"));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    assert!(full_setup.wait_for(|f| main_rs(f).starts_with("fn main() {\n    foo(\"a\");\n    let s")));
+
+    full_setup.finish();
+}
+
+#[test]
+fn matching_bracket_is_highlighted_and_jumped_to() {
+    let mut full_setup = common_start();
+
+    // right after "{" of main
+    assert!(full_setup.send_key(Keycode::End.to_key()));
+
+    // the "}" in string is not the one
+    let matching_bracket_background = Theme::default().ui.matching_bracket_background;
+    assert!(full_setup.wait_for(|f| {
+        let line = f.get_first_editor().unwrap().get_all_visible_lines().nth(3).unwrap();
+        match &f.get_frame().unwrap().buffer[line.contents.absolute_pos] {
+            Cell::Begin { style, grapheme } => grapheme == "}" && style.background == matching_bracket_background,
+            Cell::Continuation => false,
+        }
+    }));
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.jump_to_matching_bracket));
+    assert!(full_setup.wait_for(|f| cursor_lines(f) == vec!["}#⏎".to_string()]));
+
+    full_setup.finish();
+}
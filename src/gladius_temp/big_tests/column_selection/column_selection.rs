@@ -0,0 +1,44 @@
+use crate::*;
+
+fn main_rs(f: &FullSetup) -> String {
+    f.fsf()
+        .descendant_checked("src/main.rs")
+        .unwrap()
+        .read_entire_file_to_string()
+        .unwrap()
+}
+
+#[test]
+fn column_selection_types_on_every_line() {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/column_selection_test_1")
+        .with_files(["src/main.rs"])
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| f
+        .get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines()
+        .any(|line| line.contents.text.starts_with("fn main()"))));
+
+    // on "let" in "let a = 1;"
+    assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+    for _ in 0..4 {
+        assert!(full_setup.send_key(Keycode::ArrowRight.to_key()));
+    }
+
+    // "let" on both lines, empty line in between is too short and gets a simple cursor
+    for _ in 0..2 {
+        assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.column_select_down));
+    }
+    for _ in 0..3 {
+        assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.column_select_right));
+    }
+
+    assert!(full_setup.type_in("var"));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    assert!(full_setup.wait_for(|f| main_rs(f) == "fn main() {\n    var a = 1;\nvar\n    var bb = 2;\n}\n"));
+
+    full_setup.finish();
+}
@@ -0,0 +1 @@
+mod column_selection;
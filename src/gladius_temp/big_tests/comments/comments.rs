@@ -0,0 +1,58 @@
+use crate::*;
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/comments_test_1").with_files(["src/main.rs"]).build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| f
+        .get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines()
+        .any(|line| line.contents.text.starts_with("fn main()"))));
+
+    full_setup
+}
+
+fn main_rs(f: &FullSetup) -> String {
+    f.fsf()
+        .descendant_checked("src/main.rs")
+        .unwrap()
+        .read_entire_file_to_string()
+        .unwrap()
+}
+
+#[test]
+fn lines_get_commented_and_uncommented() {
+    let mut full_setup = common_start();
+
+    // second and third line
+    assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+    assert!(full_setup.send_key(Keycode::ArrowDown.to_key().with_shift()));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.toggle_comment));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    assert!(full_setup.wait_for(|f| main_rs(f) == "fn main() {\n    // let x = 1;\n    // println!(\"{}\", x);\n}\n"));
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.toggle_comment));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    assert!(full_setup.wait_for(|f| main_rs(f) == "fn main() {\n    let x = 1;\n    println!(\"{}\", x);\n}\n"));
+
+    full_setup.finish();
+}
+
+#[test]
+fn line_gets_block_comment() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.toggle_block_comment));
+
+    assert!(full_setup.wait_for(|f| f
+        .get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines()
+        .any(|line| line.contents.text.starts_with("    /*let x = 1;*/"))));
+
+    full_setup.finish();
+}
@@ -0,0 +1,148 @@
+use crate::*;
+
+const HEAD_VERSION: &'static str = "fn main() {\n    let a = 0;\n    let b = 2;\n    let c = 3;\n    println!(\"{}\", a + b);\n}\n";
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/git_test_1")
+        .with_files(["src/main.rs"])
+        .with_git_head_version("src/main.rs", HEAD_VERSION)
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+
+    full_setup
+}
+
+fn open_diff(full_setup: &mut FullSetup, option: &str) {
+    full_setup.send_key(full_setup.config().keyboard_config.global.everything_bar);
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().context_bar_op().is_some()));
+
+    let option_selected = |f: &FullSetup| {
+        f.get_first_editor()
+            .unwrap()
+            .context_bar_op()
+            .map(|c| c.selected_option().map(|c| c.trim() == option).unwrap_or(false))
+            .unwrap_or(false)
+    };
+
+    for _ in 0..20 {
+        if option_selected(full_setup) {
+            break;
+        }
+        assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+        full_setup.wait_frame();
+    }
+    assert!(option_selected(full_setup));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_diff_view().is_some()));
+}
+
+// without newline markers
+fn rows(f: &FullSetup, left: bool) -> Vec<String> {
+    let mut rows: Vec<String> = f
+        .get_diff_view()
+        .unwrap()
+        .rows(left)
+        .into_iter()
+        .map(|row| row.trim_end_matches(['⏎', '⇱']).to_string())
+        .collect();
+    while rows.last().map(|r| r.is_empty()).unwrap_or(false) {
+        rows.pop();
+    }
+    rows
+}
+
+#[test]
+fn diff_view_with_head_is_aligned() {
+    let mut full_setup = common_start();
+
+    open_diff(&mut full_setup, "diff with HEAD");
+
+    assert!(full_setup.wait_for(|f| rows(f, true).len() == 6));
+
+    assert_eq!(
+        rows(&full_setup, true),
+        vec![
+            "fn main() {",
+            "    let a = 0;",
+            "    let b = 2;",
+            "    let c = 3;",
+            "    println!(\"{}\", a + b);",
+            "}",
+        ]
+    );
+    // missing line is padded, so rows stay aligned
+    assert_eq!(
+        rows(&full_setup, false),
+        vec![
+            "fn main() {",
+            "    let a = 1;",
+            "    let b = 2;",
+            "",
+            "    println!(\"{}\", a + b);",
+            "}",
+        ]
+    );
+
+    let diff_view = full_setup.get_diff_view().unwrap();
+    assert!(diff_view.titles_line().contains("(HEAD)"));
+    assert!(diff_view.titles_line().contains("(buffer)"));
+    assert!(!diff_view.is_left_focused());
+
+    let git_theme = &full_setup.get_frame().unwrap().theme.ui.git;
+    // changed line, with changed word highlighted on top
+    assert_eq!(diff_view.background_at(true, 1, 0), Some(git_theme.diff_changed_background));
+    assert_eq!(diff_view.background_at(true, 1, 12), Some(git_theme.diff_word_background));
+    assert_eq!(diff_view.background_at(false, 1, 12), Some(git_theme.diff_word_background));
+    // removed line and its filler
+    assert_eq!(diff_view.background_at(true, 3, 0), Some(git_theme.diff_removed_background));
+    assert_eq!(diff_view.background_at(false, 3, 0), Some(git_theme.diff_filler_background));
+    // equal lines are not marked
+    assert_ne!(diff_view.background_at(true, 2, 0), Some(git_theme.diff_changed_background));
+
+    full_setup.finish();
+}
+
+#[test]
+fn diff_view_with_saved_shows_unsaved_edits() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.wait_for(|f| f
+        .get_first_editor()
+        .unwrap()
+        .get_all_visible_lines()
+        .any(|line| line.contents.text.starts_with("xfn main"))));
+
+    open_diff(&mut full_setup, "diff with saved");
+
+    assert!(full_setup.wait_for(|f| rows(f, false).first().map(|r| r.as_str()) == Some("xfn main() {")));
+    assert_eq!(rows(&full_setup, true).first().map(|r| r.as_str()), Some("fn main() {"));
+
+    full_setup.finish();
+}
+
+#[test]
+fn diff_view_copy_hunks() {
+    let mut full_setup = common_start();
+
+    open_diff(&mut full_setup, "diff with HEAD");
+    assert!(full_setup.wait_for(|f| rows(f, true).len() == 6));
+
+    full_setup.send_input(InputEvent::FocusUpdate(FocusUpdate::Left));
+    assert!(full_setup.wait_for(|f| f.get_diff_view().unwrap().is_left_focused()));
+
+    // copying into HEAD is not possible, copying from it is
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.next_hunk));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.diff.copy_hunk));
+    assert!(full_setup.wait_for(|f| rows(f, false).get(1).map(|r| r.as_str()) == Some("    let a = 0;")));
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.next_hunk));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.diff.copy_hunk));
+    assert!(full_setup.wait_for(|f| rows(f, false).get(3).map(|r| r.as_str()) == Some("    let c = 3;")));
+
+    assert_eq!(rows(&full_setup, false), rows(&full_setup, true));
+
+    full_setup.finish();
+}
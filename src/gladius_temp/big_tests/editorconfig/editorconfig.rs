@@ -0,0 +1,64 @@
+use crate::*;
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/editorconfig_test_1")
+        .with_files(["src/main.rs"])
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| f
+        .get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines()
+        .any(|line| line.contents.text.starts_with("fn main()"))));
+
+    full_setup
+}
+
+fn main_rs(f: &FullSetup) -> String {
+    f.fsf()
+        .descendant_checked("src/main.rs")
+        .unwrap()
+        .read_entire_file_to_string()
+        .unwrap()
+}
+
+#[test]
+fn tab_indents_with_tabs() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(Keycode::Tab.to_key()));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    assert!(full_setup.wait_for(|f| main_rs(f).starts_with("\tfn main() {")));
+
+    full_setup.finish();
+}
+
+#[test]
+fn save_trims_whitespace_and_adds_final_newline() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    assert!(full_setup.wait_for(|f| main_rs(f) == "xfn main() {\n  println!(\"hi\");\n}\n"));
+
+    full_setup.finish();
+}
+
+#[test]
+fn max_line_length_draws_ruler() {
+    let mut full_setup = common_start();
+
+    let ruler_background = Theme::default().ui.ruler_background;
+    assert!(full_setup.wait_for(|f| {
+        let line = f.get_first_editor().unwrap().get_all_visible_lines().nth(2).unwrap();
+        match &f.get_frame().unwrap().buffer[line.contents.absolute_pos + XY::new(20, 0)] {
+            Cell::Begin { style, grapheme: _ } => style.background == ruler_background,
+            Cell::Continuation => false,
+        }
+    }));
+
+    full_setup.finish();
+}
@@ -0,0 +1,71 @@
+use crate::*;
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/excluded_dirs_test_1")
+        .with_files(["src/main.rs"])
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+
+    full_setup
+}
+
+fn tree_labels_where(f: &FullSetup, pred: impl Fn(&TreeViewInterpreterItem) -> bool) -> Vec<String> {
+    f.get_file_tree_view()
+        .unwrap()
+        .items()
+        .into_iter()
+        .filter(|item| pred(item))
+        .map(|item| item.label)
+        .collect()
+}
+
+fn tree_labels(f: &FullSetup) -> Vec<String> {
+    tree_labels_where(f, |_| true)
+}
+
+#[test]
+fn excluded_dirs_are_not_in_file_tree() {
+    let mut full_setup = common_start();
+
+    // expanding the root
+    full_setup.send_input(InputEvent::FocusUpdate(FocusUpdate::Left));
+    assert!(full_setup.wait_for(|f| f.get_file_tree_view().unwrap().is_focused()));
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+
+    assert!(full_setup.wait_for(|f| tree_labels(f).iter().any(|label| label == "src")));
+    assert!(tree_labels(&full_setup).iter().any(|label| label == "readme.txt"));
+    assert!(!tree_labels(&full_setup).iter().any(|label| label == "generated"));
+
+    // nested excluded dir
+    let highlighted = |f: &FullSetup| tree_labels_where(f, |item| item.highlighted).pop();
+    for _ in 0..3 {
+        if highlighted(&full_setup).as_deref() == Some("src") {
+            break;
+        }
+        let before = highlighted(&full_setup);
+        assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+        assert!(full_setup.wait_for(|f| highlighted(f) != before));
+    }
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| tree_labels(f).iter().any(|label| label == "main.rs")));
+    assert!(!tree_labels(&full_setup).iter().any(|label| label == "vendored"));
+
+    full_setup.finish();
+}
+
+#[test]
+fn excluded_dirs_are_not_in_fuzzy_file_search() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.global.fuzzy_file));
+    assert!(full_setup.wait_for(|f| f.get_fuzzy_search().is_some()));
+    assert!(full_setup.type_in(".rs"));
+
+    assert!(full_setup.wait_for(|f| f.get_fuzzy_search().unwrap().items().iter().any(|item| item.contains("main.rs"))));
+    let items = full_setup.get_fuzzy_search().unwrap().items();
+    assert!(!items.iter().any(|item| item.contains("bindings.rs")));
+    assert!(!items.iter().any(|item| item.contains("lib.rs")));
+
+    full_setup.finish();
+}
@@ -0,0 +1 @@
+mod excluded_dirs;
@@ -0,0 +1,121 @@
+use crate::*;
+
+const MAIN_RS: &'static str = "src/main.rs";
+
+fn change_on_disk(f: &FullSetup, contents: &str) {
+    f.fsf()
+        .descendant_checked(MAIN_RS)
+        .unwrap()
+        .overwrite_with_str(contents, true)
+        .unwrap();
+}
+
+fn common_start(file_check_interval_ms: u64) -> FullSetup {
+    let mut config = Config::default();
+    config.global.file_check_interval_ms = Some(file_check_interval_ms);
+
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/external_change_test_1")
+        .with_config(config)
+        .with_files([MAIN_RS])
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("fn main() {")));
+
+    full_setup
+}
+
+/*
+Types in the editor, then changes the file underneath, and waits to be asked about it.
+ */
+fn conflict_start() -> FullSetup {
+    let mut full_setup = common_start(50);
+
+    assert!(full_setup.type_in("abc"));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("abcfn main() {")));
+
+    change_on_disk(&full_setup, "fn generated() {}\n");
+    assert!(full_setup.wait_for(|f| f.dialog_mentions(MAIN_RS)));
+    assert!(full_setup.wait_for(|f| f.focused_button().contains("Reload")));
+
+    full_setup
+}
+
+#[test]
+fn clean_buffer_is_reloaded() {
+    let mut full_setup = common_start(50);
+
+    change_on_disk(&full_setup, "fn generated() {}\n");
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("fn generated() {}")));
+    assert!(full_setup.get_generic_dialog().is_none());
+
+    // reload is an edit, so undo brings back what was there before
+    assert!(full_setup.send_key(Keycode::Char('z').to_key().with_ctrl()));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("fn main() {")));
+}
+
+#[test]
+fn modified_buffer_is_reloaded_on_demand() {
+    let mut full_setup = conflict_start();
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_generic_dialog().is_none()));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("fn generated() {}")));
+}
+
+#[test]
+fn modified_buffer_is_kept_on_demand() {
+    let mut full_setup = conflict_start();
+
+    assert!(full_setup.send_key(Keycode::ArrowRight.to_key().with_alt()));
+    assert!(full_setup.wait_for(|f| f.focused_button().contains("Keep")));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_generic_dialog().is_none()));
+    assert!(full_setup.first_line().starts_with("abcfn main() {"));
+
+    // it's not asked about again, and saving overwrites the file without a warning
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+    assert!(full_setup.wait_for(|f| f.on_disk(MAIN_RS).starts_with(b"abcfn main() {")));
+    assert!(full_setup.get_generic_dialog().is_none());
+}
+
+#[test]
+fn modified_buffer_is_diffed_on_demand() {
+    let mut full_setup = conflict_start();
+
+    assert!(full_setup.send_key(Keycode::ArrowRight.to_key().with_alt()));
+    assert!(full_setup.send_key(Keycode::ArrowRight.to_key().with_alt()));
+    assert!(full_setup.wait_for(|f| f.focused_button().contains("Diff")));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_diff_view().is_some()));
+    assert!(full_setup.get_generic_dialog().is_none());
+}
+
+#[test]
+fn save_warns_when_file_changed_on_disk() {
+    // not checking on its own, so nothing is reloaded or asked before save
+    let mut full_setup = common_start(0);
+
+    assert!(full_setup.type_in("abc"));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("abcfn main() {")));
+    change_on_disk(&full_setup, "fn generated() {}\n");
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+    assert!(full_setup.wait_for(|f| f.dialog_mentions("changed on disk")));
+    assert!(full_setup.wait_for(|f| f.focused_button().contains("Cancel")));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_generic_dialog().is_none()));
+    assert_eq!(full_setup.on_disk(MAIN_RS), b"fn generated() {}\n");
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+    assert!(full_setup.wait_for(|f| f.dialog_mentions("changed on disk")));
+    assert!(full_setup.send_key(Keycode::ArrowRight.to_key().with_alt()));
+    assert!(full_setup.wait_for(|f| f.focused_button().contains("Overwrite")));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_generic_dialog().is_none()));
+    assert!(full_setup.on_disk(MAIN_RS).starts_with(b"abcfn main() {"));
+}
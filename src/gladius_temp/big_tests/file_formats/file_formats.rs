@@ -0,0 +1,116 @@
+use crate::*;
+
+const WINDOWS_RS: &'static str = "src/windows.rs";
+const WINDOWS_CONTENTS: &'static str = "fn a() {}\r\nfn b() {}\r\n";
+
+fn common_start(path: &str, bytes: Vec<u8>) -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/file_format_test_1")
+        .with_mock_file(path, bytes)
+        .with_files([path])
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    full_setup
+}
+
+fn save(full_setup: &mut FullSetup) {
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+}
+
+fn selected(f: &FullSetup) -> Option<String> {
+    f.get_first_editor()
+        .unwrap()
+        .context_bar_op()
+        .and_then(|c| c.selected_option().map(|c| c.trim().to_string()))
+}
+
+fn select_in_context_bar(full_setup: &mut FullSetup, option: &str) {
+    for _ in 0..30 {
+        let before = selected(full_setup);
+        if before.as_deref() == Some(option) {
+            break;
+        }
+        assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+        assert!(full_setup.wait_for(|f| selected(f) != before));
+    }
+    assert_eq!(selected(full_setup).as_deref(), Some(option));
+}
+
+// format options are in a submenu of their own
+fn choose_file_format(full_setup: &mut FullSetup, current: &str, option: &str) {
+    full_setup.send_key(full_setup.config().keyboard_config.global.everything_bar);
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().context_bar_op().is_some()));
+
+    select_in_context_bar(full_setup, &format!("file format: {}", current));
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| selected(f).map(|s| !s.starts_with("file format")).unwrap_or(false)));
+    assert!(full_setup.get_first_editor().unwrap().context_bar_op().unwrap().is_focused());
+
+    select_in_context_bar(full_setup, option);
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().context_bar_op().is_none()));
+}
+
+#[test]
+fn plain_file_shows_no_format() {
+    let full_setup = common_start("src/plain.rs", b"fn a() {}\n".to_vec());
+
+    assert!(full_setup.first_line().starts_with("fn a() {}"));
+    assert!(!full_setup.on_screen("UTF-8"));
+}
+
+#[test]
+fn crlf_file_is_saved_with_crlf() {
+    let mut full_setup = common_start(WINDOWS_RS, WINDOWS_CONTENTS.as_bytes().to_vec());
+
+    assert!(full_setup.wait_for(|f| f.on_screen("UTF-8 CRLF")));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("fn a() {}")));
+
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("xfn a() {}")));
+    save(&mut full_setup);
+
+    assert!(full_setup.wait_for(|f| f.on_disk(WINDOWS_RS) == b"xfn a() {}\r\nfn b() {}\r\n".to_vec()));
+}
+
+#[test]
+fn line_endings_are_changed_with_command() {
+    let mut full_setup = common_start(WINDOWS_RS, WINDOWS_CONTENTS.as_bytes().to_vec());
+
+    assert!(full_setup.wait_for(|f| f.on_screen("UTF-8 CRLF")));
+    choose_file_format(&mut full_setup, "UTF-8 CRLF", "line endings: CRLF → LF");
+    assert!(full_setup.wait_for(|f| !f.on_screen("CRLF")));
+
+    save(&mut full_setup);
+    assert!(full_setup.wait_for(|f| f.on_disk(WINDOWS_RS) == b"fn a() {}\nfn b() {}\n".to_vec()));
+}
+
+#[test]
+fn utf16_file_is_saved_as_utf16() {
+    let utf16 = |text: &str| Encoding::Utf16Le.encode(text);
+    let mut full_setup = common_start("src/utf16.txt", utf16("zażółć\n"));
+
+    assert!(full_setup.wait_for(|f| f.on_screen("UTF-16 LE LF")));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("zażółć")));
+
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("xzażółć")));
+    save(&mut full_setup);
+
+    assert!(full_setup.wait_for(|f| f.on_disk("src/utf16.txt") == utf16("xzażółć\n")));
+}
+
+#[test]
+fn broken_utf16_file_is_not_opened() {
+    // UTF-16 can't have odd number of bytes
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/file_format_test_1")
+        .with_mock_file("src/broken.txt", vec![0xFF, 0xFE, b'a', 0, b'b'])
+        .with_files(["src/broken.txt"])
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_no_editor_opened()));
+    for _ in 0..10 {
+        full_setup.wait_frame();
+    }
+    assert!(full_setup.is_no_editor_opened());
+}
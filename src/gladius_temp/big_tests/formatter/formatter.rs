@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+use crate::*;
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/formatter_test_1").with_files(["src/main.rs"]).build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+
+    full_setup
+}
+
+fn cursor_line(f: &FullSetup) -> String {
+    f.get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines_with_coded_cursors()
+        .next()
+        .map(|line| line.contents.text)
+        .unwrap_or_default()
+}
+
+#[test]
+fn formatter_runs_in_background_as_one_undo_step() {
+    let mut full_setup = common_start();
+    assert!(full_setup.wait_for(|f| cursor_line(f).starts_with("#fn main() {}")));
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.reformat));
+
+    // editor still reacts while formatter runs
+    assert!(full_setup.send_key(Keycode::ArrowRight.to_key()));
+    assert!(full_setup.wait_for(|f| cursor_line(f).starts_with("f#n main() {}")));
+
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("FN MAIN() {}")));
+
+    assert!(full_setup.send_key(Keycode::Char('z').to_key().with_ctrl()));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("fn main() {}")));
+
+    full_setup.finish();
+}
+
+#[test]
+fn formatter_output_is_dropped_if_buffer_changed() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.reformat));
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("xfn main() {}")));
+
+    // formatter sleeps for a second
+    std::thread::sleep(Duration::from_secs(2));
+    assert!(full_setup.send_key(Keycode::ArrowRight.to_key()));
+    assert!(full_setup.wait_for(|f| cursor_line(f).starts_with("xf#n main() {}")));
+    assert!(full_setup.first_line().starts_with("xfn main() {}"));
+
+    full_setup.finish();
+}
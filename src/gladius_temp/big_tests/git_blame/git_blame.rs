@@ -0,0 +1,163 @@
+use crate::*;
+
+const FIRST_COMMIT: &'static str = "1111111111111111111111111111111111111111";
+const SECOND_COMMIT: &'static str = "2222222222222222222222222222222222222222";
+
+fn blame() -> Blame {
+    let porcelain = format!(
+        "{first} 1 1 2
+author Alice
+author-time 1700000000
+summary Hello world
+filename src/main.rs
+\tfn main() {{
+{first} 2 2
+\t    let a = 1;
+{second} 3 3 3
+author Bob
+author-time 1710000000
+summary Printing
+filename src/main.rs
+\t    let b = 2;
+{second} 4 4
+\t    println!(\"{{}}\", a + b);
+{second} 5 5
+\t}}
+",
+        first = FIRST_COMMIT,
+        second = SECOND_COMMIT
+    );
+
+    Blame::from_porcelain(&porcelain).unwrap()
+}
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/git_test_1")
+        .with_files(["src/main.rs"])
+        .with_git_blame("src/main.rs", blame())
+        .with_git_commit(
+            FIRST_COMMIT,
+            "commit 1111111111111111111111111111111111111111\nAuthor: Alice\n\n    Hello world\n",
+        )
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+
+    full_setup
+}
+
+// 1-based line idx -> label text
+fn blame_labels(f: &FullSetup) -> Vec<(usize, String)> {
+    f.get_first_editor()
+        .unwrap()
+        .get_type_annotations()
+        .map(|l| (l.visible_idx, l.contents.text.trim().to_string()))
+        .collect()
+}
+
+#[test]
+fn git_blame_toggle() {
+    let mut full_setup = common_start();
+
+    assert!(blame_labels(&full_setup).is_empty());
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.toggle_blame));
+    assert!(full_setup.wait_for(|f| blame_labels(f).len() == 5));
+
+    let labels = blame_labels(&full_setup);
+    assert_eq!(labels[0], (1, "Alice, 2023-11-14 (11111111)".to_string()));
+    assert_eq!(labels[2], (3, "Bob, 2024-03-09 (22222222)".to_string()));
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.toggle_blame));
+    assert!(full_setup.wait_for(|f| blame_labels(f).is_empty()));
+
+    full_setup.finish();
+}
+
+#[test]
+fn git_blame_follows_unsaved_edits() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.toggle_blame));
+    assert!(full_setup.wait_for(|f| blame_labels(f).len() == 5));
+
+    // new line is not blamed, the rest moves down
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| blame_labels(f).first().map(|l| l.0) == Some(2)));
+    assert_eq!(blame_labels(&full_setup).len(), 5);
+
+    // edited line loses its label
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.wait_for(|f| blame_labels(f).first().map(|l| l.0) == Some(3)));
+    assert_eq!(blame_labels(&full_setup).len(), 4);
+
+    full_setup.finish();
+}
+
+#[test]
+fn git_blame_show_commit() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.toggle_blame));
+    assert!(full_setup.wait_for(|f| blame_labels(f).len() == 5));
+
+    full_setup.send_key(full_setup.config().keyboard_config.global.everything_bar);
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().context_bar_op().is_some()));
+
+    let show_commit_selected = |f: &FullSetup| {
+        f.get_first_editor()
+            .unwrap()
+            .context_bar_op()
+            .map(|c| {
+                c.selected_option()
+                    .map(|c| c.trim().starts_with("show commit 11111111: Hello world"))
+                    .unwrap_or(false)
+            })
+            .unwrap_or(false)
+    };
+
+    for _ in 0..10 {
+        if show_commit_selected(&full_setup) {
+            break;
+        }
+        assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+        full_setup.wait_frame();
+    }
+    assert!(show_commit_selected(&full_setup));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| {
+        f.get_first_editor()
+            .unwrap()
+            .get_all_visible_lines()
+            .any(|line| line.contents.text.contains("Author: Alice"))
+    }));
+
+    full_setup.finish();
+}
+
+#[test]
+fn git_blame_stays_on_after_save() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.toggle_blame));
+    assert!(full_setup.wait_for(|f| blame_labels(f).len() == 5));
+
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.wait_for(|f| blame_labels(f).len() == 4));
+
+    // blame is re-read after save, edited line still has no label
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+    assert!(full_setup.wait_for(|f| {
+        f.fsf()
+            .descendant_checked("src/main.rs")
+            .unwrap()
+            .read_entire_file_to_string()
+            .unwrap()
+            .starts_with("xfn main")
+    }));
+    full_setup.wait_frame();
+    assert_eq!(blame_labels(&full_setup).len(), 4);
+
+    full_setup.finish();
+}
@@ -0,0 +1,100 @@
+use crate::*;
+
+const HEAD_VERSION: &'static str = "fn main() {\n    let a = 0;\n    let b = 2;\n    let c = 3;\n    println!(\"{}\", a + b);\n}\n";
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/git_test_1")
+        .with_files(["src/main.rs"])
+        .with_git_head_version("src/main.rs", HEAD_VERSION)
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+
+    full_setup
+}
+
+fn cursor_line(f: &FullSetup) -> Option<usize> {
+    f.get_first_editor()
+        .unwrap()
+        .get_visible_cursor_line_indices()
+        .next()
+        .map(|l| l.visible_idx)
+}
+
+#[test]
+fn git_gutter_shows_markers() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup
+        .wait_for(|f| { f.get_first_editor().unwrap().get_line_markers() == vec![(2, "▎".to_string()), (4, "▔".to_string())] }));
+
+    // editing "unchanged" line adds a marker
+    full_setup.type_in("x");
+    assert!(full_setup.wait_for(|f| {
+        f.get_first_editor().unwrap().get_line_markers() == vec![(1, "▎".to_string()), (2, "▎".to_string()), (4, "▔".to_string())]
+    }));
+
+    full_setup.finish();
+}
+
+#[test]
+fn git_gutter_next_prev_hunk() {
+    let mut full_setup = common_start();
+
+    let next_hunk = full_setup.config().keyboard_config.editor.next_hunk;
+    let prev_hunk = full_setup.config().keyboard_config.editor.prev_hunk;
+
+    assert!(full_setup.send_key(next_hunk));
+    assert!(full_setup.wait_for(|f| cursor_line(f) == Some(2)));
+
+    assert!(full_setup.send_key(next_hunk));
+    assert!(full_setup.wait_for(|f| cursor_line(f) == Some(4)));
+
+    // wraps around
+    assert!(full_setup.send_key(next_hunk));
+    assert!(full_setup.wait_for(|f| cursor_line(f) == Some(2)));
+
+    assert!(full_setup.send_key(prev_hunk));
+    assert!(full_setup.wait_for(|f| cursor_line(f) == Some(4)));
+
+    full_setup.finish();
+}
+
+#[test]
+fn git_gutter_revert_hunk() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.next_hunk));
+    assert!(full_setup.wait_for(|f| cursor_line(f) == Some(2)));
+
+    full_setup.send_key(full_setup.config().keyboard_config.global.everything_bar);
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().context_bar_op().is_some()));
+
+    let revert_selected = |f: &FullSetup| {
+        f.get_first_editor()
+            .unwrap()
+            .context_bar_op()
+            .map(|c| c.selected_option().map(|c| c.trim().starts_with("revert change")).unwrap_or(false))
+            .unwrap_or(false)
+    };
+
+    for _ in 0..10 {
+        if revert_selected(&full_setup) {
+            break;
+        }
+        assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+        full_setup.wait_frame();
+    }
+    assert!(revert_selected(&full_setup));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().get_line_markers() == vec![(4, "▔".to_string())]));
+    assert!(full_setup
+        .get_first_editor()
+        .unwrap()
+        .get_all_visible_lines()
+        .any(|line| line.contents.text.contains("let a = 0;")));
+
+    full_setup.finish();
+}
@@ -0,0 +1 @@
+mod git_gutter;
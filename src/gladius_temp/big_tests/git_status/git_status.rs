@@ -0,0 +1,83 @@
+use crate::*;
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/git_test_1")
+        .with_files(["src/main.rs"])
+        .with_git_file_status("src/main.rs", GitFileStatus::Modified)
+        .with_git_file_status("readme.txt", GitFileStatus::Ignored)
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+
+    full_setup
+}
+
+#[test]
+fn git_status_colors_file_tree() {
+    let mut full_setup = common_start();
+    let git_theme = full_setup.get_frame().unwrap().theme.ui.git.clone();
+
+    let foreground_of = |f: &FullSetup, label: &str| {
+        f.get_file_tree_view()
+            .unwrap()
+            .items()
+            .into_iter()
+            .find(|item| item.label == label)
+            .map(|item| item.foreground)
+    };
+
+    // directory containing changes is marked as modified
+    assert!(full_setup.wait_for(|f| foreground_of(f, "git_test_1") == Some(git_theme.modified)));
+
+    // expanding the root
+    full_setup.send_input(InputEvent::FocusUpdate(FocusUpdate::Left));
+    assert!(full_setup.wait_for(|f| f.get_file_tree_view().unwrap().is_focused()));
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+
+    assert!(full_setup.wait_for(|f| foreground_of(f, "readme.txt") == Some(git_theme.ignored)));
+    assert_eq!(foreground_of(&full_setup, "src"), Some(git_theme.modified));
+
+    full_setup.finish();
+}
+
+#[test]
+fn git_status_changed_files_filter() {
+    let mut full_setup = common_start();
+
+    full_setup.send_key(full_setup.config().keyboard_config.global.everything_bar);
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().context_bar_op().is_some()));
+
+    let option_selected = |f: &FullSetup| {
+        f.get_first_editor()
+            .unwrap()
+            .context_bar_op()
+            .map(|c| {
+                c.selected_option()
+                    .map(|c| c.trim().starts_with("find changed file"))
+                    .unwrap_or(false)
+            })
+            .unwrap_or(false)
+    };
+
+    for _ in 0..10 {
+        if option_selected(&full_setup) {
+            break;
+        }
+        assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+        full_setup.wait_frame();
+    }
+    assert!(option_selected(&full_setup));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_fuzzy_search().is_some()));
+
+    assert!(full_setup.wait_for(|f| f.get_fuzzy_search().unwrap().items().iter().any(|item| item.contains("main.rs"))));
+    assert!(!full_setup
+        .get_fuzzy_search()
+        .unwrap()
+        .items()
+        .iter()
+        .any(|item| item.contains("readme.txt")));
+
+    full_setup.finish();
+}
@@ -0,0 +1,166 @@
+use crate::*;
+
+const BIG_RS: &'static str = "src/big.rs";
+
+fn big_contents() -> String {
+    let mut contents = String::new();
+    for idx in 0..200 {
+        contents += &format!("fn zażółć_{}() {{ let gęślą = \"{}\"; }}\n", idx, idx);
+    }
+    contents
+}
+
+fn start_with(contents: String, threshold: u64, blame_op: Option<Blame>) -> FullSetup {
+    let mut config = Config::default();
+    config.global.large_file_threshold_bytes = Some(threshold);
+
+    let mut builder = FullSetup::new("./test_envs/large_file_test_1")
+        .with_config(config)
+        .with_mock_file(BIG_RS, contents.into_bytes())
+        .with_files([BIG_RS]);
+    if let Some(blame) = blame_op {
+        builder = builder.with_git_blame(BIG_RS, blame);
+    }
+    let mut full_setup: FullSetup = builder.build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    full_setup
+}
+
+fn common_start(threshold: u64) -> FullSetup {
+    let mut full_setup = start_with(big_contents(), threshold, None);
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("fn zażółć_0() {")));
+    full_setup
+}
+
+/*
+Whether "fn zażółć_1() {" in the second line (first one has cursor) is all of one color.
+ */
+fn one_color_line(f: &FullSetup) -> bool {
+    let line = f.get_first_editor().unwrap().get_all_visible_lines().nth(1).unwrap();
+    let frame = f.get_frame().unwrap();
+
+    let mut foregrounds = (0..15).filter_map(|x| match &frame.buffer[line.contents.absolute_pos + XY::new(x, 0)] {
+        Cell::Begin { style, grapheme: _ } => Some(style.foreground),
+        Cell::Continuation => None,
+    });
+    let first = foregrounds.next();
+    foregrounds.all(|foreground| Some(foreground) == first)
+}
+
+#[test]
+fn small_file_is_not_large() {
+    let mut full_setup = common_start(1024 * 1024);
+
+    assert!(!full_setup.on_screen("large file"));
+    assert!(full_setup.wait_for(|f| !one_color_line(f)));
+}
+
+#[test]
+fn large_file_is_marked_and_not_highlighted() {
+    let mut full_setup = common_start(100);
+
+    assert!(full_setup.wait_for(|f| f.on_screen("large file")));
+    assert!(one_color_line(&full_setup));
+}
+
+#[test]
+fn find_in_large_file() {
+    let mut full_setup = common_start(100);
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.find));
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().find_op().is_some()));
+    assert!(full_setup.type_in("ślą = \"1"));
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+
+    // match is found past non-ASCII chars, in chars, not bytes
+    assert!(full_setup.wait_for(|f| {
+        f.get_first_editor()
+            .unwrap()
+            .get_visible_cursor_lines_with_coded_cursors()
+            .any(|line| line.contents.text.contains("fn zażółć_1() { let gę(ślą = \"1]\"; }"))
+    }));
+}
+
+#[test]
+fn large_file_is_edited_and_saved() {
+    let mut full_setup = common_start(100);
+
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("xfn zażółć_0() {")));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    let expected = format!("x{}", big_contents());
+    assert!(full_setup.wait_for(|f| f.fsf().descendant_checked(BIG_RS).unwrap().read_entire_file_to_string().unwrap() == expected));
+}
+
+/*
+Widgets address lines with u16, so editor shows a window of lines of a longer text, moving it along with
+the cursor. Line numbers are of the whole text.
+ */
+#[test]
+fn lines_past_window_are_scrolled_to_and_edited() {
+    let contents: String = (0..70000).map(|idx| format!("line {} end\n", idx)).collect();
+    let mut full_setup = start_with(contents.clone(), 100, None);
+
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("line 0 end")));
+    assert!(full_setup.wait_for(|f| f.on_screen("large file")));
+    assert!(!full_setup.on_screen("lines shown"));
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.find));
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().find_op().is_some()));
+
+    let shown = |f: &FullSetup, what: &str| {
+        f.get_first_editor()
+            .unwrap()
+            .get_all_visible_lines()
+            .any(|line| line.contents.text.starts_with(what))
+    };
+    for (pattern, line_no) in [("line 40000 end", "40001"), ("line 69999 end", "70000")] {
+        assert!(full_setup.type_in(pattern));
+        assert!(full_setup.send_key(Keycode::Enter.to_key()));
+
+        assert!(full_setup.wait_for(|f| shown(f, pattern)));
+        assert!(full_setup.on_screen(line_no));
+
+        for _ in 0..pattern.len() {
+            assert!(full_setup.send_key(Keycode::Backspace.to_key()));
+        }
+    }
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.close_find_replace));
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().find_op().is_none()));
+    assert!(full_setup.type_in("edited"));
+    assert!(full_setup.wait_for(|f| shown(f, "edited")));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    let expected = contents.replace("line 69999 end", "edited");
+    assert!(full_setup.wait_for(|f| f.fsf().descendant_checked(BIG_RS).unwrap().read_entire_file_to_string().unwrap() == expected));
+}
+
+#[test]
+fn large_file_is_not_blamed() {
+    let porcelain = "1111111111111111111111111111111111111111 1 1 1
+author Alice
+author-time 1700000000
+summary Hello world
+filename src/big.rs
+\tfn zażółć_0() { let gęślą = \"0\"; }
+";
+
+    for (threshold, blamed) in [(1024 * 1024, true), (100, false)] {
+        let mut full_setup = start_with(big_contents(), threshold, Some(Blame::from_porcelain(porcelain).unwrap()));
+        assert!(full_setup.wait_for(|f| f.first_line().starts_with("fn zażółć_0() {")));
+
+        assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.toggle_blame));
+        // gives blame a couple frames to show up, if it would
+        for _ in 0..3 {
+            full_setup.wait_frame();
+        }
+
+        let labels = full_setup.get_first_editor().unwrap().get_type_annotations().count();
+        assert_eq!(labels > 0, blamed);
+
+        full_setup.finish();
+    }
+}
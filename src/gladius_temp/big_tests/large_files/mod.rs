@@ -0,0 +1 @@
+mod large_files;
@@ -0,0 +1,121 @@
+use crate::*;
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/merge_conflict_test_1")
+        .with_files(["src/main.rs"])
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+
+    full_setup
+}
+
+fn choose_from_context_bar(full_setup: &mut FullSetup, option: &str) {
+    full_setup.send_key(full_setup.config().keyboard_config.global.everything_bar);
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().context_bar_op().is_some()));
+
+    let selected = |f: &FullSetup| {
+        f.get_first_editor()
+            .unwrap()
+            .context_bar_op()
+            .and_then(|c| c.selected_option().map(|c| c.trim().to_string()))
+    };
+
+    for _ in 0..20 {
+        let before = selected(full_setup);
+        if before.as_deref() == Some(option) {
+            break;
+        }
+        assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+        // periodic frames (swap files, file checks) can come before the one showing the key was handled
+        assert!(full_setup.wait_for(|f| selected(f) != before));
+    }
+    assert_eq!(selected(full_setup).as_deref(), Some(option));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().context_bar_op().is_none()));
+}
+
+fn lines(f: &FullSetup) -> Vec<String> {
+    f.get_first_editor()
+        .unwrap()
+        .get_all_visible_lines()
+        .map(|line| line.contents.text.trim_end().trim_end_matches(['⏎', '⇱']).to_string())
+        .filter(|line| !line.is_empty())
+        .collect()
+}
+
+fn cursor_line(f: &FullSetup) -> Option<usize> {
+    f.get_first_editor()
+        .unwrap()
+        .get_visible_cursor_line_indices()
+        .next()
+        .map(|l| l.visible_idx)
+}
+
+#[test]
+fn merge_conflicts_are_highlighted() {
+    let full_setup = common_start();
+
+    let git_theme = &full_setup.get_frame().unwrap().theme.ui.git;
+    let backgrounds = full_setup.get_first_editor().unwrap().get_line_backgrounds();
+
+    assert_ne!(backgrounds[0].1, git_theme.conflict_marker_background);
+    assert_eq!(backgrounds[1], (2, git_theme.conflict_marker_background));
+    assert_eq!(backgrounds[2], (3, git_theme.conflict_ours_background));
+    assert_eq!(backgrounds[3], (4, git_theme.conflict_marker_background));
+    assert_eq!(backgrounds[4], (5, git_theme.conflict_theirs_background));
+    assert_eq!(backgrounds[5], (6, git_theme.conflict_marker_background));
+    assert_ne!(backgrounds[6].1, git_theme.conflict_ours_background);
+
+    full_setup.finish();
+}
+
+#[test]
+fn merge_conflicts_take_theirs_and_undo() {
+    let mut full_setup = common_start();
+
+    choose_from_context_bar(&mut full_setup, "next conflict");
+    assert!(full_setup.wait_for(|f| cursor_line(f) == Some(2)));
+
+    choose_from_context_bar(&mut full_setup, "take theirs");
+    assert!(full_setup.wait_for(|f| lines(f).len() == 9));
+    assert_eq!(lines(&full_setup)[1], "    let a = 2;");
+    assert_eq!(lines(&full_setup)[2], "    let b = 3;");
+
+    // whole resolution is a single undo step
+    assert!(full_setup.send_key(Keycode::Char('z').to_key().with_ctrl()));
+    assert!(full_setup.wait_for(|f| lines(f).len() == 13));
+    assert_eq!(lines(&full_setup)[1], "<<<<<<< HEAD");
+
+    full_setup.finish();
+}
+
+#[test]
+fn merge_conflicts_take_ours_and_both() {
+    let mut full_setup = common_start();
+
+    choose_from_context_bar(&mut full_setup, "next conflict");
+    choose_from_context_bar(&mut full_setup, "take ours");
+    assert!(full_setup.wait_for(|f| lines(f).len() == 9));
+    assert_eq!(lines(&full_setup)[1], "    let a = 1;");
+
+    // next one is now second conflict in the file
+    choose_from_context_bar(&mut full_setup, "next conflict");
+    assert!(full_setup.wait_for(|f| cursor_line(f) == Some(4)));
+    choose_from_context_bar(&mut full_setup, "take both");
+    assert!(full_setup.wait_for(|f| lines(f).len() == 6));
+    assert_eq!(
+        lines(&full_setup),
+        vec![
+            "fn main() {",
+            "    let a = 1;",
+            "    let b = 3;",
+            "    println!(\"{}\", a);",
+            "    println!(\"{}\", b);",
+            "}",
+        ]
+    );
+
+    full_setup.finish();
+}
@@ -0,0 +1 @@
+mod merge_conflicts;
@@ -1,9 +1,28 @@
+mod brackets;
+mod column_selection;
+mod comments;
 mod completion;
+mod diff_view;
 mod dropping_cursor;
+mod editorconfig;
+mod excluded_dirs;
+mod external_changes;
+mod file_formats;
 mod find_replace;
+mod formatter;
 mod fuzzy_file_open;
+mod git_blame;
+mod git_gutter;
+mod git_status;
 mod integration;
+mod large_files;
+mod merge_conflicts;
+mod occurrences;
 mod save;
 mod save_file_dialog;
+mod session;
 mod show_usages;
+mod swap_files;
 mod syntax_highlighting;
+mod tasks;
+mod undo_tree;
@@ -0,0 +1,57 @@
+use crate::*;
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/occurrences_test_1").with_files(["src/main.rs"]).build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| f
+        .get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines()
+        .any(|line| line.contents.text.starts_with("fn main()"))));
+
+    // on "x" in "let x = 1;"
+    assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+    for _ in 0..8 {
+        assert!(full_setup.send_key(Keycode::ArrowRight.to_key()));
+    }
+
+    full_setup
+}
+
+fn main_rs(f: &FullSetup) -> String {
+    f.fsf()
+        .descendant_checked("src/main.rs")
+        .unwrap()
+        .read_entire_file_to_string()
+        .unwrap()
+}
+
+#[test]
+fn next_occurrences_get_renamed_together() {
+    let mut full_setup = common_start();
+
+    // word first, then its two other occurrences
+    for _ in 0..3 {
+        assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.add_next_occurrence));
+    }
+    assert!(full_setup.type_in("y"));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    assert!(full_setup.wait_for(|f| main_rs(f) == "fn main() {\n    let y = 1;\n    println!(\"{}\", y + y);\n}\n"));
+
+    full_setup.finish();
+}
+
+#[test]
+fn all_occurrences_get_renamed_together() {
+    let mut full_setup = common_start();
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.select_all_occurrences));
+    assert!(full_setup.type_in("zz"));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    assert!(full_setup.wait_for(|f| main_rs(f) == "fn main() {\n    let zz = 1;\n    println!(\"{}\", zz + zz);\n}\n"));
+
+    full_setup.finish();
+}
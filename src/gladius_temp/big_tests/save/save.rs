@@ -1,3 +1,5 @@
+use std::path::PathBuf;
+
 use crate::*;
 
 fn common_start() -> FullSetup {
@@ -51,3 +53,65 @@ fn save_saves() {
 
     full_setup.finish();
 }
+
+fn main_rs(f: &FullSetup) -> String {
+    f.fsf()
+        .descendant_checked("src/main.rs")
+        .unwrap()
+        .read_entire_file_to_string()
+        .unwrap()
+}
+
+#[test]
+fn save_makes_backup() {
+    let mut config = Config::default();
+    config.global.backup = BackupMode::Tilde;
+
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/save_test_1")
+        .with_config(config)
+        .with_files(["src/main.rs"])
+        .build();
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    let before = main_rs(&full_setup);
+
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+
+    assert!(full_setup.wait_for(|f| main_rs(f) == format!("x{}", before)));
+    let backup = full_setup.fsf().descendant_checked("src/main.rs~").unwrap();
+    assert_eq!(backup.read_entire_file_to_string().unwrap(), before);
+
+    full_setup.finish();
+}
+
+#[test]
+fn failed_backup_stops_save() {
+    let mut config = Config::default();
+    config.global.backup = BackupMode::Dir(PathBuf::from(".backups"));
+
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/save_test_1")
+        .with_config(config)
+        .with_write_error(".backups/src%main.rs~", WriteError::DiskFull)
+        .with_files(["src/main.rs"])
+        .build();
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    let before = main_rs(&full_setup);
+
+    assert!(full_setup.type_in("x"));
+    assert!(full_setup.wait_for(|f| f
+        .get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines()
+        .any(|line| line.contents.text.starts_with("xuse"))));
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.save));
+    assert!(full_setup.wait_for(|f| f.dialog_mentions("Failed to save")));
+    assert!(full_setup.dialog_mentions("no space left on disk"));
+
+    assert_eq!(main_rs(&full_setup), before);
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_generic_dialog().is_none()));
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().is_editor_focused()));
+
+    full_setup.finish();
+}
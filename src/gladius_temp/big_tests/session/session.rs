@@ -0,0 +1,105 @@
+use std::path::PathBuf;
+
+use crate::*;
+
+// char index of "pub fn second" in src/lib.rs
+const SECOND_FN_IDX: usize = 19;
+
+fn cursor_line(f: &FullSetup) -> Option<String> {
+    f.get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines()
+        .next()
+        .map(|line| line.contents.text)
+}
+
+fn session_file() -> String {
+    let editor = |path: &str, char_idx: usize| SessionEditor {
+        path: PathBuf::from(path),
+        cursors: CursorSet::singleton(Cursor::new(char_idx)),
+        scroll_offset: XY::ZERO,
+    };
+
+    Session {
+        editors: vec![
+            editor("src/gone.rs", 0),
+            editor("src/lib.rs", SECOND_FN_IDX),
+            editor("src/main.rs", 0),
+        ],
+        display_idx: Some(1),
+        expanded: vec![PathBuf::from("src")],
+    }
+    .to_pretty_ron_string()
+    .unwrap()
+}
+
+#[test]
+fn session_is_saved_on_exit() {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/session_test_1")
+        .with_files(["src/main.rs", "src/lib.rs"])
+        .with_session()
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| cursor_line(f).map(|l| l.contains("pub fn first")).unwrap_or(false)));
+
+    assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+    assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+    assert!(full_setup.wait_for(|f| cursor_line(f).map(|l| l.contains("pub fn second")).unwrap_or(false)));
+
+    let finished = full_setup.finish();
+    let session: Session = finished
+        .fsf
+        .root()
+        .descendant_checked(SESSION_FILE_NAME)
+        .unwrap()
+        .read_entire_file_to_item()
+        .unwrap();
+
+    let paths: Vec<PathBuf> = session.editors.iter().map(|e| e.path.clone()).collect();
+    assert_eq!(paths, vec![PathBuf::from("src/main.rs"), PathBuf::from("src/lib.rs")]);
+    assert_eq!(session.display_idx, Some(1));
+    assert_eq!(session.editors[1].cursors.iter().next().unwrap().a, SECOND_FN_IDX);
+}
+
+#[test]
+fn session_is_restored_on_start() {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/session_test_1")
+        .with_mock_file(SESSION_FILE_NAME, session_file())
+        .with_session()
+        .build();
+
+    // missing file is skipped, the one that was shown is shown with its cursor
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| cursor_line(f).map(|l| l.contains("pub fn second")).unwrap_or(false)));
+
+    assert!(full_setup.wait_for(|f| {
+        f.get_file_tree_view()
+            .map(|tree| tree.items().iter().any(|item| item.label == "main.rs"))
+            .unwrap_or(false)
+    }));
+}
+
+#[test]
+fn session_is_ignored_without_flag() {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/session_test_1")
+        .with_mock_file(SESSION_FILE_NAME, session_file())
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.get_file_tree_view().is_some()));
+    full_setup.wait_frame();
+    assert!(!full_setup.is_editor_opened());
+
+    let finished = full_setup.finish();
+    // left as it was
+    assert_eq!(
+        finished
+            .fsf
+            .root()
+            .descendant_checked(SESSION_FILE_NAME)
+            .unwrap()
+            .read_entire_file_to_string()
+            .unwrap(),
+        session_file()
+    );
+}
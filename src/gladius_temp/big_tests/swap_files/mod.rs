@@ -0,0 +1 @@
+mod swap_files;
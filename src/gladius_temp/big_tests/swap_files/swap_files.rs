@@ -0,0 +1,111 @@
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use crate::*;
+
+const SWAP_FILE: &'static str = ".gladius_swap/left_by_crash.ron";
+
+fn swap_file(path: Option<&str>, contents: &str) -> String {
+    let swap = SwapFile {
+        path: path.map(PathBuf::from),
+        written: SystemTime::now(),
+        contents: contents.to_string(),
+    };
+
+    ron::ser::to_string_pretty(&swap, ron::ser::PrettyConfig::default()).unwrap()
+}
+
+#[test]
+fn swap_files_are_written_for_modified_buffers() {
+    let mut config = Config::default();
+    config.global.swap_interval_ms = Some(50);
+
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/swap_test_1")
+        .with_config(config)
+        .with_files(["src/main.rs"])
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.type_in("abc"));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("abc")));
+
+    // unnamed buffers are kept too
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.global.new_buffer));
+    assert!(full_setup.wait_for(|f| !f.first_line().starts_with("abc")));
+    assert!(full_setup.type_in("scratch"));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("scratch")));
+
+    let swaps = |f: &FullSetup| {
+        let mut swaps: Vec<(Option<PathBuf>, String)> = find_swap_files(&f.fsf().root())
+            .into_iter()
+            .map(|(_, swap)| (swap.path, swap.contents))
+            .collect();
+        swaps.sort();
+        swaps
+    };
+
+    // typing might be caught half way
+    assert!(full_setup.wait_for(|f| swaps(f).first().map(|(_, contents)| contents == "scratch").unwrap_or(false)));
+
+    let swaps = swaps(&full_setup);
+    assert_eq!(swaps.len(), 2);
+    assert_eq!(swaps[0].0, None);
+    assert_eq!(swaps[1].0, Some(PathBuf::from("src/main.rs")));
+    assert!(swaps[1].1.starts_with("abcfn main() {"));
+}
+
+#[test]
+fn swap_file_is_recovered() {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/swap_test_1")
+        .with_mock_file(SWAP_FILE, swap_file(Some("src/main.rs"), "recovered\n"))
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.dialog_mentions("src/main.rs")));
+    assert!(full_setup.focused_button().contains("Recover"));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_generic_dialog().is_none()));
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("recovered")));
+    assert!(full_setup.fsf().descendant_checked(SWAP_FILE).is_none());
+
+    // recovered contents are an edit of the file, so undo brings back what's on disk
+    assert!(full_setup.send_key(Keycode::Char('z').to_key().with_ctrl()));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("fn main() {")));
+}
+
+#[test]
+fn swap_file_is_diffed_with_file_on_disk() {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/swap_test_1")
+        .with_mock_file(SWAP_FILE, swap_file(Some("src/main.rs"), "recovered\n"))
+        .build();
+
+    assert!(full_setup.wait_for(|f| f.dialog_mentions("src/main.rs")));
+
+    assert!(full_setup.send_key(Keycode::ArrowRight.to_key().with_alt()));
+    assert!(full_setup.wait_for(|f| f.focused_button().contains("Diff")));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_diff_view().is_some()));
+    assert!(full_setup.get_generic_dialog().is_none());
+    assert!(full_setup.fsf().descendant_checked(SWAP_FILE).is_none());
+}
+
+#[test]
+fn swap_file_is_discarded() {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/swap_test_1")
+        .with_mock_file(SWAP_FILE, swap_file(None, "never saved"))
+        .build();
+
+    // nothing to diff unnamed buffer with
+    assert!(full_setup.wait_for(|f| f.dialog_mentions("unnamed buffer")));
+    assert_eq!(full_setup.get_generic_dialog().unwrap().buttons().len(), 2);
+
+    assert!(full_setup.send_key(Keycode::ArrowRight.to_key().with_alt()));
+    assert!(full_setup.wait_for(|f| f.focused_button().contains("Discard")));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_generic_dialog().is_none()));
+    assert!(!full_setup.is_editor_opened());
+    assert!(full_setup.fsf().descendant_checked(SWAP_FILE).is_none());
+}
@@ -0,0 +1,104 @@
+use crate::*;
+
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/tasks_test_1").build();
+
+    assert!(full_setup.wait_for(|f| f.is_no_editor_opened()));
+
+    full_setup
+}
+
+fn choose_from_context_bar(full_setup: &mut FullSetup, option: &str) {
+    full_setup.send_key(full_setup.config().keyboard_config.global.everything_bar);
+    assert!(full_setup.wait_for(|f| f.get_context_bar().is_some()));
+
+    let selected = |f: &FullSetup| f.get_context_bar().and_then(|c| c.selected_option().map(|c| c.trim().to_string()));
+
+    for _ in 0..20 {
+        let before = selected(full_setup);
+        if before.as_deref() == Some(option) {
+            break;
+        }
+        assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+        assert!(full_setup.wait_for(|f| selected(f) != before));
+    }
+    assert_eq!(selected(full_setup).as_deref(), Some(option));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_context_bar().is_none()));
+}
+
+fn task_lines(f: &FullSetup) -> Vec<String> {
+    f.get_task_output().map(|t| t.lines()).unwrap_or_default()
+}
+
+#[test]
+fn task_runs_without_editor_and_jumps_to_position() {
+    let mut full_setup = common_start();
+
+    choose_from_context_bar(&mut full_setup, "run task: check");
+    assert!(full_setup.wait_for(|f| f.get_task_output().map(|t| t.is_focused()).unwrap_or(false)));
+
+    // first lines come in while the task still runs
+    assert!(full_setup.wait_for(|f| task_lines(f).len() == 2));
+    assert_eq!(full_setup.get_task_output().unwrap().status(), "task [check] running");
+    assert_eq!(
+        task_lines(&full_setup),
+        vec!["checking...".to_string(), "error: src/main.rs:2:5: unused variable".to_string()]
+    );
+
+    // colors of the output are kept
+    let error_style = full_setup.get_task_output().unwrap().style_of("error:").unwrap();
+    assert_eq!(error_style.foreground, ansi_256_to_color(1));
+    let message_style = full_setup.get_task_output().unwrap().style_of("unused").unwrap();
+    assert_ne!(message_style.foreground, ansi_256_to_color(1));
+
+    assert!(full_setup.wait_for(|f| task_lines(f).len() == 3));
+    assert!(full_setup.wait_for(|f| f.get_task_output().unwrap().status() == "task [check] exited with code 0"));
+    assert_eq!(task_lines(&full_setup)[2], "done");
+
+    // selection follows output, so the error is one line up
+    assert!(full_setup.send_key(Keycode::ArrowUp.to_key()));
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+    assert!(full_setup.wait_for(|f| f.get_first_editor().unwrap().is_editor_focused()));
+    let cursor_line = full_setup
+        .get_first_editor()
+        .unwrap()
+        .get_visible_cursor_lines_with_coded_cursors()
+        .next()
+        .unwrap();
+    assert!(cursor_line.contents.text.starts_with("    #let unused = 1;"));
+    assert_eq!(cursor_line.visible_idx, 2);
+
+    full_setup.finish();
+}
+
+#[test]
+fn everything_bar_closes_on_escape() {
+    let mut full_setup = common_start();
+
+    full_setup.send_key(full_setup.config().keyboard_config.global.everything_bar);
+    assert!(full_setup.wait_for(|f| f.get_context_bar().is_some()));
+
+    assert!(full_setup.send_key(Keycode::Esc.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_context_bar().is_none()));
+    assert!(full_setup.wait_for(|f| f.get_frame().unwrap().get_no_editor().unwrap().is_focused()));
+
+    full_setup.finish();
+}
+
+#[test]
+fn running_task_can_be_stopped() {
+    let mut full_setup = common_start();
+
+    choose_from_context_bar(&mut full_setup, "run task: forever");
+    assert!(full_setup.wait_for(|f| task_lines(f) == vec!["started".to_string()]));
+    assert_eq!(full_setup.get_task_output().unwrap().status(), "task [forever] running");
+
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.task.stop));
+    assert!(full_setup.wait_for(|f| f.get_task_output().unwrap().status() == "task [forever] stopped"));
+
+    full_setup.finish();
+}
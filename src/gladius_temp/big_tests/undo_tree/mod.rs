@@ -0,0 +1 @@
+mod undo_tree;
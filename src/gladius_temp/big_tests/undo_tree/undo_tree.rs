@@ -0,0 +1,90 @@
+use crate::*;
+
+/*
+Leaves history with two branches: "abc" typed and undone, then "xyz" typed in its place.
+ */
+fn common_start() -> FullSetup {
+    let mut full_setup: FullSetup = FullSetup::new("./test_envs/undo_tree_test_1").with_files(["src/main.rs"]).build();
+
+    assert!(full_setup.wait_for(|f| f.is_editor_opened()));
+
+    assert!(full_setup.type_in("abc"));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("abc")));
+
+    assert!(full_setup.send_key(Keycode::Char('z').to_key().with_ctrl()));
+    assert!(full_setup.wait_for(|f| !f.first_line().starts_with("abc")));
+
+    assert!(full_setup.type_in("xyz"));
+    assert!(full_setup.wait_for(|f| f.first_line().contains("xyz")));
+
+    full_setup
+}
+
+fn open_undo_tree(full_setup: &mut FullSetup) {
+    assert!(full_setup.send_key(full_setup.config().keyboard_config.editor.undo_tree));
+    assert!(full_setup.wait_for(|f| f.get_undo_tree().map(|u| u.is_focused()).unwrap_or(false)));
+}
+
+fn highlighted_row(f: &FullSetup) -> String {
+    f.get_undo_tree().unwrap().highlighted_row().unwrap_or_default()
+}
+
+#[test]
+fn undo_tree_shows_branches() {
+    let mut full_setup = common_start();
+
+    open_undo_tree(&mut full_setup);
+
+    let undo_tree = full_setup.get_undo_tree().unwrap();
+    let rows = undo_tree.rows();
+
+    assert!(rows.iter().any(|row| row.contains('╯')), "no fork in {:?}", rows);
+    assert!(rows.last().unwrap().ends_with("start"));
+    // newest state is the current one, and it's highlighted on open
+    assert!(rows[0].trim_start().starts_with('@'), "{:?}", rows);
+    assert_eq!(undo_tree.highlighted_row(), Some(rows[0].clone()));
+    assert!(undo_tree.preview().iter().any(|line| line.contains("xyz")));
+}
+
+#[test]
+fn undo_tree_jumps_to_other_branch() {
+    let mut full_setup = common_start();
+
+    open_undo_tree(&mut full_setup);
+
+    let on_abc_branch = |f: &FullSetup| f.get_undo_tree().unwrap().preview().iter().any(|line| line.contains("abc"));
+
+    for _ in 0..10 {
+        if on_abc_branch(&full_setup) {
+            break;
+        }
+        assert!(full_setup.send_key(Keycode::ArrowDown.to_key()));
+        full_setup.wait_frame();
+    }
+    assert!(on_abc_branch(&full_setup));
+
+    assert!(full_setup.send_key(Keycode::Enter.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_undo_tree().is_none()));
+    assert!(full_setup.wait_for(|f| f.first_line().starts_with("abc")));
+    assert!(!full_setup.first_line().contains("xyz"));
+
+    // the "xyz" branch is not lost, redo of editor follows the branch we jumped to
+    open_undo_tree(&mut full_setup);
+    assert!(full_setup.get_undo_tree().unwrap().rows().iter().any(|row| row.contains('╯')));
+}
+
+#[test]
+fn undo_tree_as_of_query_and_close() {
+    let mut full_setup = common_start();
+
+    open_undo_tree(&mut full_setup);
+
+    // everything happened within last day, so state from a day ago is the start
+    assert!(full_setup.type_in("1d"));
+    assert!(full_setup.wait_for(|f| f.get_undo_tree().unwrap().query_line().ends_with("1d")));
+    assert!(full_setup.wait_for(|f| highlighted_row(f).ends_with("start")));
+
+    assert!(full_setup.send_key(Keycode::Esc.to_key()));
+    assert!(full_setup.wait_for(|f| f.get_undo_tree().is_none()));
+    assert!(full_setup.first_line().contains("xyz"));
+}
@@ -1,9 +1,9 @@
 use std::ffi::OsStr;
 use std::option::Option;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
 use std::sync::{Arc, RwLock};
 use std::thread::JoinHandle;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 use crossbeam_channel::{select, Receiver, Sender};
 use log::{debug, error, warn, LevelFilter};
@@ -18,6 +18,10 @@ pub struct FullSetupBuilder {
     recording: bool,
     step_frame: bool,
     frame_based_wait: bool,
+    git: Option<MockGitProvider>,
+    session: bool,
+    extra_files: Vec<(PathBuf, Vec<u8>)>,
+    write_errors: Vec<(PathBuf, WriteError)>,
 }
 
 impl FullSetupBuilder {
@@ -36,6 +40,52 @@ impl FullSetupBuilder {
         FullSetupBuilder { files, ..self }
     }
 
+    /*
+    Pretends the workspace is a git repository, with given contents of path in HEAD.
+     */
+    pub fn with_git_head_version<P: AsRef<Path>, T: Into<String>>(self, path: P, contents: T) -> Self {
+        let git = self.git.unwrap_or_default().with_head_version(path, contents);
+        FullSetupBuilder { git: Some(git), ..self }
+    }
+
+    pub fn with_git_file_status<P: AsRef<Path>>(self, path: P, status: GitFileStatus) -> Self {
+        let git = self.git.unwrap_or_default().with_file_status(path, status);
+        FullSetupBuilder { git: Some(git), ..self }
+    }
+
+    pub fn with_git_blame<P: AsRef<Path>>(self, path: P, blame: Blame) -> Self {
+        let git = self.git.unwrap_or_default().with_blame(path, blame);
+        FullSetupBuilder { git: Some(git), ..self }
+    }
+
+    pub fn with_git_commit<H: Into<String>, T: Into<String>>(self, hash: H, contents: T) -> Self {
+        let git = self.git.unwrap_or_default().with_commit(hash, contents);
+        FullSetupBuilder { git: Some(git), ..self }
+    }
+
+    /*
+    Restores session on start and saves it on exit, off by default so tests don't depend on each other.
+     */
+    pub fn with_session(self) -> Self {
+        Self { session: true, ..self }
+    }
+
+    /*
+    Adds (or overwrites) a file in mock filesystem, on top of test env.
+     */
+    pub fn with_mock_file<P: AsRef<Path>, B: Into<Vec<u8>>>(mut self, path: P, bytes: B) -> Self {
+        self.extra_files.push((path.as_ref().to_path_buf(), bytes.into()));
+        self
+    }
+
+    /*
+    Writes to path will fail with given error, see MockFS::with_write_error.
+     */
+    pub fn with_write_error<P: AsRef<Path>>(mut self, path: P, error: WriteError) -> Self {
+        self.write_errors.push((path.as_ref().to_path_buf(), error));
+        self
+    }
+
     pub fn with_recording(self) -> Self {
         Self { recording: true, ..self }
     }
@@ -76,7 +126,13 @@ impl FullSetupBuilder {
 
         let theme = Theme::default();
 
-        let mock_fs = MockFS::generate_from_real(self.path).unwrap();
+        let mut mock_fs = MockFS::generate_from_real(self.path).unwrap();
+        for (path, bytes) in self.extra_files.into_iter() {
+            mock_fs = mock_fs.with_file(path, bytes);
+        }
+        for (path, error) in self.write_errors.into_iter() {
+            mock_fs = mock_fs.with_write_error(path, error);
+        }
         let fsf = mock_fs.to_fsf();
         let (input, input_sender) = MockInput::new();
         let (output, output_receiver) = MockOutput::new(self.size, self.step_frame, theme.clone());
@@ -100,7 +156,7 @@ impl FullSetupBuilder {
         let mock_navcomp_loader =
             Arc::new(Box::new(MockNavcompLoader::new(mock_navcomp_event_sender, comp_matcher, symbol_matcher)) as Box<dyn NavCompLoader>);
 
-        let providers = Providers::new(
+        let mut providers = Providers::new(
             local_config,
             local_fsf,
             local_clipboard,
@@ -110,9 +166,14 @@ impl FullSetupBuilder {
             vec![],
         );
 
+        if let Some(git) = self.git {
+            providers = providers.with_git(Arc::new(Box::new(git) as Box<dyn GitProvider>));
+        }
+
         let providers_clone = providers.clone();
 
-        let handle = std::thread::spawn(move || run_gladius(providers_clone, input, output, files));
+        let session = self.session;
+        let handle = std::thread::spawn(move || run_gladius(providers_clone, input, output, files, session));
 
         FullSetup {
             fsf,
@@ -146,6 +207,10 @@ impl FullSetup {
             recording: false,
             step_frame: false,
             frame_based_wait: false,
+            git: None,
+            session: false,
+            extra_files: vec![],
+            write_errors: vec![],
         }
     }
 
@@ -197,6 +262,38 @@ impl FullSetup {
         &self.fsf
     }
 
+    /*
+    Text of first visible line of first editor, empty if there's none.
+     */
+    pub fn first_line(&self) -> String {
+        self.get_first_editor()
+            .and_then(|editor| editor.get_all_visible_lines().next())
+            .map(|line| line.contents.text)
+            .unwrap_or_default()
+    }
+
+    pub fn on_screen(&self, what: &str) -> bool {
+        self.get_frame()
+            .map(|frame| frame.buffer.lines_iter().any(|line| line.text.contains(what)))
+            .unwrap_or(false)
+    }
+
+    pub fn on_disk(&self, path: &str) -> Vec<u8> {
+        self.fsf().descendant_checked(path).unwrap().read_entire_file().unwrap()
+    }
+
+    pub fn dialog_mentions(&self, what: &str) -> bool {
+        self.get_generic_dialog()
+            .map(|dialog| dialog.lines().iter().any(|line| line.contains(what)))
+            .unwrap_or(false)
+    }
+
+    pub fn focused_button(&self) -> String {
+        self.get_generic_dialog()
+            .and_then(|dialog| dialog.focused_button())
+            .unwrap_or_default()
+    }
+
     /*
     returns lines which have cursors (editor must be opened)
      */
@@ -236,6 +333,26 @@ impl FullSetup {
         self.last_frame.as_ref().map(|frame| frame.get_code_results_view()).flatten()
     }
 
+    pub fn get_diff_view(&self) -> Option<DiffViewInterpreter<'_>> {
+        self.last_frame.as_ref().map(|frame| frame.get_diff_view()).flatten()
+    }
+
+    pub fn get_generic_dialog(&self) -> Option<GenericDialogInterpreter<'_>> {
+        self.last_frame.as_ref().map(|frame| frame.get_generic_dialog()).flatten()
+    }
+
+    pub fn get_undo_tree(&self) -> Option<UndoTreeInterpreter<'_>> {
+        self.last_frame.as_ref().map(|frame| frame.get_undo_tree()).flatten()
+    }
+
+    pub fn get_task_output(&self) -> Option<TaskOutputInterpreter<'_>> {
+        self.last_frame.as_ref().map(|frame| frame.get_task_output()).flatten()
+    }
+
+    pub fn get_context_bar(&self) -> Option<ContextBarWidgetInterpreter<'_>> {
+        self.last_frame.as_ref().map(|frame| frame.get_context_bar()).flatten()
+    }
+
     pub fn get_fuzzy_search(&self) -> Option<FuzzySearchInterpreter> {
         self.last_frame.as_ref().map(|frame| frame.get_fuzzy_search()).flatten()
     }
@@ -280,6 +397,8 @@ impl FullSetup {
          */
 
         if !self.frame_based_wait {
+            // frames keep coming on ticks, so timeout is for all of them, not for each
+            let deadline = Instant::now() + Self::DEFAULT_TIMEOUT;
             loop {
                 select! {
                     recv(self.output_receiver) -> frame_res => {
@@ -297,7 +416,7 @@ impl FullSetup {
                             }
                         }
                     },
-                    default(Self::DEFAULT_TIMEOUT) => {
+                    default(deadline.saturating_duration_since(Instant::now())) => {
                         error!("timeout, making screenshot.");
                         self.screenshot();
                         return false;
@@ -14,11 +14,12 @@ impl NavCompLoader for RealNavCompLoader {
     /*
     Config is required to "know" where the LSP servers are. We will provide reasonable defaults,
     but option to override is essential.
-    It just occurred to me: I might want to have overrides local to workspace.
+    Overrides local to workspace come with WorkspaceSettings.
      */
     fn load_handler(
         &self,
         config: &ConfigRef,
+        workspace_settings: &WorkspaceSettings,
         project_scope: &ProjectScope,
         navcomp_tick_sender: NavCompTickSender,
     ) -> Result<Box<dyn Handler>, HandlerLoadError> {
@@ -32,6 +33,7 @@ impl NavCompLoader for RealNavCompLoader {
             }
             Some(handler_id) => Ok(handler_factory(
                 config,
+                workspace_settings,
                 &handler_id,
                 project_scope.path.clone(),
                 navcomp_tick_sender.clone(),
@@ -6,7 +6,17 @@ use log::{debug, error};
 
 use crate::*;
 
-pub fn run_gladius<I: Input, O: FinalOutput>(providers: Providers, input: I, mut output: O, files: Vec<PathBuf>) {
+/*
+With use_session, what was open on last exit is reopened (before files), and what's open now is saved on
+exit. See Session.
+
+Modified buffers are written to swap files every once in a while and on exit, ones left by previous
+session are offered for recovery. See SwapWriter.
+
+Files of open buffers are checked for changes made by other programs every once in a while. See
+check_external_changes.
+ */
+pub fn run_gladius<I: Input, O: FinalOutput>(providers: Providers, input: I, mut output: O, files: Vec<PathBuf>, use_session: bool) {
     // Loading / Building workspace file
     let workspace_dir = providers.fsf().root();
     let (workspace_op, _scope_errors): (Option<Workspace>, ScopeLoadErrors) = match Workspace::try_load(workspace_dir.clone()) {
@@ -76,6 +86,9 @@ pub fn run_gladius<I: Input, O: FinalOutput>(providers: Providers, input: I, mut
 
     // At this point it is guaranteed that we have a Workspace present, though it might be not saved!
 
+    // From now on, everybody gets to see workspace settings.
+    let providers = providers.with_workspace_settings(WorkspaceSettingsRef::new(workspace.settings().clone()));
+
     // Initializing handlers
     let scope_errors: Vec<HandlerLoadError> = match workspace.initialize_handlers(providers.clone()) {
         Ok(maybe_errors) => maybe_errors,
@@ -90,6 +103,11 @@ pub fn run_gladius<I: Input, O: FinalOutput>(providers: Providers, input: I, mut
     }
 
     let mut main_view = MainView::new(providers.clone());
+    if use_session {
+        if let Some(session) = Session::load(&providers.fsf().root()) {
+            main_view.restore_session(&session);
+        }
+    }
     for f in files.iter() {
         if !providers
             .fsf()
@@ -101,6 +119,23 @@ pub fn run_gladius<I: Input, O: FinalOutput>(providers: Providers, input: I, mut
         }
     }
 
+    let swaps = find_swap_files(&providers.fsf().root());
+    if !swaps.is_empty() {
+        debug!("found {} swap files", swaps.len());
+        main_view.offer_swap_recovery(swaps);
+    }
+
+    let mut swap_writer = SwapWriter::new(&providers.fsf().root());
+    let swap_tick_receiver = match providers.config().global.get_swap_interval() {
+        Some(interval) => crossbeam_channel::tick(interval),
+        None => crossbeam_channel::never(),
+    };
+
+    let file_check_tick_receiver = match providers.config().global.get_file_check_interval() {
+        Some(interval) => crossbeam_channel::tick(interval),
+        None => crossbeam_channel::never(),
+    };
+
     let mut recorded_input: Vec<InputEvent> = Vec::new();
 
     let nav_comp_tick_receiver = providers.navcomp_group().try_read().map(|lock| lock.recvr().clone()).unwrap(); // TODO unwrap
@@ -138,8 +173,12 @@ pub fn run_gladius<I: Input, O: FinalOutput>(providers: Providers, input: I, mut
                             recorded_input.push(ie.clone());
                         }
 
+                        // embedded terminal gets all the keys, it handles "escape" on its own
+                        let terminal_focused = main_view.is_terminal_focused();
+
                         match ie {
-                            InputEvent::KeyInput(key) if key.as_focus_update().is_some() && key.modifiers.alt => {
+                            _ if terminal_focused => {}
+                            InputEvent::KeyInput(key) if key.as_focus_update().is_some() && key.modifiers.alt && !key.modifiers.shift => {
                                 ie = InputEvent::FocusUpdate(key.as_focus_update().unwrap());
                             },
                             InputEvent::KeyInput(key) if key == providers.config().keyboard_config.global.everything_bar => {
@@ -176,6 +215,29 @@ pub fn run_gladius<I: Input, O: FinalOutput>(providers: Providers, input: I, mut
                     }
                 }
             }
+
+            recv(swap_tick_receiver) -> _ => {
+                write_swap_files(&providers, &mut swap_writer);
+            }
+
+            recv(file_check_tick_receiver) -> _ => {
+                main_view.check_files_on_disk();
+            }
+        }
+    }
+
+    if providers.config().global.get_swap_interval().is_some() {
+        // previous batch might be still running, and then this one would be skipped
+        swap_writer.wait();
+        write_swap_files(&providers, &mut swap_writer);
+        swap_writer.wait();
+    }
+
+    providers.undo_history_writer().wait();
+
+    if use_session {
+        if let Err(e) = main_view.session().save(&providers.fsf().root()) {
+            error!("failed saving session: {:?}", e);
         }
     }
 
@@ -210,3 +272,14 @@ pub fn run_gladius<I: Input, O: FinalOutput>(providers: Providers, input: I, mut
         }
     }
 }
+
+fn write_swap_files(providers: &Providers, swap_writer: &mut SwapWriter) {
+    match providers.buffer_register().try_read() {
+        Ok(register) => {
+            swap_writer.update(register.buffers());
+        }
+        Err(e) => {
+            error!("failed to lock buffer register, not writing swap files: {}", e);
+        }
+    }
+}
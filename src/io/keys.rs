@@ -142,6 +142,16 @@ impl Keycode {
         return *self == Keycode::ArrowRight || *self == Keycode::ArrowLeft || *self == Keycode::ArrowUp || *self == Keycode::ArrowDown;
     }
 
+    pub fn as_arrow(&self) -> Option<Arrow> {
+        match self {
+            Keycode::ArrowUp => Some(Arrow::Up),
+            Keycode::ArrowDown => Some(Arrow::Down),
+            Keycode::ArrowLeft => Some(Arrow::Left),
+            Keycode::ArrowRight => Some(Arrow::Right),
+            _ => None,
+        }
+    }
+
     pub fn to_key(self) -> Key {
         Key {
             keycode: self,
@@ -123,8 +123,8 @@ impl Output for OverOutput<'_> {
         if let Some(intersect_rect) = meta.rect.intersect(self.visible_rect()) {
             // this will give us intersection size
             meta.rect = intersect_rect;
-            // but we also need to take account for the offset
-            meta.rect.pos += upper_left;
+            // but we also need to take account for the offset, visible part begins at parent's zero
+            meta.rect.pos = meta.rect.pos - upper_left;
 
             self.output.emit_metadata(meta);
         } else {
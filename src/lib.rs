@@ -19,6 +19,9 @@ pub use experiments::*;
 mod fs;
 pub use fs::*;
 
+mod git;
+pub use git::*;
+
 mod io;
 pub use io::*;
 
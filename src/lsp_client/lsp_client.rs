@@ -126,13 +126,13 @@ impl LspWrapper {
         })
     }
 
-    fn get_formatting_options(&self) -> lsp_types::FormattingOptions {
+    fn get_formatting_options(settings: &FormattingSettings) -> lsp_types::FormattingOptions {
         lsp_types::FormattingOptions {
-            tab_size: 4,
-            insert_spaces: true,
+            tab_size: settings.tab_width as u32,
+            insert_spaces: settings.indent_style == IndentStyle::Spaces,
             properties: Default::default(),
-            trim_trailing_whitespace: Some(true),
-            insert_final_newline: Some(true),
+            trim_trailing_whitespace: Some(settings.trim_trailing_whitespace),
+            insert_final_newline: Some(settings.insert_final_newline),
             trim_final_newlines: Some(true),
         }
     }
@@ -316,10 +316,14 @@ impl LspWrapper {
         })
     }
 
-    pub fn text_document_formatting(&mut self, url: Url) -> Result<LSPPromise<lsp_types::request::Formatting>, LspWriteError> {
+    pub fn text_document_formatting(
+        &mut self,
+        url: Url,
+        settings: &FormattingSettings,
+    ) -> Result<LSPPromise<lsp_types::request::Formatting>, LspWriteError> {
         self.send_message::<lsp_types::request::Formatting>(lsp_types::DocumentFormattingParams {
             text_document: lsp_types::TextDocumentIdentifier { uri: url },
-            options: self.get_formatting_options(),
+            options: Self::get_formatting_options(settings),
             work_done_progress_params: Default::default(),
         })
     }
@@ -0,0 +1,78 @@
+use crate::*;
+
+pub struct DiffViewInterpreter<'a> {
+    meta: &'a Metadata,
+    output: &'a MetaOutputFrame,
+
+    left_editor: &'a Metadata,
+    right_editor: &'a Metadata,
+}
+
+impl<'a> DiffViewInterpreter<'a> {
+    pub fn new(output: &'a MetaOutputFrame, meta: &'a Metadata) -> Option<Self> {
+        debug_assert!(meta.typename == DiffView::TYPENAME);
+
+        let mut editors: Vec<&Metadata> = output
+            .get_meta_by_type(EditorWidget::TYPENAME)
+            .filter(|e| meta.rect.contains_rect(e.rect))
+            .collect();
+        editors.sort_by_key(|e| e.rect.pos.x);
+
+        if editors.len() != 2 {
+            return None;
+        }
+
+        Some(Self {
+            meta,
+            output,
+            left_editor: editors[0],
+            right_editor: editors[1],
+        })
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.meta.focused
+    }
+
+    pub fn is_left_focused(&self) -> bool {
+        self.left_editor.focused
+    }
+
+    pub fn titles_line(&self) -> String {
+        self.output
+            .buffer
+            .lines_iter()
+            .with_rect(self.meta.rect)
+            .next()
+            .map(|line| line.text)
+            .unwrap_or_default()
+    }
+
+    fn editor(&self, left: bool) -> &'a Metadata {
+        if left {
+            self.left_editor
+        } else {
+            self.right_editor
+        }
+    }
+
+    /*
+    Visible rows of given side, with trailing whitespace trimmed.
+     */
+    pub fn rows(&self, left: bool) -> Vec<String> {
+        self.output
+            .buffer
+            .lines_iter()
+            .with_rect(self.editor(left).rect)
+            .map(|line| line.text.trim_end().to_string())
+            .collect()
+    }
+
+    pub fn background_at(&self, left: bool, row: u16, col: u16) -> Option<Color> {
+        let rect = self.editor(left).rect;
+        match &self.output.buffer[rect.pos + XY::new(col, row)] {
+            Cell::Begin { style, .. } => Some(style.background),
+            Cell::Continuation => None,
+        }
+    }
+}
@@ -209,6 +209,44 @@ impl<'a> EditorInterpreter<'a> {
             })
     }
 
+    /*
+    Returns (1-based display line idx, marker) for every non-empty cell of the line markers column (git gutter),
+    which is the column right after the line numbers.
+     */
+    pub fn get_line_markers(&self) -> Vec<(usize, String)> {
+        let offset = self.scroll.lowest_number().unwrap();
+        let numbers_rect = self.scroll.rect();
+        let x = numbers_rect.lower_right().x;
+
+        let mut result = Vec::new();
+        for y in numbers_rect.pos.y..numbers_rect.lower_right().y {
+            if let Cell::Begin { style: _, grapheme } = &self.mock_output.buffer[XY::new(x, y)] {
+                if !grapheme.trim().is_empty() {
+                    result.push(((y - numbers_rect.pos.y) as usize + offset, grapheme.clone()));
+                }
+            }
+        }
+        result
+    }
+
+    /*
+    Returns (1-based display line idx, background) for every visible line. Background is taken from the
+    last column, so cursor or highlighting do not interfere.
+     */
+    pub fn get_line_backgrounds(&self) -> Vec<(usize, Color)> {
+        let offset = self.scroll.lowest_number().unwrap();
+        let rect = self.rect_without_scroll;
+        let x = rect.lower_right().x - 1;
+
+        let mut result = Vec::new();
+        for y in rect.pos.y..rect.lower_right().y {
+            if let Cell::Begin { style, grapheme: _ } = &self.mock_output.buffer[XY::new(x, y)] {
+                result.push(((y - rect.pos.y) as usize + offset, style.background));
+            }
+        }
+        result
+    }
+
     pub fn completions(&self) -> Option<&CompletionInterpreter<'a>> {
         self.compeltion_op.as_ref()
     }
@@ -27,4 +27,16 @@ impl<'a> FuzzySearchInterpreter<'a> {
             .trim()
             .to_string()
     }
+
+    // lines below the query, trimmed, empty ones skipped
+    pub fn items(&self) -> Vec<String> {
+        self.output
+            .buffer
+            .lines_iter()
+            .with_rect(self.meta.rect)
+            .skip(1)
+            .map(|line| line.text.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
 }
@@ -0,0 +1,44 @@
+use crate::*;
+
+pub struct GenericDialogInterpreter<'a> {
+    meta: &'a Metadata,
+    output: &'a MetaOutputFrame,
+}
+
+impl<'a> GenericDialogInterpreter<'a> {
+    pub fn new(output: &'a MetaOutputFrame, meta: &'a Metadata) -> Self {
+        debug_assert!(meta.typename == GenericDialog::TYPENAME);
+
+        Self { meta, output }
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.meta.focused
+    }
+
+    // non-empty lines, including the one with buttons
+    pub fn lines(&self) -> Vec<String> {
+        self.output
+            .buffer
+            .lines_iter()
+            .with_rect(self.meta.rect)
+            .map(|line| line.text.trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    pub fn buttons(&self) -> Vec<ButtonWidgetInterpreter<'a>> {
+        self.output
+            .get_meta_by_type(ButtonWidget::TYPENAME)
+            .filter(|meta| self.meta.rect.contains_rect(meta.rect))
+            .map(|meta| ButtonWidgetInterpreter::new(meta, self.output))
+            .collect()
+    }
+
+    pub fn focused_button(&self) -> Option<String> {
+        self.buttons()
+            .into_iter()
+            .find(|button| button.is_focused())
+            .map(|button| button.contents())
+    }
+}
@@ -44,4 +44,38 @@ impl MetaOutputFrame {
             .map(|meta| CodeResultsViewInterpreter::new(self, meta))
             .next()
     }
+
+    pub fn get_diff_view(&self) -> Option<DiffViewInterpreter> {
+        self.get_meta_by_type(DiffView::TYPENAME)
+            .map(|meta| DiffViewInterpreter::new(self, meta))
+            .flatten()
+            .next()
+    }
+
+    pub fn get_generic_dialog(&self) -> Option<GenericDialogInterpreter> {
+        self.get_meta_by_type(GenericDialog::TYPENAME)
+            .map(|meta| GenericDialogInterpreter::new(self, meta))
+            .next()
+    }
+
+    pub fn get_undo_tree(&self) -> Option<UndoTreeInterpreter> {
+        self.get_meta_by_type(UndoTreeWidget::TYPENAME)
+            .map(|meta| UndoTreeInterpreter::new(self, meta))
+            .next()
+    }
+
+    pub fn get_task_output(&self) -> Option<TaskOutputInterpreter> {
+        self.get_meta_by_type(TaskOutputView::TYPENAME)
+            .map(|meta| TaskOutputInterpreter::new(self, meta))
+            .next()
+    }
+
+    // context bar of MainView, editors have their own (see EditorInterpreter::context_bar_op)
+    pub fn get_context_bar(&self) -> Option<ContextBarWidgetInterpreter> {
+        let editor_rects: Vec<Rect> = self.get_meta_by_type(EditorView::TYPENAME).map(|meta| meta.rect).collect();
+        self.get_meta_by_type(ContextBarWidget::TYPENAME)
+            .filter(|meta| !editor_rects.iter().any(|rect| rect.contains_rect(meta.rect)))
+            .map(|meta| ContextBarWidgetInterpreter::new(meta, self))
+            .next()
+    }
 }
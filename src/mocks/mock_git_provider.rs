@@ -0,0 +1,65 @@
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+use crate::*;
+
+/*
+Paths are relative to workspace root, same as SPath::relative_path.
+ */
+#[derive(Debug, Default)]
+pub struct MockGitProvider {
+    pub head_versions: HashMap<PathBuf, String>,
+    pub status: GitStatus,
+    pub blames: HashMap<PathBuf, Blame>,
+    pub commits: HashMap<String, String>,
+}
+
+impl MockGitProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_head_version<P: AsRef<Path>, T: Into<String>>(mut self, path: P, contents: T) -> Self {
+        self.head_versions.insert(path.as_ref().to_path_buf(), contents.into());
+        self
+    }
+
+    pub fn with_file_status<P: AsRef<Path>>(mut self, path: P, status: GitFileStatus) -> Self {
+        self.status = self.status.with_entry(path, status);
+        self
+    }
+
+    pub fn with_blame<P: AsRef<Path>>(mut self, path: P, blame: Blame) -> Self {
+        self.blames.insert(path.as_ref().to_path_buf(), blame);
+        self
+    }
+
+    pub fn with_commit<H: Into<String>, T: Into<String>>(mut self, hash: H, contents: T) -> Self {
+        self.commits.insert(hash.into(), contents.into());
+        self
+    }
+}
+
+impl GitProvider for MockGitProvider {
+    fn head_version(&self, path: &SPath) -> Result<Option<String>, GitError> {
+        Ok(self.head_versions.get(&path.relative_path()).cloned())
+    }
+
+    fn status(&self) -> Result<GitStatus, GitError> {
+        Ok(self.status.clone())
+    }
+
+    fn blame(&self, path: &SPath) -> Result<Blame, GitError> {
+        self.blames
+            .get(&path.relative_path())
+            .cloned()
+            .ok_or_else(|| GitError::CommandFailed(format!("no blame for {}", path)))
+    }
+
+    fn show_commit(&self, hash: &str) -> Result<String, GitError> {
+        self.commits
+            .get(hash)
+            .cloned()
+            .ok_or_else(|| GitError::CommandFailed(format!("unknown commit {}", hash)))
+    }
+}
@@ -28,6 +28,7 @@ impl NavCompLoader for MockNavcompLoader {
     fn load_handler(
         &self,
         config: &ConfigRef,
+        _workspace_settings: &WorkspaceSettings,
         project_scope: &ProjectScope,
         navcomp_tick_sender: NavCompTickSender,
     ) -> Result<Box<dyn Handler>, HandlerLoadError> {
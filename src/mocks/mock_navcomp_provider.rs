@@ -244,7 +244,7 @@ impl NavCompProvider for MockNavCompProvider {
         res
     }
 
-    fn todo_reformat(&self, _path: &SPath) -> Option<FormattingPromise> {
+    fn todo_reformat(&self, _path: &SPath, _settings: &FormattingSettings) -> Option<FormattingPromise> {
         todo!()
     }
 
@@ -17,6 +17,9 @@ pub use completion_interpreter::CompletionInterpreter;
 mod context_bar_interpreter;
 pub use context_bar_interpreter::ContextBarWidgetInterpreter;
 
+mod diff_view_interpreter;
+pub use diff_view_interpreter::DiffViewInterpreter;
+
 mod editbox_interpreter;
 pub use editbox_interpreter::EditWidgetInterpreter;
 
@@ -26,6 +29,9 @@ pub use editor_interpreter::EditorInterpreter;
 mod fuzzy_search_interpreter;
 pub use fuzzy_search_interpreter::FuzzySearchInterpreter;
 
+mod generic_dialog_interpreter;
+pub use generic_dialog_interpreter::GenericDialogInterpreter;
+
 mod listview_interpreter;
 pub use listview_interpreter::{ListViewInterpreter, ListViewInterpreterItem};
 
@@ -35,6 +41,9 @@ pub use meta_frame::MetaOutputFrame;
 mod mock_clipboard;
 pub use mock_clipboard::MockClipboard;
 
+mod mock_git_provider;
+pub use mock_git_provider::MockGitProvider;
+
 mod mock_input;
 pub use mock_input::MockInput;
 
@@ -64,8 +73,14 @@ pub use savefile_interpreter::SaveFileInterpreter;
 mod scroll_interpreter;
 pub use scroll_interpreter::ScrollInterpreter;
 
+mod task_output_interpreter;
+pub use task_output_interpreter::TaskOutputInterpreter;
+
 mod treeview_interpreter;
 pub use treeview_interpreter::{TreeViewInterpreter, TreeViewInterpreterItem};
 
+mod undo_tree_interpreter;
+pub use undo_tree_interpreter::UndoTreeInterpreter;
+
 mod with_scroll_interpreter;
 pub use with_scroll_interpreter::WithScrollWidgetInterpreter;
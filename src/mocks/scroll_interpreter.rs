@@ -10,6 +10,10 @@ impl<'a> ScrollInterpreter<'a> {
         ScrollInterpreter { rect, output: mock_output }
     }
 
+    pub fn rect(&self) -> Rect {
+        self.rect
+    }
+
     pub fn lowest_number(&self) -> Option<usize> {
         self.output
             .buffer
@@ -0,0 +1,49 @@
+use crate::*;
+
+pub struct TaskOutputInterpreter<'a> {
+    meta: &'a Metadata,
+    output: &'a MetaOutputFrame,
+}
+
+impl<'a> TaskOutputInterpreter<'a> {
+    pub fn new(output: &'a MetaOutputFrame, meta: &'a Metadata) -> Self {
+        debug_assert!(meta.typename == TaskOutputView::TYPENAME);
+
+        Self { meta, output }
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.meta.focused
+    }
+
+    pub fn status(&self) -> String {
+        self.output
+            .buffer
+            .lines_iter()
+            .with_rect(self.meta.rect)
+            .next()
+            .map(|line| line.text.trim_end().to_string())
+            .unwrap_or_default()
+    }
+
+    // lines below status, empty ones are skipped
+    pub fn lines(&self) -> Vec<String> {
+        self.output
+            .buffer
+            .lines_iter()
+            .with_rect(self.meta.rect)
+            .skip(1)
+            .map(|line| line.text.trim_end().to_string())
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+
+    // style of the first character of given text, looked up in output lines
+    pub fn style_of(&self, text: &str) -> Option<TextStyle> {
+        self.output.buffer.lines_iter().with_rect(self.meta.rect).skip(1).find_map(|line| {
+            let x = line.text.find(text)?;
+            let pos = line.absolute_pos + XY::new(line.text[..x].chars().count() as u16, 0);
+            self.output.buffer[pos].style().copied()
+        })
+    }
+}
@@ -14,6 +14,7 @@ pub struct TreeViewInterpreterItem {
     pub leaf: bool,
     pub expanded: bool,
     pub highlighted: bool,
+    pub foreground: Color,
 }
 
 impl<'a> TreeViewInterpreter<'a> {
@@ -45,7 +46,8 @@ impl<'a> TreeViewInterpreter<'a> {
             }
 
             let pos_first = self.meta.rect.pos + XY::new(first_non_blank, line_idx as u16);
-            let highlighted = self.output.buffer[pos_first].style().unwrap().background == self.output.theme.highlighted(true).background;
+            let style = self.output.buffer[pos_first].style().unwrap();
+            let highlighted = style.background == self.output.theme.highlighted(true).background;
 
             res.push(TreeViewInterpreterItem {
                 label: line_no_sham.trim().to_string(),
@@ -53,6 +55,7 @@ impl<'a> TreeViewInterpreter<'a> {
                 leaf: !is_dir,
                 expanded,
                 highlighted,
+                foreground: style.foreground,
             })
         }
 
@@ -0,0 +1,85 @@
+use crate::*;
+
+pub struct UndoTreeInterpreter<'a> {
+    meta: &'a Metadata,
+    output: &'a MetaOutputFrame,
+}
+
+impl<'a> UndoTreeInterpreter<'a> {
+    pub fn new(output: &'a MetaOutputFrame, meta: &'a Metadata) -> Self {
+        debug_assert!(meta.typename == UndoTreeWidget::TYPENAME);
+
+        Self { meta, output }
+    }
+
+    pub fn is_focused(&self) -> bool {
+        self.meta.focused
+    }
+
+    pub fn query_line(&self) -> String {
+        self.output
+            .buffer
+            .lines_iter()
+            .with_rect(self.meta.rect)
+            .next()
+            .map(|line| line.text.trim_end().to_string())
+            .unwrap_or_default()
+    }
+
+    // lines below query, split at the separator between rows and preview
+    fn split_lines(&self) -> Vec<(String, String)> {
+        let lines: Vec<Vec<char>> = self
+            .output
+            .buffer
+            .lines_iter()
+            .with_rect(self.meta.rect)
+            .skip(1)
+            .map(|line| line.text.chars().collect())
+            .collect();
+
+        // graph uses the same character, so separator is the rightmost column where all lines have it
+        let width = lines.iter().map(|l| l.len()).min().unwrap_or(0);
+        let separator = (0..width).rev().find(|x| lines.iter().all(|l| l[*x] == '│')).unwrap_or(width);
+
+        lines
+            .iter()
+            .map(|l| {
+                let left: String = l[..separator].iter().collect();
+                let right: String = l[separator.min(l.len())..].iter().skip(1).collect();
+                (left.trim_end().to_string(), right.trim_end().to_string())
+            })
+            .collect()
+    }
+
+    pub fn rows(&self) -> Vec<String> {
+        self.split_lines()
+            .into_iter()
+            .map(|(row, _)| row)
+            .filter(|row| !row.is_empty())
+            .collect()
+    }
+
+    pub fn highlighted_row(&self) -> Option<String> {
+        let highlighted_bg = self.output.theme.highlighted(self.meta.focused).background;
+
+        self.split_lines()
+            .into_iter()
+            .enumerate()
+            .find(|(idx, _)| {
+                let pos = self.meta.rect.pos + XY::new(0, *idx as u16 + 1);
+                match &self.output.buffer[pos] {
+                    Cell::Begin { style, .. } => style.background == highlighted_bg,
+                    Cell::Continuation => false,
+                }
+            })
+            .map(|(_, (row, _))| row)
+    }
+
+    pub fn preview(&self) -> Vec<String> {
+        self.split_lines()
+            .into_iter()
+            .map(|(_, preview)| preview)
+            .filter(|line| !line.is_empty())
+            .collect()
+    }
+}
@@ -0,0 +1,317 @@
+use tree_sitter::Node;
+
+use crate::*;
+
+/*
+Smart newline: new line gets indentation of the one it was broken from, plus a level if it was broken
+inside a bracket (or other indenting node) opened in that very line. What opens a level is told by syntax
+tree, through kinds of nodes listed per language below. Buffers without a tree (no language, large files)
+go by the last char before the cursor being an opening bracket.
+
+Typing a closing bracket as the first thing in a line moves the line back to indentation of the line where
+the bracket was opened.
+ */
+
+/*
+Kinds of nodes that indent their contents, when broken in the line they start in.
+ */
+pub fn indent_node_kinds(lang_id: LangId) -> &'static [&'static str] {
+    match lang_id {
+        LangId::C => &[
+            "compound_statement",
+            "field_declaration_list",
+            "enumerator_list",
+            "initializer_list",
+            "argument_list",
+            "parameter_list",
+            "parenthesized_expression",
+        ],
+        LangId::CPP => &[
+            "compound_statement",
+            "declaration_list",
+            "field_declaration_list",
+            "enumerator_list",
+            "initializer_list",
+            "argument_list",
+            "parameter_list",
+            "template_argument_list",
+            "parenthesized_expression",
+        ],
+        LangId::HTML => &["element", "script_element", "style_element"],
+        LangId::ELM => &[
+            "value_declaration",
+            "case_of_branch",
+            "let_in_expr",
+            "record_expr",
+            "list_expr",
+            "parenthesized_expr",
+            "tuple_expr",
+            "record_type",
+        ],
+        LangId::GO => &[
+            "block",
+            "field_declaration_list",
+            "interface_type",
+            "literal_value",
+            "argument_list",
+            "parameter_list",
+            "import_spec_list",
+            "const_declaration",
+            "var_declaration",
+            "expression_case",
+            "type_case",
+            "default_case",
+            "communication_case",
+        ],
+        LangId::RUST => &[
+            "block",
+            "declaration_list",
+            "field_declaration_list",
+            "ordered_field_declaration_list",
+            "enum_variant_list",
+            "field_initializer_list",
+            "match_block",
+            "arguments",
+            "parameters",
+            "array_expression",
+            "tuple_expression",
+            "parenthesized_expression",
+            "use_list",
+            "token_tree",
+            "type_parameters",
+            "type_arguments",
+            "where_clause",
+        ],
+    }
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct NewlineIndent {
+    // goes right after the newline
+    pub indent: String,
+    // when broken right before the bracket that closes the level, the bracket gets a line of its own,
+    // with this indentation, and cursor stays in the line in between
+    pub closer_indent: Option<String>,
+}
+
+/*
+Indentation of a line about to be broken at char_idx.
+ */
+pub fn newline_indent(buffer: &dyn TextBuffer, char_idx: usize) -> NewlineIndent {
+    let line_begin = buffer
+        .char_to_line(char_idx)
+        .and_then(|line_idx| buffer.line_to_char(line_idx))
+        .unwrap_or(char_idx);
+
+    // only whitespace before the cursor, if it's inside it
+    let base: String = (line_begin..char_idx)
+        .map_while(|idx| buffer.char_at(idx).filter(|c| is_indent_char(*c)))
+        .collect();
+
+    let opened = match buffer.parsing().filter(|parsing| parsing.tree.is_some()) {
+        Some(parsing) => opened_in_tree(buffer, parsing, line_begin, char_idx),
+        None => opened_by_bracket(buffer, line_begin, char_idx),
+    };
+
+    match opened {
+        None => NewlineIndent {
+            indent: base,
+            closer_indent: None,
+        },
+        Some(closer_op) => NewlineIndent {
+            indent: format!("{}{}", base, indent_level(buffer)),
+            closer_indent: closer_op.map(|_| base),
+        },
+    }
+}
+
+/*
+Where the line of closing bracket at char_idx should begin, and with what indentation. None if bracket
+is not the first thing in its line, or the line is indented right already.
+ */
+pub fn closer_indent(buffer: &dyn TextBuffer, char_idx: usize) -> Option<(usize, String)> {
+    let line_idx = buffer.char_to_line(char_idx)?;
+    let line_begin = buffer.line_to_char(line_idx)?;
+    let current: String = (line_begin..char_idx).filter_map(|idx| buffer.char_at(idx)).collect();
+    if !current.chars().all(is_indent_char) {
+        return None;
+    }
+
+    let wanted = match buffer.parsing().filter(|parsing| parsing.tree.is_some()) {
+        Some(parsing) => {
+            let opening_line = opening_line_in_tree(buffer, parsing, char_idx, line_idx)?;
+            line_indentation(buffer, opening_line)
+        }
+        // without a tree we can only guess it's a level back
+        None => current.strip_suffix(&indent_level(buffer))?.to_string(),
+    };
+
+    if wanted == current {
+        None
+    } else {
+        Some((line_begin, wanted))
+    }
+}
+
+fn is_indent_char(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn is_closer(node: &Node) -> bool {
+    matches!(node.kind(), ")" | "]" | "}" | "end_tag")
+}
+
+fn indent_level(buffer: &dyn TextBuffer) -> String {
+    match buffer.indent_style() {
+        IndentStyle::Tabs => "\t".to_string(),
+        IndentStyle::Spaces => " ".repeat(buffer.tab_width()),
+    }
+}
+
+fn line_indentation(buffer: &dyn TextBuffer, line_idx: usize) -> String {
+    let line_begin = unpack_or!(buffer.line_to_char(line_idx), String::new());
+    (line_begin..buffer.len_chars())
+        .map_while(|idx| buffer.char_at(idx).filter(|c| is_indent_char(*c)))
+        .collect()
+}
+
+/*
+Some if a level is opened at char_idx, with position of the bracket closing it, if it's right after
+char_idx (whitespace aside).
+ */
+fn opened_in_tree(buffer: &dyn TextBuffer, parsing: &ParsingTuple, line_begin: usize, char_idx: usize) -> Option<Option<usize>> {
+    let tree = parsing.tree.as_ref()?;
+    let kinds = indent_node_kinds(parsing.lang_id);
+    let line_idx = buffer.char_to_line(char_idx)?;
+    let byte_idx = buffer.char_to_byte(char_idx)?;
+
+    let mut node_op = tree.root_node().descendant_for_byte_range(byte_idx, byte_idx);
+    let in_error = node_op.map(|node| node.has_error()).unwrap_or(false);
+
+    while let Some(node) = node_op {
+        if kinds.contains(&node.kind())
+            && node.start_position().row == line_idx
+            && node.start_byte() < byte_idx
+            && (byte_idx < node.end_byte() || node.has_error())
+        {
+            let closer_op = node
+                .child(node.child_count().saturating_sub(1))
+                .filter(|last| is_closer(last) && !last.is_missing())
+                .and_then(|last| buffer.byte_to_char(last.start_byte()))
+                .filter(|closer_idx| *closer_idx >= char_idx && only_indent_between(buffer, char_idx, *closer_idx));
+
+            return Some(closer_op);
+        }
+
+        node_op = node.parent();
+    }
+
+    // code being typed often doesn't parse yet, brackets are a better guess then
+    if in_error {
+        opened_by_bracket(buffer, line_begin, char_idx)
+    } else {
+        None
+    }
+}
+
+fn opened_by_bracket(buffer: &dyn TextBuffer, line_begin: usize, char_idx: usize) -> Option<Option<usize>> {
+    let last = (line_begin..char_idx)
+        .rev()
+        .filter_map(|idx| buffer.char_at(idx))
+        .find(|c| !is_indent_char(*c))?;
+    let closer = match last {
+        '(' => ')',
+        '[' => ']',
+        '{' => '}',
+        _ => return None,
+    };
+
+    let next_idx = (char_idx..buffer.len_chars()).find(|idx| buffer.char_at(*idx).map(|c| !is_indent_char(c)).unwrap_or(false));
+    Some(next_idx.filter(|idx| buffer.char_at(*idx) == Some(closer)))
+}
+
+fn only_indent_between(buffer: &dyn TextBuffer, begin: usize, end: usize) -> bool {
+    (begin..end).all(|idx| buffer.char_at(idx).map(is_indent_char).unwrap_or(false))
+}
+
+/*
+Line in which the node closed by bracket at char_idx begins, if it's an earlier one.
+ */
+fn opening_line_in_tree(buffer: &dyn TextBuffer, parsing: &ParsingTuple, char_idx: usize, line_idx: usize) -> Option<usize> {
+    let tree = parsing.tree.as_ref()?;
+    let byte_idx = buffer.char_to_byte(char_idx)?;
+
+    let closer = tree.root_node().descendant_for_byte_range(byte_idx, byte_idx + 1)?;
+    if closer.start_byte() != byte_idx || !is_closer(&closer) || closer.is_missing() {
+        return None;
+    }
+
+    let opened = closer.parent()?;
+    if opened.is_error() || opened.start_position().row >= line_idx {
+        return None;
+    }
+
+    Some(opened.start_position().row)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn rust_buffer(text: &str) -> BufferState {
+        BufferState::full(
+            Some(Arc::new(TreeSitterWrapper::new(LanguageSet::full()))),
+            DocumentIdentifier::new_unique(),
+        )
+        .with_lang(LangId::RUST)
+        .with_text(text)
+    }
+
+    fn indent(text: &str, newline: bool) -> (NewlineIndent, NewlineIndent) {
+        let char_idx = text.find('|').unwrap();
+        let text = text.replace('|', "");
+
+        let plain = BufferState::full(None, DocumentIdentifier::new_unique()).with_text(&text);
+        let parsed = rust_buffer(&text);
+        assert!(!newline || parsed.parsing().is_some());
+
+        (newline_indent(&plain, char_idx), newline_indent(&parsed, char_idx))
+    }
+
+    #[test]
+    fn test_newline_indent() {
+        let copied = NewlineIndent {
+            indent: "    ".to_string(),
+            closer_indent: None,
+        };
+        let level = NewlineIndent {
+            indent: "        ".to_string(),
+            closer_indent: None,
+        };
+        let split = NewlineIndent {
+            indent: "        ".to_string(),
+            closer_indent: Some("    ".to_string()),
+        };
+
+        assert_eq!(indent("fn f() {\n    let x = 1;|\n}", true), (copied.clone(), copied.clone()));
+        assert_eq!(indent("fn f() {\n    if x {|\n    }\n}", true), (level.clone(), level.clone()));
+        assert_eq!(indent("fn f() {\n    if x {|}\n}", true), (split.clone(), split.clone()));
+        assert_eq!(indent("fn f() {\n    foo(|\n}", true), (level.clone(), level.clone()));
+        // bracket in a string doesn't open anything, but only syntax tree knows that
+        assert_eq!(indent("fn f() {\n    let s = \"{|\";\n}", true), (level.clone(), copied.clone()));
+    }
+
+    #[test]
+    fn test_closer_indent() {
+        let parsed = rust_buffer("fn f() {\n    if x {\n        y();\n        }\n}");
+        // "}" closing "if"
+        assert_eq!(closer_indent(&parsed, 41), Some((33, "    ".to_string())));
+        // last "}" is right already
+        assert_eq!(closer_indent(&parsed, 43), None);
+
+        let plain = BufferState::full(None, DocumentIdentifier::new_unique()).with_text("{\n    a\n    }");
+        assert_eq!(closer_indent(&plain, 12), Some((8, "".to_string())));
+    }
+}
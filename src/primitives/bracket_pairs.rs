@@ -0,0 +1,318 @@
+use tree_sitter::Node;
+
+use crate::*;
+
+/*
+Auto-pairing: typed opening bracket or quote gets closed right away, typing the closer right before the
+one inserted just steps over it, Backspace between an empty pair removes both, and a bracket typed over
+a selection wraps it. Whether the cursor is in a string (or a comment), where nothing gets paired, is
+told by syntax tree. Buffers without a tree go by counting quotes in the line.
+
+Bracket matching: bracket at (or right before) the cursor and the one it pairs with. Syntax tree knows
+which brackets are code and which are just text in strings, without it brackets are counted.
+ */
+
+pub const BRACKETS: &[(char, char)] = &[('(', ')'), ('[', ']'), ('{', '}')];
+
+// without a tree, matching bracket is looked for that far, so a stray one in a big file is not too
+// slow
+const MAX_BRACKET_SCAN_CHARS: usize = 20_000;
+
+/*
+Quotes paired in given language. In Rust ' starts lifetimes more often than chars, so it's left alone.
+ */
+pub fn pair_quotes(lang_id_op: Option<LangId>) -> &'static [char] {
+    match lang_id_op {
+        Some(LangId::RUST) => &['"'],
+        Some(LangId::GO) => &['"', '\'', '`'],
+        Some(LangId::C) | Some(LangId::CPP) | Some(LangId::ELM) | Some(LangId::HTML) | None => &['"', '\''],
+    }
+}
+
+/*
+Closer of a bracket or quote that gets paired in this buffer.
+ */
+pub fn pair_closer(buffer: &dyn TextBuffer, opener: char) -> Option<char> {
+    if let Some((_, closer)) = BRACKETS.iter().find(|(o, _)| *o == opener) {
+        return Some(*closer);
+    }
+
+    if quotes(buffer).contains(&opener) {
+        Some(opener)
+    } else {
+        None
+    }
+}
+
+/*
+Whether opener typed at char_idx should get its closer inserted after it.
+ */
+pub fn should_close_pair(buffer: &dyn TextBuffer, char_idx: usize, opener: char) -> bool {
+    if pair_closer(buffer, opener).is_none() || in_string_or_comment(buffer, char_idx) {
+        return false;
+    }
+
+    // closing only before things that can't be what bracket was meant to enclose
+    let next_fits = match buffer.char_at(char_idx) {
+        None => true,
+        Some(next) => next.is_whitespace() || matches!(next, ')' | ']' | '}' | ',' | ';' | ':'),
+    };
+
+    let is_quote = quotes(buffer).contains(&opener);
+    // quote right after a word is an apostrophe or a prefix (like b"..."), not a new string
+    let prev_fits = !is_quote
+        || char_idx == 0
+        || buffer
+            .char_at(char_idx - 1)
+            .map(|prev| !prev.is_alphanumeric() && prev != '_' && prev != '\\' && prev != opener)
+            .unwrap_or(true);
+
+    next_fits && prev_fits
+}
+
+/*
+Whether typing c at char_idx should just move over c that is already there.
+ */
+pub fn should_skip_closer(buffer: &dyn TextBuffer, char_idx: usize, c: char) -> bool {
+    if buffer.char_at(char_idx) != Some(c) {
+        return false;
+    }
+
+    if BRACKETS.iter().any(|(_, closer)| *closer == c) {
+        return true;
+    }
+
+    // quote right ahead closes string only if we are in one
+    quotes(buffer).contains(&c) && in_string_or_comment(buffer, char_idx)
+}
+
+/*
+Whether char_idx is right between an opener and its closer, so they both go on Backspace.
+ */
+pub fn is_in_empty_pair(buffer: &dyn TextBuffer, char_idx: usize) -> bool {
+    if char_idx == 0 {
+        return false;
+    }
+
+    let opener = unpack_or!(buffer.char_at(char_idx - 1), false);
+    let closer = unpack_or!(pair_closer(buffer, opener), false);
+    buffer.char_at(char_idx) == Some(closer)
+}
+
+/*
+Bracket next to cursor at char_idx (the one after it goes first) and the one matching it.
+ */
+pub fn matching_bracket_pair(buffer: &dyn TextBuffer, char_idx: usize) -> Option<(usize, usize)> {
+    let candidates = [Some(char_idx), char_idx.checked_sub(1)];
+
+    candidates
+        .into_iter()
+        .flatten()
+        .find_map(|idx| matching_bracket(buffer, idx).map(|other| (idx, other)))
+}
+
+/*
+Position of bracket matching the one at char_idx.
+ */
+pub fn matching_bracket(buffer: &dyn TextBuffer, char_idx: usize) -> Option<usize> {
+    let c = buffer.char_at(char_idx)?;
+    if !BRACKETS.iter().any(|(opener, closer)| *opener == c || *closer == c) {
+        return None;
+    }
+
+    match buffer.parsing().and_then(|parsing| parsing.tree.as_ref()) {
+        Some(tree) => matching_bracket_in_tree(buffer, tree, char_idx, c),
+        None => matching_bracket_by_counting(buffer, char_idx, c),
+    }
+}
+
+fn quotes(buffer: &dyn TextBuffer) -> &'static [char] {
+    pair_quotes(buffer.parsing().map(|parsing| parsing.lang_id))
+}
+
+fn is_string_or_comment(node: &Node) -> bool {
+    let kind = node.kind();
+    kind.contains("string") || kind.contains("comment") || kind == "char_literal"
+}
+
+fn in_string_or_comment(buffer: &dyn TextBuffer, char_idx: usize) -> bool {
+    match buffer.parsing().and_then(|parsing| parsing.tree.as_ref()) {
+        Some(tree) => {
+            let byte_idx = unpack_or!(buffer.char_to_byte(char_idx), false);
+            let mut node_op = tree.root_node().descendant_for_byte_range(byte_idx, byte_idx);
+
+            while let Some(node) = node_op {
+                // strictly inside, being right before a string is being outside of it
+                if is_string_or_comment(&node) && node.start_byte() < byte_idx && byte_idx < node.end_byte() {
+                    return true;
+                }
+                node_op = node.parent();
+            }
+
+            false
+        }
+        None => in_string_by_counting(buffer, char_idx),
+    }
+}
+
+fn in_string_by_counting(buffer: &dyn TextBuffer, char_idx: usize) -> bool {
+    let line_begin = buffer
+        .char_to_line(char_idx)
+        .and_then(|line_idx| buffer.line_to_char(line_idx))
+        .unwrap_or(char_idx);
+    let quotes = quotes(buffer);
+
+    let mut open_quote: Option<char> = None;
+    let mut escaped = false;
+    for c in (line_begin..char_idx).filter_map(|idx| buffer.char_at(idx)) {
+        if escaped {
+            escaped = false;
+            continue;
+        }
+
+        match open_quote {
+            Some(_) if c == '\\' => escaped = true,
+            Some(quote) if c == quote => open_quote = None,
+            None if quotes.contains(&c) => open_quote = Some(c),
+            _ => {}
+        }
+    }
+
+    open_quote.is_some()
+}
+
+fn matching_bracket_in_tree(buffer: &dyn TextBuffer, tree: &tree_sitter::Tree, char_idx: usize, c: char) -> Option<usize> {
+    let byte_idx = buffer.char_to_byte(char_idx)?;
+    let node = tree.root_node().descendant_for_byte_range(byte_idx, byte_idx + 1)?;
+    // brackets in strings and comments are not nodes of their own
+    if node.start_byte() != byte_idx || node.is_missing() || node.kind().chars().ne([c]) {
+        return None;
+    }
+
+    let parent = node.parent()?;
+    let (opener, closer) = BRACKETS.iter().find(|(opener, closer)| *opener == c || *closer == c)?;
+    let forward = c == *opener;
+
+    let mut cursor = parent.walk();
+    let siblings: Vec<Node> = parent.children(&mut cursor).collect();
+    let own_idx = siblings.iter().position(|sibling| sibling.id() == node.id())?;
+
+    let mut depth: usize = 0;
+    let others: Box<dyn Iterator<Item = &Node>> = if forward {
+        Box::new(siblings[own_idx + 1..].iter())
+    } else {
+        Box::new(siblings[..own_idx].iter().rev())
+    };
+
+    for sibling in others {
+        if sibling.is_missing() {
+            continue;
+        }
+
+        let kind = sibling.kind();
+        let (same, other) = if forward { (opener, closer) } else { (closer, opener) };
+        if kind.chars().eq([*same]) {
+            depth += 1;
+        } else if kind.chars().eq([*other]) {
+            if depth == 0 {
+                return buffer.byte_to_char(sibling.start_byte());
+            }
+            depth -= 1;
+        }
+    }
+
+    None
+}
+
+fn matching_bracket_by_counting(buffer: &dyn TextBuffer, char_idx: usize, c: char) -> Option<usize> {
+    let (opener, closer) = BRACKETS.iter().find(|(opener, closer)| *opener == c || *closer == c)?;
+    let forward = c == *opener;
+
+    let others: Box<dyn Iterator<Item = usize>> = if forward {
+        Box::new((char_idx + 1..buffer.len_chars()).take(MAX_BRACKET_SCAN_CHARS))
+    } else {
+        Box::new((0..char_idx).rev().take(MAX_BRACKET_SCAN_CHARS))
+    };
+
+    let (same, other) = if forward { (opener, closer) } else { (closer, opener) };
+    let mut depth: usize = 0;
+    for idx in others {
+        let current = buffer.char_at(idx)?;
+        if current == *same {
+            depth += 1;
+        } else if current == *other {
+            if depth == 0 {
+                return Some(idx);
+            }
+            depth -= 1;
+        }
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+
+    use super::*;
+
+    fn rust_buffer(text: &str) -> BufferState {
+        BufferState::full(
+            Some(Arc::new(TreeSitterWrapper::new(LanguageSet::full()))),
+            DocumentIdentifier::new_unique(),
+        )
+        .with_lang(LangId::RUST)
+        .with_text(text)
+    }
+
+    fn plain_buffer(text: &str) -> BufferState {
+        BufferState::full(None, DocumentIdentifier::new_unique()).with_text(text)
+    }
+
+    #[test]
+    fn test_pairing_rules() {
+        let text = "fn f() { let s = \"a(\"; x }";
+        let parsed = rust_buffer(text);
+        let plain = plain_buffer(text);
+
+        for buffer in [&parsed, &plain] {
+            // before " let"
+            assert!(should_close_pair(buffer, 8, '('));
+            // before "x"
+            assert!(!should_close_pair(buffer, 23, '('));
+            // in string
+            assert!(!should_close_pair(buffer, 19, '['));
+            // closing quote of string is stepped over, opening one is not
+            assert!(should_skip_closer(buffer, 20, '"'));
+            assert!(!should_skip_closer(buffer, 17, '"'));
+        }
+
+        // lifetimes
+        assert_eq!(pair_closer(&parsed, '\''), None);
+        assert_eq!(pair_closer(&plain, '\''), Some('\''));
+
+        assert!(is_in_empty_pair(&plain_buffer("a()"), 2));
+        assert!(!is_in_empty_pair(&plain_buffer("a(b)"), 2));
+    }
+
+    #[test]
+    fn test_matching_bracket() {
+        let text = "fn f() { let s = \"}\"; g(h[1]) }";
+        let parsed = rust_buffer(text);
+        let plain = plain_buffer(text);
+
+        // "{" of the body, tree knows the "}" in string is not it
+        assert_eq!(matching_bracket(&parsed, 7), Some(30));
+        assert_eq!(matching_bracket(&parsed, 30), Some(7));
+        assert_eq!(matching_bracket(&plain, 7), Some(18));
+
+        for buffer in [&parsed, &plain] {
+            // "(" of g, cursor right before and right after it
+            assert_eq!(matching_bracket_pair(buffer, 23), Some((23, 28)));
+            assert_eq!(matching_bracket_pair(buffer, 24), Some((23, 28)));
+            assert_eq!(matching_bracket_pair(buffer, 26), Some((25, 27)));
+            assert_eq!(matching_bracket_pair(buffer, 12), None);
+        }
+    }
+}
@@ -1,7 +1,7 @@
 use std::collections::HashSet;
 use std::ops::Range;
 
-use log::{error, warn};
+use log::{debug, error, warn};
 use streaming_iterator::StreamingIterator;
 use unicode_segmentation::UnicodeSegmentation;
 
@@ -43,6 +43,8 @@ pub enum CommonEditMsg {
 
     Copy,
     Paste,
+    // clipboard lines one under another at the same column, as copied from a column selection
+    PasteBlock,
     Undo,
     Redo,
 
@@ -52,6 +54,25 @@ pub enum CommonEditMsg {
     SubstituteBlock { char_range: Range<usize>, with_what: String },
     Tab,
     ShiftTab,
+    // with language's comment tokens, on lines covered by cursors
+    ToggleLineComment,
+    // around selections, or lines of simple cursors
+    ToggleBlockComment,
+    // line operations, on all lines covered by cursors
+    MoveLinesUp,
+    MoveLinesDown,
+    // selections if all cursors have one, lines otherwise
+    DuplicateLines,
+    DeleteLines,
+    // lines of each selection, or line of simple cursor with the next one
+    JoinLines,
+    InsertLineAbove,
+    InsertLineBelow,
+    SortLines,
+    UniqueLines,
+    ReverseLines,
+    // every selection, as a single edit
+    Transform(TextTransform),
 }
 
 impl CommonEditMsg {
@@ -105,6 +126,7 @@ impl CommonEditMsg {
             CommonEditMsg::Delete => true,
             CommonEditMsg::Copy => false,
             CommonEditMsg::Paste => true,
+            CommonEditMsg::PasteBlock => true,
             CommonEditMsg::Undo => true,
             CommonEditMsg::Redo => true,
             CommonEditMsg::DeleteBlock { .. } => true,
@@ -112,6 +134,19 @@ impl CommonEditMsg {
             CommonEditMsg::SubstituteBlock { .. } => true,
             CommonEditMsg::Tab => true,
             CommonEditMsg::ShiftTab => true,
+            CommonEditMsg::ToggleLineComment => true,
+            CommonEditMsg::ToggleBlockComment => true,
+            CommonEditMsg::MoveLinesUp => true,
+            CommonEditMsg::MoveLinesDown => true,
+            CommonEditMsg::DuplicateLines => true,
+            CommonEditMsg::DeleteLines => true,
+            CommonEditMsg::JoinLines => true,
+            CommonEditMsg::InsertLineAbove => true,
+            CommonEditMsg::InsertLineBelow => true,
+            CommonEditMsg::SortLines => true,
+            CommonEditMsg::UniqueLines => true,
+            CommonEditMsg::ReverseLines => true,
+            CommonEditMsg::Transform(_) => true,
         }
     }
 }
@@ -303,6 +338,313 @@ fn insert_to_rope(
     (diff_len, res)
 }
 
+/*
+Enter, with indentation of the new line (see auto_indent). It differs between cursors, so they are done
+one by one.
+ */
+fn insert_newlines(cursor_set: &mut CursorSet, other_cursor_sets: &mut Vec<&mut CursorSet>, rope: &mut dyn TextBuffer) -> (usize, bool) {
+    let mut res = false;
+    let mut diff_len: usize = 0;
+
+    let mut cursor_idx: usize = 0;
+    while cursor_idx < cursor_set.len() {
+        let indent = newline_indent(rope, cursor_set.set()[cursor_idx].get_begin());
+
+        let mut what = format!("\n{}", indent.indent);
+        // cursor goes back before closing bracket's line
+        let closer_line = indent.closer_indent.map(|closer_indent| format!("\n{}", closer_indent));
+        if let Some(closer_line) = closer_line.as_ref() {
+            what += closer_line;
+        }
+
+        let (dl, r) = insert_to_rope(cursor_set, other_cursor_sets, rope, Some(cursor_idx), &what);
+        if r {
+            if let Some(closer_line) = closer_line.as_ref() {
+                cursor_set.set_mut()[cursor_idx].shift_by(-(closer_line.chars().count() as isize));
+            }
+        }
+
+        res |= r;
+        diff_len += dl;
+        cursor_idx += 1;
+    }
+
+    (diff_len, res)
+}
+
+/*
+Closing bracket typed as the first thing in a line moves the line back to indentation of the one that
+opened it (see auto_indent).
+ */
+fn insert_closing_bracket(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+    closer: char,
+) -> (usize, bool) {
+    let (mut diff_len, res) = if rope.auto_pairing() {
+        insert_char_with_pairs(cursor_set, other_cursor_sets, rope, closer)
+    } else {
+        insert_to_rope(cursor_set, other_cursor_sets, rope, None, closer.to_string().as_str())
+    };
+    if !res {
+        return (diff_len, res);
+    }
+
+    // from the last one, so changing indentation of a line doesn't move cursors yet to be looked at
+    let mut cursor_idx = cursor_set.len();
+    while cursor_idx > 0 {
+        cursor_idx -= 1;
+        // fixing one line could have removed cursors in its indentation
+        let cursor = match cursor_set.set().get(cursor_idx) {
+            Some(cursor) => *cursor,
+            None => continue,
+        };
+
+        if !cursor.is_simple() || cursor.a == 0 || rope.char_at(cursor.a - 1) != Some(closer) {
+            continue;
+        }
+
+        let (line_begin, indent) = match closer_indent(rope, cursor.a - 1) {
+            Some(line_begin_and_indent) => line_begin_and_indent,
+            None => continue,
+        };
+
+        if cursor.a - 1 > line_begin {
+            diff_len += remove_from_rope_at_random_place(cursor_set, other_cursor_sets, rope, line_begin..cursor.a - 1).0;
+        }
+        if !indent.is_empty() {
+            diff_len += insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, line_begin, &indent).0;
+        }
+    }
+
+    (diff_len, res)
+}
+
+/*
+Char typed with auto-pairing on (see bracket_pairs): selection gets wrapped, closer gets added or
+stepped over. It differs between cursors, so they are done one by one.
+ */
+fn insert_char_with_pairs(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+    c: char,
+) -> (usize, bool) {
+    let mut res = false;
+    let mut diff_len: usize = 0;
+
+    let mut cursor_idx: usize = 0;
+    while cursor_idx < cursor_set.len() {
+        let cursor = cursor_set.set()[cursor_idx];
+
+        let (dl, r) = match (cursor.s, pair_closer(rope, c)) {
+            // selection stays, now between the pair
+            (Some(sel), Some(closer)) => {
+                let (dl_closer, r_closer) = insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, sel.e, &closer.to_string());
+                let (dl_opener, r_opener) = insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, sel.b, &c.to_string());
+                (dl_closer + dl_opener, r_closer || r_opener)
+            }
+            (None, _) if should_skip_closer(rope, cursor.a, c) => {
+                let moved = &mut cursor_set.set_mut()[cursor_idx];
+                moved.shift_by(1);
+                moved.clear_pc();
+                (0, true)
+            }
+            (None, Some(closer)) if should_close_pair(rope, cursor.a, c) => {
+                let (dl, r) = insert_to_rope(cursor_set, other_cursor_sets, rope, Some(cursor_idx), &format!("{}{}", c, closer));
+                if r {
+                    cursor_set.set_mut()[cursor_idx].shift_by(-1);
+                }
+                (dl, r)
+            }
+            _ => insert_to_rope(cursor_set, other_cursor_sets, rope, Some(cursor_idx), &c.to_string()),
+        };
+
+        res |= r;
+        diff_len += dl;
+        cursor_idx += 1;
+    }
+
+    cursor_set.reduce_right();
+    debug_assert!(cursor_set.check_invariant());
+    (diff_len, res)
+}
+
+/*
+Comments out lines covered by cursors, or uncomments them if all are comments already. Blank lines are
+left alone. Languages without line comments get each line in a block comment instead.
+ */
+fn toggle_line_comments(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+) -> (usize, bool) {
+    let lang_id = unpack_or!(rope.lang_id(), (0, false), "no language, so no comments");
+    let (prefix, suffix) = match lang_id.line_comment_prefix() {
+        Some(prefix) => (prefix, ""),
+        None => lang_id.block_comment_tokens(),
+    };
+
+    // begin of line, length of its indentation and what's after it
+    let mut lines: Vec<(usize, usize, String)> = Vec::new();
+    for line_idx in cursors_to_line_indices(rope, cursor_set) {
+        let (begin, line) = match rope.line_to_char(line_idx).zip(rope.get_line(line_idx)) {
+            Some(begin_and_line) => begin_and_line,
+            None => {
+                error!("failed getting line {}", line_idx);
+                continue;
+            }
+        };
+
+        let line = line.trim_end_matches('\r');
+        let indent = line.chars().take_while(|c| *c == ' ' || *c == '\t').count();
+        let rest: String = line.chars().skip(indent).collect();
+        if !rest.is_empty() {
+            lines.push((begin, indent, rest));
+        }
+    }
+
+    let is_commented = |rest: &String| rest.len() >= prefix.len() + suffix.len() && rest.starts_with(prefix) && rest.ends_with(suffix);
+    let all_commented = !lines.is_empty() && lines.iter().all(|(_, _, rest)| is_commented(rest));
+
+    let mut diff_len: usize = 0;
+    let mut res = false;
+
+    // from the last line, so changes don't move lines yet to be done
+    if all_commented {
+        for (begin, indent, rest) in lines.iter().rev() {
+            let inner = &rest[prefix.len()..rest.len() - suffix.len()];
+            // spaces added when commenting go too
+            let leading_space = inner.starts_with(' ') as usize;
+            let trailing_space = (!suffix.is_empty() && inner.len() > leading_space && inner.ends_with(' ')) as usize;
+
+            let content_begin = begin + indent;
+            let content_end = content_begin + rest.chars().count();
+            if !suffix.is_empty() {
+                let (dl, r) = remove_from_rope_keeping_cursors(
+                    cursor_set,
+                    other_cursor_sets,
+                    rope,
+                    content_end - suffix.chars().count() - trailing_space..content_end,
+                );
+                diff_len += dl;
+                res |= r;
+            }
+
+            let (dl, r) = remove_from_rope_keeping_cursors(
+                cursor_set,
+                other_cursor_sets,
+                rope,
+                content_begin..content_begin + prefix.chars().count() + leading_space,
+            );
+            diff_len += dl;
+            res |= r;
+        }
+    } else {
+        // all prefixes in one column, so commented block keeps its shape
+        let column = lines.iter().map(|(_, indent, _)| *indent).min().unwrap_or(0);
+
+        for (begin, indent, rest) in lines.iter().rev() {
+            if !suffix.is_empty() {
+                let content_end = begin + indent + rest.chars().count();
+                let (dl, r) = insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, content_end, &format!(" {}", suffix));
+                diff_len += dl;
+                res |= r;
+            }
+
+            let (dl, r) = insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, begin + column, &format!("{} ", prefix));
+            diff_len += dl;
+            res |= r;
+        }
+    }
+
+    (diff_len, res)
+}
+
+/*
+Puts selections in a block comment, or takes them out of one (tokens can be just inside or just outside
+of selection). Simple cursors do that to their lines, indentation aside.
+ */
+fn toggle_block_comments(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+) -> (usize, bool) {
+    let lang_id = unpack_or!(rope.lang_id(), (0, false), "no language, so no comments");
+    let (open, close) = lang_id.block_comment_tokens();
+    let (open_len, close_len) = (open.chars().count(), close.chars().count());
+
+    let mut diff_len: usize = 0;
+    let mut res = false;
+    let mut done_lines: HashSet<usize> = HashSet::new();
+
+    // from the last one, so changes don't move cursors yet to be done
+    let mut cursor_idx = cursor_set.len();
+    while cursor_idx > 0 {
+        cursor_idx -= 1;
+        let cursor = match cursor_set.set().get(cursor_idx) {
+            Some(cursor) => *cursor,
+            None => continue,
+        };
+
+        let range = match cursor.s {
+            Some(sel) => sel.b..sel.e,
+            None => {
+                let line_idx = match rope.char_to_line(cursor.a) {
+                    Some(line_idx) => line_idx,
+                    None => continue,
+                };
+                // two cursors in one line would comment it and back
+                if !done_lines.insert(line_idx) {
+                    continue;
+                }
+
+                let (begin, line) = match rope.line_to_char(line_idx).zip(rope.get_line(line_idx)) {
+                    Some(begin_and_line) => begin_and_line,
+                    None => continue,
+                };
+                let line = line.trim_end_matches('\r');
+                let indent = line.chars().take_while(|c| *c == ' ' || *c == '\t').count();
+                begin + indent..begin + line.chars().count()
+            }
+        };
+
+        if range.is_empty() {
+            continue;
+        }
+
+        let around = range.start >= open_len
+            && chars_in(rope, range.start - open_len..range.start) == open
+            && chars_in(rope, range.end..range.end + close_len) == close;
+        let inside = range.len() >= open_len + close_len
+            && chars_in(rope, range.start..range.start + open_len) == open
+            && chars_in(rope, range.end - close_len..range.end) == close;
+
+        let ((dl_close, r_close), (dl_open, r_open)) = if around {
+            (
+                remove_from_rope_keeping_cursors(cursor_set, other_cursor_sets, rope, range.end..range.end + close_len),
+                remove_from_rope_keeping_cursors(cursor_set, other_cursor_sets, rope, range.start - open_len..range.start),
+            )
+        } else if inside {
+            (
+                remove_from_rope_keeping_cursors(cursor_set, other_cursor_sets, rope, range.end - close_len..range.end),
+                remove_from_rope_keeping_cursors(cursor_set, other_cursor_sets, rope, range.start..range.start + open_len),
+            )
+        } else {
+            (
+                insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, range.end, close),
+                insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, range.start, open),
+            )
+        };
+
+        diff_len += dl_close + dl_open;
+        res |= r_close || r_open;
+    }
+
+    (diff_len, res)
+}
+
 fn insert_to_rope_at_random_place(
     cursor_set: &mut CursorSet,
     other_cursor_sets: &mut Vec<&mut CursorSet>,
@@ -456,6 +798,538 @@ fn remove_from_rope_at_random_place(
     }
 }
 
+/*
+Like remove_from_rope_at_random_place, but cursors in removed block are moved to its beginning instead
+of being dropped (comment tokens go from right next to cursors).
+ */
+fn remove_from_rope_keeping_cursors(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+    char_range: Range<usize>,
+) -> (usize, bool) {
+    if char_range.is_empty() || !rope.remove(char_range.start, char_range.end) {
+        error!("failed to remove block {:?}", char_range);
+        return (0, false);
+    }
+
+    move_cursors_after_removal(cursor_set, &char_range);
+    for other_cursor_set in other_cursor_sets.iter_mut() {
+        move_cursors_after_removal(other_cursor_set, &char_range);
+    }
+
+    (char_range.len(), true)
+}
+
+fn move_cursors_after_removal(cs: &mut CursorSet, char_range: &Range<usize>) {
+    let stride = char_range.len();
+    map_cursors(cs, &|pos| {
+        if pos >= char_range.end {
+            pos - stride
+        } else {
+            pos.min(char_range.start)
+        }
+    });
+}
+
+/*
+Moves every position of every cursor with new_pos. Selections that got empty are dropped, cursors that
+met are merged.
+ */
+fn map_cursors(cs: &mut CursorSet, new_pos: &dyn Fn(usize) -> usize) {
+    for c in cs.iter_mut() {
+        match c.s {
+            None => c.a = new_pos(c.a),
+            Some(sel) => {
+                let (b, e) = (new_pos(sel.b), new_pos(sel.e));
+                let (b, e) = (b.min(e), b.max(e));
+                c.a = if c.anchor_left() { b } else { e };
+                c.s = if b < e { Some(Selection::new(b, e)) } else { None };
+            }
+        }
+    }
+
+    // some could have met
+    cs.reduce_left();
+    debug_assert!(cs.check_invariant());
+}
+
+fn chars_in(rope: &dyn TextBuffer, char_range: Range<usize>) -> String {
+    char_range.filter_map(|idx| rope.char_at(idx)).collect()
+}
+
+// runs of consecutive lines covered by cursors, as (first, last)
+fn cursors_to_line_blocks(rope: &dyn TextBuffer, cs: &CursorSet) -> Vec<(usize, usize)> {
+    let mut blocks: Vec<(usize, usize)> = Vec::new();
+    for line_idx in cursors_to_line_indices(rope, cs) {
+        match blocks.last_mut() {
+            Some((_, last)) if *last + 1 == line_idx => *last = line_idx,
+            _ => blocks.push((line_idx, line_idx)),
+        }
+    }
+    blocks
+}
+
+// chars of lines first..=last, without the newline ending the last one
+fn lines_to_char_range(rope: &dyn TextBuffer, first: usize, last: usize) -> Option<Range<usize>> {
+    let begin = rope.line_to_char(first)?;
+    let end = if last + 1 < rope.len_lines() {
+        rope.line_to_char(last + 1)? - 1
+    } else {
+        rope.len_chars()
+    };
+    Some(begin..end)
+}
+
+/*
+Replaces chars in char_range with new_text. Cursors within it (both ends included) are moved with
+inside, ones after it keep their place in the text that follows.
+ */
+fn replace_region(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+    char_range: Range<usize>,
+    new_text: &str,
+    inside: &dyn Fn(usize) -> usize,
+) -> (usize, bool) {
+    let new_len = new_text.chars().count();
+    let text_changed = chars_in(rope, char_range.clone()) != new_text;
+
+    if text_changed {
+        if !char_range.is_empty() && !rope.remove(char_range.start, char_range.end) {
+            error!("failed to remove block {:?}", char_range);
+            return (0, false);
+        }
+        if !new_text.is_empty() && !rope.insert_block(char_range.start, new_text) {
+            error!("failed to insert block at {}", char_range.start);
+            return (char_range.len(), true);
+        }
+    }
+
+    let new_pos = |pos: usize| {
+        if pos < char_range.start {
+            pos
+        } else if pos > char_range.end {
+            pos - char_range.len() + new_len
+        } else {
+            inside(pos)
+        }
+    };
+
+    let cursors_before = cursor_set.clone();
+    map_cursors(cursor_set, &new_pos);
+    for other_cursor_set in other_cursor_sets.iter_mut() {
+        map_cursors(other_cursor_set, &new_pos);
+    }
+
+    let diff_len = if text_changed {
+        std::cmp::max(char_range.len(), new_len)
+    } else {
+        0
+    };
+    (diff_len, text_changed || *cursor_set != cursors_before)
+}
+
+/*
+Swaps each block of lines with the line above (or below) it, cursors go with the block.
+ */
+fn move_lines(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+    up: bool,
+) -> (usize, bool) {
+    let mut diff_len: usize = 0;
+    let mut res = false;
+
+    // swapping keeps line lengths, so blocks found up front stay valid
+    for (first, last) in cursors_to_line_blocks(rope, cursor_set) {
+        let ranges_op = if up {
+            if first == 0 {
+                continue;
+            }
+            lines_to_char_range(rope, first - 1, first - 1).zip(lines_to_char_range(rope, first, last))
+        } else {
+            if last + 1 >= rope.len_lines() {
+                continue;
+            }
+            lines_to_char_range(rope, first, last).zip(lines_to_char_range(rope, last + 1, last + 1))
+        };
+
+        let (upper, lower) = match ranges_op {
+            Some(ranges) => ranges,
+            None => {
+                error!("failed getting lines around {}..={}", first, last);
+                continue;
+            }
+        };
+
+        let new_text = format!("{}\n{}", chars_in(rope, lower.clone()), chars_in(rope, upper.clone()));
+        let (dl, r) = replace_region(cursor_set, other_cursor_sets, rope, upper.start..lower.end, &new_text, &|pos| {
+            if pos >= lower.start {
+                pos - upper.len() - 1
+            } else {
+                pos + lower.len() + 1
+            }
+        });
+        diff_len += dl;
+        res |= r;
+    }
+
+    (diff_len, res)
+}
+
+fn duplicate_lines(cursor_set: &mut CursorSet, other_cursor_sets: &mut Vec<&mut CursorSet>, rope: &mut dyn TextBuffer) -> (usize, bool) {
+    let mut diff_len: usize = 0;
+    let mut res = false;
+
+    if cursor_set.iter().all(|c| c.s.is_some()) {
+        // copy goes right after the selection and gets selected. Last one first, so the cursors yet to be
+        // done don't move.
+        for cursor_idx in (0..cursor_set.len()).rev() {
+            let cursor = cursor_set.set()[cursor_idx];
+            let sel = match cursor.s {
+                Some(sel) => sel,
+                None => continue,
+            };
+
+            let text = chars_in(rope, sel.b..sel.e);
+            let (dl, r) = insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, sel.e, &text);
+            if r {
+                let copy = Selection::new(sel.e, sel.e + sel.len());
+                let anchor = if cursor.anchor_left() { copy.b } else { copy.e };
+                cursor_set.set_mut()[cursor_idx] = Cursor::new(anchor).with_selection(copy);
+            }
+            diff_len += dl;
+            res |= r;
+        }
+    } else {
+        // copy goes below, cursors go with it
+        for (first, last) in cursors_to_line_blocks(rope, cursor_set).into_iter().rev() {
+            let block = unpack_or!(
+                lines_to_char_range(rope, first, last),
+                (diff_len, res),
+                "failed getting lines {}..={}",
+                first,
+                last
+            );
+            let text = chars_in(rope, block.clone());
+            let (dl, r) = replace_region(
+                cursor_set,
+                other_cursor_sets,
+                rope,
+                block.clone(),
+                &format!("{}\n{}", text, text),
+                &|pos| pos + block.len() + 1,
+            );
+            diff_len += dl;
+            res |= r;
+        }
+    }
+
+    (diff_len, res)
+}
+
+fn delete_lines(cursor_set: &mut CursorSet, other_cursor_sets: &mut Vec<&mut CursorSet>, rope: &mut dyn TextBuffer) -> (usize, bool) {
+    let mut diff_len: usize = 0;
+    let mut res = false;
+
+    for (first, last) in cursors_to_line_blocks(rope, cursor_set).into_iter().rev() {
+        let block = unpack_or!(
+            lines_to_char_range(rope, first, last),
+            (diff_len, res),
+            "failed getting lines {}..={}",
+            first,
+            last
+        );
+
+        // together with the newline after, or the one before if it's the last line
+        let char_range = if block.end < rope.len_chars() {
+            block.start..block.end + 1
+        } else if block.start > 0 {
+            block.start - 1..block.end
+        } else {
+            block
+        };
+
+        if char_range.is_empty() {
+            continue;
+        }
+
+        let (dl, r) = remove_from_rope_keeping_cursors(cursor_set, other_cursor_sets, rope, char_range);
+        diff_len += dl;
+        res |= r;
+    }
+
+    (diff_len, res)
+}
+
+/*
+Joins lines with a single space in place of the line break and the indentation after it.
+ */
+fn join_lines(cursor_set: &mut CursorSet, other_cursor_sets: &mut Vec<&mut CursorSet>, rope: &mut dyn TextBuffer) -> (usize, bool) {
+    let mut diff_len: usize = 0;
+    let mut res = false;
+
+    for (first, last) in cursors_to_line_blocks(rope, cursor_set).into_iter().rev() {
+        let last = if first == last { last + 1 } else { last };
+        if last >= rope.len_lines() {
+            continue;
+        }
+
+        // from the bottom, so lines yet to be joined don't move
+        for line_idx in (first..last).rev() {
+            let ranges_op = lines_to_char_range(rope, line_idx, line_idx).zip(lines_to_char_range(rope, line_idx + 1, line_idx + 1));
+            let (current, next) = match ranges_op {
+                Some(ranges) => ranges,
+                None => {
+                    error!("failed getting lines {} and {}", line_idx, line_idx + 1);
+                    continue;
+                }
+            };
+
+            let current_text = chars_in(rope, current.clone());
+            let next_text = chars_in(rope, next.clone());
+            let content_end = current.start + current_text.trim_end().chars().count();
+            let next_indent = next_text.chars().take_while(|c| c.is_whitespace()).count();
+
+            let (dl, r) = remove_from_rope_keeping_cursors(cursor_set, other_cursor_sets, rope, content_end..next.start + next_indent);
+            diff_len += dl;
+            res |= r;
+
+            // nothing to separate if either side is blank
+            if content_end > current.start && next_indent < next_text.chars().count() {
+                let (dl, r) = insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, content_end, " ");
+                diff_len += dl;
+                res |= r;
+            }
+        }
+    }
+
+    (diff_len, res)
+}
+
+/*
+Opens an empty line above (or below) each line with a cursor, indented like that line, and moves the
+cursors there.
+ */
+fn insert_lines(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+    above: bool,
+) -> (usize, bool) {
+    let mut line_indices: Vec<usize> = cursor_set.iter().filter_map(|c| rope.char_to_line(c.a)).collect();
+    line_indices.dedup();
+
+    let mut diff_len: usize = 0;
+    let mut res = false;
+    let mut new_cursors: Vec<usize> = Vec::new();
+
+    for line_idx in line_indices.into_iter().rev() {
+        let line = unpack_or!(
+            lines_to_char_range(rope, line_idx, line_idx),
+            (diff_len, res),
+            "failed getting line {}",
+            line_idx
+        );
+        let indent: String = chars_in(rope, line.clone())
+            .chars()
+            .take_while(|c| *c == ' ' || *c == '\t')
+            .collect();
+        let indent_len = indent.chars().count();
+
+        let (char_pos, what, cursor_pos) = if above {
+            (line.start, format!("{}\n", indent), line.start + indent_len)
+        } else {
+            (line.end, format!("\n{}", indent), line.end + 1 + indent_len)
+        };
+
+        let (dl, r) = insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, char_pos, &what);
+        if r {
+            // ones found so far are below
+            for new_cursor in new_cursors.iter_mut() {
+                *new_cursor += indent_len + 1;
+            }
+            new_cursors.push(cursor_pos);
+        }
+        diff_len += dl;
+        res |= r;
+    }
+
+    if !new_cursors.is_empty() {
+        new_cursors.reverse();
+        *cursor_set.set_mut() = new_cursors.into_iter().map(Cursor::new).collect();
+    }
+
+    (diff_len, res)
+}
+
+/*
+Replaces each selection with its transformed text, which stays selected. Simple cursors are skipped,
+except in NumberSequence, where they get their number inserted.
+ */
+fn transform_selections(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+    transform: TextTransform,
+) -> (usize, bool) {
+    let sequence_start = cursor_set
+        .iter()
+        .next()
+        .and_then(|c| c.s)
+        .map(|sel| TextTransform::sequence_start(&chars_in(rope, sel.b..sel.e)))
+        .unwrap_or(1);
+
+    let mut diff_len: usize = 0;
+    let mut res = false;
+
+    // from the last one, so cursors yet to be done don't move
+    for cursor_idx in (0..cursor_set.len()).rev() {
+        let cursor = unpack_or!(
+            cursor_set.set().get(cursor_idx).copied(),
+            (diff_len, res),
+            "cursor {} gone",
+            cursor_idx
+        );
+        let char_range = match cursor.s {
+            Some(sel) => sel.b..sel.e,
+            None if transform == TextTransform::NumberSequence => cursor.a..cursor.a,
+            None => continue,
+        };
+
+        let new_text = match transform.apply(&chars_in(rope, char_range.clone()), sequence_start, cursor_idx) {
+            Some(new_text) => new_text,
+            None => {
+                debug!("can't apply {:?} to selection {:?}", transform, char_range);
+                continue;
+            }
+        };
+
+        let new_end = char_range.start + new_text.chars().count();
+        let selected = !char_range.is_empty();
+        let (dl, r) = replace_region(cursor_set, other_cursor_sets, rope, char_range.clone(), &new_text, &|pos| {
+            if selected && pos == char_range.start {
+                char_range.start
+            } else {
+                new_end
+            }
+        });
+        diff_len += dl;
+        res |= r;
+    }
+
+    (diff_len, res)
+}
+
+/*
+Reorders lines of each block of at least two with how (sort, reverse etc.).
+ */
+fn reorder_lines(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+    how: fn(&mut Vec<String>),
+) -> (usize, bool) {
+    let mut diff_len: usize = 0;
+    let mut res = false;
+
+    for (first, last) in cursors_to_line_blocks(rope, cursor_set).into_iter().rev() {
+        if first == last {
+            continue;
+        }
+
+        let block = unpack_or!(
+            lines_to_char_range(rope, first, last),
+            (diff_len, res),
+            "failed getting lines {}..={}",
+            first,
+            last
+        );
+        let mut lines: Vec<String> = chars_in(rope, block.clone()).split('\n').map(|line| line.to_string()).collect();
+        how(&mut lines);
+        let new_text = lines.join("\n");
+
+        // removing duplicates shortens the block, cursors are kept within what's left
+        let new_end = block.start + new_text.chars().count();
+        let (dl, r) = replace_region(cursor_set, other_cursor_sets, rope, block, &new_text, &|pos| pos.min(new_end));
+        diff_len += dl;
+        res |= r;
+    }
+
+    (diff_len, res)
+}
+
+/*
+Inserts lines of contents one under another, each at the display column of char_pos, so a block copied
+from column selection keeps its shape. Lines too short to reach that column are padded with spaces,
+lines missing at the end of buffer are added.
+ */
+fn paste_block(
+    cursor_set: &mut CursorSet,
+    other_cursor_sets: &mut Vec<&mut CursorSet>,
+    rope: &mut dyn TextBuffer,
+    char_pos: usize,
+    contents: &str,
+) -> (usize, bool) {
+    let lines: Vec<&str> = contents.lines().collect();
+    if lines.is_empty() {
+        warn!("not pasting empty contents");
+        return (0, false);
+    }
+
+    let first_line = unpack_or!(rope.char_to_line(char_pos), (0, false), "failed getting line of {}", char_pos);
+    let column = unpack_or!(
+        char_to_display_column(rope, char_pos),
+        (0, false),
+        "failed getting column of {}",
+        char_pos
+    );
+
+    let mut diff_len: usize = 0;
+    let mut res = false;
+
+    let missing_lines = (first_line + lines.len()).saturating_sub(rope.len_lines());
+    if missing_lines > 0 {
+        let buffer_end = rope.len_chars();
+        let (dl, r) = insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, buffer_end, &"\n".repeat(missing_lines));
+        if !r {
+            return (0, false);
+        }
+        diff_len += dl;
+        res = true;
+    }
+
+    // from the last line, so the ones yet to be done don't move
+    let mut new_cursor_pos = char_pos;
+    for (offset, line) in lines.iter().enumerate().rev() {
+        let line_idx = first_line + offset;
+        let char_idx = unpack_or!(
+            display_column_to_char(rope, line_idx, column),
+            (diff_len, res),
+            "failed getting column {} of line {}",
+            column,
+            line_idx
+        );
+        let padding = column.saturating_sub(char_to_display_column(rope, char_idx).unwrap_or(column));
+        let what = format!("{}{}", " ".repeat(padding), line);
+
+        let (dl, r) = insert_to_rope_at_random_place(cursor_set, other_cursor_sets, rope, char_idx, &what);
+        if r && offset == 0 {
+            new_cursor_pos = char_idx + what.chars().count();
+        }
+        diff_len += dl;
+        res |= r;
+    }
+
+    // newlines added at the end could have pushed the cursor there
+    *cursor_set = CursorSet::singleton(Cursor::new(new_cursor_pos));
+
+    (diff_len, res)
+}
+
 fn handle_backspace_and_delete(
     cursor_set: &mut CursorSet,
     other_cursor_sets: &mut Vec<&mut CursorSet>,
@@ -492,9 +1366,8 @@ fn handle_backspace_and_delete(
             let change = (sel.e - sel.b) as isize;
             modifier -= change;
 
-            if c.anchor_right() {
-                c.shift_by(change);
-            }
+            // whichever end it was on, anchor ends up where the selection began
+            c.a = sel.b;
         } else {
             if backspace {
                 if c.a == 0 {
@@ -506,11 +1379,20 @@ fn handle_backspace_and_delete(
                 }
             }
 
-            let (b, e) = if backspace { (c.a - 1, c.a) } else { (c.a, c.a + 1) };
+            let (b, e) = if backspace {
+                // empty pair goes as a whole (see bracket_pairs)
+                if rope.auto_pairing() && is_in_empty_pair(rope, c.a) {
+                    (c.a - 1, c.a + 1)
+                } else {
+                    (c.a - 1, c.a)
+                }
+            } else {
+                (c.a, c.a + 1)
+            };
 
             if rope.remove(b, e) {
                 res |= true;
-                diff_len += 1;
+                diff_len += e - b;
 
                 for other_cursor_set in other_cursor_sets.iter_mut() {
                     update_cursors_after_removal(other_cursor_set, b..e);
@@ -518,7 +1400,7 @@ fn handle_backspace_and_delete(
             } else {
                 warn!("expected to remove char but failed");
             }
-            modifier -= 1;
+            modifier -= (e - b) as isize;
 
             if backspace {
                 c.shift_by(-1);
@@ -545,6 +1427,9 @@ pub fn _apply_cem(
     clipboard: Option<&ClipboardRef>,
 ) -> (usize, bool) {
     let res = match cem {
+        CommonEditMsg::Char('\n') => insert_newlines(cursor_set, observer_cursor_sets, rope),
+        CommonEditMsg::Char(closer @ (')' | ']' | '}')) => insert_closing_bracket(cursor_set, observer_cursor_sets, rope, closer),
+        CommonEditMsg::Char(char) if rope.auto_pairing() => insert_char_with_pairs(cursor_set, observer_cursor_sets, rope, char),
         CommonEditMsg::Char(char) => {
             // TODO optimise
             insert_to_rope(cursor_set, observer_cursor_sets, rope, None, char.to_string().as_str())
@@ -629,18 +1514,26 @@ pub fn _apply_cem(
                 (0, false)
             }
         }
-        CommonEditMsg::Undo => (0, rope.undo()),
-        CommonEditMsg::Redo => (0, rope.redo()),
+        CommonEditMsg::PasteBlock => match (clipboard, cursor_set.as_single()) {
+            (Some(clipboard), Some(cursor)) if cursor.s.is_none() => {
+                paste_block(cursor_set, observer_cursor_sets, rope, cursor.a, &clipboard.get())
+            }
+            // with many cursors (or a selection) it's a regular paste
+            _ => _apply_cem(CommonEditMsg::Paste, cursor_set, observer_cursor_sets, rope, page_height, clipboard),
+        },
+        // undo/redo restore cursors in buffer, so the cursor_set passed here is stale afterwards and
+        // not checked below
+        CommonEditMsg::Undo => return (0, rope.undo()),
+        CommonEditMsg::Redo => return (0, rope.redo()),
         CommonEditMsg::DeleteBlock { char_range } => remove_from_rope_at_random_place(cursor_set, observer_cursor_sets, rope, char_range),
         CommonEditMsg::InsertBlock { char_pos, what } => {
             insert_to_rope_at_random_place(cursor_set, observer_cursor_sets, rope, char_pos, &what)
         }
         CommonEditMsg::Tab => {
-            let mut tab: String = String::new();
-            for _ in 0..rope.tab_width() {
-                tab.push(' ');
-            }
-            let tab = tab;
+            let tab: String = match rope.indent_style() {
+                IndentStyle::Tabs => "\t".to_string(),
+                IndentStyle::Spaces => " ".repeat(rope.tab_width()),
+            };
 
             // if they are simple, we just add spaces
             if cursor_set.are_simple() {
@@ -727,6 +1620,22 @@ pub fn _apply_cem(
 
             (chars_removed, modified)
         }
+        CommonEditMsg::ToggleLineComment => toggle_line_comments(cursor_set, observer_cursor_sets, rope),
+        CommonEditMsg::ToggleBlockComment => toggle_block_comments(cursor_set, observer_cursor_sets, rope),
+        CommonEditMsg::MoveLinesUp => move_lines(cursor_set, observer_cursor_sets, rope, true),
+        CommonEditMsg::MoveLinesDown => move_lines(cursor_set, observer_cursor_sets, rope, false),
+        CommonEditMsg::DuplicateLines => duplicate_lines(cursor_set, observer_cursor_sets, rope),
+        CommonEditMsg::DeleteLines => delete_lines(cursor_set, observer_cursor_sets, rope),
+        CommonEditMsg::JoinLines => join_lines(cursor_set, observer_cursor_sets, rope),
+        CommonEditMsg::InsertLineAbove => insert_lines(cursor_set, observer_cursor_sets, rope, true),
+        CommonEditMsg::InsertLineBelow => insert_lines(cursor_set, observer_cursor_sets, rope, false),
+        CommonEditMsg::SortLines => reorder_lines(cursor_set, observer_cursor_sets, rope, |lines| lines.sort()),
+        CommonEditMsg::UniqueLines => reorder_lines(cursor_set, observer_cursor_sets, rope, |lines| {
+            let mut seen: HashSet<String> = HashSet::new();
+            lines.retain(|line| seen.insert(line.clone()));
+        }),
+        CommonEditMsg::ReverseLines => reorder_lines(cursor_set, observer_cursor_sets, rope, |lines| lines.reverse()),
+        CommonEditMsg::Transform(transform) => transform_selections(cursor_set, observer_cursor_sets, rope, transform),
         CommonEditMsg::SubstituteBlock { char_range, with_what } => {
             let removal_result = if !char_range.is_empty() {
                 remove_from_rope_at_random_place(cursor_set, observer_cursor_sets, rope, char_range.clone())
@@ -773,6 +1682,7 @@ pub fn cme_to_direction(cme: &CommonEditMsg) -> Option<Arrow> {
         CommonEditMsg::Delete => None,
         CommonEditMsg::Copy => None,
         CommonEditMsg::Paste => Some(Arrow::Right),
+        CommonEditMsg::PasteBlock => Some(Arrow::Down),
         CommonEditMsg::Undo => None,
         CommonEditMsg::Redo => None,
         CommonEditMsg::ShiftTab => Some(Arrow::Left),
@@ -781,5 +1691,18 @@ pub fn cme_to_direction(cme: &CommonEditMsg) -> Option<Arrow> {
         CommonEditMsg::DeleteBlock { .. } => None,
         CommonEditMsg::InsertBlock { .. } => None,
         CommonEditMsg::SubstituteBlock { .. } => None,
+        CommonEditMsg::ToggleLineComment => None,
+        CommonEditMsg::ToggleBlockComment => None,
+        CommonEditMsg::MoveLinesUp => Some(Arrow::Up),
+        CommonEditMsg::MoveLinesDown => Some(Arrow::Down),
+        CommonEditMsg::DuplicateLines => Some(Arrow::Down),
+        CommonEditMsg::DeleteLines => None,
+        CommonEditMsg::JoinLines => None,
+        CommonEditMsg::InsertLineAbove => Some(Arrow::Up),
+        CommonEditMsg::InsertLineBelow => Some(Arrow::Down),
+        CommonEditMsg::SortLines => None,
+        CommonEditMsg::UniqueLines => None,
+        CommonEditMsg::ReverseLines => None,
+        CommonEditMsg::Transform(_) => None,
     }
 }
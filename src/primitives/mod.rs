@@ -28,12 +28,23 @@ pub use xy::{NeighboursIterator, XY};
 mod scroll;
 pub use scroll::{Scroll, ScrollDirection};
 
+mod auto_indent;
+pub use auto_indent::{closer_indent, indent_node_kinds, newline_indent, NewlineIndent};
+
+mod bracket_pairs;
+pub use bracket_pairs::{
+    is_in_empty_pair, matching_bracket, matching_bracket_pair, pair_closer, pair_quotes, should_close_pair, should_skip_closer, BRACKETS,
+};
+
 mod common_edit_msgs;
 pub use common_edit_msgs::{CommonEditMsg, _apply_cem, cme_to_direction, key_to_edit_msg};
 
 mod common_query;
 pub use common_query::CommonQuery;
 
+mod text_transforms;
+pub use text_transforms::TextTransform;
+
 mod is_default;
 pub use is_default::IsDefault;
 
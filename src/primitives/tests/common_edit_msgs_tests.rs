@@ -235,3 +235,37 @@ aa(aa
       dddd";
     assert_eq!(text_to_text(text_1, CommonEditMsg::Tab, None), text_1_after);
 }
+
+#[test]
+fn newline_keeps_indent() {
+    let text_1 = "
+    aa#aa
+        bb{#
+    cc{#}";
+    let text_1_after = "
+    aa
+    #aa
+        bb{
+            #
+    cc{
+        #
+    }";
+
+    assert_eq!(text_to_text(text_1, CommonEditMsg::Char('\n'), None), text_1_after);
+}
+
+#[test]
+fn closing_bracket_dedents() {
+    let text_1 = "
+{
+    aa
+        #
+    bb#";
+    let text_1_after = "
+{
+    aa
+    }#
+    bb}#";
+
+    assert_eq!(text_to_text(text_1, CommonEditMsg::Char('}'), None), text_1_after);
+}
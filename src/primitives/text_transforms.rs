@@ -0,0 +1,243 @@
+use base64::Engine;
+use heck::{ToKebabCase, ToLowerCamelCase, ToShoutySnakeCase, ToSnakeCase};
+use percent_encoding::{percent_decode_str, utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+
+/*
+Transformations of selected text. Each selection is transformed on its own, all of them as a single
+edit (see CommonEditMsg::Transform). Identifier cases (snake, camel etc.) are applied line by line, so
+multi-line selections keep their lines and indentation.
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq, Hash)]
+#[cfg_attr(feature = "arbitrary", derive(arbitrary::Arbitrary))]
+pub enum TextTransform {
+    UpperCase,
+    LowerCase,
+    TitleCase,
+    SnakeCase,
+    CamelCase,
+    KebabCase,
+    ScreamingSnakeCase,
+    // ends of selection and ends of lines in it
+    TrimWhitespace,
+    UrlEncode,
+    UrlDecode,
+    Base64Encode,
+    Base64Decode,
+    // every integer in selection
+    IncrementNumbers,
+    DecrementNumbers,
+    // 1, 2, 3... or counting up from number in first selection. Simple cursors get their number inserted.
+    NumberSequence,
+}
+
+// RFC 3986 unreserved characters are left as they are
+const URL_ENCODE_SET: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'_').remove(b'.').remove(b'~');
+
+impl TextTransform {
+    pub const ALL: &'static [TextTransform] = &[
+        TextTransform::UpperCase,
+        TextTransform::LowerCase,
+        TextTransform::TitleCase,
+        TextTransform::SnakeCase,
+        TextTransform::CamelCase,
+        TextTransform::KebabCase,
+        TextTransform::ScreamingSnakeCase,
+        TextTransform::TrimWhitespace,
+        TextTransform::UrlEncode,
+        TextTransform::UrlDecode,
+        TextTransform::Base64Encode,
+        TextTransform::Base64Decode,
+        TextTransform::IncrementNumbers,
+        TextTransform::DecrementNumbers,
+        TextTransform::NumberSequence,
+    ];
+
+    pub fn title(&self) -> &'static str {
+        match self {
+            TextTransform::UpperCase => "to UPPER CASE",
+            TextTransform::LowerCase => "to lower case",
+            TextTransform::TitleCase => "to Title Case",
+            TextTransform::SnakeCase => "to snake_case",
+            TextTransform::CamelCase => "to camelCase",
+            TextTransform::KebabCase => "to kebab-case",
+            TextTransform::ScreamingSnakeCase => "to SCREAMING_SNAKE_CASE",
+            TextTransform::TrimWhitespace => "trim whitespace",
+            TextTransform::UrlEncode => "url encode",
+            TextTransform::UrlDecode => "url decode",
+            TextTransform::Base64Encode => "base64 encode",
+            TextTransform::Base64Decode => "base64 decode",
+            TextTransform::IncrementNumbers => "increment numbers",
+            TextTransform::DecrementNumbers => "decrement numbers",
+            TextTransform::NumberSequence => "number sequentially",
+        }
+    }
+
+    /*
+    Transformed text, None if it can't be transformed (like invalid base64). sequence_start and
+    sequence_idx (position of the selection among all of them) matter only to NumberSequence.
+     */
+    pub fn apply(&self, text: &str, sequence_start: i64, sequence_idx: usize) -> Option<String> {
+        match self {
+            TextTransform::UpperCase => Some(text.to_uppercase()),
+            TextTransform::LowerCase => Some(text.to_lowercase()),
+            TextTransform::TitleCase => Some(title_case(text)),
+            TextTransform::SnakeCase => Some(per_line(text, |line| line.to_snake_case())),
+            TextTransform::CamelCase => Some(per_line(text, |line| line.to_lower_camel_case())),
+            TextTransform::KebabCase => Some(per_line(text, |line| line.to_kebab_case())),
+            TextTransform::ScreamingSnakeCase => Some(per_line(text, |line| line.to_shouty_snake_case())),
+            TextTransform::TrimWhitespace => Some(text.trim().split('\n').map(|line| line.trim_end()).collect::<Vec<_>>().join("\n")),
+            TextTransform::UrlEncode => Some(utf8_percent_encode(text, URL_ENCODE_SET).to_string()),
+            TextTransform::UrlDecode => percent_decode_str(text).decode_utf8().ok().map(|decoded| decoded.to_string()),
+            TextTransform::Base64Encode => Some(base64::engine::general_purpose::STANDARD.encode(text)),
+            TextTransform::Base64Decode => base64::engine::general_purpose::STANDARD
+                .decode(text.trim())
+                .ok()
+                .and_then(|bytes| String::from_utf8(bytes).ok()),
+            TextTransform::IncrementNumbers => Some(shift_numbers(text, 1)),
+            TextTransform::DecrementNumbers => Some(shift_numbers(text, -1)),
+            TextTransform::NumberSequence => sequence_start.checked_add(sequence_idx as i64).map(|number| number.to_string()),
+        }
+    }
+
+    /*
+    First number of NumberSequence, taken from first selection if it holds one.
+     */
+    pub fn sequence_start(first_text: &str) -> i64 {
+        first_text.trim().parse::<i64>().unwrap_or(1)
+    }
+}
+
+fn per_line<F: Fn(&str) -> String>(text: &str, f: F) -> String {
+    text.split('\n')
+        .map(|line| {
+            let content = line.trim();
+            if content.is_empty() {
+                return line.to_string();
+            }
+
+            let leading = &line[..line.len() - line.trim_start().len()];
+            let trailing = &line[line.trim_end().len()..];
+            format!("{}{}{}", leading, f(content), trailing)
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn title_case(text: &str) -> String {
+    let mut res = String::with_capacity(text.len());
+    let mut in_word = false;
+    for c in text.chars() {
+        if c.is_alphanumeric() {
+            if in_word {
+                res.extend(c.to_lowercase());
+            } else {
+                res.extend(c.to_uppercase());
+            }
+            in_word = true;
+        } else {
+            res.push(c);
+            // "don't" is one word
+            in_word = in_word && c == '\'';
+        }
+    }
+    res
+}
+
+/*
+Adds delta to every integer in text. Minus counts as sign only when it doesn't follow a word (so "a-1"
+stays a subtraction), zero padding is kept, numbers that don't fit i64 are left alone.
+ */
+fn shift_numbers(text: &str, delta: i64) -> String {
+    let chars: Vec<char> = text.chars().collect();
+    let mut res = String::with_capacity(text.len());
+    let mut idx = 0;
+
+    while idx < chars.len() {
+        let negative = chars[idx] == '-'
+            && chars.get(idx + 1).map(|c| c.is_ascii_digit()).unwrap_or(false)
+            && (idx == 0 || !chars[idx - 1].is_alphanumeric());
+        let digits_begin = if negative { idx + 1 } else { idx };
+
+        if !chars[digits_begin].is_ascii_digit() {
+            res.push(chars[idx]);
+            idx += 1;
+            continue;
+        }
+
+        let mut digits_end = digits_begin;
+        while digits_end < chars.len() && chars[digits_end].is_ascii_digit() {
+            digits_end += 1;
+        }
+
+        let literal: String = chars[idx..digits_end].iter().collect();
+        let width = digits_end - digits_begin;
+        let padded = width > 1 && chars[digits_begin] == '0';
+
+        match literal.parse::<i64>().ok().and_then(|number| number.checked_add(delta)) {
+            Some(number) if padded => {
+                let sign = if number < 0 { "-" } else { "" };
+                res.push_str(&format!("{}{:0width$}", sign, number.unsigned_abs(), width = width));
+            }
+            Some(number) => res.push_str(&number.to_string()),
+            None => res.push_str(&literal),
+        }
+        idx = digits_end;
+    }
+
+    res
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn apply(transform: TextTransform, text: &str) -> Option<String> {
+        transform.apply(text, 1, 0)
+    }
+
+    #[test]
+    fn test_cases() {
+        assert_eq!(apply(TextTransform::UpperCase, "ąb c"), Some("ĄB C".to_string()));
+        assert_eq!(
+            apply(TextTransform::TitleCase, "the QUICK fox's tail"),
+            Some("The Quick Fox's Tail".to_string())
+        );
+        assert_eq!(
+            apply(TextTransform::SnakeCase, "  someValue\n  otherValue"),
+            Some("  some_value\n  other_value".to_string())
+        );
+        assert_eq!(apply(TextTransform::CamelCase, "some_value"), Some("someValue".to_string()));
+        assert_eq!(apply(TextTransform::KebabCase, "SomeValue"), Some("some-value".to_string()));
+        assert_eq!(
+            apply(TextTransform::ScreamingSnakeCase, "some value"),
+            Some("SOME_VALUE".to_string())
+        );
+        assert_eq!(apply(TextTransform::TrimWhitespace, "  a  \n b \n"), Some("a\n b".to_string()));
+    }
+
+    #[test]
+    fn test_encodings() {
+        assert_eq!(apply(TextTransform::UrlEncode, "a b/ą~"), Some("a%20b%2F%C4%85~".to_string()));
+        assert_eq!(apply(TextTransform::UrlDecode, "a%20b%2F%C4%85~"), Some("a b/ą~".to_string()));
+        assert_eq!(apply(TextTransform::Base64Encode, "hello"), Some("aGVsbG8=".to_string()));
+        assert_eq!(apply(TextTransform::Base64Decode, "aGVsbG8="), Some("hello".to_string()));
+        assert_eq!(apply(TextTransform::Base64Decode, "not base64!"), None);
+    }
+
+    #[test]
+    fn test_numbers() {
+        assert_eq!(
+            apply(TextTransform::IncrementNumbers, "x9 = -1, a-1, 099"),
+            Some("x10 = 0, a-2, 100".to_string())
+        );
+        assert_eq!(apply(TextTransform::DecrementNumbers, "0 007 -9"), Some("-1 006 -10".to_string()));
+        assert_eq!(
+            apply(TextTransform::IncrementNumbers, "9223372036854775807"),
+            Some("9223372036854775807".to_string())
+        );
+
+        assert_eq!(TextTransform::sequence_start(" 5 "), 5);
+        assert_eq!(TextTransform::sequence_start("abc"), 1);
+        assert_eq!(TextTransform::NumberSequence.apply("whatever", 5, 2), Some("7".to_string()));
+    }
+}
@@ -1,4 +1,4 @@
-use std::cmp::max;
+use std::cmp::{max, min};
 use std::fmt::Debug;
 use std::ops::Range;
 use std::sync::Arc;
@@ -46,15 +46,39 @@ pub struct BufferState {
     subtype: BufferType,
 
     tree_sitter_op: Option<Arc<TreeSitterWrapper>>,
-    history: Vec<ContentsAndCursors>,
-    history_pos: usize,
+    text: ContentsAndCursors,
+    // undo/redo, as edits applied to text (not snapshots of it)
+    history: EditHistory,
+
+    // bumped on every change of contents (including undo/redo), so others can cheaply tell if they are stale
+    content_version: u64,
 
     lang_id: Option<LangId>,
 
+    tab_width: usize,
+    indent_style: IndentStyle,
+
     document_identifier: DocumentIdentifier,
+
+    // version of file as of when we last read or wrote it, to tell if someone else changed it since
+    disk_stamp: Option<FileStamp>,
+    // encoding and line endings used when reading and writing the file
+    file_format: FileFormat,
+    // the one file is in on disk, changing file_format away from it modifies the buffer
+    saved_file_format: FileFormat,
+
+    // files above GlobalEditorOptions::large_file_threshold_bytes are not parsed, nor measured whole on edits
+    large_file: bool,
+    // width of widest line, kept only for large files. It can grow, but never shrinks.
+    widest_line: u16,
+
+    // settings of .editorconfig files that apply to the file, as of when it was opened
+    editorconfig: EditorConfigOptions,
 }
 
 impl BufferState {
+    pub const DEFAULT_LARGE_FILE_THRESHOLD_BYTES: u64 = 16 * 1024 * 1024;
+
     pub fn into_bsr(self) -> BufferSharedRef {
         BufferSharedRef::new_from_buffer(self)
     }
@@ -237,13 +261,16 @@ impl BufferState {
         true
     }
 
-    pub fn get_visible_chars_range(&self, output: &mut dyn Output) -> Option<Range<usize>> {
+    /*
+    first_line is line of buffer drawn at y = 0 of output.
+     */
+    pub fn get_visible_chars_range(&self, output: &mut dyn Output, first_line: usize) -> Option<Range<usize>> {
         let rope = self.text().rope();
 
         let visible_rect = output.visible_rect();
 
-        let first_line = visible_rect.upper_left().y as usize;
-        let beyond_last_lane = visible_rect.lower_right().y as usize + 1;
+        let beyond_last_lane = first_line + visible_rect.lower_right().y as usize + 1;
+        let first_line = first_line + visible_rect.upper_left().y as usize;
 
         let first_char_idx = rope.try_line_to_char(first_line).ok()?;
         let beyond_last_char_idx = rope.try_line_to_char(beyond_last_lane).unwrap_or(rope.len_chars() + 1);
@@ -251,6 +278,21 @@ impl BufferState {
         Some(first_char_idx..beyond_last_char_idx)
     }
 
+    /*
+    Like lines().skip(line_idx), but without going through the lines skipped.
+     */
+    pub fn lines_from(&self, line_idx: usize) -> LinesIter<'_> {
+        let rope = self.text().rope();
+        match rope
+            .try_line_to_char(line_idx)
+            .ok()
+            .and_then(|char_idx| rope.get_chars_at(char_idx))
+        {
+            Some(chars) => LinesIter::new(chars),
+            None => LinesIter::new(std::iter::empty()),
+        }
+    }
+
     pub fn cursors(&self, widget_id: WID) -> Option<&CursorSet> {
         self.text().get_cursor_set(widget_id)
     }
@@ -285,14 +327,45 @@ impl BufferState {
         }
     }
 
+    /*
+    See ContentsAndCursors::add_next_occurrence. Changes only cursors, but as an undo step of its own,
+    like find_once.
+     */
+    pub fn add_next_occurrence(&mut self, widget_id: WID, skip: bool) -> Result<bool, FindError> {
+        self.set_milestone();
+        let res = self.text_mut().add_next_occurrence(widget_id, skip);
+        if res != Ok(true) {
+            self.undo_milestone();
+        }
+        res
+    }
+
+    pub fn select_all_occurrences(&mut self, widget_id: WID) -> Result<bool, FindError> {
+        self.set_milestone();
+        let res = self.text_mut().select_all_occurrences(widget_id);
+        if res != Ok(true) {
+            self.undo_milestone();
+        }
+        res
+    }
+
     pub fn full(tree_sitter_op: Option<Arc<TreeSitterWrapper>>, document_identifier: DocumentIdentifier) -> BufferState {
         let res = BufferState {
             subtype: BufferType::Full,
             tree_sitter_op,
-            history: vec![ContentsAndCursors::empty()],
-            history_pos: 0,
+            text: ContentsAndCursors::empty(),
+            history: EditHistory::new(EditHistory::DEFAULT_LIMIT_BYTES),
+            content_version: 0,
             lang_id: None,
+            tab_width: ResolvedLanguageOptions::default().tab_width,
+            indent_style: IndentStyle::default(),
             document_identifier,
+            disk_stamp: None,
+            file_format: FileFormat::default(),
+            saved_file_format: FileFormat::default(),
+            large_file: false,
+            widest_line: 0,
+            editorconfig: EditorConfigOptions::default(),
         };
 
         debug_assert!(res.check_invariant());
@@ -351,29 +424,224 @@ impl BufferState {
     }
 
     pub fn remove_history(&mut self) {
-        if self.history_pos != 0 {
-            self.history.swap(0, self.history_pos)
+        self.history.clear();
+    }
+
+    /*
+    Undo history is capped, oldest steps are forgotten once it uses more than limit_bytes.
+     */
+    pub fn set_history_limit(&mut self, limit_bytes: usize) {
+        self.history.set_limit_bytes(limit_bytes);
+    }
+
+    pub fn with_history_limit(mut self, limit_bytes: usize) -> Self {
+        self.set_history_limit(limit_bytes);
+        self
+    }
+
+    pub fn history(&self) -> &EditHistory {
+        &self.history
+    }
+
+    /*
+    Whether text differs from what was last saved (or loaded, or set).
+     */
+    pub fn is_modified(&self) -> bool {
+        self.history.is_modified() || self.file_format != self.saved_file_format
+    }
+
+    pub fn mark_saved(&mut self) {
+        self.history.mark_saved();
+        self.saved_file_format = self.file_format;
+    }
+
+    pub fn disk_stamp(&self) -> Option<FileStamp> {
+        self.disk_stamp
+    }
+
+    /*
+    To be called whenever buffer is read from or written to its file.
+     */
+    pub fn set_disk_stamp(&mut self, stamp: Option<FileStamp>) {
+        self.disk_stamp = stamp;
+    }
+
+    pub fn file_format(&self) -> FileFormat {
+        self.file_format
+    }
+
+    /*
+    Format of text as it was read from file, it's used on save.
+     */
+    pub fn set_file_format(&mut self, file_format: FileFormat) {
+        self.file_format = file_format;
+        self.saved_file_format = file_format;
+    }
+
+    pub fn with_file_format(mut self, file_format: FileFormat) -> Self {
+        self.set_file_format(file_format);
+        self
+    }
+
+    /*
+    Unlike set_file_format, this is a change to be saved, so it modifies the buffer.
+     */
+    pub fn set_encoding(&mut self, encoding: Encoding) {
+        self.file_format.encoding = encoding;
+    }
+
+    /*
+    Picking LF or CRLF for file with mixed line endings drops CRs from buffer, that can be undone (the
+    choice itself can't). Like set_encoding, it modifies the buffer. Returns whether text changed.
+     */
+    pub fn set_line_ending(&mut self, line_ending: LineEnding) -> bool {
+        if line_ending == LineEnding::Mixed {
+            error!("mixed line endings can be only found, not picked");
+            return false;
+        }
+
+        let changed = if self.file_format.line_ending == LineEnding::Mixed {
+            let text = self.text.rope().to_string().replace("\r\n", "\n");
+            self.replace_text(text)
+        } else {
+            false
+        };
+
+        self.file_format.line_ending = line_ending;
+        changed
+    }
+
+    /*
+    Writes text to file in buffer's format. If the encoding can't hold the text anymore (non Latin-1
+    chars were typed in Latin-1 file), the file becomes UTF-8 rather than loose them.
+     */
+    pub fn write_to_file(&mut self, path: &SPath) -> Result<usize, WriteError> {
+        if self.file_format.is_default() {
+            return path.overwrite_with_stream(&mut self.streaming_iterator(), false);
+        }
+
+        let text = self.text.rope().to_string();
+        if !self.file_format.encoding.can_encode(&text) {
+            warn!("{} can't hold text of {}, saving it as UTF-8", self.file_format.encoding, path);
+            self.file_format.encoding = Encoding::Utf8;
+        }
+
+        path.overwrite_with_bytes(&self.file_format.encode(&text), false)
+    }
+
+    pub fn is_large_file(&self) -> bool {
+        self.large_file
+    }
+
+    /*
+    Large file has no syntax tree and no language server, and its width is measured once, then only
+    where it's edited, so both typing and scrolling don't depend on its size.
+     */
+    pub fn set_large_file(&mut self, large_file: bool) {
+        self.large_file = large_file;
+        if large_file {
+            self.text.drop_parsing();
+            self.measure_widest_line();
+        } else {
+            self.set_parsing_tuple();
+        }
+    }
+
+    pub fn with_large_file(mut self, large_file: bool) -> Self {
+        self.set_large_file(large_file);
+        self
+    }
+
+    fn measure_widest_line(&mut self) {
+        if !self.large_file {
+            return;
+        }
+
+        self.widest_line = self.text.rope().lines().map(|line| line_width(line)).max().unwrap_or(0);
+    }
+
+    /*
+    Lines overlapping given range of chars, after an edit.
+     */
+    fn widen_to_lines(&mut self, char_idx_begin: usize, char_idx_end: usize) {
+        if !self.large_file {
+            return;
+        }
+
+        let rope = self.text.rope();
+        let first = rope.char_to_line(min(char_idx_begin, rope.len_chars()));
+        let last = rope.char_to_line(min(char_idx_end, rope.len_chars()));
+        for line_idx in first..=last {
+            if let Some(line) = rope.get_line(line_idx) {
+                self.widest_line = max(self.widest_line, line_width(line));
+            }
+        }
+    }
+
+    /*
+    Replaces undo history, used to restore one from previous session. It has to end at current text.
+     */
+    pub fn set_history(&mut self, history: EditHistory) {
+        self.history = history;
+    }
+
+    pub fn with_history(mut self, history: EditHistory) -> Self {
+        self.set_history(history);
+        self
+    }
+
+    /*
+    Text of any state of undo tree, buffer stays as it is.
+     */
+    pub fn history_text_at(&self, node_id: HistoryNodeId) -> Option<Rope> {
+        self.history.text_at(self.text.rope(), node_id)
+    }
+
+    /*
+    Moves buffer to any state of undo tree, by undoing up to the common ancestor and redoing down the
+    other branch. Returns whether anything changed.
+     */
+    pub fn jump_to_history_node(&mut self, node_id: HistoryNodeId) -> bool {
+        let (up, down) = unpack_or_e!(
+            self.history.path(self.history.current(), node_id),
+            false,
+            "no history node {}",
+            node_id
+        );
+
+        for _ in up.iter() {
+            if !self.undo() {
+                error!("failed to undo on the way to history node {}", node_id);
+                return false;
+            }
+        }
+        for child in down.iter() {
+            if !self.history.select_redo_child(*child) || !self.redo() {
+                error!("failed to redo on the way to history node {}", node_id);
+                return false;
+            }
         }
-        self.history.truncate(1);
-        self.history_pos = 0;
+
+        !(up.is_empty() && down.is_empty())
     }
 
-    /* removes previous to last milestone, and moves last one to it's position.
+    /* merges last milestone into previous one.
       used to chain multiple operations into a single milestone
     */
     fn reduce_merge_milestone(&mut self) {
-        debug_assert!(self.history_pos + 1 == self.history.len());
-        debug_assert!(self.history_pos >= 1);
-
-        self.history.remove(self.history_pos - 1);
-        self.history_pos -= 1;
+        self.history.merge_last_two();
     }
 
-    // TODO overload and optimise?
     pub fn size(&self) -> XY {
         let mut size = XY::ZERO;
 
-        size.y = self.len_lines() as u16; // TODO overflow
+        // editor shows a window of lines of longer texts, see EditorWidget::LINE_WINDOW
+        size.y = min(self.len_lines(), u16::MAX as usize) as u16;
+
+        if self.large_file {
+            size.x = self.widest_line;
+            return size;
+        }
 
         let mut lines_iter = self.lines();
         while let Some(line) = lines_iter.next() {
@@ -403,6 +671,118 @@ impl BufferState {
         }
     }
 
+    pub fn set_indentation(&mut self, tab_width: usize, indent_style: IndentStyle) {
+        if tab_width == 0 {
+            error!("ignoring tab_width == 0");
+        } else {
+            self.tab_width = tab_width;
+        }
+        self.indent_style = indent_style;
+    }
+
+    pub fn editorconfig(&self) -> &EditorConfigOptions {
+        &self.editorconfig
+    }
+
+    /*
+    Only remembers the options, for save (see clean_up_for_save) and for the ruler. Indentation and file
+    format are set separately, as they have other sources too.
+     */
+    pub fn set_editorconfig(&mut self, editorconfig: EditorConfigOptions) {
+        self.editorconfig = editorconfig;
+    }
+
+    pub fn with_editorconfig(mut self, editorconfig: EditorConfigOptions) -> Self {
+        self.set_editorconfig(editorconfig);
+        self
+    }
+
+    pub fn formatting_settings(&self) -> FormattingSettings {
+        FormattingSettings {
+            tab_width: self.tab_width,
+            indent_style: self.indent_style,
+            trim_trailing_whitespace: self.editorconfig.trim_trailing_whitespace.unwrap_or(true),
+            insert_final_newline: self.editorconfig.insert_final_newline.unwrap_or(true),
+        }
+    }
+
+    /*
+    Trims trailing whitespace and adds final newline, if .editorconfig asks for it. It's one undo step.
+    Cursors past removed whitespace move back with the text, selections are dropped. Large files are left
+    alone, as it would take going through all of them on every save. Returns whether text changed.
+
+    Line ending from .editorconfig is applied here too, and not on open, so just opening a file with mixed
+    line endings doesn't modify it (see set_line_ending).
+     */
+    pub fn clean_up_for_save(&mut self) -> bool {
+        let line_ending_changed_text = match self.editorconfig.end_of_line {
+            Some(line_ending) if line_ending != self.file_format.line_ending => self.set_line_ending(line_ending),
+            _ => false,
+        };
+
+        if self.large_file {
+            return line_ending_changed_text;
+        }
+
+        let mut removed: Vec<(usize, usize)> = Vec::new();
+        if self.editorconfig.trim_trailing_whitespace == Some(true) {
+            let rope = self.text.rope();
+            for line_idx in 0..rope.len_lines() {
+                let line = rope.line(line_idx);
+                let line_begin = rope.line_to_char(line_idx);
+
+                let mut end = line.len_chars();
+                while end > 0 && (line.char(end - 1) == '\n' || line.char(end - 1) == '\r') {
+                    end -= 1;
+                }
+                let mut begin = end;
+                while begin > 0 && (line.char(begin - 1) == ' ' || line.char(begin - 1) == '\t') {
+                    begin -= 1;
+                }
+
+                if begin < end {
+                    removed.push((line_begin + begin, line_begin + end));
+                }
+            }
+        }
+
+        let len_chars = self.len_chars();
+        let missing_newline =
+            self.editorconfig.insert_final_newline == Some(true) && len_chars > 0 && self.text.rope().char(len_chars - 1) != '\n';
+
+        if removed.is_empty() && !missing_newline {
+            return line_ending_changed_text;
+        }
+
+        self.set_milestone();
+        for (begin, end) in removed.iter().rev() {
+            if !self.remove(*begin, *end) {
+                error!("failed to remove trailing whitespace at {}..{}", begin, end);
+            }
+        }
+        if missing_newline && !self.insert_block(self.len_chars(), "\n") {
+            error!("failed to insert final newline");
+        }
+
+        let cursor_sets: Vec<(WID, CursorSet)> = self.text.cursor_sets().clone();
+        for (widget_id, mut cursor_set) in cursor_sets.into_iter() {
+            for cursor in cursor_set.set_mut().iter_mut() {
+                let shift: usize = removed
+                    .iter()
+                    .filter(|(begin, _)| *begin < cursor.a)
+                    .map(|(begin, end)| min(*end, cursor.a) - begin)
+                    .sum();
+                cursor.a -= shift;
+                cursor.s = None;
+                cursor.preferred_column = None;
+            }
+            cursor_set.set_mut().dedup_by_key(|cursor| cursor.a);
+            self.text.set_cursor_set(widget_id, cursor_set);
+        }
+
+        true
+    }
+
     pub fn set_lang(&mut self, lang_id: Option<LangId>) {
         if self.subtype != BufferType::Full {
             error!("setting lang in non TextBuffer::Full!");
@@ -419,13 +799,16 @@ impl BufferState {
     set_milestone drops "forward history".
      */
     fn set_milestone(&mut self) -> bool {
-        self.history.truncate(self.history_pos + 1);
-        self.history.push(self.history[self.history_pos].clone());
-        self.history_pos += 1;
+        self.history.begin_entry(self.text.cursor_sets().clone());
         true
     }
 
     fn set_parsing_tuple(&mut self) -> bool {
+        if self.large_file {
+            debug!("not parsing large file {:?}", self.get_path());
+            return false;
+        }
+
         let lang_id = match self.lang_id {
             Some(li) => li,
             None => match self.get_path().map(filename_to_language).flatten() {
@@ -464,10 +847,19 @@ impl BufferState {
         let res = BufferState {
             subtype: BufferType::SingleLine,
             tree_sitter_op: None,
-            history: vec![ContentsAndCursors::empty()],
-            history_pos: 0,
+            text: ContentsAndCursors::empty(),
+            history: EditHistory::new(EditHistory::DEFAULT_LIMIT_BYTES),
+            content_version: 0,
             lang_id: None,
+            tab_width: ResolvedLanguageOptions::default().tab_width,
+            indent_style: IndentStyle::default(),
             document_identifier: doc_id,
+            disk_stamp: None,
+            file_format: FileFormat::default(),
+            saved_file_format: FileFormat::default(),
+            large_file: false,
+            widest_line: 0,
+            editorconfig: EditorConfigOptions::default(),
         };
 
         debug_assert!(res.check_invariant());
@@ -482,31 +874,47 @@ impl BufferState {
         }
     }
 
+    pub fn content_version(&self) -> u64 {
+        self.content_version
+    }
+
     pub fn subtype(&self) -> &BufferType {
         &self.subtype
     }
 
     pub fn initialize_for_widget(&mut self, widget_id: WID, cursors_op: Option<CursorSet>) -> bool {
         let cursor_set = cursors_op.unwrap_or(CursorSet::single());
-        self.history[self.history_pos].add_cursor_set(widget_id, cursor_set)
+        self.text.add_cursor_set(widget_id, cursor_set)
+    }
+
+    /*
+    Counterpart of initialize_for_widget, for widgets that go away while buffer stays.
+     */
+    pub fn remove_widget(&mut self, widget_id: WID) -> bool {
+        self.text.remove_cursor_set(widget_id)
     }
 
     pub fn text(&self) -> &ContentsAndCursors {
-        debug_assert!(self.history.len() >= self.history_pos);
-        &self.history[self.history_pos]
+        &self.text
     }
 
     pub fn text_mut(&mut self) -> &mut ContentsAndCursors {
-        debug_assert!(self.history.len() >= self.history_pos);
-        &mut self.history[self.history_pos]
+        &mut self.text
     }
 
-    // to be used only in apply_cem
+    // to be used only in apply_cem (and friends). Reverts whatever was done since last milestone.
     fn undo_milestone(&mut self) {
-        debug_assert!(self.history_pos + 1 == self.history.len());
-        debug_assert!(self.history_pos > 0);
-        self.history_pos -= 1;
-        self.history.truncate(self.history_pos + 1);
+        let entry = unpack_or_e!(self.history.pop_entry(), (), "no milestone to undo");
+
+        if !entry.edits.is_empty() {
+            for edit in entry.edits.iter().rev() {
+                if !apply_edit(&mut self.text, edit, false) {
+                    error!("failed to revert edit {:?}", edit);
+                }
+            }
+            restore_cursors(&mut self.text, &entry.cursors_before);
+            self.content_version += 1;
+        }
     }
 
     pub fn with_lang(self, lang_id: LangId) -> Self {
@@ -524,16 +932,22 @@ impl BufferState {
         res
     }
 
+    pub fn with_indentation(mut self, tab_width: usize, indent_style: IndentStyle) -> Self {
+        self.set_indentation(tab_width, indent_style);
+        self
+    }
+
     pub fn with_text<T: AsRef<str>>(self, text: T) -> Self {
         let rope = ropey::Rope::from_str(text.as_ref());
 
         let mut result = Self {
-            history: vec![ContentsAndCursors::empty().with_rope(rope)],
-            history_pos: 0,
+            text: ContentsAndCursors::empty().with_rope(rope),
             ..self
         };
+        result.history.clear();
 
         result.set_parsing_tuple();
+        result.measure_widest_line();
 
         debug_assert!(result.check_invariant());
 
@@ -544,27 +958,69 @@ impl BufferState {
     Destroys history
      */
     pub fn set_text<T: AsRef<str>>(&mut self, text: T) {
-        self.history = vec![ContentsAndCursors::empty().with_rope(Rope::from_str(text.as_ref()))];
-        self.history_pos = 0;
+        self.text = ContentsAndCursors::empty().with_rope(Rope::from_str(text.as_ref()));
+        self.history.clear();
+        self.content_version += 1;
 
         self.set_parsing_tuple();
+        self.measure_widest_line();
         self.check_invariant();
     }
 
+    /*
+    Replaces whole text as a single undo step, so unlike set_text it keeps history (and buffer becomes
+    modified). Cursors that no longer fit are moved to the end.
+     */
+    pub fn replace_text<T: AsRef<str>>(&mut self, text: T) -> bool {
+        let text = text.as_ref();
+        if self.text.rope() == text {
+            return false;
+        }
+
+        self.set_milestone();
+        let len_chars = self.len_chars();
+        if len_chars > 0 && !self.remove(0, len_chars) {
+            error!("failed to remove text");
+        }
+        if !text.is_empty() && !self.insert_block(0, text) {
+            error!("failed to insert text");
+        }
+        restore_cursors(&mut self.text, &[]);
+
+        true
+    }
+
+    /*
+    Like replace_text, but with no undo step, history is dropped. For large files, where history can't
+    afford to hold both versions.
+     */
+    pub fn replace_text_dropping_history(&mut self, rope: Rope) -> bool {
+        if *self.text.rope() == rope {
+            return false;
+        }
+
+        *self.text.rope_mut() = rope;
+        self.history.clear();
+        self.content_version += 1;
+
+        self.set_parsing_tuple();
+        self.measure_widest_line();
+        restore_cursors(&mut self.text, &[]);
+
+        true
+    }
+
     /*
     This is expected to be used only in construction, it clears the history.
      */
     pub fn with_text_from_rope(self, rope: Rope, lang_id: Option<LangId>) -> Self {
         let text = ContentsAndCursors::empty().with_rope(rope);
 
-        let mut res = Self {
-            history: vec![text],
-            history_pos: 0,
-            lang_id,
-            ..self
-        };
+        let mut res = Self { text, lang_id, ..self };
+        res.history.clear();
 
         res.set_parsing_tuple();
+        res.measure_widest_line();
 
         res
     }
@@ -572,11 +1028,105 @@ impl BufferState {
 
 impl HasInvariant for BufferState {
     fn check_invariant(&self) -> bool {
-        if self.history_pos >= self.history.len() {
-            return false;
+        self.history.check_invariant()
+    }
+}
+
+fn line_width(line: ropey::RopeSlice) -> u16 {
+    let width: usize = line.chunks().map(|chunk| chunk.width()).sum();
+    min(width, u16::MAX as usize) as u16
+}
+
+fn cursor_set_within(cursor_set: &CursorSet, len_chars: usize) -> bool {
+    cursor_set
+        .iter()
+        .all(|c| c.a <= len_chars && c.s.map(|s| s.e <= len_chars).unwrap_or(true))
+}
+
+/*
+Raw rope modifications, keeping parse tree up to date. They don't touch history, so they are used both
+for "new" edits (that get recorded) and for undo/redo of recorded ones.
+ */
+fn rope_insert(text: &mut ContentsAndCursors, char_idx: usize, block: &str, parse_len: usize) -> bool {
+    match text.rope_mut().try_insert(char_idx, block) {
+        Ok(_) => {
+            let rope_clone = text.rope().clone();
+
+            text.parsing_mut().map_or_else(
+                || {
+                    debug!("failed to acquire parse_tuple 2");
+                },
+                |r| {
+                    r.update_parse_on_insert(&rope_clone, char_idx, char_idx + parse_len);
+                },
+            );
+
+            true
         }
+        Err(e) => {
+            warn!("failed inserting block {} (len {}) because {}", char_idx, parse_len, e);
+            false
+        }
+    }
+}
 
-        true
+fn rope_remove(text: &mut ContentsAndCursors, char_idx_begin: usize, char_idx_end: usize) -> bool {
+    match text.rope_mut().try_remove(char_idx_begin..char_idx_end) {
+        Ok(_) => {
+            let rope_clone = text.rope().clone();
+
+            text.parsing_mut().map_or_else(
+                || {
+                    debug!("failed to acquire parse_tuple 3");
+                },
+                |r| {
+                    r.update_parse_on_delete(&rope_clone, char_idx_begin, char_idx_end);
+                },
+            );
+
+            true
+        }
+        Err(e) => {
+            warn!("failed removing char {:?}-{:?} because {}", char_idx_begin, char_idx_end, e);
+            false
+        }
+    }
+}
+
+// applies edit (forward) or its inverse (backward)
+fn apply_edit(text: &mut ContentsAndCursors, edit: &TextEdit, forward: bool) -> bool {
+    let (char_idx, block, insert) = match edit {
+        TextEdit::Insert { char_idx, text } => (*char_idx, text, forward),
+        TextEdit::Remove { char_idx, text } => (*char_idx, text, !forward),
+    };
+
+    if insert {
+        rope_insert(text, char_idx, block, block.as_str().graphemes(true).count())
+    } else {
+        rope_remove(text, char_idx, char_idx + block.chars().count())
+    }
+}
+
+/*
+Views that were around when the step was recorded get their cursors back. Others (registered later)
+keep theirs, unless they no longer fit in the text.
+ */
+fn restore_cursors(text: &mut ContentsAndCursors, cursor_sets: &[(WID, CursorSet)]) {
+    for (widget_id, cursor_set) in cursor_sets.iter() {
+        if text.has_cursor_set_for(*widget_id) {
+            text.set_cursor_set(*widget_id, cursor_set.clone());
+        }
+    }
+
+    let len_chars = text.rope().len_chars();
+    let out_of_bounds: Vec<WID> = text
+        .cursor_sets()
+        .iter()
+        .filter(|(_, cs)| !cursor_set_within(cs, len_chars))
+        .map(|(wid, _)| *wid)
+        .collect();
+    for widget_id in out_of_bounds {
+        text.set_cursor_set(widget_id, CursorSet::singleton(Cursor::new(len_chars)));
     }
 }
 
@@ -603,11 +1153,11 @@ impl TextBuffer for BufferState {
     }
 
     fn can_redo(&self) -> bool {
-        self.history_pos + 1 < self.history.len()
+        self.history.can_redo()
     }
 
     fn can_undo(&self) -> bool {
-        self.history_pos > 0
+        self.history.can_undo()
     }
 
     fn char_at(&self, char_idx: usize) -> Option<char> {
@@ -636,52 +1186,31 @@ impl TextBuffer for BufferState {
     fn insert_block(&mut self, char_idx: usize, block: &str) -> bool {
         // TODO maybe blocks will be more performant?
         let grapheme_len = block.graphemes(true).count();
-        let text = self.text_mut();
 
-        match text.rope_mut().try_insert(char_idx, block) {
-            Ok(_) => {
-                let rope_clone = text.rope().clone();
-
-                text.parsing_mut().map_or_else(
-                    || {
-                        debug!("failed to acquire parse_tuple 2");
-                    },
-                    |r| {
-                        r.update_parse_on_insert(&rope_clone, char_idx, char_idx + grapheme_len);
-                    },
-                );
-
-                true
-            }
-            Err(e) => {
-                warn!("failed inserting block {} (len {}) because {}", char_idx, grapheme_len, e);
-                false
-            }
+        if rope_insert(&mut self.text, char_idx, block, grapheme_len) {
+            self.history.record(TextEdit::Insert {
+                char_idx,
+                text: block.to_string(),
+            });
+            self.content_version += 1;
+            self.widen_to_lines(char_idx, char_idx + block.chars().count());
+            true
+        } else {
+            false
         }
     }
 
     fn insert_char(&mut self, char_idx: usize, ch: char) -> bool {
-        let text = self.text_mut();
-        match text.rope_mut().try_insert_char(char_idx, ch) {
-            Ok(_) => {
-                // TODO maybe this method should be moved to text object.
-                let rope_clone = text.rope().clone();
-
-                text.parsing_mut().map_or_else(
-                    || {
-                        debug!("failed to acquire parse_tuple 1");
-                    },
-                    |r| {
-                        r.update_parse_on_insert(&rope_clone, char_idx, char_idx + 1);
-                    },
-                );
-
-                true
-            }
-            Err(e) => {
-                warn!("failed inserting char {} because {}", char_idx, e);
-                false
-            }
+        if rope_insert(&mut self.text, char_idx, ch.encode_utf8(&mut [0; 4]), 1) {
+            self.history.record(TextEdit::Insert {
+                char_idx,
+                text: ch.to_string(),
+            });
+            self.content_version += 1;
+            self.widen_to_lines(char_idx, char_idx + 1);
+            true
+        } else {
+            false
         }
     }
 
@@ -713,13 +1242,18 @@ impl TextBuffer for BufferState {
     }
 
     fn redo(&mut self) -> bool {
-        debug!("REDO pos {} len {}", self.history_pos, self.history.len());
-        if self.history_pos + 1 < self.history.len() {
-            self.history_pos += 1;
-            true
-        } else {
-            false
+        let BufferState { text, history, .. } = self;
+        let entry = unpack_or!(history.redo(), false, "nothing to redo");
+
+        for edit in entry.edits.iter() {
+            if !apply_edit(text, edit, true) {
+                error!("failed to redo edit {:?}", edit);
+            }
         }
+        restore_cursors(text, &entry.cursors_after);
+
+        self.content_version += 1;
+        true
     }
 
     fn remove(&mut self, char_idx_begin: usize, char_idx_end: usize) -> bool {
@@ -728,37 +1262,62 @@ impl TextBuffer for BufferState {
             return false;
         }
 
-        let text = self.text_mut();
-        match text.rope_mut().try_remove(char_idx_begin..char_idx_end) {
-            Ok(_) => {
-                let rope_clone = text.rope().clone();
+        let removed = unpack_or_e!(
+            self.text.rope().get_slice(char_idx_begin..char_idx_end),
+            false,
+            "failed removing char {:?}-{:?}, out of bounds",
+            char_idx_begin,
+            char_idx_end
+        )
+        .to_string();
+
+        if rope_remove(&mut self.text, char_idx_begin, char_idx_end) {
+            self.history.record(TextEdit::Remove {
+                char_idx: char_idx_begin,
+                text: removed,
+            });
+            self.content_version += 1;
+            self.widen_to_lines(char_idx_begin, char_idx_begin);
+            true
+        } else {
+            false
+        }
+    }
 
-                text.parsing_mut().map_or_else(
-                    || {
-                        debug!("failed to acquire parse_tuple 3");
-                    },
-                    |r| {
-                        r.update_parse_on_delete(&rope_clone, char_idx_begin, char_idx_end);
-                    },
-                );
+    fn tab_width(&self) -> usize {
+        self.tab_width
+    }
 
-                true
-            }
-            Err(e) => {
-                warn!("failed removing char {:?}-{:?} because {}", char_idx_begin, char_idx_end, e);
-                false
-            }
-        }
+    fn indent_style(&self) -> IndentStyle {
+        self.indent_style
+    }
+
+    fn parsing(&self) -> Option<&ParsingTuple> {
+        self.text.parsing()
+    }
+
+    fn lang_id(&self) -> Option<LangId> {
+        self.lang_id
+    }
+
+    fn auto_pairing(&self) -> bool {
+        self.subtype == BufferType::Full
     }
 
     fn undo(&mut self) -> bool {
-        debug!("UNDO pos {} len {}", self.history_pos, self.history.len());
-        if self.history_pos > 0 {
-            self.history_pos -= 1;
-            true
-        } else {
-            false
+        let BufferState { text, history, .. } = self;
+        let cursors_after = text.cursor_sets().clone();
+        let entry = unpack_or!(history.undo(cursors_after), false, "nothing to undo");
+
+        for edit in entry.edits.iter().rev() {
+            if !apply_edit(text, edit, false) {
+                error!("failed to undo edit {:?}", edit);
+            }
         }
+        restore_cursors(text, &entry.cursors_before);
+
+        self.content_version += 1;
+        true
     }
 }
 
@@ -13,7 +13,7 @@ impl<'a> Arbitrary<'a> for BufferState {
 
         let mut text = u.arbitrary::<String>()?;
         let mut bf = match subtype {
-            BufferType::Full => BufferState::full(None).with_text(text.clone()),
+            BufferType::Full => BufferState::full(None, DocumentIdentifier::new_unique()).with_text(text.clone()),
             BufferType::SingleLine => {
                 text = text.replace("\n", "");
                 BufferState::simplified_single_line().with_text(text.clone())
@@ -82,3 +82,240 @@ impl<'a> Arbitrary<'a> for BufferState {
         Ok(bf)
     }
 }
+
+/*
+Poor man's fuzzing: same idea as the fuzz target, but with bytes from a seeded generator, so it runs with
+regular tests and every failure is reproducible by seed.
+
+Checked against a model: list of texts (and cursors) the buffer went through. Undo and redo have to
+move along that list, no matter how edits and undos are interleaved.
+ */
+#[cfg(test)]
+mod tests {
+    use libfuzzer_sys::arbitrary::{Result, Unstructured};
+
+    use crate::*;
+
+    const SEEDS: u64 = 200;
+    const OPS_PER_SEED: usize = 64;
+
+    // xorshift, good enough to shake the buffer
+    fn pseudo_random_bytes(seed: u64, len: usize) -> Vec<u8> {
+        let mut state = seed.wrapping_mul(0x9E3779B97F4A7C15) | 1;
+        (0..len)
+            .map(|_| {
+                state ^= state << 13;
+                state ^= state >> 7;
+                state ^= state << 17;
+                (state >> 32) as u8
+            })
+            .collect()
+    }
+
+    fn arbitrary_cem(u: &mut Unstructured) -> Result<CommonEditMsg> {
+        let selecting = u.arbitrary::<bool>()?;
+
+        Ok(match u.int_in_range(0..=23u8)? {
+            0 | 1 => CommonEditMsg::Char(*u.choose(&['a', 'b', ' ', '\n', 'ą', '😀'])?),
+            2 => CommonEditMsg::Block(String::from(*u.choose(&["xyz", "\n\n", "ą ę\n", "fn a() {}"])?)),
+            3 => CommonEditMsg::Backspace,
+            4 => CommonEditMsg::Delete,
+            5 => CommonEditMsg::CursorLeft { selecting },
+            6 => CommonEditMsg::CursorRight { selecting },
+            // jumps can take selection past its other end
+            7 => match u.int_in_range(0..=3u8)? {
+                0 => CommonEditMsg::CursorUp { selecting },
+                1 => CommonEditMsg::CursorDown { selecting },
+                2 => CommonEditMsg::LineBegin { selecting },
+                _ => CommonEditMsg::LineEnd { selecting },
+            },
+            8 => match u.arbitrary::<bool>()? {
+                true => CommonEditMsg::WordBegin { selecting },
+                false => CommonEditMsg::WordEnd { selecting },
+            },
+            9 | 10 => CommonEditMsg::Undo,
+            11 => CommonEditMsg::Redo,
+            12 => CommonEditMsg::Tab,
+            13 => CommonEditMsg::MoveLinesUp,
+            14 => CommonEditMsg::MoveLinesDown,
+            15 => CommonEditMsg::DuplicateLines,
+            16 => CommonEditMsg::DeleteLines,
+            17 => CommonEditMsg::JoinLines,
+            18 => CommonEditMsg::InsertLineAbove,
+            19 => CommonEditMsg::InsertLineBelow,
+            20 => CommonEditMsg::SortLines,
+            21 => CommonEditMsg::UniqueLines,
+            22 => CommonEditMsg::ReverseLines,
+            _ => CommonEditMsg::Transform(*u.choose(TextTransform::ALL)?),
+        })
+    }
+
+    struct Model {
+        texts: Vec<String>,
+        // cursors before step i (leading to texts[i + 1]) and after it (filled on undo)
+        cursors_before: Vec<Vec<(WID, CursorSet)>>,
+        cursors_after: Vec<Option<Vec<(WID, CursorSet)>>>,
+        pos: usize,
+        // oldest of texts undo is known to still reach. With a history limit it moves forward, as undo
+        // fails at it, and never back.
+        floor: usize,
+    }
+
+    impl Model {
+        fn new(buffer: &BufferState) -> Self {
+            Model {
+                texts: vec![buffer.to_string()],
+                cursors_before: Vec::new(),
+                cursors_after: Vec::new(),
+                pos: 0,
+                floor: 0,
+            }
+        }
+    }
+
+    // returns whether history limit made undo forget some steps
+    fn run_seed(seed: u64, buffer: BufferState, history_limit_op: Option<usize>) -> bool {
+        let mut buffer = buffer;
+        if let Some(limit) = history_limit_op {
+            buffer.set_history_limit(limit);
+        }
+
+        let view = get_new_widget_id();
+        assert!(buffer.initialize_for_widget(view, None));
+
+        let bytes = pseudo_random_bytes(seed, OPS_PER_SEED * 16);
+        let mut u = Unstructured::new(&bytes);
+        let mut model = Model::new(&buffer);
+
+        for _ in 0..OPS_PER_SEED {
+            let cem = arbitrary_cem(&mut u).unwrap();
+            let cursors = buffer.text().cursor_sets().clone();
+
+            let changed = buffer.apply_cem(cem.clone(), view, 10, None);
+
+            match cem {
+                CommonEditMsg::Undo => {
+                    if history_limit_op.is_none() {
+                        assert_eq!(changed, model.pos > 0, "seed {} undo", seed);
+                    } else if changed {
+                        assert!(model.pos > model.floor, "seed {} undo past forgotten step", seed);
+                    } else {
+                        // with a history limit, oldest steps are gone
+                        assert!(model.pos >= model.floor, "seed {} undo failed early", seed);
+                        model.floor = model.pos;
+                    }
+                    if changed {
+                        model.cursors_after[model.pos - 1] = Some(cursors);
+                        model.pos -= 1;
+                        assert_eq!(
+                            buffer.text().cursor_sets(),
+                            &model.cursors_before[model.pos],
+                            "seed {} undo cursors",
+                            seed
+                        );
+                    }
+                }
+                CommonEditMsg::Redo => {
+                    assert_eq!(changed, model.pos + 1 < model.texts.len(), "seed {} redo", seed);
+                    if changed {
+                        model.pos += 1;
+                        assert_eq!(
+                            Some(buffer.text().cursor_sets()),
+                            model.cursors_after[model.pos - 1].as_ref(),
+                            "seed {} redo cursors",
+                            seed
+                        );
+                    }
+                }
+                _ => {
+                    // a change starts a new branch of undo tree, so there is nothing to redo
+                    if changed {
+                        model.texts.truncate(model.pos + 1);
+                        model.cursors_before.truncate(model.pos);
+                        model.cursors_after.truncate(model.pos);
+
+                        model.texts.push(buffer.to_string());
+                        model.cursors_before.push(cursors);
+                        model.cursors_after.push(None);
+                        model.pos += 1;
+
+                        // only the step just made can go past the limit
+                        if let Some(limit) = history_limit_op {
+                            let history = buffer.history();
+                            let step = history.node(history.current()).unwrap();
+                            assert!(
+                                history.size_bytes() - step.size_bytes() <= limit || step.parent == Some(history.root()),
+                                "seed {} over limit",
+                                seed
+                            );
+                        }
+                    }
+                }
+            }
+
+            assert_eq!(buffer.to_string(), model.texts[model.pos], "seed {} after {:?}", seed, cem);
+            assert!(buffer.check_invariant(), "seed {}", seed);
+        }
+
+        // all the way back and forth again
+        let undone = {
+            let mut undone = 0;
+            while buffer.apply_cem(CommonEditMsg::Undo, view, 10, None) {
+                undone += 1;
+                assert_eq!(buffer.to_string(), model.texts[model.pos - undone], "seed {} full undo", seed);
+            }
+            undone
+        };
+        if history_limit_op.is_none() {
+            assert_eq!(undone, model.pos, "seed {}", seed);
+        } else {
+            assert!(undone <= model.pos - model.floor, "seed {}", seed);
+        }
+
+        for redone in 1..=undone {
+            assert!(buffer.apply_cem(CommonEditMsg::Redo, view, 10, None));
+            assert_eq!(
+                buffer.to_string(),
+                model.texts[model.pos - undone + redone],
+                "seed {} full redo",
+                seed
+            );
+        }
+
+        model.floor > 0 || undone < model.pos
+    }
+
+    #[test]
+    fn fuzz_undo_redo_round_trip() {
+        for seed in 0..SEEDS {
+            let buffer = BufferState::full(None, DocumentIdentifier::new_unique()).with_text("fn main() {\n    let a = 1;\n}\n");
+            assert!(!run_seed(seed, buffer, None));
+        }
+    }
+
+    #[test]
+    fn fuzz_undo_redo_round_trip_with_parsing() {
+        let tree_sitter = std::sync::Arc::new(TreeSitterWrapper::new(LanguageSet::full()));
+
+        for seed in 0..SEEDS / 10 {
+            let buffer = BufferState::full(Some(tree_sitter.clone()), DocumentIdentifier::new_unique())
+                .with_text("fn main() {\n    let a = 1;\n}\n")
+                .with_lang(LangId::RUST);
+            assert!(!run_seed(seed, buffer, None));
+        }
+    }
+
+    #[test]
+    fn fuzz_undo_redo_with_history_limit() {
+        let mut limit_hit = 0;
+        for seed in 0..SEEDS {
+            let buffer = BufferState::full(None, DocumentIdentifier::new_unique()).with_text("fn main() {\n    let a = 1;\n}\n");
+            if run_seed(seed, buffer, Some(2048)) {
+                limit_hit += 1;
+            }
+        }
+
+        // otherwise it's the same as no limit
+        assert!(limit_hit > SEEDS / 2, "limit hit in {} seeds", limit_hit);
+    }
+}
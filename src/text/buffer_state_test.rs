@@ -6,3 +6,500 @@ fn fuzz_1() {
 
     bf.apply_cem(CommonEditMsg::Char('䄀'), get_new_widget_id(), 10, None);
 }
+
+#[test]
+fn replace_text_is_undoable() {
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique()).with_text("abc");
+    let wid = get_new_widget_id();
+    bf.initialize_for_widget(wid, Some(CursorSet::singleton(Cursor::new(3))));
+    assert!(!bf.is_modified());
+
+    assert!(bf.replace_text("x"));
+    assert_eq!(bf.to_string(), "x");
+    assert!(bf.is_modified());
+    // cursor past the end moved back into text
+    assert_eq!(bf.cursors(wid).unwrap().iter().next().unwrap().a, 1);
+
+    assert!(bf.undo());
+    assert_eq!(bf.to_string(), "abc");
+    assert!(!bf.is_modified());
+}
+
+#[test]
+fn picking_line_ending_of_mixed_file_drops_crs() {
+    let (file_format, text) = FileFormat::decode(b"a\r\nb\nc\r\n").unwrap();
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique())
+        .with_text(text)
+        .with_file_format(file_format);
+    assert_eq!(bf.file_format().line_ending, LineEnding::Mixed);
+
+    assert!(bf.set_line_ending(LineEnding::CrLf));
+    assert_eq!(bf.to_string(), "a\nb\nc\n");
+    assert_eq!(bf.file_format().encode(&bf.to_string()), b"a\r\nb\r\nc\r\n".to_vec());
+
+    // nothing more to drop
+    assert!(!bf.set_line_ending(LineEnding::Lf));
+    assert!(!bf.set_line_ending(LineEnding::Mixed));
+    assert_eq!(bf.file_format().line_ending, LineEnding::Lf);
+}
+
+#[test]
+fn changing_file_format_modifies_buffer() {
+    let (file_format, text) = FileFormat::decode(b"a\r\nb\r\n").unwrap();
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique())
+        .with_text(text)
+        .with_file_format(file_format);
+    assert!(!bf.is_modified());
+
+    assert!(!bf.set_line_ending(LineEnding::Lf));
+    assert!(bf.is_modified());
+    assert!(!bf.set_line_ending(LineEnding::CrLf));
+    assert!(!bf.is_modified());
+
+    bf.set_encoding(Encoding::Utf16Le);
+    assert!(bf.is_modified());
+    bf.mark_saved();
+    assert!(!bf.is_modified());
+}
+
+#[test]
+fn large_file_width_follows_edits() {
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique())
+        .with_large_file(true)
+        .with_text("ab\nabcd\nżółć\n");
+    assert_eq!(bf.size(), XY::new(4, 4));
+
+    let wid = get_new_widget_id();
+    bf.initialize_for_widget(wid, None);
+    assert!(bf.apply_cem(CommonEditMsg::Block("xyz".to_string()), wid, 10, None));
+    assert_eq!(bf.size(), XY::new(5, 4));
+
+    // removing newline joins lines
+    assert!(bf.remove(10, 11));
+    assert_eq!(bf.to_string(), "xyzab\nabcdżółć\n");
+    assert_eq!(bf.size(), XY::new(8, 3));
+
+    // it never shrinks
+    assert!(bf.undo());
+    assert_eq!(bf.size(), XY::new(8, 4));
+}
+
+#[test]
+fn clean_up_for_save_follows_editorconfig() {
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique()).with_text("ab  \ncd\t\n  \nef");
+    let wid = get_new_widget_id();
+    bf.initialize_for_widget(wid, Some(CursorSet::new(vec![Cursor::new(4), Cursor::new(13)])));
+
+    // nothing asked for
+    assert!(!bf.clean_up_for_save());
+
+    bf.set_editorconfig(EditorConfigOptions {
+        trim_trailing_whitespace: Some(true),
+        insert_final_newline: Some(true),
+        ..Default::default()
+    });
+    assert!(bf.clean_up_for_save());
+    assert_eq!(bf.to_string(), "ab\ncd\n\nef\n");
+    let anchors: Vec<usize> = bf.cursors(wid).unwrap().iter().map(|c| c.a).collect();
+    assert_eq!(anchors, vec![2, 8]);
+    assert!(!bf.clean_up_for_save());
+
+    // one step
+    assert!(bf.undo());
+    assert_eq!(bf.to_string(), "ab  \ncd\t\n  \nef");
+}
+
+#[test]
+fn clean_up_for_save_applies_end_of_line() {
+    let (file_format, text) = FileFormat::decode(b"a\r\nb\n").unwrap();
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique())
+        .with_text(text)
+        .with_file_format(file_format)
+        .with_editorconfig(EditorConfigOptions {
+            end_of_line: Some(LineEnding::Lf),
+            ..Default::default()
+        });
+    // opening alone doesn't touch the file
+    assert!(!bf.is_modified());
+    assert_eq!(bf.to_string(), "a\r\nb\n");
+
+    assert!(bf.clean_up_for_save());
+    assert_eq!(bf.to_string(), "a\nb\n");
+    assert_eq!(bf.file_format().line_ending, LineEnding::Lf);
+
+    // no text to change, only format
+    bf.set_editorconfig(EditorConfigOptions {
+        end_of_line: Some(LineEnding::CrLf),
+        ..Default::default()
+    });
+    assert!(!bf.clean_up_for_save());
+    assert_eq!(bf.file_format().line_ending, LineEnding::CrLf);
+}
+
+#[test]
+fn brackets_and_quotes_get_paired() {
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique()).with_text("ab cd");
+    let wid = get_new_widget_id();
+    bf.initialize_for_widget(wid, Some(CursorSet::new(vec![Cursor::new(2), Cursor::new(5)])));
+
+    // both cursors get a pair and land inside it
+    assert!(bf.apply_cem(CommonEditMsg::Char('('), wid, 10, None));
+    assert_eq!(bf.to_string(), "ab() cd()");
+    assert!(bf.apply_cem(CommonEditMsg::Char('"'), wid, 10, None));
+    assert_eq!(bf.to_string(), "ab(\"\") cd(\"\")");
+
+    // closers are stepped over
+    assert!(bf.apply_cem(CommonEditMsg::Char('x'), wid, 10, None));
+    assert!(bf.apply_cem(CommonEditMsg::Char('"'), wid, 10, None));
+    assert!(bf.apply_cem(CommonEditMsg::Char(')'), wid, 10, None));
+    assert_eq!(bf.to_string(), "ab(\"x\") cd(\"x\")");
+    assert_eq!(bf.cursors(wid).unwrap().iter().map(|c| c.a).collect::<Vec<_>>(), vec![7, 15]);
+
+    // not before a word
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique()).with_text("ab cd");
+    bf.initialize_for_widget(wid, Some(CursorSet::new(vec![Cursor::new(2), Cursor::new(3)])));
+    assert!(bf.apply_cem(CommonEditMsg::Char('['), wid, 10, None));
+    assert_eq!(bf.to_string(), "ab[] [cd");
+
+    // Backspace removes empty pair whole
+    assert!(bf.apply_cem(CommonEditMsg::Backspace, wid, 10, None));
+    assert_eq!(bf.to_string(), "ab cd");
+
+    // nor as an apostrophe
+    assert!(bf.apply_cem(CommonEditMsg::Char('\''), wid, 10, None));
+    assert_eq!(bf.to_string(), "ab' 'cd");
+}
+
+#[test]
+fn bracket_wraps_selection() {
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique()).with_text("ab cd");
+    let wid = get_new_widget_id();
+    bf.initialize_for_widget(
+        wid,
+        Some(CursorSet::new(vec![
+            Cursor::new(2).with_selection(Selection::new(0, 2)),
+            Cursor::new(5).with_selection(Selection::new(3, 5)),
+        ])),
+    );
+
+    assert!(bf.apply_cem(CommonEditMsg::Char('{'), wid, 10, None));
+    assert_eq!(bf.to_string(), "{ab} {cd}");
+
+    // still selected, so it can be wrapped again
+    assert!(bf.apply_cem(CommonEditMsg::Char('"'), wid, 10, None));
+    assert_eq!(bf.to_string(), "{\"ab\"} {\"cd\"}");
+
+    // single line buffers don't pair
+    let mut single = BufferState::simplified_single_line().with_text("ab");
+    single.initialize_for_widget(wid, Some(CursorSet::singleton(Cursor::new(2))));
+    assert!(single.apply_cem(CommonEditMsg::Char('('), wid, 1, None));
+    assert_eq!(single.to_string(), "ab(");
+}
+
+#[test]
+fn line_comments_toggle_as_one_undo_step() {
+    let text = "fn a() {\n    x();\n\n      y();\n}\n    z();";
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique())
+        .with_lang(LangId::RUST)
+        .with_text(text);
+    let wid = get_new_widget_id();
+    // selection over "x", blank line and "y", and a cursor in the last line
+    bf.initialize_for_widget(
+        wid,
+        Some(CursorSet::new(vec![
+            Cursor::new(13).with_selection(Selection::new(13, 26)),
+            Cursor::new(33).with_selection(Selection::new(33, 34)),
+        ])),
+    );
+
+    assert!(bf.apply_cem(CommonEditMsg::ToggleLineComment, wid, 10, None));
+    assert_eq!(bf.to_string(), "fn a() {\n    // x();\n\n    //   y();\n}\n    // z();");
+
+    assert!(bf.apply_cem(CommonEditMsg::ToggleLineComment, wid, 10, None));
+    assert_eq!(bf.to_string(), text);
+
+    assert!(bf.apply_cem(CommonEditMsg::ToggleLineComment, wid, 10, None));
+    assert!(bf.undo());
+    assert_eq!(bf.to_string(), text);
+
+    // no line comments in HTML
+    let mut html = BufferState::full(None, DocumentIdentifier::new_unique())
+        .with_lang(LangId::HTML)
+        .with_text("  <p>");
+    html.initialize_for_widget(wid, Some(CursorSet::singleton(Cursor::new(5))));
+    assert!(html.apply_cem(CommonEditMsg::ToggleLineComment, wid, 10, None));
+    assert_eq!(html.to_string(), "  <!-- <p> -->");
+    assert!(html.apply_cem(CommonEditMsg::ToggleLineComment, wid, 10, None));
+    assert_eq!(html.to_string(), "  <p>");
+
+    // nothing to comment with
+    let mut plain = BufferState::full(None, DocumentIdentifier::new_unique()).with_text("abc");
+    plain.initialize_for_widget(wid, None);
+    assert!(!plain.apply_cem(CommonEditMsg::ToggleLineComment, wid, 10, None));
+}
+
+#[test]
+fn block_comments_toggle() {
+    let text = "  f(x, y);\n  g();";
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique())
+        .with_lang(LangId::C)
+        .with_text(text);
+    let wid = get_new_widget_id();
+    // "x" selected, cursor at the end of second line
+    bf.initialize_for_widget(
+        wid,
+        Some(CursorSet::new(vec![
+            Cursor::new(5).with_selection(Selection::new(4, 5)),
+            Cursor::new(17),
+        ])),
+    );
+
+    assert!(bf.apply_cem(CommonEditMsg::ToggleBlockComment, wid, 10, None));
+    assert_eq!(bf.to_string(), "  f(/*x*/, y);\n  /*g();*/");
+    // selection still on "x", cursor still at line end
+    let cursors: Vec<Cursor> = bf.cursors(wid).unwrap().iter().cloned().collect();
+    assert_eq!(cursors[0].s, Some(Selection::new(6, 7)));
+    assert_eq!(cursors[1].a, 25);
+
+    assert!(bf.apply_cem(CommonEditMsg::ToggleBlockComment, wid, 10, None));
+    assert_eq!(bf.to_string(), text);
+    let cursors: Vec<Cursor> = bf.cursors(wid).unwrap().iter().cloned().collect();
+    assert_eq!(cursors[0].s, Some(Selection::new(4, 5)));
+    assert_eq!(cursors[1].a, 17);
+}
+
+fn line_ops_buffer(text: &str, cursors: Vec<Cursor>) -> (BufferState, WID) {
+    let mut bf = BufferState::full(None, DocumentIdentifier::new_unique()).with_text(text);
+    let wid = get_new_widget_id();
+    bf.initialize_for_widget(wid, Some(CursorSet::new(cursors)));
+    (bf, wid)
+}
+
+fn anchors(bf: &BufferState, wid: WID) -> Vec<usize> {
+    bf.cursors(wid).unwrap().iter().map(|c| c.a).collect()
+}
+
+#[test]
+fn lines_move_and_duplicate() {
+    let (mut bf, wid) = line_ops_buffer("a\nbb\nccc", vec![Cursor::new(3)]);
+
+    assert!(bf.apply_cem(CommonEditMsg::MoveLinesUp, wid, 10, None));
+    assert_eq!(bf.to_string(), "bb\na\nccc");
+    assert_eq!(anchors(&bf, wid), vec![1]);
+    // first line can't go higher
+    assert!(!bf.apply_cem(CommonEditMsg::MoveLinesUp, wid, 10, None));
+
+    assert!(bf.apply_cem(CommonEditMsg::MoveLinesDown, wid, 10, None));
+    assert!(bf.apply_cem(CommonEditMsg::MoveLinesDown, wid, 10, None));
+    assert_eq!(bf.to_string(), "a\nccc\nbb");
+    assert_eq!(anchors(&bf, wid), vec![7]);
+
+    assert!(bf.undo());
+    assert_eq!(bf.to_string(), "a\nbb\nccc");
+
+    // cursor goes to the copy
+    assert!(bf.apply_cem(CommonEditMsg::DuplicateLines, wid, 10, None));
+    assert_eq!(bf.to_string(), "a\nbb\nbb\nccc");
+    assert_eq!(anchors(&bf, wid), vec![6]);
+
+    // with selections, just the selected text is duplicated and the copy gets selected
+    let (mut bf, wid) = line_ops_buffer("abc", vec![Cursor::new(0).with_selection(Selection::new(0, 2))]);
+    assert!(bf.apply_cem(CommonEditMsg::DuplicateLines, wid, 10, None));
+    assert_eq!(bf.to_string(), "ababc");
+    assert_eq!(
+        bf.cursors(wid).unwrap().as_single(),
+        Some(Cursor::new(2).with_selection(Selection::new(2, 4)))
+    );
+}
+
+#[test]
+fn lines_delete_join_and_insert() {
+    let (mut bf, wid) = line_ops_buffer("a\nbb\nccc", vec![Cursor::new(0), Cursor::new(7)]);
+    assert!(bf.apply_cem(CommonEditMsg::DeleteLines, wid, 10, None));
+    assert_eq!(bf.to_string(), "bb");
+    assert_eq!(anchors(&bf, wid), vec![0, 2]);
+
+    let text = "fn a() {\n    x();\n}";
+    let (mut bf, wid) = line_ops_buffer(text, vec![Cursor::new(0)]);
+    assert!(bf.apply_cem(CommonEditMsg::JoinLines, wid, 10, None));
+    assert_eq!(bf.to_string(), "fn a() { x();\n}");
+
+    // selection joins all lines it covers
+    let (mut bf, wid) = line_ops_buffer(text, vec![Cursor::new(0).with_selection(Selection::new(0, 19))]);
+    assert!(bf.apply_cem(CommonEditMsg::JoinLines, wid, 10, None));
+    assert_eq!(bf.to_string(), "fn a() { x(); }");
+
+    // new lines keep indentation
+    let (mut bf, wid) = line_ops_buffer("  a\nb", vec![Cursor::new(1), Cursor::new(5)]);
+    assert!(bf.apply_cem(CommonEditMsg::InsertLineBelow, wid, 10, None));
+    assert_eq!(bf.to_string(), "  a\n  \nb\n");
+    assert_eq!(anchors(&bf, wid), vec![6, 9]);
+
+    let (mut bf, wid) = line_ops_buffer("  a\nb", vec![Cursor::new(1)]);
+    assert!(bf.apply_cem(CommonEditMsg::InsertLineAbove, wid, 10, None));
+    assert_eq!(bf.to_string(), "  \n  a\nb");
+    assert_eq!(anchors(&bf, wid), vec![2]);
+}
+
+#[test]
+fn lines_sort_unique_and_reverse() {
+    let (mut bf, wid) = line_ops_buffer("c\na\nc\nb\nz", vec![Cursor::new(0).with_selection(Selection::new(0, 7))]);
+
+    assert!(bf.apply_cem(CommonEditMsg::SortLines, wid, 10, None));
+    assert_eq!(bf.to_string(), "a\nb\nc\nc\nz");
+    assert!(bf.apply_cem(CommonEditMsg::UniqueLines, wid, 10, None));
+    assert_eq!(bf.to_string(), "a\nb\nc\nz");
+    assert!(bf.apply_cem(CommonEditMsg::ReverseLines, wid, 10, None));
+    assert_eq!(bf.to_string(), "c\nb\na\nz");
+
+    // a single line has nothing to reorder
+    let (mut bf, wid) = line_ops_buffer("b\na", vec![Cursor::new(0)]);
+    assert!(!bf.apply_cem(CommonEditMsg::SortLines, wid, 10, None));
+    assert_eq!(bf.to_string(), "b\na");
+}
+
+#[test]
+fn selections_transform_as_one_undo_step() {
+    let text = "some_name = 1\nother_name = 1";
+    let (mut bf, wid) = line_ops_buffer(
+        text,
+        vec![
+            Cursor::new(0).with_selection(Selection::new(0, 9)),
+            Cursor::new(14).with_selection(Selection::new(14, 24)),
+        ],
+    );
+
+    assert!(bf.apply_cem(CommonEditMsg::Transform(TextTransform::CamelCase), wid, 10, None));
+    assert_eq!(bf.to_string(), "someName = 1\notherName = 1");
+    // transformed text stays selected
+    let cursors: Vec<Cursor> = bf.cursors(wid).unwrap().iter().cloned().collect();
+    assert_eq!(cursors[0], Cursor::new(0).with_selection(Selection::new(0, 8)));
+    assert_eq!(cursors[1], Cursor::new(13).with_selection(Selection::new(13, 22)));
+
+    assert!(bf.apply_cem(CommonEditMsg::Transform(TextTransform::UpperCase), wid, 10, None));
+    assert!(bf.undo());
+    assert!(bf.undo());
+    assert_eq!(bf.to_string(), text);
+    let cursors: Vec<Cursor> = bf.cursors(wid).unwrap().iter().cloned().collect();
+    assert_eq!(cursors[1], Cursor::new(14).with_selection(Selection::new(14, 24)));
+
+    // nothing to decode
+    assert!(!bf.apply_cem(CommonEditMsg::Transform(TextTransform::Base64Decode), wid, 10, None));
+}
+
+#[test]
+fn cursors_get_numbered_sequentially() {
+    // counting starts from number in the first selection, simple cursors get theirs inserted
+    let (mut bf, wid) = line_ops_buffer(
+        "a[7]\na[]\na[]",
+        vec![Cursor::new(2).with_selection(Selection::new(2, 3)), Cursor::new(7), Cursor::new(11)],
+    );
+    assert!(bf.apply_cem(CommonEditMsg::Transform(TextTransform::NumberSequence), wid, 10, None));
+    assert_eq!(bf.to_string(), "a[7]\na[8]\na[9]");
+    assert_eq!(anchors(&bf, wid), vec![2, 8, 13]);
+
+    let (mut bf, wid) = line_ops_buffer("x\ny", vec![Cursor::new(0), Cursor::new(2)]);
+    assert!(bf.apply_cem(CommonEditMsg::Transform(TextTransform::NumberSequence), wid, 10, None));
+    assert_eq!(bf.to_string(), "1x\n2y");
+}
+
+fn selections(bf: &BufferState, wid: WID) -> Vec<(usize, usize)> {
+    bf.cursors(wid)
+        .unwrap()
+        .iter()
+        .filter_map(|c| c.s.map(|sel| (sel.b, sel.e)))
+        .collect()
+}
+
+#[test]
+fn next_occurrences_get_cursors() {
+    // "a" in "ab" is not the whole word
+    let (mut bf, wid) = line_ops_buffer("a ab a\na", vec![Cursor::new(0)]);
+
+    // first the word under cursor gets selected
+    assert_eq!(bf.add_next_occurrence(wid, false), Ok(true));
+    assert_eq!(selections(&bf, wid), vec![(0, 1)]);
+
+    assert_eq!(bf.add_next_occurrence(wid, false), Ok(true));
+    assert_eq!(selections(&bf, wid), vec![(0, 1), (5, 6)]);
+
+    // skipping drops the last one
+    assert_eq!(bf.add_next_occurrence(wid, true), Ok(true));
+    assert_eq!(selections(&bf, wid), vec![(0, 1), (7, 8)]);
+
+    // all taken
+    assert_eq!(bf.add_next_occurrence(wid, false), Ok(true));
+    assert_eq!(bf.add_next_occurrence(wid, false), Ok(false));
+    assert_eq!(selections(&bf, wid), vec![(0, 1), (5, 6), (7, 8)]);
+
+    // typing goes to all of them, undo brings back cursors from before
+    assert!(bf.apply_cem(CommonEditMsg::Char('x'), wid, 10, None));
+    assert_eq!(bf.to_string(), "x ab x\nx");
+    assert!(bf.undo());
+    assert!(bf.undo());
+    assert_eq!(selections(&bf, wid), vec![(0, 1), (7, 8)]);
+}
+
+#[test]
+fn all_occurrences_get_selected() {
+    // selection that is not a whole word matches inside words too
+    let (mut bf, wid) = line_ops_buffer("abc xabc ab", vec![Cursor::new(0).with_selection(Selection::new(0, 2))]);
+    assert_eq!(bf.select_all_occurrences(wid), Ok(true));
+    assert_eq!(selections(&bf, wid), vec![(0, 2), (5, 7), (9, 11)]);
+    assert_eq!(bf.select_all_occurrences(wid), Ok(false));
+
+    let (mut bf, wid) = line_ops_buffer("a.b a.b", vec![Cursor::new(6)]);
+    assert_eq!(bf.select_all_occurrences(wid), Ok(true));
+    assert_eq!(selections(&bf, wid), vec![(2, 3), (6, 7)]);
+
+    // nothing under cursor
+    let (mut bf, wid) = line_ops_buffer("a  b", vec![Cursor::new(2)]);
+    assert_eq!(bf.select_all_occurrences(wid), Ok(false));
+}
+
+#[test]
+fn column_selection_copies_and_pastes_as_block() {
+    let text = "abcd\nab\n日本x";
+    let (mut bf, wid) = line_ops_buffer(text, vec![Cursor::new(0)]);
+    let block = block_to_cursor_set(&bf, BlockCorner::new(1, 0), BlockCorner::new(3, 2)).unwrap();
+    bf.text_mut().set_cursor_set(wid, block);
+
+    let clipboard = MockClipboard::default().into_clipboardref();
+    bf.apply_cem(CommonEditMsg::Copy, wid, 10, Some(&clipboard));
+    assert_eq!(clipboard.get(), "bc\nb\n本");
+
+    // pasted at column 5 of last line, lines below get added and padded
+    let (mut bf, wid) = line_ops_buffer(text, vec![Cursor::new(11)]);
+    assert!(bf.apply_cem(CommonEditMsg::PasteBlock, wid, 10, Some(&clipboard)));
+    assert_eq!(bf.to_string(), "abcd\nab\n日本xbc\n     b\n     本");
+    assert_eq!(anchors(&bf, wid), vec![13]);
+
+    assert!(bf.undo());
+    assert_eq!(bf.to_string(), text);
+
+    // with a cursor per line it's a regular paste, line by line
+    let (mut bf, wid) = line_ops_buffer(text, vec![Cursor::new(0), Cursor::new(5), Cursor::new(8)]);
+    assert!(bf.apply_cem(CommonEditMsg::PasteBlock, wid, 10, Some(&clipboard)));
+    assert_eq!(bf.to_string(), "bcabcd\nbab\n本日本x");
+}
+
+#[test]
+fn deleting_right_anchored_selections_leaves_cursors_where_they_began() {
+    let (mut bf, wid) = line_ops_buffer(
+        "abcdef\nghijkl",
+        vec![
+            Cursor::new(4).with_selection(Selection::new(1, 4)),
+            Cursor::new(12).with_selection(Selection::new(9, 12)),
+        ],
+    );
+
+    assert!(bf.apply_cem(CommonEditMsg::Delete, wid, 10, None));
+    assert_eq!(bf.to_string(), "aef\nghl");
+    assert_eq!(anchors(&bf, wid), vec![1, 6]);
+    assert!(bf.cursors(wid).unwrap().iter().all(|c| c.s.is_none()));
+
+    let (mut bf, wid) = line_ops_buffer("abcdef", vec![Cursor::new(4).with_selection(Selection::new(1, 4))]);
+    assert!(bf.apply_cem(CommonEditMsg::Backspace, wid, 10, None));
+    assert_eq!(bf.to_string(), "aef");
+    assert_eq!(anchors(&bf, wid), vec![1]);
+}
@@ -1,6 +1,7 @@
+use std::ops::Range;
 use std::sync::Arc;
 
-use log::{debug, error};
+use log::{debug, error, warn};
 use ropey::Rope;
 use unicode_segmentation::UnicodeSegmentation;
 
@@ -140,6 +141,161 @@ impl ContentsAndCursors {
         }
     }
 
+    /*
+    Multi-cursor "next occurrence". A single simple cursor in a word just gets the word selected. Otherwise
+    text of the last selection is looked for after it (wrapping around the end of buffer), and the first
+    match not taken by other cursors gets a cursor of its own. With skip, the last cursor is dropped in
+    favour of the new one. Selected words match only whole words.
+
+    returns Ok(true) iff cursors changed
+     */
+    pub fn add_next_occurrence(&mut self, widget_id: WID, skip: bool) -> Result<bool, FindError> {
+        let cursor_set = unpack_or_e!(
+            self.get_cursor_set(widget_id),
+            Err(FindError::WidgetIdNotFound),
+            "WidgetId not found"
+        )
+        .clone();
+
+        if let Some(word) = self.word_under_single_cursor(&cursor_set) {
+            self.set_cursor_set(
+                widget_id,
+                CursorSet::singleton(Cursor::new(word.end).with_selection(Selection::new(word.start, word.end))),
+            );
+            return Ok(true);
+        }
+
+        let last = *unpack_or_e!(cursor_set.set().last(), Ok(false), "empty cursor set");
+        let pattern = unpack_or!(self.occurrence_pattern(&last, &cursor_set), Ok(false));
+        let anchor_left = cursor_set.iter().any(|c| c.anchor_left());
+
+        let mut base_set = cursor_set.clone();
+        if skip && !base_set.remove_by_anchor(last.a) {
+            error!("failed removing last cursor {:?}", last);
+            return Ok(false);
+        }
+
+        // after the last cursor, then from the top
+        let after_last = regex_find(&pattern, &self.rope, Some(last.get_end()))?;
+        let before_last = regex_find(&pattern, &self.rope, None)?.take_while(|m| m.0 < last.get_begin());
+
+        let mut new_set_op: Option<CursorSet> = None;
+        for (begin, end) in after_last.chain(before_last) {
+            if begin == end || (begin, end) == (last.get_begin(), last.get_end()) {
+                continue;
+            }
+
+            let anchor = if anchor_left { begin } else { end };
+            let mut new_set = base_set.clone();
+            if new_set.add_cursor(Cursor::new(anchor).with_selection(Selection::new(begin, end))) {
+                new_set_op = Some(new_set);
+                break;
+            }
+        }
+
+        let new_set = unpack_or!(new_set_op, Ok(false));
+        self.set_cursor_set(widget_id, new_set);
+        Ok(true)
+    }
+
+    /*
+    Replaces cursors with selections of all occurrences of the last selection (or word under single
+    cursor).
+
+    returns Ok(true) iff cursors changed
+     */
+    pub fn select_all_occurrences(&mut self, widget_id: WID) -> Result<bool, FindError> {
+        let cursor_set = unpack_or_e!(
+            self.get_cursor_set(widget_id),
+            Err(FindError::WidgetIdNotFound),
+            "WidgetId not found"
+        )
+        .clone();
+
+        let last = match self.word_under_single_cursor(&cursor_set) {
+            Some(word) => Cursor::new(word.end).with_selection(Selection::new(word.start, word.end)),
+            None => *unpack_or_e!(cursor_set.set().last(), Ok(false), "empty cursor set"),
+        };
+        let pattern = unpack_or!(self.occurrence_pattern(&last, &cursor_set), Ok(false));
+        let anchor_left = cursor_set.iter().any(|c| c.anchor_left());
+
+        let mut new_set_op: Option<CursorSet> = None;
+        for (begin, end) in regex_find(&pattern, &self.rope, None)? {
+            if begin == end {
+                continue;
+            }
+
+            let anchor = if anchor_left { begin } else { end };
+            let cursor = Cursor::new(anchor).with_selection(Selection::new(begin, end));
+            match new_set_op.as_mut() {
+                None => new_set_op = Some(CursorSet::singleton(cursor)),
+                Some(new_set) => {
+                    if !new_set.add_cursor(cursor) {
+                        warn!("overlapping occurrence {:?}", cursor);
+                    }
+                }
+            }
+        }
+
+        let new_set = unpack_or!(new_set_op, Ok(false));
+        if new_set == cursor_set {
+            return Ok(false);
+        }
+
+        self.set_cursor_set(widget_id, new_set);
+        Ok(true)
+    }
+
+    fn is_word_char(&self, char_idx: usize) -> bool {
+        self.rope
+            .get_char(char_idx)
+            .map(|c| c.is_alphanumeric() || c == '_')
+            .unwrap_or(false)
+    }
+
+    fn word_under_single_cursor(&self, cursor_set: &CursorSet) -> Option<Range<usize>> {
+        let cursor = cursor_set.as_single().filter(|c| c.is_simple())?;
+
+        let mut begin = cursor.a;
+        while begin > 0 && self.is_word_char(begin - 1) {
+            begin -= 1;
+        }
+        let mut end = cursor.a;
+        while self.is_word_char(end) {
+            end += 1;
+        }
+
+        if begin < end {
+            Some(begin..end)
+        } else {
+            None
+        }
+    }
+
+    fn is_whole_word(&self, sel: &Selection) -> bool {
+        (sel.b..sel.e).all(|idx| self.is_word_char(idx)) && (sel.b == 0 || !self.is_word_char(sel.b - 1)) && !self.is_word_char(sel.e)
+    }
+
+    /*
+    Regex matching text selected by cursor. Only whole words match, if all selections so far are words
+    standing on their own.
+     */
+    fn occurrence_pattern(&self, cursor: &Cursor, cursor_set: &CursorSet) -> Option<String> {
+        let sel = cursor.s?;
+        let text = self.rope.get_slice(sel.b..sel.e)?.to_string();
+        if text.is_empty() {
+            return None;
+        }
+
+        let whole_word = self.is_whole_word(&sel) && cursor_set.iter().filter_map(|c| c.s).all(|sel| self.is_whole_word(&sel));
+
+        if whole_word {
+            Some(format!("\\b{}\\b", regex::escape(&text)))
+        } else {
+            Some(regex::escape(&text))
+        }
+    }
+
     pub fn get_cursor_set(&self, widget_id: WID) -> Option<&CursorSet> {
         self.cursor_sets.iter().find(|(wid, _)| *wid == widget_id).map(|(_, cs)| cs)
     }
@@ -158,6 +314,10 @@ impl ContentsAndCursors {
         }
     }
 
+    pub fn drop_parsing(&mut self) {
+        self.parsing = None;
+    }
+
     pub fn parsing(&self) -> Option<&ParsingTuple> {
         self.parsing.as_ref()
     }
@@ -174,10 +334,23 @@ impl ContentsAndCursors {
         &mut self.rope
     }
 
+    pub fn cursor_sets(&self) -> &Vec<(WID, CursorSet)> {
+        &self.cursor_sets
+    }
+
     pub fn has_cursor_set_for(&self, widget_id: WID) -> bool {
         self.cursor_sets.iter().find(|(wid, _)| *wid == widget_id).is_some()
     }
 
+    /*
+    Returns false if there was no cursor set for given widget.
+     */
+    pub fn remove_cursor_set(&mut self, widget_id: WID) -> bool {
+        let len_before = self.cursor_sets.len();
+        self.cursor_sets.retain(|(wid, _)| *wid != widget_id);
+        self.cursor_sets.len() != len_before
+    }
+
     pub fn set_cursor_set(&mut self, widget_id: WID, cursor_set: CursorSet) -> bool {
         match self.get_cursor_set_mut(widget_id) {
             Some(old_cs) => {
@@ -0,0 +1,861 @@
+use std::collections::{BTreeMap, HashSet};
+use std::mem::size_of;
+use std::time::SystemTime;
+
+use log::{debug, warn};
+use ropey::Rope;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/*
+Single modification of rope. Both variants carry the text, so every edit can be reversed without
+looking at the buffer.
+ */
+#[derive(Clone, Debug, Eq, PartialEq, Serialize, Deserialize)]
+pub enum TextEdit {
+    Insert { char_idx: usize, text: String },
+    Remove { char_idx: usize, text: String },
+}
+
+impl TextEdit {
+    pub fn inverse(&self) -> TextEdit {
+        match self {
+            TextEdit::Insert { char_idx, text } => TextEdit::Remove {
+                char_idx: *char_idx,
+                text: text.clone(),
+            },
+            TextEdit::Remove { char_idx, text } => TextEdit::Insert {
+                char_idx: *char_idx,
+                text: text.clone(),
+            },
+        }
+    }
+
+    pub fn char_idx(&self) -> usize {
+        match self {
+            TextEdit::Insert { char_idx, .. } => *char_idx,
+            TextEdit::Remove { char_idx, .. } => *char_idx,
+        }
+    }
+
+    /*
+    Applies edit (forward) or its inverse (backward) to a bare rope, without parsing or cursors. Used to
+    preview other states of history.
+     */
+    pub fn apply_to_rope(&self, rope: &mut Rope, forward: bool) -> bool {
+        let (char_idx, text, insert) = match self {
+            TextEdit::Insert { char_idx, text } => (*char_idx, text, forward),
+            TextEdit::Remove { char_idx, text } => (*char_idx, text, !forward),
+        };
+
+        if insert {
+            rope.try_insert(char_idx, text).is_ok()
+        } else {
+            rope.try_remove(char_idx..char_idx + text.chars().count()).is_ok()
+        }
+    }
+
+    fn size_bytes(&self) -> usize {
+        let text = match self {
+            TextEdit::Insert { text, .. } => text,
+            TextEdit::Remove { text, .. } => text,
+        };
+
+        size_of::<TextEdit>() + text.len()
+    }
+}
+
+/*
+One undo step: edits in order they were applied, plus cursors of all views from before the first
+edit and (filled on undo) after the last one.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct HistoryEntry {
+    pub edits: Vec<TextEdit>,
+    pub cursors_before: Vec<(WID, CursorSet)>,
+    pub cursors_after: Vec<(WID, CursorSet)>,
+}
+
+impl HistoryEntry {
+    // (chars inserted, chars removed)
+    pub fn chars_changed(&self) -> (usize, usize) {
+        self.edits.iter().fold((0, 0), |(inserted, removed), edit| match edit {
+            TextEdit::Insert { text, .. } => (inserted + text.chars().count(), removed),
+            TextEdit::Remove { text, .. } => (inserted, removed + text.chars().count()),
+        })
+    }
+
+    fn size_bytes(&self) -> usize {
+        let cursors_size = |cursor_sets: &[(WID, CursorSet)]| -> usize {
+            cursor_sets
+                .iter()
+                .map(|(_, cs)| size_of::<(WID, CursorSet)>() + cs.len() * size_of::<Cursor>())
+                .sum()
+        };
+
+        size_of::<HistoryEntry>()
+            + self.edits.iter().map(|e| e.size_bytes()).sum::<usize>()
+            + cursors_size(&self.cursors_before)
+            + cursors_size(&self.cursors_after)
+    }
+}
+
+/*
+Ids are given in order of creation and never reused, so sorting by id is sorting by time.
+ */
+pub type HistoryNodeId = usize;
+
+/*
+A state of buffer in undo tree. Entry holds edits that lead to it from parent (root's one is empty).
+ */
+#[derive(Clone, Debug)]
+pub struct HistoryNode {
+    pub parent: Option<HistoryNodeId>,
+    pub children: Vec<HistoryNodeId>,
+    pub entry: HistoryEntry,
+    pub created: SystemTime,
+
+    // which of children "redo" goes to, last visited one
+    redo_child: Option<HistoryNodeId>,
+}
+
+impl HistoryNode {
+    fn new(parent: Option<HistoryNodeId>, entry: HistoryEntry) -> Self {
+        HistoryNode {
+            parent,
+            children: Vec::new(),
+            entry,
+            created: SystemTime::now(),
+            redo_child: None,
+        }
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        size_of::<HistoryNode>() + self.entry.size_bytes()
+    }
+}
+
+/*
+Undo tree in a form that outlives the session (see UndoHistoryStore). Cursors are not kept, widget ids
+mean nothing in the next session. Nodes are listed oldest first, so parents come before children.
+ */
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerializableHistoryNode {
+    pub id: HistoryNodeId,
+    pub parent: Option<HistoryNodeId>,
+    pub created: SystemTime,
+    pub edits: Vec<TextEdit>,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SerializableEditHistory {
+    pub nodes: Vec<SerializableHistoryNode>,
+    pub current: HistoryNodeId,
+}
+
+/*
+Undo tree of BufferState. Root is the oldest state remembered, current is the state the buffer is in.
+Undo moves to parent, redo to the child visited last. Recording an edit after undo does not drop
+anything, it starts a new branch.
+
+Memory used is estimated (text of edits plus cursors), and once it's over the limit, branches other than
+the one leading to current state are forgotten (oldest first), then the oldest states. The step being
+recorded is never dropped, so a single huge edit can still be undone.
+ */
+#[derive(Debug)]
+pub struct EditHistory {
+    nodes: BTreeMap<HistoryNodeId, HistoryNode>,
+    root: HistoryNodeId,
+    current: HistoryNodeId,
+    next_id: HistoryNodeId,
+
+    // redo_child of current's parent from before begin_entry, restored by pop_entry
+    stashed_redo_child: Option<HistoryNodeId>,
+    // state that matches the file on disk, None if it was forgotten
+    saved: Option<HistoryNodeId>,
+
+    size_bytes: usize,
+    limit_bytes: usize,
+}
+
+impl EditHistory {
+    pub const DEFAULT_LIMIT_BYTES: usize = 64 * 1024 * 1024;
+
+    pub fn new(limit_bytes: usize) -> Self {
+        let mut res = EditHistory {
+            nodes: BTreeMap::new(),
+            root: 0,
+            current: 0,
+            next_id: 0,
+            stashed_redo_child: None,
+            saved: None,
+            size_bytes: 0,
+            limit_bytes,
+        };
+        res.clear();
+        res
+    }
+
+    pub fn clear(&mut self) {
+        let root = HistoryNode::new(None, HistoryEntry::default());
+
+        self.nodes.clear();
+        self.size_bytes = root.size_bytes();
+        self.root = self.next_id;
+        self.current = self.next_id;
+        self.nodes.insert(self.next_id, root);
+        self.next_id += 1;
+        self.stashed_redo_child = None;
+        self.saved = Some(self.root);
+    }
+
+    pub fn set_limit_bytes(&mut self, limit_bytes: usize) {
+        self.limit_bytes = limit_bytes;
+        self.enforce_limit();
+    }
+
+    pub fn limit_bytes(&self) -> usize {
+        self.limit_bytes
+    }
+
+    pub fn size_bytes(&self) -> usize {
+        self.size_bytes
+    }
+
+    // number of steps (all branches), root state is not one
+    pub fn len(&self) -> usize {
+        self.nodes.len() - 1
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn can_undo(&self) -> bool {
+        self.current != self.root
+    }
+
+    pub fn can_redo(&self) -> bool {
+        self.current_node().redo_child.is_some()
+    }
+
+    pub fn root(&self) -> HistoryNodeId {
+        self.root
+    }
+
+    pub fn current(&self) -> HistoryNodeId {
+        self.current
+    }
+
+    /*
+    Marks current state as the one on disk.
+     */
+    pub fn mark_saved(&mut self) {
+        self.saved = Some(self.current);
+    }
+
+    /*
+    Whether text differs from the one on disk: some step between saved and current state edits it (steps
+    that only moved cursors don't count). If saved state was forgotten, there is no telling, so it does.
+     */
+    pub fn is_modified(&self) -> bool {
+        let saved = unpack_or!(self.saved, true);
+        let (up, down) = unpack_or!(self.path(saved, self.current), true);
+
+        up.iter().chain(down.iter()).any(|id| !self.nodes[id].entry.edits.is_empty())
+    }
+
+    pub fn node(&self, id: HistoryNodeId) -> Option<&HistoryNode> {
+        self.nodes.get(&id)
+    }
+
+    // all states, oldest first
+    pub fn nodes(&self) -> impl Iterator<Item = (HistoryNodeId, &HistoryNode)> + '_ {
+        self.nodes.iter().map(|(id, node)| (*id, node))
+    }
+
+    /*
+    Starts a new undo step (a new child of current state).
+     */
+    pub fn begin_entry(&mut self, cursors_before: Vec<(WID, CursorSet)>) {
+        // Steps that only move cursors never record anything, so this is where they are kept within
+        // limit. It's done before the new step is in, so it's not counted. New step could still be
+        // popped, so redo branch stays, it has to be there to get restored.
+        self.enforce_limit_keeping_redo(true);
+
+        let id = self.next_id;
+        self.next_id += 1;
+
+        let node = HistoryNode::new(
+            Some(self.current),
+            HistoryEntry {
+                edits: Vec::new(),
+                cursors_before,
+                cursors_after: Vec::new(),
+            },
+        );
+        self.size_bytes += node.size_bytes();
+        self.nodes.insert(id, node);
+
+        let parent = self.current_node_mut();
+        let stashed_redo_child = parent.redo_child.replace(id);
+        parent.children.push(id);
+        self.stashed_redo_child = stashed_redo_child;
+        self.current = id;
+    }
+
+    /*
+    Adds edit to current undo step. Edits made without one (or in a state that already has children, like
+    right after undo) start a new step, so history always matches the buffer.
+     */
+    pub fn record(&mut self, edit: TextEdit) {
+        if self.current == self.root || !self.current_node().children.is_empty() {
+            self.begin_entry(Vec::new());
+        }
+
+        self.size_bytes += edit.size_bytes();
+        self.current_node_mut().entry.edits.push(edit);
+
+        self.enforce_limit();
+    }
+
+    /*
+    Removes the last undo step (that has to be a fresh leaf), returning it so the caller can revert
+    its edits.
+     */
+    pub fn pop_entry(&mut self) -> Option<HistoryEntry> {
+        debug_assert!(self.current_node().children.is_empty());
+        if self.current == self.root || !self.current_node().children.is_empty() {
+            return None;
+        }
+
+        let id = self.current;
+        let node = self.nodes.remove(&id)?;
+        self.size_bytes -= node.size_bytes();
+
+        let stashed_redo_child = self.stashed_redo_child.take();
+        self.current = node.parent?;
+        let parent = self.current_node_mut();
+        parent.children.retain(|c| *c != id);
+        parent.redo_child = stashed_redo_child.filter(|c| parent.children.contains(c));
+
+        Some(node.entry)
+    }
+
+    /*
+    Merges the last undo step into previous one, used to chain multiple operations into a single step.
+    Previous step can't be extended if it's the root or other branches grow from it, then both stay.
+     */
+    pub fn merge_last_two(&mut self) {
+        debug_assert!(self.current_node().children.is_empty());
+
+        let parent_id = unpack_or!(self.current_node().parent, (), "nothing to merge with");
+        let parent = &self.nodes[&parent_id];
+        if parent_id == self.root || parent.children.len() > 1 {
+            debug!("not merging history steps, previous one is root or branched");
+            return;
+        }
+
+        let last = self.nodes.remove(&self.current).unwrap();
+        self.size_bytes -= last.size_bytes();
+        self.current = parent_id;
+        self.stashed_redo_child = None;
+
+        // "before" cursors of the last one are in the middle of merged step, not interesting anymore
+        let previous = self.nodes.get_mut(&parent_id).unwrap();
+        self.size_bytes -= previous.size_bytes();
+        previous.entry.edits.extend(last.entry.edits);
+        previous.children.clear();
+        previous.redo_child = None;
+        self.size_bytes += previous.size_bytes();
+    }
+
+    /*
+    Moves one step back. Returns the step to revert, cursors_after are filled with given ones.
+     */
+    pub fn undo(&mut self, cursors_after: Vec<(WID, CursorSet)>) -> Option<&HistoryEntry> {
+        let id = self.current;
+        let parent_id = self.current_node().parent?;
+
+        let node = self.nodes.get_mut(&id).unwrap();
+        self.size_bytes -= node.size_bytes();
+        node.entry.cursors_after = cursors_after;
+        self.size_bytes += node.size_bytes();
+
+        self.nodes.get_mut(&parent_id).unwrap().redo_child = Some(id);
+        self.current = parent_id;
+        self.stashed_redo_child = None;
+
+        debug!("undo to {} of {}, {} bytes", self.current, self.nodes.len(), self.size_bytes);
+        Some(&self.nodes[&id].entry)
+    }
+
+    /*
+    Moves one step forward (to the child visited last). Returns the step to re-apply.
+     */
+    pub fn redo(&mut self) -> Option<&HistoryEntry> {
+        let id = self.current_node().redo_child?;
+        self.current = id;
+        self.stashed_redo_child = None;
+
+        debug!("redo to {} of {}", self.current, self.nodes.len());
+        Some(&self.nodes[&id].entry)
+    }
+
+    /*
+    Makes redo follow the given child of current state. Returns false if it's not one.
+     */
+    pub fn select_redo_child(&mut self, child: HistoryNodeId) -> bool {
+        let node = self.current_node_mut();
+        if !node.children.contains(&child) {
+            return false;
+        }
+
+        node.redo_child = Some(child);
+        true
+    }
+
+    /*
+    Steps to walk from one state to the other: states to undo (starting with "from"), and then states to
+    redo (ending with "to").
+     */
+    pub fn path(&self, from: HistoryNodeId, to: HistoryNodeId) -> Option<(Vec<HistoryNodeId>, Vec<HistoryNodeId>)> {
+        let mut up = self.ancestors(from)?;
+        let mut down = self.ancestors(to)?;
+
+        // both end with root, so drop common part
+        while !up.is_empty() && !down.is_empty() && up.last() == down.last() {
+            up.pop();
+            down.pop();
+        }
+        down.reverse();
+
+        Some((up, down))
+    }
+
+    /*
+    State as of given time: the one recorded last before it (or root, if none was).
+     */
+    pub fn node_at_time(&self, time: SystemTime) -> HistoryNodeId {
+        self.nodes
+            .iter()
+            .rev()
+            .find(|(id, node)| **id != self.root && node.created <= time)
+            .map(|(id, _)| *id)
+            .unwrap_or(self.root)
+    }
+
+    /*
+    Text of given state, computed by walking edits from current state on a copy of current text.
+     */
+    pub fn text_at(&self, current_text: &Rope, id: HistoryNodeId) -> Option<Rope> {
+        let (up, down) = self.path(self.current, id)?;
+        let mut rope = current_text.clone();
+
+        for node_id in up.iter() {
+            for edit in self.nodes[node_id].entry.edits.iter().rev() {
+                if !edit.apply_to_rope(&mut rope, false) {
+                    return None;
+                }
+            }
+        }
+        for node_id in down.iter() {
+            for edit in self.nodes[node_id].entry.edits.iter() {
+                if !edit.apply_to_rope(&mut rope, true) {
+                    return None;
+                }
+            }
+        }
+
+        Some(rope)
+    }
+
+    pub fn serializable(&self) -> SerializableEditHistory {
+        SerializableEditHistory {
+            nodes: self
+                .nodes
+                .iter()
+                .map(|(id, node)| SerializableHistoryNode {
+                    id: *id,
+                    parent: node.parent,
+                    created: node.created,
+                    edits: node.entry.edits.clone(),
+                })
+                .collect(),
+            current: self.current,
+        }
+    }
+
+    /*
+    Rebuilds the tree, redo following the path to current state. Returns None if it's not a tree (single
+    root, parents listed before children, current among nodes). Limit is applied right away.
+     */
+    pub fn from_serializable(sh: SerializableEditHistory, limit_bytes: usize) -> Option<EditHistory> {
+        let mut nodes: BTreeMap<HistoryNodeId, HistoryNode> = BTreeMap::new();
+        let mut root_op: Option<HistoryNodeId> = None;
+
+        for sn in sh.nodes.into_iter() {
+            if nodes.last_key_value().map(|(last, _)| *last >= sn.id).unwrap_or(false) {
+                warn!("history nodes out of order at {}", sn.id);
+                return None;
+            }
+
+            match sn.parent {
+                None if root_op.is_none() => root_op = Some(sn.id),
+                None => {
+                    warn!("second root in history: {}", sn.id);
+                    return None;
+                }
+                Some(parent) => {
+                    let parent_node = unpack_or!(nodes.get_mut(&parent), None, "no parent {} of history node {}", parent, sn.id);
+                    parent_node.children.push(sn.id);
+                    parent_node.redo_child = Some(sn.id);
+                }
+            }
+
+            let mut node = HistoryNode::new(
+                sn.parent,
+                HistoryEntry {
+                    edits: sn.edits,
+                    ..Default::default()
+                },
+            );
+            node.created = sn.created;
+            nodes.insert(sn.id, node);
+        }
+
+        let root = root_op?;
+        if !nodes.contains_key(&sh.current) {
+            warn!("no current node {} in history", sh.current);
+            return None;
+        }
+
+        let mut res = EditHistory {
+            size_bytes: nodes.values().map(|n| n.size_bytes()).sum(),
+            next_id: nodes.keys().last().map(|id| id + 1).unwrap_or(0),
+            nodes,
+            root,
+            current: sh.current,
+            stashed_redo_child: None,
+            // histories are kept for files as they were saved
+            saved: Some(sh.current),
+            limit_bytes,
+        };
+
+        // redo goes back towards where we were
+        let path = res.ancestors(res.current)?;
+        for pair in path.windows(2) {
+            res.nodes.get_mut(&pair[1]).unwrap().redo_child = Some(pair[0]);
+        }
+
+        res.enforce_limit();
+        debug_assert!(res.check_invariant());
+        Some(res)
+    }
+
+    // given node and all its ancestors, root last
+    fn ancestors(&self, id: HistoryNodeId) -> Option<Vec<HistoryNodeId>> {
+        let mut res = vec![id];
+        let mut node = self.nodes.get(&id)?;
+        while let Some(parent) = node.parent {
+            res.push(parent);
+            node = &self.nodes[&parent];
+        }
+        Some(res)
+    }
+
+    fn current_node(&self) -> &HistoryNode {
+        &self.nodes[&self.current]
+    }
+
+    fn current_node_mut(&mut self) -> &mut HistoryNode {
+        self.nodes.get_mut(&self.current).unwrap()
+    }
+
+    fn remove_node(&mut self, id: HistoryNodeId) {
+        let node = self.nodes.remove(&id).unwrap();
+        self.size_bytes -= node.size_bytes();
+
+        if let Some(parent) = node.parent.and_then(|p| self.nodes.get_mut(&p)) {
+            parent.children.retain(|c| *c != id);
+            if parent.redo_child == Some(id) {
+                parent.redo_child = parent.children.last().copied();
+            }
+        }
+    }
+
+    fn enforce_limit(&mut self) {
+        self.enforce_limit_keeping_redo(false);
+    }
+
+    fn enforce_limit_keeping_redo(&mut self, keep_redo: bool) {
+        if self.size_bytes <= self.limit_bytes {
+            return;
+        }
+
+        // other branches go first, oldest leaves first
+        let mut kept: HashSet<HistoryNodeId> = self.ancestors(self.current).unwrap_or_default().into_iter().collect();
+        if keep_redo {
+            let mut redo_op = self.current_node().redo_child;
+            while let Some(redo) = redo_op {
+                kept.insert(redo);
+                redo_op = self.nodes[&redo].redo_child;
+            }
+        }
+        while self.size_bytes > self.limit_bytes {
+            let leaf_op = self
+                .nodes
+                .iter()
+                .find(|(id, node)| node.children.is_empty() && !kept.contains(id))
+                .map(|(id, _)| *id);
+
+            match leaf_op {
+                Some(leaf) => {
+                    if self.stashed_redo_child == Some(leaf) {
+                        self.stashed_redo_child = None;
+                    }
+                    self.remove_node(leaf)
+                }
+                None => break,
+            }
+        }
+
+        // then oldest states, moving root forward. Current step stays.
+        while self.size_bytes > self.limit_bytes && self.current != self.root && self.current_node().parent != Some(self.root) {
+            let old_root = self.nodes.remove(&self.root).unwrap();
+            self.size_bytes -= old_root.size_bytes();
+            debug_assert!(old_root.children.len() == 1);
+
+            self.root = old_root.children[0];
+            let new_root = self.nodes.get_mut(&self.root).unwrap();
+            self.size_bytes -= new_root.size_bytes();
+            new_root.parent = None;
+            // edits that lead to root are gone with its parent
+            new_root.entry = HistoryEntry::default();
+            self.size_bytes += new_root.size_bytes();
+        }
+    }
+}
+
+impl HasInvariant for EditHistory {
+    fn check_invariant(&self) -> bool {
+        self.nodes.contains_key(&self.root)
+            && self.nodes.contains_key(&self.current)
+            && self.size_bytes == self.nodes.values().map(|n| n.size_bytes()).sum::<usize>()
+            && self.nodes.iter().all(|(id, node)| match node.parent {
+                None => *id == self.root,
+                Some(parent) => self.nodes.get(&parent).map(|p| p.children.contains(id)).unwrap_or(false),
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Duration;
+
+    use super::*;
+
+    fn insert(char_idx: usize, text: &str) -> TextEdit {
+        TextEdit::Insert {
+            char_idx,
+            text: text.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_undo_redo_positions() {
+        let mut history = EditHistory::new(EditHistory::DEFAULT_LIMIT_BYTES);
+
+        history.begin_entry(vec![]);
+        history.record(insert(0, "a"));
+        history.begin_entry(vec![]);
+        history.record(insert(1, "b"));
+
+        assert_eq!(history.undo(vec![]).unwrap().edits, vec![insert(1, "b")]);
+        assert!(history.can_redo());
+        assert_eq!(history.redo().unwrap().edits, vec![insert(1, "b")]);
+        assert!(!history.can_redo());
+
+        // edit after undo starts a new branch, the old one stays in the tree
+        history.undo(vec![]);
+        history.record(insert(1, "c"));
+        assert!(!history.can_redo());
+        assert_eq!(history.len(), 3);
+
+        history.begin_entry(vec![]);
+        history.record(insert(2, "d"));
+        history.merge_last_two();
+        assert_eq!(history.len(), 3);
+        assert_eq!(history.undo(vec![]).unwrap().edits, vec![insert(1, "c"), insert(2, "d")]);
+        assert!(history.check_invariant());
+    }
+
+    #[test]
+    fn test_branches_and_paths() {
+        let mut history = EditHistory::new(EditHistory::DEFAULT_LIMIT_BYTES);
+
+        history.record(insert(0, "a"));
+        let a = history.current();
+        history.begin_entry(vec![]);
+        history.record(insert(1, "b"));
+        let ab = history.current();
+        history.undo(vec![]);
+        history.record(insert(1, "c"));
+        let ac = history.current();
+
+        assert_eq!(history.node(a).unwrap().children, vec![ab, ac]);
+        assert_eq!(history.path(ac, ab), Some((vec![ac], vec![ab])));
+        assert_eq!(history.path(ab, history.root()), Some((vec![ab, a], vec![])));
+
+        let text = Rope::from_str("ac");
+        assert_eq!(history.text_at(&text, ab).unwrap().to_string(), "ab");
+        assert_eq!(history.text_at(&text, history.root()).unwrap().to_string(), "");
+
+        // redo goes to branch visited last
+        history.undo(vec![]);
+        assert!(history.select_redo_child(ab));
+        assert_eq!(history.redo().unwrap().edits, vec![insert(1, "b")]);
+        assert_eq!(history.current(), ab);
+    }
+
+    #[test]
+    fn test_is_modified() {
+        let mut history = EditHistory::new(EditHistory::DEFAULT_LIMIT_BYTES);
+        assert!(!history.is_modified());
+
+        history.begin_entry(vec![]);
+        history.record(insert(0, "a"));
+        assert!(history.is_modified());
+
+        history.mark_saved();
+        assert!(!history.is_modified());
+
+        // cursor only step doesn't change text
+        history.begin_entry(vec![]);
+        assert!(!history.is_modified());
+
+        history.undo(vec![]);
+        history.undo(vec![]);
+        assert!(history.is_modified());
+        history.redo();
+        assert!(!history.is_modified());
+
+        history.clear();
+        assert!(!history.is_modified());
+    }
+
+    #[test]
+    fn test_node_at_time() {
+        let mut history = EditHistory::new(EditHistory::DEFAULT_LIMIT_BYTES);
+        let now = SystemTime::now();
+
+        for (i, minutes_ago) in [30u64, 20, 10].iter().enumerate() {
+            history.begin_entry(vec![]);
+            history.record(insert(i, "x"));
+            history.current_node_mut().created = now - Duration::from_secs(minutes_ago * 60);
+        }
+
+        let ids: Vec<HistoryNodeId> = history.nodes().map(|(id, _)| id).collect();
+        assert_eq!(history.node_at_time(now), ids[3]);
+        assert_eq!(history.node_at_time(now - Duration::from_secs(15 * 60)), ids[2]);
+        assert_eq!(history.node_at_time(now - Duration::from_secs(60 * 60)), history.root());
+    }
+
+    #[test]
+    fn test_limit_drops_other_branches_then_oldest() {
+        let entry_size = HistoryNode::new(
+            None,
+            HistoryEntry {
+                edits: vec![insert(0, "0123456789")],
+                ..Default::default()
+            },
+        )
+        .size_bytes();
+        let mut history = EditHistory::new(entry_size * 4);
+
+        for i in 0..10 {
+            history.begin_entry(vec![]);
+            history.record(insert(i * 10, "0123456789"));
+        }
+
+        assert!(history.len() <= 3);
+        assert!(history.size_bytes() <= history.limit_bytes());
+        assert_eq!(history.undo(vec![]).unwrap().edits, vec![insert(90, "0123456789")]);
+
+        // branch we're not on goes first
+        history.record(insert(90, "abc"));
+        let len_before = history.len();
+        history.set_limit_bytes(history.size_bytes() - 1);
+        assert_eq!(history.len(), len_before - 1);
+        assert_eq!(history.undo(vec![]).unwrap().edits, vec![insert(90, "abc")]);
+        history.redo();
+
+        // single step over limit is kept, so it can be undone
+        history.set_limit_bytes(1);
+        assert_eq!(history.len(), 1);
+        assert!(history.can_undo());
+        assert!(history.check_invariant());
+
+        let mut history = EditHistory::new(1);
+        history.record(insert(0, "a very long text"));
+        assert!(history.can_undo());
+    }
+
+    #[test]
+    fn test_popping_empty_step_over_limit_restores_redo() {
+        let mut history = EditHistory::new(EditHistory::DEFAULT_LIMIT_BYTES);
+
+        history.begin_entry(vec![]);
+        history.record(insert(0, "a"));
+        history.begin_entry(vec![]);
+        history.record(insert(1, "b"));
+        let b = history.current();
+        history.undo(vec![]);
+
+        // empty step alone pushes history over the limit, but gets popped before anything is recorded
+        history.set_limit_bytes(history.size_bytes());
+        history.begin_entry(vec![]);
+        assert!(history.pop_entry().is_some());
+
+        assert!(history.can_redo());
+        assert_eq!(history.redo().unwrap().edits, vec![insert(1, "b")]);
+        assert_eq!(history.current(), b);
+        assert!(history.check_invariant());
+    }
+
+    #[test]
+    fn test_serializable_round_trip() {
+        let mut history = EditHistory::new(EditHistory::DEFAULT_LIMIT_BYTES);
+
+        history.record(insert(0, "a"));
+        history.begin_entry(vec![]);
+        history.record(insert(1, "b"));
+        history.undo(vec![]);
+        history.begin_entry(vec![]);
+        history.record(insert(1, "c"));
+        history.undo(vec![]);
+
+        let restored = EditHistory::from_serializable(history.serializable(), EditHistory::DEFAULT_LIMIT_BYTES).unwrap();
+        assert!(restored.check_invariant());
+        assert_eq!(restored.len(), history.len());
+        assert_eq!(restored.current(), history.current());
+        assert_eq!(restored.root(), history.root());
+
+        let mut restored = restored;
+        // redo goes to newest child when nothing else is known
+        assert_eq!(restored.redo().unwrap().edits, vec![insert(1, "c")]);
+        restored.undo(vec![]);
+        restored.undo(vec![]);
+        assert_eq!(restored.redo().unwrap().edits, vec![insert(0, "a")]);
+
+        // not a tree
+        let mut broken = history.serializable();
+        broken.nodes[1].parent = Some(100);
+        assert!(EditHistory::from_serializable(broken, EditHistory::DEFAULT_LIMIT_BYTES).is_none());
+
+        let mut broken = history.serializable();
+        broken.current = 100;
+        assert!(EditHistory::from_serializable(broken, EditHistory::DEFAULT_LIMIT_BYTES).is_none());
+    }
+}
@@ -0,0 +1,541 @@
+use std::borrow::Cow;
+use std::cmp::{max, min};
+use std::fmt::{Display, Formatter};
+use std::io::{ErrorKind, Read};
+use std::str::Utf8Error;
+
+use log::warn;
+use ropey::{Rope, RopeBuilder};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/*
+How text of a file is stored on disk. It's detected when file is read, kept per buffer and used again on
+save, so files are written back the way they were found.
+
+Buffers hold LF only, CRLF is converted on load and save. Files with mixed line endings are kept as they
+are (with CRs in buffer), until someone picks one of the styles for them. UTF-16 is only recognized by
+its BOM, anything that is not valid UTF-8 otherwise is taken for Latin-1. Files that claim UTF-8 or
+UTF-16 with a BOM, but are not valid in it, are not decoded at all, as replacing broken chars would
+change them on save.
+ */
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    Utf8Bom,
+    Utf16Le,
+    Utf16Be,
+    Latin1,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+    Mixed,
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct FileFormat {
+    pub encoding: Encoding,
+    pub line_ending: LineEnding,
+}
+
+const UTF8_BOM: [u8; 3] = [0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: [u8; 2] = [0xFF, 0xFE];
+const UTF16_BE_BOM: [u8; 2] = [0xFE, 0xFF];
+
+// large files are read and decoded piece by piece of about that size, see
+// FileFormat::decode_to_rope
+const DECODE_CHUNK_BYTES: usize = 64 * 1024;
+
+impl Encoding {
+    pub const ALL: [Encoding; 5] = [
+        Encoding::Utf8,
+        Encoding::Utf8Bom,
+        Encoding::Utf16Le,
+        Encoding::Utf16Be,
+        Encoding::Latin1,
+    ];
+
+    pub fn detect_and_decode(bytes: &[u8]) -> Result<(Encoding, String), ReadError> {
+        if let Some(rest) = bytes.strip_prefix(&UTF8_BOM) {
+            return Ok((Encoding::Utf8Bom, std::str::from_utf8(rest)?.to_string()));
+        }
+        if let Some(rest) = bytes.strip_prefix(&UTF16_LE_BOM) {
+            let text = decode_utf16(rest, u16::from_le_bytes).ok_or(ReadError::Utf16Error)?;
+            return Ok((Encoding::Utf16Le, text));
+        }
+        if let Some(rest) = bytes.strip_prefix(&UTF16_BE_BOM) {
+            let text = decode_utf16(rest, u16::from_be_bytes).ok_or(ReadError::Utf16Error)?;
+            return Ok((Encoding::Utf16Be, text));
+        }
+
+        match std::str::from_utf8(bytes) {
+            Ok(text) => Ok((Encoding::Utf8, text.to_string())),
+            Err(_) => Ok((Encoding::Latin1, bytes.iter().map(|b| *b as char).collect())),
+        }
+    }
+
+    /*
+    Decodes bytes as if they were in this encoding, with no BOM. None if they are not valid in it, so
+    nothing is lost by replacing bytes that don't fit.
+     */
+    pub fn decode_without_bom(&self, bytes: &[u8]) -> Option<String> {
+        match self {
+            Encoding::Utf8 | Encoding::Utf8Bom => std::str::from_utf8(bytes).ok().map(|text| text.to_string()),
+            Encoding::Utf16Le => decode_utf16(bytes, u16::from_le_bytes),
+            Encoding::Utf16Be => decode_utf16(bytes, u16::from_be_bytes),
+            Encoding::Latin1 => Some(bytes.iter().map(|b| *b as char).collect()),
+        }
+    }
+
+    /*
+    Only Latin-1 can't hold every char.
+     */
+    pub fn can_encode(&self, text: &str) -> bool {
+        match self {
+            Encoding::Latin1 => text.chars().all(|c| (c as u32) <= 0xFF),
+            _ => true,
+        }
+    }
+
+    /*
+    Chars that can't be encoded (see can_encode) are written as '?'.
+     */
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self {
+            Encoding::Utf8 => text.as_bytes().to_vec(),
+            Encoding::Utf8Bom => UTF8_BOM.iter().chain(text.as_bytes().iter()).copied().collect(),
+            Encoding::Utf16Le => UTF16_LE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(|unit| unit.to_le_bytes()))
+                .collect(),
+            Encoding::Utf16Be => UTF16_BE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(|unit| unit.to_be_bytes()))
+                .collect(),
+            Encoding::Latin1 => text.chars().map(|c| if (c as u32) <= 0xFF { c as u8 } else { b'?' }).collect(),
+        }
+    }
+}
+
+// None on odd number of bytes or unpaired surrogates
+fn decode_utf16(bytes: &[u8], unit: fn([u8; 2]) -> u16) -> Option<String> {
+    if !bytes.len().is_multiple_of(2) {
+        return None;
+    }
+
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|pair| unit([pair[0], pair[1]])).collect();
+    String::from_utf16(&units).ok()
+}
+
+impl LineEnding {
+    pub fn detect(text: &str) -> LineEnding {
+        let all = text.matches('\n').count();
+        let crlf = text.matches("\r\n").count();
+
+        if crlf == 0 {
+            LineEnding::Lf
+        } else if crlf == all {
+            LineEnding::CrLf
+        } else {
+            LineEnding::Mixed
+        }
+    }
+}
+
+impl FileFormat {
+    pub fn is_default(&self) -> bool {
+        *self == FileFormat::default()
+    }
+
+    /*
+    Returns format of file contents, and text as it's kept in buffer.
+     */
+    pub fn decode(bytes: &[u8]) -> Result<(FileFormat, String), ReadError> {
+        let (encoding, text) = Encoding::detect_and_decode(bytes)?;
+        Ok(FileFormat::with_line_ending_detected(encoding, text))
+    }
+
+    /*
+    Same as decode, but for file known to be in given encoding (see EditorConfigOptions::charset). Only
+    files without a BOM are decoded with it, as guessing between UTF-8 and Latin-1 can go wrong. Files
+    with a BOM are read the way it says, the encoding is what they will be saved in.
+
+    Setting can be wrong too. Bytes that are not valid in the given encoding are decoded as if there was
+    no setting, so file is not garbled on save.
+     */
+    pub fn decode_as(bytes: &[u8], encoding: Encoding) -> Result<(FileFormat, String), ReadError> {
+        let text = match Encoding::detect_and_decode(bytes)? {
+            (Encoding::Utf8, _) | (Encoding::Latin1, _) => match encoding.decode_without_bom(bytes) {
+                Some(text) => text,
+                None => {
+                    warn!(
+                        ".editorconfig says {}, but file is not valid in it, detecting its encoding",
+                        encoding
+                    );
+                    return FileFormat::decode(bytes);
+                }
+            },
+            (_, text) => text,
+        };
+        Ok(FileFormat::with_line_ending_detected(encoding, text))
+    }
+
+    fn with_line_ending_detected(encoding: Encoding, text: String) -> (FileFormat, String) {
+        let line_ending = LineEnding::detect(&text);
+
+        let text = match line_ending {
+            LineEnding::CrLf => text.replace("\r\n", "\n"),
+            LineEnding::Lf | LineEnding::Mixed => text,
+        };
+
+        (FileFormat { encoding, line_ending }, text)
+    }
+
+    /*
+    Same as decode, but file is read a chunk at a time, straight into a rope, so there is never a copy of
+    the whole file (nor of its bytes) in memory. It goes through the file twice, first to learn encoding
+    and line endings, hence "open" instead of a reader. Used for large files. UTF-16 is rare enough to be
+    decoded at once.
+     */
+    pub fn decode_to_rope<R: Read>(open: impl FnMut() -> Result<R, ReadError>) -> Result<(FileFormat, Rope), ReadError> {
+        decode_to_rope_in_chunks(open, DECODE_CHUNK_BYTES)
+    }
+
+    pub fn encode(&self, text: &str) -> Vec<u8> {
+        match self.line_ending {
+            // CRLF could have been pasted in
+            LineEnding::CrLf => self.encoding.encode(&text.replace("\r\n", "\n").replace('\n', "\r\n")),
+            LineEnding::Lf | LineEnding::Mixed => self.encoding.encode(text),
+        }
+    }
+}
+
+// reads until buf is full or there is nothing more, so only the last chunk is shorter
+fn read_chunk<R: Read>(reader: &mut R, buf: &mut [u8]) -> Result<usize, ReadError> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(n) => filled += n,
+            Err(e) if e.kind() == ErrorKind::Interrupted => continue,
+            Err(e) => return Err(e.into()),
+        }
+    }
+    Ok(filled)
+}
+
+// length of the longest prefix that is whole UTF-8, Err if bytes are broken rather than cut short
+fn utf8_prefix_len(bytes: &[u8]) -> Result<usize, Utf8Error> {
+    match std::str::from_utf8(bytes) {
+        Ok(_) => Ok(bytes.len()),
+        Err(e) if e.error_len().is_none() => Ok(e.valid_up_to()),
+        Err(e) => Err(e),
+    }
+}
+
+fn decode_to_rope_in_chunks<R: Read>(
+    mut open: impl FnMut() -> Result<R, ReadError>,
+    chunk_bytes: usize,
+) -> Result<(FileFormat, Rope), ReadError> {
+    // first chunk holds the whole BOM
+    let mut buf = vec![0u8; max(chunk_bytes, UTF8_BOM.len())];
+
+    // first pass, encoding and line endings
+    let mut reader = open()?;
+    let mut encoding = Encoding::Utf8;
+    // UTF-8 cut short by end of chunk
+    let mut pending: Vec<u8> = Vec::new();
+    let mut all: usize = 0;
+    let mut crlf: usize = 0;
+    let mut last_byte: Option<u8> = None;
+    let mut first = true;
+    loop {
+        let len = read_chunk(&mut reader, &mut buf)?;
+        let mut chunk = &buf[..len];
+
+        if first {
+            first = false;
+            if chunk.starts_with(&UTF16_LE_BOM) || chunk.starts_with(&UTF16_BE_BOM) {
+                let mut bytes = chunk.to_vec();
+                reader.read_to_end(&mut bytes)?;
+                let (file_format, text) = FileFormat::decode(&bytes)?;
+                return Ok((file_format, Rope::from_str(&text)));
+            }
+            if let Some(rest) = chunk.strip_prefix(&UTF8_BOM) {
+                encoding = Encoding::Utf8Bom;
+                chunk = rest;
+            }
+        }
+
+        if len == 0 {
+            break;
+        }
+
+        // in both UTF-8 and Latin-1 line endings are just these bytes
+        all += chunk.iter().filter(|b| **b == b'\n').count();
+        crlf += chunk.windows(2).filter(|pair| *pair == b"\r\n").count();
+        if last_byte == Some(b'\r') && chunk.first() == Some(&b'\n') {
+            crlf += 1;
+        }
+        last_byte = chunk.last().copied().or(last_byte);
+
+        if encoding != Encoding::Latin1 {
+            pending.extend_from_slice(chunk);
+            match utf8_prefix_len(&pending) {
+                Ok(valid) => {
+                    pending.drain(..valid);
+                }
+                Err(e) if encoding == Encoding::Utf8Bom => return Err(e.into()),
+                Err(_) => {
+                    encoding = Encoding::Latin1;
+                    pending.clear();
+                }
+            }
+        }
+    }
+
+    // file ended in the middle of a char
+    if !pending.is_empty() {
+        if encoding == Encoding::Utf8Bom {
+            std::str::from_utf8(&pending)?;
+        }
+        encoding = Encoding::Latin1;
+    }
+
+    let line_ending = if crlf == 0 {
+        LineEnding::Lf
+    } else if crlf == all {
+        LineEnding::CrLf
+    } else {
+        LineEnding::Mixed
+    };
+
+    // second pass, the text
+    let mut reader = open()?;
+    let mut builder = RopeBuilder::new();
+    let mut pending: Vec<u8> = Vec::new();
+    let mut bom_left = if encoding == Encoding::Utf8Bom { UTF8_BOM.len() } else { 0 };
+    loop {
+        let len = read_chunk(&mut reader, &mut buf)?;
+        let skipped = min(bom_left, len);
+        bom_left -= skipped;
+        pending.extend_from_slice(&buf[skipped..len]);
+        let done = len == 0;
+
+        // not splitting chars of UTF-8, nor CRLF pairs
+        let mut end = match encoding {
+            Encoding::Latin1 => pending.len(),
+            _ => utf8_prefix_len(&pending)?,
+        };
+        if !done && end > 0 && pending[end - 1] == b'\r' {
+            end -= 1;
+        }
+
+        let text: Cow<str> = match encoding {
+            Encoding::Latin1 => Cow::Owned(pending[..end].iter().map(|b| *b as char).collect()),
+            _ => Cow::Borrowed(std::str::from_utf8(&pending[..end])?),
+        };
+        match line_ending {
+            LineEnding::CrLf => builder.append(&text.replace("\r\n", "\n")),
+            LineEnding::Lf | LineEnding::Mixed => builder.append(&text),
+        }
+        pending.drain(..end);
+
+        if done {
+            break;
+        }
+    }
+
+    // file changed between the passes
+    if !pending.is_empty() {
+        std::str::from_utf8(&pending)?;
+    }
+
+    Ok((FileFormat { encoding, line_ending }, builder.finish()))
+}
+
+impl Display for Encoding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            Encoding::Utf8 => "UTF-8",
+            Encoding::Utf8Bom => "UTF-8 with BOM",
+            Encoding::Utf16Le => "UTF-16 LE",
+            Encoding::Utf16Be => "UTF-16 BE",
+            Encoding::Latin1 => "Latin-1",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for LineEnding {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        let name = match self {
+            LineEnding::Lf => "LF",
+            LineEnding::CrLf => "CRLF",
+            LineEnding::Mixed => "mixed",
+        };
+        write!(f, "{}", name)
+    }
+}
+
+impl Display for FileFormat {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} {}", self.encoding, self.line_ending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trips() {
+        let cases: Vec<(Vec<u8>, FileFormat, &str)> = vec![
+            (b"a\nb\n".to_vec(), FileFormat::default(), "a\nb\n"),
+            (
+                b"a\r\nb\r\n".to_vec(),
+                FileFormat {
+                    encoding: Encoding::Utf8,
+                    line_ending: LineEnding::CrLf,
+                },
+                "a\nb\n",
+            ),
+            (
+                b"a\r\nb\n".to_vec(),
+                FileFormat {
+                    encoding: Encoding::Utf8,
+                    line_ending: LineEnding::Mixed,
+                },
+                "a\r\nb\n",
+            ),
+            (
+                [&UTF8_BOM[..], "zażółć\n".as_bytes()].concat(),
+                FileFormat {
+                    encoding: Encoding::Utf8Bom,
+                    line_ending: LineEnding::Lf,
+                },
+                "zażółć\n",
+            ),
+            (
+                vec![0xFF, 0xFE, b'a', 0, b'\r', 0, b'\n', 0],
+                FileFormat {
+                    encoding: Encoding::Utf16Le,
+                    line_ending: LineEnding::CrLf,
+                },
+                "a\n",
+            ),
+            (
+                vec![0xFE, 0xFF, 0, b'a', 0, b'\n'],
+                FileFormat {
+                    encoding: Encoding::Utf16Be,
+                    line_ending: LineEnding::Lf,
+                },
+                "a\n",
+            ),
+            (
+                vec![b'c', b'a', b'f', 0xE9, b'\n'],
+                FileFormat {
+                    encoding: Encoding::Latin1,
+                    line_ending: LineEnding::Lf,
+                },
+                "café\n",
+            ),
+        ];
+
+        for (bytes, format, text) in cases.into_iter() {
+            assert_eq!(FileFormat::decode(&bytes), Ok((format, text.to_string())));
+            assert_eq!(
+                FileFormat::decode_to_rope(|| Ok(bytes.as_slice())),
+                Ok((format, Rope::from_str(text)))
+            );
+            assert_eq!(format.encode(text), bytes, "{:?}", format);
+        }
+    }
+
+    #[test]
+    fn test_decoding_to_rope_in_small_chunks() {
+        let cases: Vec<Vec<u8>> = vec![
+            "zażółć\r\ngęślą\r\njaźń\r\n".as_bytes().to_vec(),
+            "zażółć\ngęślą\r\njaźń".as_bytes().to_vec(),
+            [&UTF8_BOM[..], "ąę\r\n".as_bytes()].concat(),
+            vec![b'c', b'a', b'f', 0xE9, b'\r', b'\n', 0xE9],
+        ];
+
+        for bytes in cases.into_iter() {
+            let (format, text) = FileFormat::decode(&bytes).unwrap();
+            for chunk_bytes in 1..5 {
+                assert_eq!(
+                    decode_to_rope_in_chunks(|| Ok(bytes.as_slice()), chunk_bytes),
+                    Ok((format, Rope::from_str(&text)))
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_latin1_can_not_encode_everything() {
+        assert!(Encoding::Latin1.can_encode("café"));
+        assert!(!Encoding::Latin1.can_encode("zażółć"));
+        assert_eq!(Encoding::Latin1.encode("ż"), b"?".to_vec());
+        assert!(Encoding::Utf16Le.can_encode("zażółć"));
+    }
+
+    #[test]
+    fn test_decoding_as_known_encoding() {
+        let utf8 = "zażółć\r\n".as_bytes();
+        assert_eq!(
+            FileFormat::decode_as(utf8, Encoding::Latin1),
+            Ok((
+                FileFormat {
+                    encoding: Encoding::Latin1,
+                    line_ending: LineEnding::CrLf,
+                },
+                "za\u{c5}\u{bc}\u{c3}\u{b3}\u{c5}\u{82}\u{c4}\u{87}\n".to_string()
+            ))
+        );
+
+        // setting that doesn't fit is not followed
+        let latin1 = [b'c', b'a', b'f', 0xE9, b'\n'];
+        assert_eq!(FileFormat::decode_as(&latin1, Encoding::Utf8), FileFormat::decode(&latin1));
+        assert_eq!(FileFormat::decode_as(b"abc", Encoding::Utf16Le), FileFormat::decode(b"abc"));
+
+        // BOM says more than a setting
+        let bom = [&UTF8_BOM[..], "ąę".as_bytes()].concat();
+        assert_eq!(
+            FileFormat::decode_as(&bom, Encoding::Utf8),
+            Ok((
+                FileFormat {
+                    encoding: Encoding::Utf8,
+                    line_ending: LineEnding::Lf,
+                },
+                "ąę".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn test_broken_files_are_not_decoded() {
+        let cases: Vec<Vec<u8>> = vec![
+            // UTF-8 BOM, but not UTF-8
+            [&UTF8_BOM[..], &[b'a', 0xE9]].concat(),
+            // odd number of bytes
+            vec![0xFF, 0xFE, b'a', 0, b'b'],
+            // unpaired surrogate
+            vec![0xFE, 0xFF, 0xD8, 0x00, 0, b'a'],
+        ];
+
+        for bytes in cases.into_iter() {
+            assert!(FileFormat::decode(&bytes).is_err(), "{:?}", bytes);
+            assert!(FileFormat::decode_to_rope(|| Ok(bytes.as_slice())).is_err(), "{:?}", bytes);
+            assert!(FileFormat::decode_as(&bytes, Encoding::Latin1).is_err(), "{:?}", bytes);
+        }
+    }
+}
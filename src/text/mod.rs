@@ -3,6 +3,12 @@ pub use buffer_state::{BufferState, BufferStateStreamingIterator, BufferType, Se
 
 mod buffer_state_fuzz;
 
+mod file_format;
+pub use file_format::{Encoding, FileFormat, LineEnding};
+
+mod edit_history;
+pub use edit_history::{EditHistory, HistoryEntry, HistoryNode, HistoryNodeId, SerializableEditHistory, SerializableHistoryNode, TextEdit};
+
 mod text_buffer;
 pub use text_buffer::{LinesIter, TextBuffer};
 
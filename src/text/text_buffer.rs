@@ -9,6 +9,12 @@ use crate::*;
 //TODO create tests for undo/redo/set milestone
 
 pub trait TextBuffer: ToString {
+    // whether typed brackets and quotes get closed (see bracket_pairs), code editors want it, text
+    // fields don't
+    fn auto_pairing(&self) -> bool {
+        false
+    }
+
     fn byte_to_char(&self, byte_idx: usize) -> Option<usize>;
 
     fn callback_for_parser<'a>(&'a self) -> Box<dyn FnMut(usize, tree_sitter::Point) -> &'a [u8] + 'a>;
@@ -50,6 +56,10 @@ pub trait TextBuffer: ToString {
     fn insert_block(&mut self, char_idx: usize, block: &str) -> bool;
     fn insert_char(&mut self, char_idx: usize, ch: char) -> bool;
     fn is_editable(&self) -> bool;
+    // language of contents, even if it's not parsed (see parsing)
+    fn lang_id(&self) -> Option<LangId> {
+        None
+    }
     fn len_bytes(&self) -> usize;
     fn len_chars(&self) -> usize;
     fn len_lines(&self) -> usize;
@@ -62,9 +72,16 @@ pub trait TextBuffer: ToString {
     fn tab_width(&self) -> usize {
         4
     }
+    fn indent_style(&self) -> IndentStyle {
+        IndentStyle::Spaces
+    }
     fn try_parse(&mut self, _lang_id: LangId) -> bool {
         false
     }
+    // syntax tree, for things smarter than text (see auto_indent)
+    fn parsing(&self) -> Option<&ParsingTuple> {
+        None
+    }
     fn undo(&mut self) -> bool {
         false
     }
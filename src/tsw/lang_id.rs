@@ -23,6 +23,30 @@ impl LangId {
             LangId::RUST => "rust",
         }
     }
+
+    // None if language has no comments that end with the line
+    pub fn line_comment_prefix(&self) -> Option<&'static str> {
+        match self {
+            LangId::C => Some("//"),
+            LangId::CPP => Some("//"),
+            LangId::HTML => None,
+            LangId::ELM => Some("--"),
+            LangId::GO => Some("//"),
+            LangId::RUST => Some("//"),
+        }
+    }
+
+    // opening and closing token
+    pub fn block_comment_tokens(&self) -> (&'static str, &'static str) {
+        match self {
+            LangId::C => ("/*", "*/"),
+            LangId::CPP => ("/*", "*/"),
+            LangId::HTML => ("<!--", "-->"),
+            LangId::ELM => ("{-", "-}"),
+            LangId::GO => ("/*", "*/"),
+            LangId::RUST => ("/*", "*/"),
+        }
+    }
 }
 
 impl Display for LangId {
@@ -0,0 +1,204 @@
+use log::{debug, error, warn};
+
+use crate::*;
+
+/*
+Files open in buffers can be changed by other programs (git checkout, formatters, code generators). Every
+once in a while stamps of their files (see FileStamp) are compared with ones taken when buffers were last
+read or written. Buffers with no unsaved changes are reloaded right away, the ones with unsaved changes
+are reported, so the user can decide what to keep.
+
+Files that disappeared are not reported, buffer just keeps its contents until saved.
+ */
+pub const DEFAULT_FILE_CHECK_INTERVAL_MS: u64 = 1000;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ExternalChange {
+    // buffer had no unsaved changes, so it was reloaded
+    Reloaded,
+    // buffer has unsaved changes, it's left as it was
+    Conflict,
+}
+
+/*
+Current stamp of buffer's file, if it differs from the one buffer remembers. None also if buffer has no
+file, or it's gone.
+ */
+pub fn changed_on_disk(buffer: &BufferState) -> Option<FileStamp> {
+    let path = buffer.get_path()?;
+    let known = buffer.disk_stamp()?;
+
+    match path.stamp() {
+        Ok(stamp) if stamp != known => Some(stamp),
+        Ok(_) => None,
+        Err(e) => {
+            debug!("failed to get stamp of {}: {}", path, e);
+            None
+        }
+    }
+}
+
+/*
+Replaces contents of buffer with its file, as an edit that can be undone (except for large files, those
+are read in chunks and lose history instead). Buffer is not modified after. Returns whether text changed.
+ */
+pub fn reload_from_disk(buffer: &mut BufferState) -> Result<bool, ReadError> {
+    let path = match buffer.get_path() {
+        Some(path) => path.clone(),
+        None => {
+            error!("can't reload buffer without a file");
+            return Err(ReadError::FileNotFound);
+        }
+    };
+
+    // taken before reading, like on open
+    let stamp = path.stamp()?;
+    let (changed, file_format) = if buffer.is_large_file() {
+        let (file_format, rope) = FileFormat::decode_to_rope(|| path.open_reader())?;
+        (buffer.replace_text_dropping_history(rope), file_format)
+    } else {
+        let (text, file_format) = path.read_entire_file_to_text()?;
+        (buffer.replace_text(text), file_format)
+    };
+
+    buffer.set_file_format(file_format);
+    buffer.mark_saved();
+    buffer.set_disk_stamp(Some(stamp));
+
+    Ok(changed)
+}
+
+pub fn check_external_change(buffer: &mut BufferState) -> Option<ExternalChange> {
+    changed_on_disk(buffer)?;
+
+    if buffer.is_modified() {
+        return Some(ExternalChange::Conflict);
+    }
+
+    match reload_from_disk(buffer) {
+        Ok(_) => Some(ExternalChange::Reloaded),
+        Err(e) => {
+            warn!("failed to reload {:?}: {}", buffer.get_path(), e);
+            None
+        }
+    }
+}
+
+/*
+Checks all buffers, reloading the ones that can be reloaded. Returns paths of ones in conflict.
+
+Stamps are compared under read lock, and only buffers with a file and a stamp are looked at. Write lock
+is taken only for the ones that actually changed.
+ */
+pub fn check_external_changes<'a, I: Iterator<Item = &'a BufferSharedRef>>(buffers: I) -> Vec<SPath> {
+    let mut conflicts: Vec<SPath> = Vec::new();
+
+    for buffer_ref in buffers {
+        let changed = match buffer_ref.lock() {
+            Some(buffer) => changed_on_disk(&buffer).is_some(),
+            None => {
+                warn!("failed to lock buffer, not checking its file");
+                continue;
+            }
+        };
+
+        if !changed {
+            continue;
+        }
+
+        let mut buffer = match buffer_ref.lock_rw() {
+            Some(buffer) => buffer,
+            None => {
+                warn!("failed to lock buffer, not reloading its file");
+                continue;
+            }
+        };
+
+        match check_external_change(&mut buffer) {
+            Some(ExternalChange::Reloaded) => {
+                debug!("reloaded {:?}, it was changed on disk", buffer.get_path());
+            }
+            Some(ExternalChange::Conflict) => {
+                if let Some(path) = buffer.get_path() {
+                    conflicts.push(path.clone());
+                }
+            }
+            None => {}
+        }
+    }
+
+    conflicts
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn open(fsf: &FsfRef, path: &str) -> BufferState {
+        let path = spath!(fsf, path).unwrap();
+        let text = path.read_entire_file_to_string().unwrap();
+
+        let mut buffer = BufferState::full(None, DocumentIdentifier::new_unique().with_file_path(path.clone())).with_text(text);
+        buffer.set_disk_stamp(path.stamp().ok());
+        buffer
+    }
+
+    #[test]
+    fn test_clean_buffer_is_reloaded() {
+        let fsf = MockFS::new("/tmp").with_file("file.txt", "abc").to_fsf();
+        let mut buffer = open(&fsf, "file.txt");
+
+        assert_eq!(check_external_change(&mut buffer), None);
+
+        spath!(fsf, "file.txt").unwrap().overwrite_with_str("xyz", true).unwrap();
+        assert_eq!(check_external_change(&mut buffer), Some(ExternalChange::Reloaded));
+        assert_eq!(buffer.to_string(), "xyz");
+        assert!(!buffer.is_modified());
+
+        // reload can be undone
+        assert_eq!(check_external_change(&mut buffer), None);
+        assert!(buffer.undo());
+        assert_eq!(buffer.to_string(), "abc");
+    }
+
+    #[test]
+    fn test_modified_buffer_is_left_alone() {
+        let fsf = MockFS::new("/tmp").with_file("file.txt", "abc").to_fsf();
+        let mut buffer = open(&fsf, "file.txt");
+
+        let wid = get_new_widget_id();
+        buffer.initialize_for_widget(wid, None);
+        buffer.apply_cem(CommonEditMsg::Char('d'), wid, 10, None);
+
+        let file = spath!(fsf, "file.txt").unwrap();
+        file.overwrite_with_str("xyz", true).unwrap();
+        assert_eq!(check_external_change(&mut buffer), Some(ExternalChange::Conflict));
+        assert_eq!(buffer.to_string(), "dabc");
+        assert_eq!(changed_on_disk(&buffer), file.stamp().ok());
+
+        assert_eq!(reload_from_disk(&mut buffer), Ok(true));
+        assert_eq!(buffer.to_string(), "xyz");
+        assert_eq!(changed_on_disk(&buffer), None);
+
+        // gone file is not a change
+        file.remove_file().unwrap();
+        assert_eq!(check_external_change(&mut buffer), None);
+    }
+
+    #[test]
+    fn test_unchanged_buffers_are_only_read() {
+        let fsf = MockFS::new("/tmp").with_file("file.txt", "abc").to_fsf();
+        let buffer_ref = BufferSharedRef::new_from_buffer(open(&fsf, "file.txt"));
+        let scratch_ref = BufferSharedRef::new_empty(None);
+
+        // someone else reading doesn't stop the check
+        let reader = buffer_ref.lock().unwrap();
+        let _scratch_reader = scratch_ref.lock().unwrap();
+        assert!(check_external_changes([&buffer_ref, &scratch_ref].into_iter()).is_empty());
+        drop(reader);
+
+        spath!(fsf, "file.txt").unwrap().overwrite_with_str("xyz", true).unwrap();
+        assert!(check_external_changes([&buffer_ref, &scratch_ref].into_iter()).is_empty());
+        assert_eq!(buffer_ref.lock().unwrap().to_string(), "xyz");
+    }
+}
@@ -10,6 +10,7 @@ This is a single point of entry to loading LanguageHandlers, to be used by both
  */
 pub fn handler_factory(
     config: &ConfigRef,
+    workspace_settings: &WorkspaceSettings,
     handler_id: &str,
     ff: SPath,
     navcomp_tick_sender: NavCompTickSender,
@@ -18,14 +19,22 @@ pub fn handler_factory(
     match handler_id {
         "rust" => {
             //So handler can "partially work", meaning for instance that running/debugging works, but LSP does
-            // not. TODO move lsp_path to workspace?
-            let lsp_path = config.global.get_rust_lsp_path().ok_or(HandlerLoadError::LspNotFound)?;
-            let workspace_root = ff.absolute_path();
+            // not.
+            let lsp_settings = workspace_settings.lsp_settings(LangId::RUST);
             let mut navcomp_op: Option<NavCompRef> = None;
-            if let Some(navcomp_lsp) = NavCompProviderLsp::new(lsp_path, workspace_root, navcomp_tick_sender) {
-                navcomp_op = Some(Arc::new(Box::new(navcomp_lsp)));
+            if lsp_settings.map(|s| s.disabled).unwrap_or(false) {
+                debug!("rust lsp disabled in workspace settings");
             } else {
-                error!("LspWrapper construction failed.")
+                let lsp_path = lsp_settings
+                    .and_then(|s| s.path.clone())
+                    .or_else(|| config.global.get_rust_lsp_path())
+                    .ok_or(HandlerLoadError::LspNotFound)?;
+                let workspace_root = ff.absolute_path();
+                if let Some(navcomp_lsp) = NavCompProviderLsp::new(lsp_path, workspace_root, navcomp_tick_sender) {
+                    navcomp_op = Some(Arc::new(Box::new(navcomp_lsp)));
+                } else {
+                    error!("LspWrapper construction failed.")
+                }
             }
 
             match RustHandler::load(config, ff, navcomp_op) {
@@ -3,6 +3,11 @@
 mod buffer_state_shared_ref;
 pub use buffer_state_shared_ref::{BufferR, BufferRW, BufferSharedRef};
 
+mod external_changes;
+pub use external_changes::{
+    changed_on_disk, check_external_change, check_external_changes, reload_from_disk, ExternalChange, DEFAULT_FILE_CHECK_INTERVAL_MS,
+};
+
 mod handler;
 pub use handler::{Handler, NavCompRef};
 
@@ -20,8 +25,9 @@ pub use navcomp_group::{NavCompGroup, NavCompGroupRef, NavCompTick, NavCompTickR
 
 mod navcomp_provider;
 pub use navcomp_provider::{
-    Completion, CompletionAction, CompletionsPromise, FormattingPromise, NavCompProvider, NavCompSymbol, NavCompSymbolContextActions,
-    StupidSubstituteMessage, SymbolContextActionsPromise, SymbolPromise, SymbolType, SymbolUsage, SymbolUsagesPromise,
+    Completion, CompletionAction, CompletionsPromise, FormattingPromise, FormattingSettings, NavCompProvider, NavCompSymbol,
+    NavCompSymbolContextActions, StupidSubstituteMessage, SymbolContextActionsPromise, SymbolPromise, SymbolType, SymbolUsage,
+    SymbolUsagesPromise,
 };
 
 mod navcomp_provider_lsp;
@@ -33,8 +39,23 @@ pub use project_scope::{ProjectLoadError, ProjectScope, SerializableProjectScope
 mod rust;
 pub use rust::*;
 
+mod session;
+pub use session::{Session, SessionEditor, SESSION_FILE_NAME};
+
 mod suggestions_provider;
 pub use suggestions_provider::SuggestionProvider;
 
+mod swap_files;
+pub use swap_files::{find_swap_files, SwapFile, SwapWriter, SWAP_DIR_NAME};
+
+mod undo_history_store;
+pub use undo_history_store::{
+    clean_up_undo_histories, content_hash, load_undo_history, save_undo_history, HistorySnapshot, SavedHistory, UndoHistoryStore,
+    UndoHistoryWriter, UndoHistoryWriterRef, UNDO_HISTORY_DIR_NAME,
+};
+
 mod workspace;
 pub use workspace::{ScopeLoadErrors, Scopes, SerializableWorkspace, Workspace, WorkspaceLoadError, WORKSPACE_FILE_NAME};
+
+mod workspace_settings;
+pub use workspace_settings::{LspSettings, TaskDefinition, WorkspaceSettings, WorkspaceSettingsRef};
@@ -8,6 +8,9 @@ use crate::*;
 #[derive(Debug, Copy, Clone)]
 pub enum NavCompTick {
     LspTick(LangId, usize),
+    // new output from a running task or terminal, or finished formatter. Not really navcomp, but it's the
+    // channel that wakes up the main loop.
+    TaskTick,
 }
 
 pub type NavCompTickSender = crossbeam_channel::Sender<NavCompTick>;
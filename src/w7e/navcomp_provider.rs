@@ -78,6 +78,17 @@ pub struct StupidSubstituteMessage {
     pub stupid_range: (StupidCursor, StupidCursor),
 }
 
+/*
+How the file is expected to look like after reformat, taken from buffer (see BufferState::formatting_settings).
+ */
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct FormattingSettings {
+    pub tab_width: usize,
+    pub indent_style: IndentStyle,
+    pub trim_trailing_whitespace: bool,
+    pub insert_final_newline: bool,
+}
+
 #[derive(Debug, Clone)]
 pub struct SymbolUsage {
     pub path: String,
@@ -127,7 +138,7 @@ pub trait NavCompProvider: Debug + Send + Sync {
     missing items:
     - formatting non-saved files
      */
-    fn todo_reformat(&self, path: &SPath) -> Option<FormattingPromise>;
+    fn todo_reformat(&self, path: &SPath, settings: &FormattingSettings) -> Option<FormattingPromise>;
 
     fn file_closed(&self, path: &SPath);
 
@@ -200,11 +200,11 @@ impl NavCompProvider for NavCompProviderLsp {
         }
     }
 
-    fn todo_reformat(&self, path: &SPath) -> Option<FormattingPromise> {
+    fn todo_reformat(&self, path: &SPath, settings: &FormattingSettings) -> Option<FormattingPromise> {
         let url = unpack_or_e!(path.to_url().ok(), None, "failed to convert spath [{}] to url", path);
         let mut lock = unpack_or_e!(self.lsp.try_write().ok(), None, "failed acquiring lock");
 
-        match lock.text_document_formatting(url) {
+        match lock.text_document_formatting(url, settings) {
             Ok(resp) => {
                 let new_promise = resp.map(|response| match response {
                     None => None,
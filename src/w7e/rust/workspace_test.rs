@@ -10,6 +10,7 @@ fn test_write_rust_workspace() {
             path: PathBuf::from("rust_repo"),
             handler_id_op: Some("rust".to_string()),
         }],
+        settings: WorkspaceSettings::default(),
     };
 
     let item = workspace_pill.to_pretty_ron_string().unwrap();
@@ -49,6 +50,54 @@ fn test_read_rust_workspace() {
     assert_eq!(workspace_pill.scopes[0].handler_id_op, Some("rust".to_string()));
 }
 
+#[test]
+fn test_read_workspace_with_settings() {
+    let workspace = r#"(
+    scopes: [],
+    settings: (
+        default_language: (
+            tab_width: Some(2),
+        ),
+        languages: {
+            RUST: (
+                tab_width: Some(4),
+                formatter: Some(Disabled),
+            ),
+        },
+        excluded_dirs: ["target"],
+        lsp: {
+            RUST: (
+                path: Some("/opt/rust-analyzer"),
+            ),
+        },
+        tasks: [
+            (
+                name: "test",
+                command: "cargo",
+                args: ["test"],
+            ),
+        ],
+    ),
+)
+        "#;
+
+    let workspace_pill = ron::from_str::<SerializableWorkspace>(workspace).unwrap();
+    let settings = &workspace_pill.settings;
+
+    assert_eq!(settings.default_language.tab_width, Some(2));
+    assert_eq!(settings.languages[&LangId::RUST].formatter, Some(FormatterChoice::Disabled));
+    assert_eq!(settings.excluded_dirs, vec![PathBuf::from("target")]);
+    assert_eq!(
+        settings.lsp_settings(LangId::RUST).unwrap().path,
+        Some(PathBuf::from("/opt/rust-analyzer"))
+    );
+    assert_eq!(settings.get_task("test").unwrap().args, vec!["test".to_string()]);
+
+    let config = Config::default();
+    assert_eq!(settings.language_options(&config, Some(LangId::RUST)).tab_width, 4);
+    assert_eq!(settings.language_options(&config, Some(LangId::GO)).tab_width, 2);
+}
+
 fn test_read_workspace() {
     let repo_folder = Path::new("workspace");
     let mock_fs = MockFS::new("/tmp")
@@ -0,0 +1,55 @@
+use std::path::PathBuf;
+
+use log::{debug, error};
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/*
+What was open when Gladius was closed, restored on next start in the same workspace root. Only editors
+of files are kept, other displays (results, tasks, terminals, diffs) and unnamed buffers can't be
+recreated from a path.
+ */
+pub const SESSION_FILE_NAME: &'static str = ".gladius_session.ron";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SessionEditor {
+    // relative to workspace root
+    pub path: PathBuf,
+    pub cursors: CursorSet,
+    pub scroll_offset: XY,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Session {
+    pub editors: Vec<SessionEditor>,
+    // index into editors of the one that was shown, None if it was other kind of display
+    pub display_idx: Option<usize>,
+    // dirs expanded in file tree, relative to workspace root
+    pub expanded: Vec<PathBuf>,
+}
+
+impl ToPrettyRonString for Session {}
+
+impl Session {
+    /*
+    None if there was no session, or it's unreadable (that is logged).
+     */
+    pub fn load(root: &SPath) -> Option<Session> {
+        let file = root.descendant_checked(SESSION_FILE_NAME)?;
+        debug!("loading session from {:?}", file.absolute_path());
+
+        match file.read_entire_file_to_item::<Session>() {
+            Ok(session) => Some(session),
+            Err(e) => {
+                error!("failed reading {}, starting with no session: {}", SESSION_FILE_NAME, e);
+                None
+            }
+        }
+    }
+
+    pub fn save(&self, root: &SPath) -> Result<usize, WriteOrSerError> {
+        let file = root.descendant_unchecked(SESSION_FILE_NAME).unwrap();
+        file.overwrite_with_ron(self, false)
+    }
+}
@@ -0,0 +1,268 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::thread::JoinHandle;
+use std::time::SystemTime;
+
+use log::{debug, error, warn};
+use ropey::Rope;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/*
+Modified buffers (including unnamed ones) are periodically written to swap files in a dir in workspace
+root, one per buffer. A swap file is removed once its buffer is saved (or undone to saved state), so
+whatever is left on next start was not saved when Gladius crashed or was closed, and is offered for
+recovery.
+
+Two instances running in the same workspace would offer each other's swaps, that is not handled.
+ */
+pub const SWAP_DIR_NAME: &'static str = ".gladius_swap";
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct SwapFile {
+    // relative to workspace root, None for buffers that were never saved
+    pub path: Option<PathBuf>,
+    pub written: SystemTime,
+    pub contents: String,
+}
+
+fn swap_file_name(buffer_id: &BufferId) -> String {
+    format!("{}.ron", buffer_id)
+}
+
+enum SwapJob {
+    Write {
+        buffer_id: BufferId,
+        file: SPath,
+        path: Option<PathBuf>,
+        // ropes share their nodes, so this is a cheap snapshot
+        contents: Rope,
+    },
+    Remove {
+        file: SPath,
+    },
+}
+
+/*
+Serializing and writing swap files is done in background, so it doesn't freeze the UI. Only one batch
+runs at a time, a tick that comes while it runs is skipped, changes get picked up by the next one.
+ */
+pub struct SwapWriter {
+    dir: SPath,
+    // content version of buffer as of its last swap file
+    written: HashMap<BufferId, u64>,
+    // returns ids of buffers which swap files failed to write
+    worker_op: Option<JoinHandle<Vec<BufferId>>>,
+}
+
+impl SwapWriter {
+    pub const DEFAULT_INTERVAL_MS: u64 = 2000;
+
+    pub fn new(root: &SPath) -> Self {
+        SwapWriter {
+            dir: root.descendant_unchecked(SWAP_DIR_NAME).unwrap(),
+            written: HashMap::new(),
+            worker_op: None,
+        }
+    }
+
+    /*
+    Starts writing swap files of modified buffers that changed since last call, and removing the ones
+    this writer wrote for buffers that are not modified anymore. Returns how many are to be written, 0
+    if previous batch is still running.
+     */
+    pub fn update<'a, I: Iterator<Item = &'a BufferSharedRef>>(&mut self, buffers: I) -> usize {
+        if self.worker_op.as_ref().map(|worker| !worker.is_finished()).unwrap_or(false) {
+            debug!("previous swap files are still being written, skipping");
+            return 0;
+        }
+        self.wait();
+
+        let mut jobs: Vec<SwapJob> = Vec::new();
+        let mut written: usize = 0;
+
+        for buffer_ref in buffers {
+            let buffer = match buffer_ref.lock() {
+                Some(buffer) => buffer,
+                None => {
+                    warn!("failed to lock buffer, skipping its swap file");
+                    continue;
+                }
+            };
+
+            // writing whole large file every few seconds would cost more than the swap is worth
+            if buffer.is_large_file() {
+                continue;
+            }
+
+            let buffer_id = buffer.get_document_identifier().buffer_id;
+            let file = self.dir.descendant_unchecked(swap_file_name(&buffer_id)).unwrap();
+
+            if !buffer.is_modified() {
+                if self.written.remove(&buffer_id).is_some() {
+                    jobs.push(SwapJob::Remove { file });
+                }
+                continue;
+            }
+
+            if self.written.get(&buffer_id) == Some(&buffer.content_version()) {
+                continue;
+            }
+
+            // failed ones are forgotten once worker is done, so they are retried
+            self.written.insert(buffer_id, buffer.content_version());
+            jobs.push(SwapJob::Write {
+                buffer_id,
+                file,
+                path: buffer.get_path().map(|path| path.relative_path()),
+                contents: buffer.text().rope().clone(),
+            });
+            written += 1;
+        }
+
+        if !jobs.is_empty() {
+            let dir = self.dir.clone();
+            self.worker_op = Some(std::thread::spawn(move || run_jobs(&dir, jobs)));
+        }
+
+        written
+    }
+
+    /*
+    Blocks until swap files of last update are written. Used on exit.
+     */
+    pub fn wait(&mut self) {
+        let worker = unpack_or!(self.worker_op.take(), ());
+        match worker.join() {
+            Ok(failed) => {
+                for buffer_id in failed.iter() {
+                    self.written.remove(buffer_id);
+                }
+            }
+            Err(_) => {
+                error!("swap writer panicked");
+                self.written.clear();
+            }
+        }
+    }
+}
+
+fn run_jobs(dir: &SPath, jobs: Vec<SwapJob>) -> Vec<BufferId> {
+    let mut failed: Vec<BufferId> = Vec::new();
+
+    for job in jobs.into_iter() {
+        match job {
+            SwapJob::Write {
+                buffer_id,
+                file,
+                path,
+                contents,
+            } => {
+                let swap = SwapFile {
+                    path,
+                    written: SystemTime::now(),
+                    contents: contents.to_string(),
+                };
+
+                if let Err(e) = write_swap(dir, &file, &swap) {
+                    error!("failed writing swap file {}: {:?}", file, e);
+                    failed.push(buffer_id);
+                }
+            }
+            SwapJob::Remove { file } => {
+                debug!("removing swap file {}", file);
+                if let Err(e) = file.remove_file() {
+                    warn!("failed removing swap file {}: {:?}", file, e);
+                }
+            }
+        }
+    }
+
+    failed
+}
+
+fn write_swap(dir: &SPath, file: &SPath, swap: &SwapFile) -> Result<usize, WriteOrSerError> {
+    dir.create_dir_all()?;
+    file.overwrite_with_ron(swap, false)
+}
+
+/*
+Swap files left by previous sessions, oldest first. Unreadable ones are logged and skipped (they stay
+on disk).
+ */
+pub fn find_swap_files(root: &SPath) -> Vec<(SPath, SwapFile)> {
+    let dir = unpack_or!(root.descendant_checked(SWAP_DIR_NAME), vec![]);
+    let items = match dir.blocking_list() {
+        Ok(items) => items,
+        Err(e) => {
+            error!("failed listing {}: {:?}", dir, e);
+            return vec![];
+        }
+    };
+
+    let mut result: Vec<(SPath, SwapFile)> = Vec::new();
+    for item in items.into_iter().filter(|item| item.is_file()) {
+        match item.read_entire_file_to_item::<SwapFile>() {
+            Ok(swap) => result.push((item, swap)),
+            Err(e) => {
+                error!("failed reading swap file {}, skipping it: {}", item, e);
+            }
+        }
+    }
+
+    result.sort_by_key(|(_, swap)| swap.written);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_swap_files_follow_modifications() {
+        let fsf = MockFS::new("/tmp").with_file("file.txt", "abc").to_fsf();
+        let path = spath!(fsf, "file.txt").unwrap();
+
+        let named = BufferState::full(None, DocumentIdentifier::new_unique().with_file_path(path))
+            .with_text("abc")
+            .into_bsr();
+        let unnamed = BufferState::full(None, DocumentIdentifier::new_unique()).into_bsr();
+        let buffers = vec![named.clone(), unnamed.clone()];
+
+        let mut writer = SwapWriter::new(&fsf.root());
+        assert_eq!(writer.update(buffers.iter()), 0);
+        assert!(find_swap_files(&fsf.root()).is_empty());
+
+        let wid = get_new_widget_id();
+        for (buffer, c) in buffers.iter().zip(['x', 'y']) {
+            let mut lock = buffer.lock_rw().unwrap();
+            lock.initialize_for_widget(wid, None);
+            lock.apply_cem(CommonEditMsg::Char(c), wid, 10, None);
+        }
+
+        assert_eq!(writer.update(buffers.iter()), 2);
+        writer.wait();
+        // nothing changed since
+        assert_eq!(writer.update(buffers.iter()), 0);
+
+        let mut contents: Vec<(Option<PathBuf>, String)> = find_swap_files(&fsf.root())
+            .into_iter()
+            .map(|(_, swap)| (swap.path, swap.contents))
+            .collect();
+        contents.sort();
+        assert_eq!(
+            contents,
+            vec![(None, "y".to_string()), (Some(PathBuf::from("file.txt")), "xabc".to_string())]
+        );
+
+        // saved buffer is not modified anymore, so its swap goes away
+        named.lock_rw().unwrap().mark_saved();
+        assert_eq!(writer.update(buffers.iter()), 0);
+        writer.wait();
+
+        let swaps = find_swap_files(&fsf.root());
+        assert_eq!(swaps.len(), 1);
+        assert_eq!(swaps[0].1.path, None);
+    }
+}
@@ -0,0 +1,484 @@
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use std::time::{Duration, SystemTime};
+
+use log::{debug, error, warn};
+use ropey::Rope;
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/*
+Undo histories of files, kept between sessions in a dir next to the workspace file, one file per path.
+History is stored when the file is saved, together with a hash of what was written. If the file doesn't
+hash the same when it's opened again, it was changed outside the editor, and history is discarded -
+edits would not apply to it.
+
+Saving writes only the file of the path saved, in background (see UndoHistoryWriter). Total limit (and
+dropping histories of files that are gone) is up to clean_up, run with --clean-undo-history.
+ */
+pub const UNDO_HISTORY_DIR_NAME: &'static str = ".gladius_undo_history";
+
+#[derive(Debug, Serialize, Deserialize)]
+pub struct SavedHistory {
+    // relative to workspace root, tells apart paths whose names hash the same
+    pub path: PathBuf,
+    pub content_hash: String,
+    pub saved: SystemTime,
+    pub size_bytes: usize,
+    pub history: SerializableEditHistory,
+}
+
+/*
+FNV-1a, std's hashers are not guaranteed to be the same between releases, and these hashes are kept on
+disk.
+ */
+fn fnv1a<I: Iterator<Item = u8>>(bytes: I) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+pub fn content_hash(rope: &Rope) -> String {
+    format!("{:016x}", fnv1a(rope.chunks().flat_map(|chunk| chunk.bytes())))
+}
+
+fn history_file_name(relative_path: &Path) -> String {
+    format!("{:016x}.ron", fnv1a(relative_path.to_string_lossy().bytes()))
+}
+
+/*
+What gets stored for a file that was just saved. Taking it is cheap compared to hashing the text and
+serializing, which are left to write_snapshot, so they can be done in background.
+ */
+pub struct HistorySnapshot {
+    relative_path: PathBuf,
+    // trimmed to file limit and its size, None if nothing is to be kept
+    history_op: Option<(SerializableEditHistory, usize)>,
+    // ropes share their nodes, so this is not a copy
+    text: Rope,
+}
+
+pub struct UndoHistoryStore {
+    dir: SPath,
+}
+
+impl UndoHistoryStore {
+    pub const DEFAULT_FILE_LIMIT_BYTES: usize = 1024 * 1024;
+    pub const DEFAULT_TOTAL_LIMIT_BYTES: usize = 16 * 1024 * 1024;
+    pub const DEFAULT_MAX_AGE_DAYS: u64 = 30;
+
+    pub fn new(root: &SPath) -> Self {
+        UndoHistoryStore {
+            dir: root.descendant_unchecked(UNDO_HISTORY_DIR_NAME).unwrap(),
+        }
+    }
+
+    fn file_for(&self, relative_path: &Path) -> SPath {
+        self.dir.descendant_unchecked(history_file_name(relative_path)).unwrap()
+    }
+
+    /*
+    Missing file means nothing was kept, unreadable one is logged and will be overwritten.
+     */
+    fn read(file: &SPath) -> Option<SavedHistory> {
+        if !file.exists() {
+            return None;
+        }
+
+        match file.read_entire_file_to_item::<SavedHistory>() {
+            Ok(saved) => Some(saved),
+            Err(e) => {
+                error!("failed reading undo history {}: {}", file, e);
+                None
+            }
+        }
+    }
+
+    fn write(&self, saved: &SavedHistory) -> Result<usize, WriteOrSerError> {
+        self.dir.create_dir_all()?;
+        self.file_for(&saved.path).overwrite_with_ron(saved, false)
+    }
+
+    /*
+    History of file, if it ends at given text and is not older than max_age.
+     */
+    pub fn history_for(&self, path: &SPath, text: &Rope, limit_bytes: usize, max_age: Duration) -> Option<EditHistory> {
+        let relative_path = path.relative_path();
+        let saved = Self::read(&self.file_for(&relative_path))?;
+
+        if saved.path != relative_path {
+            debug!("undo history of {:?} found in place of {}'s, ignoring it", saved.path, path);
+            return None;
+        }
+
+        if !is_younger_than(&saved, max_age) {
+            debug!("undo history of {} is too old, discarding it", path);
+            return None;
+        }
+
+        if saved.content_hash != content_hash(text) {
+            debug!("{} changed since its undo history was saved, discarding it", path);
+            return None;
+        }
+
+        EditHistory::from_serializable(saved.history, limit_bytes)
+    }
+
+    /*
+    Keeps history of file that was just saved with given text, trimmed to file limit.
+     */
+    pub fn store(&self, path: &SPath, history: &EditHistory, text: &Rope, options: &GlobalEditorOptions) -> Result<(), WriteOrSerError> {
+        let snapshot = unpack_or!(Self::snapshot(path, history, text, options), Ok(()));
+        self.write_snapshot(snapshot)
+    }
+
+    /*
+    History is rebuilt only if it's over file limit, to trim it.
+     */
+    pub fn snapshot(path: &SPath, history: &EditHistory, text: &Rope, options: &GlobalEditorOptions) -> Option<HistorySnapshot> {
+        let file_limit = options.get_persistent_undo_file_limit_bytes();
+
+        let history_op = if history.is_empty() || file_limit == 0 {
+            None
+        } else if history.size_bytes() <= file_limit {
+            Some((history.serializable(), history.size_bytes()))
+        } else {
+            let trimmed = unpack_or_e!(
+                EditHistory::from_serializable(history.serializable(), file_limit),
+                None,
+                "failed to trim undo history of {}",
+                path
+            );
+            Some((trimmed.serializable(), trimmed.size_bytes()))
+        };
+
+        Some(HistorySnapshot {
+            relative_path: path.relative_path(),
+            history_op,
+            text: text.clone(),
+        })
+    }
+
+    pub fn write_snapshot(&self, snapshot: HistorySnapshot) -> Result<(), WriteOrSerError> {
+        let (history, size_bytes) = match snapshot.history_op {
+            Some(history) => history,
+            None => {
+                let file = self.file_for(&snapshot.relative_path);
+                if file.exists() {
+                    file.remove_file()?;
+                }
+                return Ok(());
+            }
+        };
+
+        self.write(&SavedHistory {
+            path: snapshot.relative_path,
+            content_hash: content_hash(&snapshot.text),
+            saved: SystemTime::now(),
+            size_bytes,
+            history,
+        })?;
+
+        Ok(())
+    }
+
+    /*
+    Drops histories that can't be used anymore: unreadable ones, of files that are gone or were changed
+    outside the editor, and ones over max age. Then the ones saved longest ago, until the rest fits
+    total limit. Returns how many were dropped.
+     */
+    pub fn clean_up(&self, root: &SPath, max_age: Duration, total_limit_bytes: usize) -> Result<usize, WriteOrSerError> {
+        if !self.dir.exists() {
+            return Ok(0);
+        }
+
+        let items = match self.dir.blocking_list() {
+            Ok(items) => items,
+            Err(e) => {
+                error!("failed listing {}: {:?}", self.dir, e);
+                return Ok(0);
+            }
+        };
+
+        let mut dropped: usize = 0;
+        let mut kept: Vec<(SPath, SavedHistory)> = Vec::new();
+
+        for item in items.into_iter().filter(|item| item.is_file()) {
+            let usable = match Self::read(&item) {
+                Some(saved) if is_younger_than(&saved, max_age) && still_matches(root, &saved) => Some(saved),
+                _ => None,
+            };
+
+            match usable {
+                Some(saved) => kept.push((item, saved)),
+                None => {
+                    item.remove_file()?;
+                    dropped += 1;
+                }
+            }
+        }
+
+        let mut total: usize = kept.iter().map(|(_, saved)| saved.size_bytes).sum();
+        kept.sort_by_key(|(_, saved)| saved.saved);
+        for (item, saved) in kept.into_iter() {
+            if total <= total_limit_bytes {
+                break;
+            }
+            item.remove_file()?;
+            total -= saved.size_bytes;
+            dropped += 1;
+        }
+
+        Ok(dropped)
+    }
+}
+
+fn is_younger_than(saved: &SavedHistory, max_age: Duration) -> bool {
+    SystemTime::now()
+        .duration_since(saved.saved)
+        .map(|age| age <= max_age)
+        .unwrap_or(true)
+}
+
+// file history was saved for still exists, and hashes the same as in buffer
+fn still_matches(root: &SPath, saved: &SavedHistory) -> bool {
+    let file = unpack_or!(root.descendant_checked(&saved.path), false);
+    match file.read_entire_file_to_text() {
+        Ok((text, _)) => content_hash(&Rope::from_str(&text)) == saved.content_hash,
+        Err(e) => {
+            warn!("failed reading {}, dropping its undo history: {}", file, e);
+            false
+        }
+    }
+}
+
+/*
+Undo history kept from previous session for file just opened with given text, if there is one.
+ */
+pub fn load_undo_history(providers: &Providers, path: &SPath, text: &Rope) -> Option<EditHistory> {
+    let options = &providers.config().global;
+    if options.get_persistent_undo_file_limit_bytes() == 0 {
+        return None;
+    }
+
+    UndoHistoryStore::new(&providers.fsf().root()).history_for(
+        path,
+        text,
+        options.get_undo_memory_limit_bytes(),
+        options.get_persistent_undo_max_age(),
+    )
+}
+
+/*
+Writes undo histories in background, one after another, so the last one saved is the one kept.
+ */
+#[derive(Default)]
+pub struct UndoHistoryWriter {
+    last_op: Mutex<Option<JoinHandle<()>>>,
+}
+
+pub type UndoHistoryWriterRef = Arc<UndoHistoryWriter>;
+
+impl UndoHistoryWriter {
+    pub fn write(&self, store: UndoHistoryStore, snapshot: HistorySnapshot) {
+        let mut last = unpack_or_e!(self.last_op.lock().ok(), (), "failed to lock undo history writer");
+        let previous_op = last.take();
+
+        *last = Some(std::thread::spawn(move || {
+            if let Some(previous) = previous_op {
+                if previous.join().is_err() {
+                    error!("undo history writer panicked");
+                }
+            }
+
+            let path = snapshot.relative_path.clone();
+            if let Err(e) = store.write_snapshot(snapshot) {
+                error!("failed saving undo history of {:?}: {:?}", path, e);
+            }
+        }));
+    }
+
+    /*
+    Blocks until all histories given so far are written. Used on exit.
+     */
+    pub fn wait(&self) {
+        let last_op = unpack_or_e!(self.last_op.lock().ok(), (), "failed to lock undo history writer").take();
+        if let Some(last) = last_op {
+            if last.join().is_err() {
+                error!("undo history writer panicked");
+            }
+        }
+    }
+}
+
+/*
+Keeps undo history of buffer that was just saved to path.
+ */
+pub fn save_undo_history(providers: &Providers, path: &SPath, buffer: &BufferState) {
+    // it would never be loaded, see BufferRegister::open_file
+    if buffer.is_large_file() {
+        debug!("not storing undo history of large file {}", path);
+        return;
+    }
+
+    let options = &providers.config().global;
+    let snapshot = unpack_or!(
+        UndoHistoryStore::snapshot(path, buffer.history(), buffer.text().rope(), options),
+        ()
+    );
+
+    providers
+        .undo_history_writer()
+        .write(UndoHistoryStore::new(&providers.fsf().root()), snapshot);
+}
+
+/*
+Drops undo histories that are no use anymore or over total limit (see UndoHistoryStore::clean_up),
+returns how many.
+ */
+pub fn clean_up_undo_histories(fsf: &FsfRef, options: &GlobalEditorOptions) -> Result<usize, WriteOrSerError> {
+    let root = fsf.root();
+    UndoHistoryStore::new(&root).clean_up(
+        &root,
+        options.get_persistent_undo_max_age(),
+        options.get_persistent_undo_total_limit_bytes(),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn history_of(text: &str) -> (EditHistory, Rope) {
+        let mut buffer = BufferState::full(None, DocumentIdentifier::new_unique());
+        let wid = get_new_widget_id();
+        buffer.initialize_for_widget(wid, None);
+
+        for c in text.chars() {
+            buffer.apply_cem(CommonEditMsg::Char(c), wid, 10, None);
+        }
+
+        let history = EditHistory::from_serializable(buffer.history().serializable(), EditHistory::DEFAULT_LIMIT_BYTES).unwrap();
+        (history, buffer.text().rope().clone())
+    }
+
+    fn stored_paths(fsf: &FsfRef) -> Vec<PathBuf> {
+        let dir = fsf.root().descendant_checked(UNDO_HISTORY_DIR_NAME).unwrap();
+        let mut paths: Vec<PathBuf> = dir
+            .blocking_list()
+            .unwrap()
+            .iter()
+            .filter_map(|item| UndoHistoryStore::read(item))
+            .map(|saved| saved.path)
+            .collect();
+        paths.sort();
+        paths
+    }
+
+    const MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+    #[test]
+    fn test_history_is_discarded_if_file_changed() {
+        let fsf = MockFS::new("/tmp").to_fsf();
+        let path = spath!(fsf, "file.txt").unwrap();
+        let options = GlobalEditorOptions::default();
+
+        let (history, rope) = history_of("abc");
+        let store = UndoHistoryStore::new(&fsf.root());
+        store.store(&path, &history, &rope, &options).unwrap();
+
+        assert_eq!(
+            store
+                .history_for(&path, &rope, EditHistory::DEFAULT_LIMIT_BYTES, MAX_AGE)
+                .map(|h| h.len()),
+            Some(history.len())
+        );
+        assert!(store
+            .history_for(&path, &Rope::from_str("abcd"), EditHistory::DEFAULT_LIMIT_BYTES, MAX_AGE)
+            .is_none());
+    }
+
+    #[test]
+    fn test_writer_keeps_last_history() {
+        let fsf = MockFS::new("/tmp").to_fsf();
+        let path = spath!(fsf, "file.txt").unwrap();
+        let options = GlobalEditorOptions::default();
+        let writer = UndoHistoryWriter::default();
+
+        let (first, first_rope) = history_of("abc");
+        let (second, second_rope) = history_of("abcd");
+        for (history, rope) in [(&first, &first_rope), (&second, &second_rope)] {
+            let snapshot = UndoHistoryStore::snapshot(&path, history, rope, &options).unwrap();
+            writer.write(UndoHistoryStore::new(&fsf.root()), snapshot);
+        }
+        writer.wait();
+
+        let store = UndoHistoryStore::new(&fsf.root());
+        assert!(store
+            .history_for(&path, &first_rope, EditHistory::DEFAULT_LIMIT_BYTES, MAX_AGE)
+            .is_none());
+        assert_eq!(
+            store
+                .history_for(&path, &second_rope, EditHistory::DEFAULT_LIMIT_BYTES, MAX_AGE)
+                .map(|h| h.len()),
+            Some(second.len())
+        );
+    }
+
+    #[test]
+    fn test_each_path_is_stored_separately() {
+        let fsf = MockFS::new("/tmp").to_fsf();
+        let first = spath!(fsf, "first.txt").unwrap();
+        let second = spath!(fsf, "src", "second.txt").unwrap();
+
+        let (history, rope) = history_of("abc");
+        let mut options = GlobalEditorOptions::default();
+        // total limit is not enforced on save
+        options.persistent_undo_total_limit_bytes = Some(1);
+
+        let store = UndoHistoryStore::new(&fsf.root());
+        store.store(&first, &history, &rope, &options).unwrap();
+        store.store(&second, &history, &rope, &options).unwrap();
+        assert_eq!(stored_paths(&fsf), vec![first.relative_path(), second.relative_path()]);
+
+        // limit of 0 turns it off, and removes what was kept
+        options.persistent_undo_file_limit_bytes = Some(0);
+        store.store(&second, &history, &rope, &options).unwrap();
+        assert_eq!(stored_paths(&fsf), vec![first.relative_path()]);
+    }
+
+    #[test]
+    fn test_clean_up() {
+        let fsf = MockFS::new("/tmp")
+            .with_file("kept.txt", "abc")
+            .with_file("older.txt", "abc")
+            .with_file("changed.txt", "abcd")
+            .with_file("too_old.txt", "abc")
+            .to_fsf();
+        let (history, rope) = history_of("abc");
+        let options = GlobalEditorOptions::default();
+
+        let store = UndoHistoryStore::new(&fsf.root());
+        for name in ["kept.txt", "older.txt", "changed.txt", "gone.txt", "too_old.txt"] {
+            store.store(&spath!(fsf, name).unwrap(), &history, &rope, &options).unwrap();
+        }
+
+        let mut saved_ago = |name: &str, ago: Duration| {
+            let mut saved = UndoHistoryStore::read(&store.file_for(Path::new(name))).unwrap();
+            saved.saved = SystemTime::now() - ago;
+            store.write(&saved).unwrap();
+            saved.size_bytes
+        };
+        let size_bytes = saved_ago("older.txt", Duration::from_secs(60));
+        saved_ago("too_old.txt", MAX_AGE * 2);
+
+        // changed, gone and too old are dropped, then older one doesn't fit total limit
+        assert_eq!(store.clean_up(&fsf.root(), MAX_AGE, size_bytes).unwrap(), 4);
+        assert_eq!(stored_paths(&fsf), vec![PathBuf::from("kept.txt")]);
+    }
+}
@@ -23,11 +23,14 @@ pub type ScopeLoadErrors = Vec<(PathBuf, ProjectLoadError)>;
 pub struct Workspace {
     root_path: SPath,
     scopes: Vec<ProjectScope>,
+    settings: WorkspaceSettings,
 }
 
 #[derive(Serialize, Deserialize)]
 pub struct SerializableWorkspace {
     pub scopes: Vec<SerializableProjectScope>,
+    #[serde(default, skip_serializing_if = "WorkspaceSettings::is_default")]
+    pub settings: WorkspaceSettings,
 }
 
 impl ToPrettyRonString for SerializableWorkspace {}
@@ -46,7 +49,15 @@ impl From<ReadError> for WorkspaceLoadError {
 
 impl Workspace {
     pub fn new(root_path: SPath, scopes: Vec<ProjectScope>) -> Workspace {
-        Workspace { root_path, scopes }
+        Workspace {
+            root_path,
+            scopes,
+            settings: WorkspaceSettings::default(),
+        }
+    }
+
+    pub fn with_settings(self, settings: WorkspaceSettings) -> Self {
+        Workspace { settings, ..self }
     }
 
     pub fn try_load(root_path: SPath) -> Result<(Workspace, ScopeLoadErrors), WorkspaceLoadError> {
@@ -75,13 +86,21 @@ impl Workspace {
             }
         }
 
-        Ok((Workspace { root_path, scopes }, scope_errors))
+        Ok((
+            Workspace {
+                root_path,
+                scopes,
+                settings: sw.settings,
+            },
+            scope_errors,
+        ))
     }
 
     pub fn serializable(&self) -> SerializableWorkspace {
         let serializable_scopes: Vec<_> = self.scopes.iter().map(|scope| scope.serializable()).collect();
         SerializableWorkspace {
             scopes: serializable_scopes,
+            settings: self.settings.clone(),
         }
     }
 
@@ -92,7 +111,7 @@ impl Workspace {
         for scope in self.scopes.iter_mut() {
             match providers
                 .navcomp_loader()
-                .load_handler(providers.config(), &scope, nav_comp_group.todo_sender().clone())
+                .load_handler(providers.config(), &self.settings, &scope, nav_comp_group.todo_sender().clone())
             {
                 Ok(handler) => {
                     scope.handler = Some(handler);
@@ -125,4 +144,8 @@ impl Workspace {
     pub fn scopes(&self) -> &Vec<ProjectScope> {
         &self.scopes
     }
+
+    pub fn settings(&self) -> &WorkspaceSettings {
+        &self.settings
+    }
 }
@@ -0,0 +1,157 @@
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use serde::{Deserialize, Serialize};
+
+use crate::*;
+
+/*
+These are settings local to a workspace, stored along the scopes in .gladius_workspace.ron.
+All fields are optional in the file, so old workspace files load just fine.
+
+Precedence of language settings (first one that is set wins):
+0) .editorconfig of the file, for indentation only (see EditorConfigOptions), applied on open
+1) workspace, language specific (languages[lang_id])
+2) workspace, default (default_language)
+3) global config (Config.global.language_options)
+4) hardcoded defaults (ResolvedLanguageOptions::default())
+ */
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct WorkspaceSettings {
+    #[serde(default, skip_serializing_if = "LanguageOptions::is_default")]
+    pub default_language: LanguageOptions,
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub languages: HashMap<LangId, LanguageOptions>,
+
+    // relative to workspace root
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub excluded_dirs: Vec<PathBuf>,
+
+    #[serde(default, skip_serializing_if = "HashMap::is_empty")]
+    pub lsp: HashMap<LangId, LspSettings>,
+
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tasks: Vec<TaskDefinition>,
+}
+
+pub type WorkspaceSettingsRef = Arc<WorkspaceSettings>;
+
+#[derive(Clone, Debug, Default, Deserialize, Serialize, PartialEq, Eq)]
+pub struct LspSettings {
+    // overrides path from global config (and discovery)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub path: Option<PathBuf>,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub disabled: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, Eq)]
+pub struct TaskDefinition {
+    pub name: String,
+    pub command: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub args: Vec<String>,
+    // relative to workspace root, None means root itself
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cwd: Option<PathBuf>,
+}
+
+impl WorkspaceSettings {
+    pub fn language_options(&self, config: &Config, lang_id_op: Option<LangId>) -> ResolvedLanguageOptions {
+        let specific = lang_id_op
+            .and_then(|lang_id| self.languages.get(&lang_id))
+            .cloned()
+            .unwrap_or_default();
+
+        specific.or(&self.default_language).or(&config.global.language_options).resolve()
+    }
+
+    /*
+    Returns true if path is within one of excluded_dirs.
+     */
+    pub fn is_excluded(&self, path: &SPath) -> bool {
+        if self.excluded_dirs.is_empty() {
+            return false;
+        }
+
+        let relative = path.relative_path();
+        self.excluded_dirs.iter().any(|dir| relative.starts_with(dir))
+    }
+
+    pub fn lsp_settings(&self, lang_id: LangId) -> Option<&LspSettings> {
+        self.lsp.get(&lang_id)
+    }
+
+    pub fn get_task(&self, name: &str) -> Option<&TaskDefinition> {
+        self.tasks.iter().find(|task| task.name == name)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_empty_settings_de() {
+        let read = ron::from_str::<WorkspaceSettings>("()");
+        assert_eq!(read.as_ref().err(), None);
+        assert_eq!(read.unwrap(), WorkspaceSettings::default());
+    }
+
+    #[test]
+    fn test_settings_ser_de() {
+        let mut settings = WorkspaceSettings::default();
+        settings.languages.insert(
+            LangId::GO,
+            LanguageOptions {
+                indent_style: Some(IndentStyle::Tabs),
+                ..Default::default()
+            },
+        );
+        settings.excluded_dirs.push(PathBuf::from("target"));
+        settings.tasks.push(TaskDefinition {
+            name: "build".to_string(),
+            command: "cargo".to_string(),
+            args: vec!["build".to_string()],
+            cwd: None,
+        });
+
+        let item = ron::ser::to_string_pretty(&settings, ron::ser::PrettyConfig::new());
+        assert_eq!(item.as_ref().err(), None);
+        let read = ron::from_str::<WorkspaceSettings>(item.as_ref().unwrap());
+        assert_eq!(read.as_ref().err(), None);
+        assert_eq!(read.unwrap(), settings);
+    }
+
+    #[test]
+    fn test_language_options_precedence() {
+        let mut config = Config::default();
+        config.global.language_options.tab_width = Some(8);
+        config.global.language_options.indent_style = Some(IndentStyle::Tabs);
+
+        let mut settings = WorkspaceSettings::default();
+        settings.default_language.tab_width = Some(2);
+        settings.languages.insert(
+            LangId::RUST,
+            LanguageOptions {
+                tab_width: Some(4),
+                formatter: Some(FormatterChoice::Disabled),
+                ..Default::default()
+            },
+        );
+
+        let rust = settings.language_options(&config, Some(LangId::RUST));
+        assert_eq!(rust.tab_width, 4);
+        assert_eq!(rust.indent_style, IndentStyle::Tabs);
+        assert_eq!(rust.formatter, FormatterChoice::Disabled);
+
+        let go = settings.language_options(&config, Some(LangId::GO));
+        assert_eq!(go.tab_width, 2);
+        assert_eq!(go.indent_style, IndentStyle::Tabs);
+        assert_eq!(go.formatter, FormatterChoice::Navcomp);
+
+        let none = WorkspaceSettings::default().language_options(&Config::default(), None);
+        assert_eq!(none, ResolvedLanguageOptions::default());
+    }
+}
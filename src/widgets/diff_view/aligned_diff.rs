@@ -0,0 +1,361 @@
+use std::ops::Range;
+
+use similar::{capture_diff_slices, Algorithm, ChangeTag, DiffOp, TextDiff};
+
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum DiffLineKind {
+    Equal,
+    // line exists on both sides, but differs
+    Changed,
+    // line exists only on this side
+    Only,
+    // padding, so both sides have the same number of rows
+    Filler,
+}
+
+/*
+One side of diff, as displayed. Text has exactly one line per row (rows are joined with '\n'), so
+row idx == line idx in buffer created from it.
+ */
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AlignedSide {
+    pub text: String,
+    // row -> line of original text, None for filler
+    pub real_lines: Vec<Option<usize>>,
+    pub kinds: Vec<DiffLineKind>,
+    // char ranges of "text" that differ from the other side, within Changed rows. Sorted.
+    pub changed_chars: Vec<Range<usize>>,
+    // lines of original text
+    lines: Vec<String>,
+}
+
+impl AlignedSide {
+    pub fn lines_count(&self) -> usize {
+        self.lines.len()
+    }
+
+    /*
+    Text of given lines of original, each followed by newline.
+     */
+    pub fn lines_text(&self, lines: Range<usize>) -> String {
+        let mut result = String::new();
+        for line in self.lines[lines].iter() {
+            result.push_str(line);
+            result.push('\n');
+        }
+        result
+    }
+}
+
+/*
+Rows are 0-based, lines (of original texts) are 0-based, end exclusive.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct AlignedHunk {
+    pub rows: Range<usize>,
+    pub left_lines: Range<usize>,
+    pub right_lines: Range<usize>,
+}
+
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct AlignedDiff {
+    pub left: AlignedSide,
+    pub right: AlignedSide,
+    pub hunks: Vec<AlignedHunk>,
+}
+
+impl AlignedDiff {
+    pub fn new(left: &str, right: &str) -> Self {
+        let left_lines = split_lines(left);
+        let right_lines = split_lines(right);
+
+        let mut builder = Builder::default();
+        let mut pending: Option<(Range<usize>, Range<usize>)> = None;
+
+        for op in capture_diff_slices(Algorithm::Myers, &left_lines, &right_lines) {
+            match op {
+                DiffOp::Equal { old_index, new_index, len } => {
+                    if let Some((old, new)) = pending.take() {
+                        builder.push_hunk(&left_lines, &right_lines, old, new);
+                    }
+                    for i in 0..len {
+                        builder.push_row(
+                            Some((old_index + i, left_lines[old_index + i])),
+                            Some((new_index + i, right_lines[new_index + i])),
+                            DiffLineKind::Equal,
+                        );
+                    }
+                }
+                _ => {
+                    let (old, new) = (op.old_range(), op.new_range());
+                    // adjacent deletes and inserts make up one hunk
+                    pending = Some(match pending.take() {
+                        Some((pending_old, pending_new)) => (pending_old.start..old.end, pending_new.start..new.end),
+                        None => (old, new),
+                    });
+                }
+            }
+        }
+
+        if let Some((old, new)) = pending.take() {
+            builder.push_hunk(&left_lines, &right_lines, old, new);
+        }
+
+        builder.left.lines = left_lines.iter().map(|l| l.to_string()).collect();
+        builder.right.lines = right_lines.iter().map(|l| l.to_string()).collect();
+
+        AlignedDiff {
+            left: builder.left,
+            right: builder.right,
+            hunks: builder.hunks,
+        }
+    }
+
+    pub fn rows_count(&self) -> usize {
+        self.left.kinds.len()
+    }
+
+    pub fn hunk_idx_at(&self, row: usize) -> Option<usize> {
+        self.hunks.iter().position(|h| h.rows.contains(&row))
+    }
+
+    /*
+    First row of next hunk (after row). Wraps around.
+     */
+    pub fn next_hunk_row(&self, row: usize) -> Option<usize> {
+        self.hunks
+            .iter()
+            .find(|h| h.rows.start > row)
+            .or_else(|| self.hunks.first())
+            .map(|h| h.rows.start)
+    }
+
+    /*
+    First row of previous hunk (before the one containing row). Wraps around.
+     */
+    pub fn prev_hunk_row(&self, row: usize) -> Option<usize> {
+        self.hunks
+            .iter()
+            .rev()
+            .find(|h| h.rows.end <= row)
+            .or_else(|| self.hunks.last())
+            .map(|h| h.rows.start)
+    }
+}
+
+#[derive(Default)]
+struct Builder {
+    left: AlignedSide,
+    right: AlignedSide,
+    hunks: Vec<AlignedHunk>,
+    // chars pushed so far, per side
+    left_chars: usize,
+    right_chars: usize,
+}
+
+impl Builder {
+    fn push_row(&mut self, left: Option<(usize, &str)>, right: Option<(usize, &str)>, kind: DiffLineKind) {
+        let (left_kind, right_kind) = match (left.is_some(), right.is_some()) {
+            (true, false) => (DiffLineKind::Only, DiffLineKind::Filler),
+            (false, true) => (DiffLineKind::Filler, DiffLineKind::Only),
+            _ => (kind, kind),
+        };
+
+        if let (DiffLineKind::Changed, Some((_, left_line)), Some((_, right_line))) = (kind, left, right) {
+            // row will begin after separating newline
+            let separator = if self.left.kinds.is_empty() { 0 } else { 1 };
+            let left_offset = self.left_chars + separator;
+            let right_offset = self.right_chars + separator;
+
+            let (left_ranges, right_ranges) = changed_chars(displayed(left_line), displayed(right_line));
+            self.left
+                .changed_chars
+                .extend(left_ranges.into_iter().map(|r| r.start + left_offset..r.end + left_offset));
+            self.right
+                .changed_chars
+                .extend(right_ranges.into_iter().map(|r| r.start + right_offset..r.end + right_offset));
+        }
+
+        Self::push_side(&mut self.left, &mut self.left_chars, left, left_kind);
+        Self::push_side(&mut self.right, &mut self.right_chars, right, right_kind);
+    }
+
+    fn push_side(side: &mut AlignedSide, chars: &mut usize, line: Option<(usize, &str)>, kind: DiffLineKind) {
+        if !side.kinds.is_empty() {
+            side.text.push('\n');
+            *chars += 1;
+        }
+
+        if let Some((_, text)) = line {
+            let text = displayed(text);
+            side.text.push_str(text);
+            *chars += text.chars().count();
+        }
+
+        side.real_lines.push(line.map(|(idx, _)| idx));
+        side.kinds.push(kind);
+    }
+
+    fn push_hunk(&mut self, left_lines: &[&str], right_lines: &[&str], old: Range<usize>, new: Range<usize>) {
+        let rows_start = self.left.kinds.len();
+        let rows = old.len().max(new.len());
+
+        for i in 0..rows {
+            let left = if i < old.len() {
+                Some((old.start + i, left_lines[old.start + i]))
+            } else {
+                None
+            };
+            let right = if i < new.len() {
+                Some((new.start + i, right_lines[new.start + i]))
+            } else {
+                None
+            };
+            self.push_row(left, right, DiffLineKind::Changed);
+        }
+
+        self.hunks.push(AlignedHunk {
+            rows: rows_start..rows_start + rows,
+            left_lines: old,
+            right_lines: new,
+        });
+    }
+}
+
+/*
+Unlike str::lines, keeps '\r', so lines differing only in line ending show up as changed, and copied hunks
+keep their endings.
+ */
+fn split_lines(text: &str) -> Vec<&str> {
+    let mut lines: Vec<&str> = text.split('\n').collect();
+    // nothing after final newline
+    if lines.last() == Some(&"") {
+        lines.pop();
+    }
+    lines
+}
+
+/*
+Line as shown in view. '\r' is dropped, as buffer would treat it as another line break.
+ */
+fn displayed(line: &str) -> &str {
+    line.strip_suffix('\r').unwrap_or(line)
+}
+
+/*
+Word level diff of two lines, returns char ranges (relative to line begin) that differ.
+ */
+fn changed_chars(left: &str, right: &str) -> (Vec<Range<usize>>, Vec<Range<usize>>) {
+    let mut left_ranges: Vec<Range<usize>> = Vec::new();
+    let mut right_ranges: Vec<Range<usize>> = Vec::new();
+    let mut left_pos: usize = 0;
+    let mut right_pos: usize = 0;
+
+    fn push(ranges: &mut Vec<Range<usize>>, range: Range<usize>) {
+        match ranges.last_mut() {
+            Some(last) if last.end == range.start => last.end = range.end,
+            _ => ranges.push(range),
+        }
+    }
+
+    let diff = TextDiff::from_words(left, right);
+    for change in diff.iter_all_changes() {
+        let len = change.value().chars().count();
+        match change.tag() {
+            ChangeTag::Equal => {
+                left_pos += len;
+                right_pos += len;
+            }
+            ChangeTag::Delete => {
+                push(&mut left_ranges, left_pos..left_pos + len);
+                left_pos += len;
+            }
+            ChangeTag::Insert => {
+                push(&mut right_ranges, right_pos..right_pos + len);
+                right_pos += len;
+            }
+        }
+    }
+
+    (left_ranges, right_ranges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_alignment() {
+        let left = "a\nb\nc\nd\n";
+        let right = "a\nB\nB2\nc\n";
+        let diff = AlignedDiff::new(left, right);
+
+        assert_eq!(diff.left.text, "a\nb\n\nc\nd");
+        assert_eq!(diff.right.text, "a\nB\nB2\nc\n");
+        assert_eq!(diff.rows_count(), 5);
+
+        assert_eq!(
+            diff.left.kinds,
+            vec![
+                DiffLineKind::Equal,
+                DiffLineKind::Changed,
+                DiffLineKind::Filler,
+                DiffLineKind::Equal,
+                DiffLineKind::Only
+            ]
+        );
+        assert_eq!(diff.left.real_lines, vec![Some(0), Some(1), None, Some(2), Some(3)]);
+        assert_eq!(diff.right.real_lines, vec![Some(0), Some(1), Some(2), Some(3), None]);
+
+        assert_eq!(
+            diff.hunks,
+            vec![
+                AlignedHunk {
+                    rows: 1..3,
+                    left_lines: 1..2,
+                    right_lines: 1..3,
+                },
+                AlignedHunk {
+                    rows: 4..5,
+                    left_lines: 3..4,
+                    right_lines: 4..4,
+                },
+            ]
+        );
+
+        assert_eq!(diff.next_hunk_row(0), Some(1));
+        assert_eq!(diff.next_hunk_row(1), Some(4));
+        assert_eq!(diff.next_hunk_row(4), Some(1));
+        assert_eq!(diff.prev_hunk_row(4), Some(1));
+        assert_eq!(diff.prev_hunk_row(2), Some(4));
+
+        assert_eq!(diff.right.lines_text(1..3), "B\nB2\n");
+    }
+
+    #[test]
+    fn test_changed_chars() {
+        let diff = AlignedDiff::new("x\nlet a = 1;\n", "x\nlet b = 1;\n");
+
+        // "x\n" is 2 chars, "a" is 4 chars further
+        assert_eq!(diff.left.changed_chars, vec![6..7]);
+        assert_eq!(diff.right.changed_chars, vec![6..7]);
+    }
+
+    #[test]
+    fn test_line_endings() {
+        let diff = AlignedDiff::new("a\r\nb\r\n", "a\r\nb\n");
+
+        assert_eq!(diff.left.text, "a\nb");
+        assert_eq!(diff.right.text, "a\nb");
+        assert_eq!(diff.left.kinds, vec![DiffLineKind::Equal, DiffLineKind::Changed]);
+        assert_eq!(
+            diff.hunks,
+            vec![AlignedHunk {
+                rows: 1..2,
+                left_lines: 1..2,
+                right_lines: 1..2,
+            }]
+        );
+        assert_eq!(diff.left.lines_text(1..2), "b\r\n");
+    }
+}
@@ -0,0 +1,120 @@
+use log::error;
+
+use crate::*;
+
+/*
+What to compare, as requested from outside (context bar). MainView turns it into DiffSides.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DiffSource {
+    // saved file on the left, buffer on the right
+    BufferVsDisk { path: SPath },
+    // HEAD version on the left, buffer on the right
+    BufferVsHead { path: SPath },
+    // two files (opened as buffers, so copied hunks can be saved as usual)
+    Files { left: SPath, right: SPath },
+}
+
+pub enum DiffSide {
+    // live buffer, copying hunks into it edits the buffer
+    Buffer(BufferSharedRef),
+    // read-only text, like HEAD version
+    Snapshot(String),
+    // read-only saved version of buffer's file, re-read whenever buffer is saved (or reloaded)
+    Saved {
+        buffer: BufferSharedRef,
+        path: SPath,
+        text: String,
+        // buffer's disk stamp the text was read for
+        stamp: Option<FileStamp>,
+        // bumped on every re-read
+        version: u64,
+    },
+}
+
+impl DiffSide {
+    pub fn saved(buffer: BufferSharedRef, path: SPath) -> Result<Self, ReadError> {
+        let stamp = buffer.lock().and_then(|b| b.disk_stamp());
+        let (text, _) = path.read_entire_file_to_text()?;
+
+        Ok(DiffSide::Saved {
+            buffer,
+            path,
+            text,
+            stamp,
+            version: 0,
+        })
+    }
+
+    // None for snapshots, they never change
+    pub fn content_version(&self) -> Option<u64> {
+        match self {
+            DiffSide::Buffer(buffer) => buffer.lock().map(|b| b.content_version()),
+            DiffSide::Snapshot(_) => None,
+            DiffSide::Saved { version, .. } => Some(*version),
+        }
+    }
+
+    pub fn text(&self) -> Option<String> {
+        match self {
+            DiffSide::Buffer(buffer) => buffer.lock().map(|b| b.to_string()),
+            DiffSide::Snapshot(text) => Some(text.clone()),
+            DiffSide::Saved { text, .. } => Some(text.clone()),
+        }
+    }
+
+    /*
+    Re-reads saved file if buffer was written (or read) since. Keeps old text if that fails.
+     */
+    pub fn update(&mut self) {
+        if let DiffSide::Saved {
+            buffer,
+            path,
+            text,
+            stamp,
+            version,
+        } = self
+        {
+            let current_stamp = unpack_or_e!(buffer.lock().map(|b| b.disk_stamp()), (), "failed to lock buffer");
+            if current_stamp == *stamp {
+                return;
+            }
+
+            match path.read_entire_file_to_text() {
+                Ok((new_text, _)) => {
+                    *text = new_text;
+                    *stamp = current_stamp;
+                    *version += 1;
+                }
+                Err(e) => error!("failed to re-read {}: {:?}", path, e),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_saved_side_follows_saves() {
+        let fsf = MockFS::new("/tmp").with_file("file.txt", "abc").to_fsf();
+        let path = spath!(fsf, "file.txt").unwrap();
+
+        let mut buffer = BufferState::full(None, DocumentIdentifier::new_unique().with_file_path(path.clone())).with_text("abc");
+        buffer.set_disk_stamp(path.stamp().ok());
+        let buffer = BufferSharedRef::new_from_buffer(buffer);
+
+        let mut side = DiffSide::saved(buffer.clone(), path.clone()).unwrap();
+        side.update();
+        assert_eq!(side.content_version(), Some(0));
+
+        // as if buffer was saved
+        path.overwrite_with_str("xyz", true).unwrap();
+        buffer.lock_rw().unwrap().set_disk_stamp(path.stamp().ok());
+
+        side.update();
+        assert_eq!(side.content_version(), Some(1));
+        assert_eq!(side.text(), Some("xyz".to_string()));
+    }
+}
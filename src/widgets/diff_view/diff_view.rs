@@ -0,0 +1,522 @@
+use log::{debug, error, warn};
+
+use crate::*;
+
+/*
+Two read-only editors side by side. Their buffers are private, filled with "aligned" texts (changes
+padded with filler lines, so both sides have the same number of rows), recomputed whenever any side
+changes. Both sides scroll together, cursor row is shared too.
+ */
+pub struct DiffView {
+    wid: WID,
+    providers: Providers,
+    title: String,
+
+    left_title: TextWidget,
+    right_title: TextWidget,
+    left: WithScroll<EditorWidget>,
+    right: WithScroll<EditorWidget>,
+
+    left_side: DiffSide,
+    right_side: DiffSide,
+
+    diff: AlignedDiff,
+    // content versions of both sides the diff was computed for
+    computed_for: Option<(Option<u64>, Option<u64>)>,
+
+    display_state: Option<DisplayState<DiffView>>,
+}
+
+impl DiffView {
+    pub const TYPENAME: &'static str = "diff_view";
+    pub const MIN_SIZE: XY = XY::new(20, 3);
+
+    pub fn new(
+        providers: Providers,
+        left_title: String,
+        left_side: DiffSide,
+        right_title: String,
+        right_side: DiffSide,
+        lang_op: Option<LangId>,
+    ) -> Self {
+        let wid = get_new_widget_id();
+
+        // copying hunks is an edit like any other, so we need our own cursors in the buffers
+        for side in [&left_side, &right_side] {
+            if let DiffSide::Buffer(buffer) = side {
+                match buffer.lock_rw() {
+                    Some(mut buffer_lock) => {
+                        buffer_lock.initialize_for_widget(wid, None);
+                    }
+                    None => error!("failed to lock buffer to register diff view"),
+                }
+            }
+        }
+
+        let mut res = DiffView {
+            wid,
+            title: format!("{} vs {}", left_title, right_title),
+            left_title: TextWidget::new(Box::new(left_title)).with_size_policy(SizePolicy::MATCH_LAYOUTS_WIDTH),
+            right_title: TextWidget::new(Box::new(right_title)).with_size_policy(SizePolicy::MATCH_LAYOUTS_WIDTH),
+            left: Self::side_editor(&providers, lang_op),
+            right: Self::side_editor(&providers, lang_op),
+            providers,
+            left_side,
+            right_side,
+            diff: AlignedDiff::default(),
+            computed_for: None,
+            display_state: None,
+        };
+
+        res.refresh();
+        res
+    }
+
+    /*
+    Returns None (and logs why) if any of the sides can't be read.
+     */
+    pub fn from_source(providers: Providers, source: &DiffSource) -> Option<Self> {
+        let (left_title, left_side, right_title, right_side, lang_path) = match source {
+            DiffSource::BufferVsDisk { path } => {
+                let buffer = Self::open_buffer(&providers, path)?;
+                let saved_buffer = match &buffer {
+                    DiffSide::Buffer(buffer) => buffer.clone(),
+                    _ => return None,
+                };
+                let saved = match DiffSide::saved(saved_buffer, path.clone()) {
+                    Ok(saved) => saved,
+                    Err(e) => {
+                        error!("failed to read {}: {:?}", path, e);
+                        return None;
+                    }
+                };
+
+                (format!("{} (saved)", path), saved, format!("{} (buffer)", path), buffer, path)
+            }
+            DiffSource::BufferVsHead { path } => {
+                let git = unpack_or_e!(providers.git(), None, "no git, no HEAD");
+                let head = match git.head_version(path) {
+                    // untracked file is all new
+                    Ok(head) => head.unwrap_or_default(),
+                    Err(e) => {
+                        error!("failed to get HEAD version of {}: {}", path, e);
+                        return None;
+                    }
+                };
+                let buffer = Self::open_buffer(&providers, path)?;
+
+                (
+                    format!("{} (HEAD)", path),
+                    DiffSide::Snapshot(head),
+                    format!("{} (buffer)", path),
+                    buffer,
+                    path,
+                )
+            }
+            DiffSource::Files { left, right } => {
+                let left_buffer = Self::open_buffer(&providers, left)?;
+                let right_buffer = Self::open_buffer(&providers, right)?;
+
+                (left.to_string(), left_buffer, right.to_string(), right_buffer, right)
+            }
+        };
+
+        let lang_op = filename_to_language(lang_path);
+        Some(Self::new(providers, left_title, left_side, right_title, right_side, lang_op))
+    }
+
+    fn open_buffer(providers: &Providers, path: &SPath) -> Option<DiffSide> {
+        let mut register = unpack_or_e!(providers.buffer_register().try_write().ok(), None, "failed to lock register");
+        let OpenResult { buffer_shared_ref, opened } = register.open_file(providers, path);
+
+        let buffer = match buffer_shared_ref {
+            Ok(buffer) => buffer,
+            Err(e) => {
+                error!("failed to open {}: {:?}", path, e);
+                return None;
+            }
+        };
+
+        if opened {
+            if let Some(mut buffer_lock) = buffer.lock_rw() {
+                buffer_lock.set_lang(filename_to_language(path));
+            }
+        }
+
+        Some(DiffSide::Buffer(buffer))
+    }
+
+    fn side_editor(providers: &Providers, lang_op: Option<LangId>) -> WithScroll<EditorWidget> {
+        let mut buffer_state = BufferState::full(Some(providers.tree_sitter().clone()), DocumentIdentifier::new_unique());
+        buffer_state.set_lang(lang_op);
+
+        let editor = EditorWidget::new(providers.clone(), BufferSharedRef::new_from_buffer(buffer_state))
+            .with_readonly()
+            .with_extra_context_items(vec![
+                ContextBarItem::COPY_HUNK,
+                ContextBarItem::NEXT_DIFFERENCE,
+                ContextBarItem::PREV_DIFFERENCE,
+            ]);
+
+        WithScroll::new(ScrollDirection::Both, editor).with_first_line_no(|editor: &EditorWidget| editor.first_line())
+    }
+
+    pub fn get_title(&self) -> &str {
+        &self.title
+    }
+
+    pub fn diff(&self) -> &AlignedDiff {
+        &self.diff
+    }
+
+    pub fn is_left_focused(&self) -> bool {
+        self.get_focused().map(|w| w.id()) == Some(self.left.id())
+    }
+
+    fn cursor_row(editor: &EditorWidget) -> Option<usize> {
+        let buffer = editor.get_buffer().lock()?;
+        let cursor_set = buffer.cursors(editor.id())?;
+        buffer.char_to_line(cursor_set.supercursor().a)
+    }
+
+    fn set_cursor_row(editor: &mut EditorWidget, row: usize) -> bool {
+        let char_idx = {
+            let buffer = unpack_or_e!(editor.get_buffer().lock(), false, "failed to lock view buffer");
+            let last_row = buffer.len_lines().saturating_sub(1);
+            unpack_or_e!(buffer.line_to_char(row.min(last_row)), false, "no row {}", row)
+        };
+
+        editor.set_cursors(CursorSet::singleton(Cursor::new(char_idx)))
+    }
+
+    fn focused_row(&self) -> Option<usize> {
+        if self.is_left_focused() {
+            Self::cursor_row(self.left.internal())
+        } else {
+            Self::cursor_row(self.right.internal())
+        }
+    }
+
+    /*
+    Recomputes the diff if any of the sides changed.
+     */
+    fn refresh(&mut self) {
+        self.left_side.update();
+        self.right_side.update();
+
+        let versions = (self.left_side.content_version(), self.right_side.content_version());
+        if self.computed_for == Some(versions) {
+            return;
+        }
+
+        let left_text = unpack_or_e!(self.left_side.text(), (), "failed to read left side");
+        let right_text = unpack_or_e!(self.right_side.text(), (), "failed to read right side");
+
+        self.computed_for = Some(versions);
+        self.diff = AlignedDiff::new(&left_text, &right_text);
+        debug!("diff view: {} rows, {} hunks", self.diff.rows_count(), self.diff.hunks.len());
+
+        let git_theme = &self.providers.theme().ui.git;
+        Self::fill_editor(
+            self.left.internal_mut(),
+            &self.diff.left,
+            git_theme,
+            git_theme.diff_removed_background,
+        );
+        Self::fill_editor(
+            self.right.internal_mut(),
+            &self.diff.right,
+            git_theme,
+            git_theme.diff_added_background,
+        );
+    }
+
+    fn fill_editor(editor: &mut EditorWidget, side: &AlignedSide, git_theme: &GitTheme, only_here_background: Color) {
+        let row = Self::cursor_row(editor).unwrap_or(0);
+
+        {
+            let mut buffer = unpack_or_e!(editor.get_buffer().lock_rw(), (), "failed to lock view buffer");
+            // set_text drops cursors along with history
+            buffer.set_text(&side.text);
+            buffer.initialize_for_widget(editor.id(), None);
+        }
+        Self::set_cursor_row(editor, row);
+
+        let line_backgrounds: Vec<Option<Color>> = side
+            .kinds
+            .iter()
+            .map(|kind| match kind {
+                DiffLineKind::Equal => None,
+                DiffLineKind::Changed => Some(git_theme.diff_changed_background),
+                DiffLineKind::Only => Some(only_here_background),
+                DiffLineKind::Filler => Some(git_theme.diff_filler_background),
+            })
+            .collect();
+        let char_backgrounds = side
+            .changed_chars
+            .iter()
+            .map(|range| (range.clone(), git_theme.diff_word_background))
+            .collect();
+
+        editor.set_backgrounds(line_backgrounds, char_backgrounds);
+    }
+
+    fn jump_to_hunk(&mut self, forward: bool) {
+        let row = self.focused_row().unwrap_or(0);
+        let target = if forward {
+            self.diff.next_hunk_row(row)
+        } else {
+            self.diff.prev_hunk_row(row)
+        };
+        let target = unpack_or!(target, (), "no differences to jump to");
+
+        Self::set_cursor_row(self.left.internal_mut(), target);
+        Self::set_cursor_row(self.right.internal_mut(), target);
+    }
+
+    /*
+    Replaces lines of hunk under cursor on the "other" side with lines from the focused side. Works only
+    if the other side is a buffer.
+     */
+    fn copy_hunk(&mut self) -> bool {
+        let row = unpack_or!(self.focused_row(), false, "no cursor");
+        let hunk_idx = unpack_or!(self.diff.hunk_idx_at(row), false, "no difference under cursor");
+        let hunk = self.diff.hunks[hunk_idx].clone();
+
+        let (source, target, target_side, source_lines, target_lines) = if self.is_left_focused() {
+            (
+                &self.diff.left,
+                &self.diff.right,
+                &self.right_side,
+                hunk.left_lines,
+                hunk.right_lines,
+            )
+        } else {
+            (
+                &self.diff.right,
+                &self.diff.left,
+                &self.left_side,
+                hunk.right_lines,
+                hunk.left_lines,
+            )
+        };
+
+        let buffer = match target_side {
+            DiffSide::Buffer(buffer) => buffer.clone(),
+            DiffSide::Snapshot(_) | DiffSide::Saved { .. } => {
+                warn!("can't copy a difference into read-only side");
+                return false;
+            }
+        };
+        let mut buffer = unpack_or_e!(buffer.lock_rw(), false, "failed to lock target buffer");
+
+        let len_chars = buffer.len_chars();
+        let char_at_line = |line: usize| {
+            if line >= target.lines_count() {
+                len_chars
+            } else {
+                buffer.line_to_char(line).unwrap_or(len_chars)
+            }
+        };
+        let char_range = char_at_line(target_lines.start)..char_at_line(target_lines.end);
+
+        let mut with_what = source.lines_text(source_lines);
+        // last line without newline stays that way
+        let no_final_newline = len_chars > 0 && buffer.char_at(len_chars - 1) != Some('\n');
+        if char_range.end == len_chars && no_final_newline && with_what.ends_with('\n') {
+            with_what.pop();
+            if char_range.start == len_chars {
+                with_what.insert(0, '\n');
+            }
+        }
+
+        buffer.apply_cem(
+            CommonEditMsg::SubstituteBlock { char_range, with_what },
+            self.wid,
+            1,
+            Some(self.providers.clipboard()),
+        )
+    }
+}
+
+/*
+Buffers outlive the view, so it has to take back cursors it registered in them.
+ */
+impl Drop for DiffView {
+    fn drop(&mut self) {
+        for side in [&self.left_side, &self.right_side] {
+            if let DiffSide::Buffer(buffer) = side {
+                match buffer.lock_rw() {
+                    Some(mut buffer_lock) => {
+                        buffer_lock.remove_widget(self.wid);
+                    }
+                    None => error!("failed to lock buffer to unregister diff view"),
+                }
+            }
+        }
+    }
+}
+
+impl Widget for DiffView {
+    fn id(&self) -> WID {
+        self.wid
+    }
+
+    fn static_typename() -> &'static str
+    where
+        Self: Sized,
+    {
+        Self::TYPENAME
+    }
+
+    fn typename(&self) -> &'static str {
+        Self::TYPENAME
+    }
+
+    fn prelayout(&mut self) {
+        self.refresh();
+
+        // cursor row follows the focused side
+        if let Some(row) = self.focused_row() {
+            let left_focused = self.is_left_focused();
+            let other = if left_focused {
+                self.right.internal_mut()
+            } else {
+                self.left.internal_mut()
+            };
+            if Self::cursor_row(other) != Some(row) {
+                Self::set_cursor_row(other, row);
+            }
+        }
+
+        self.complex_prelayout();
+    }
+
+    fn size_policy(&self) -> SizePolicy {
+        SizePolicy::MATCH_LAYOUT
+    }
+
+    fn full_size(&self) -> XY {
+        Self::MIN_SIZE
+    }
+
+    fn layout(&mut self, screenspace: Screenspace) {
+        self.complex_layout(screenspace);
+
+        // scrolling follows the focused side, so both show the same rows
+        let left_focused = self.is_left_focused();
+        let (from, to) = if left_focused {
+            (&self.left, &mut self.right)
+        } else {
+            (&self.right, &mut self.left)
+        };
+        to.scroll_mut().offset.y = from.scroll().offset.y;
+    }
+
+    fn on_input(&self, input_event: InputEvent) -> Option<Box<dyn AnyMsg>> {
+        let editor_config = &self.providers.config().keyboard_config.editor;
+        let diff_config = &self.providers.config().keyboard_config.diff;
+
+        match input_event {
+            // titles are not worth focusing, so only sideways
+            InputEvent::FocusUpdate(focus_update)
+                if matches!(focus_update, FocusUpdate::Left | FocusUpdate::Right) && self.will_accept_focus_update(focus_update) =>
+            {
+                DiffViewMsg::FocusUpdateMsg(focus_update).someboxed()
+            }
+            InputEvent::KeyInput(key) if key == editor_config.next_hunk => DiffViewMsg::NextHunk.someboxed(),
+            InputEvent::KeyInput(key) if key == editor_config.prev_hunk => DiffViewMsg::PrevHunk.someboxed(),
+            InputEvent::KeyInput(key) if key == diff_config.copy_hunk => DiffViewMsg::CopyHunk.someboxed(),
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, msg: Box<dyn AnyMsg>) -> Option<Box<dyn AnyMsg>> {
+        let our_msg = match msg.as_msg::<DiffViewMsg>() {
+            Some(our_msg) => our_msg,
+            None => {
+                // context bar items addressed higher up
+                return Some(msg);
+            }
+        };
+
+        match our_msg {
+            DiffViewMsg::FocusUpdateMsg(focus_update) => {
+                if !self.update_focus(*focus_update) {
+                    warn!("failed to update focus");
+                }
+            }
+            DiffViewMsg::NextHunk => self.jump_to_hunk(true),
+            DiffViewMsg::PrevHunk => self.jump_to_hunk(false),
+            DiffViewMsg::CopyHunk => {
+                if self.copy_hunk() {
+                    self.refresh();
+                }
+            }
+        }
+
+        None
+    }
+
+    fn get_focused(&self) -> Option<&dyn Widget> {
+        self.complex_get_focused()
+    }
+
+    fn get_focused_mut(&mut self) -> Option<&mut dyn Widget> {
+        self.complex_get_focused_mut()
+    }
+
+    fn render(&self, theme: &Theme, focused: bool, output: &mut dyn Output) {
+        #[cfg(test)]
+        {
+            output.emit_metadata(Metadata {
+                id: self.wid,
+                typename: self.typename().to_string(),
+                rect: Rect::from_zero(output.size()),
+                focused,
+            });
+        }
+
+        self.complex_render(theme, focused, output)
+    }
+}
+
+impl ComplexWidget for DiffView {
+    fn get_layout(&self) -> Box<dyn Layout<Self>> {
+        let titles = SplitLayout::new(SplitDirection::Horizontal)
+            .with(
+                SplitRule::Proportional(1.0f32),
+                LeafLayout::new(subwidget!(Self.left_title)).boxed(),
+            )
+            .with(
+                SplitRule::Proportional(1.0f32),
+                LeafLayout::new(subwidget!(Self.right_title)).boxed(),
+            );
+
+        let sides = SplitLayout::new(SplitDirection::Horizontal)
+            .with(SplitRule::Proportional(1.0f32), LeafLayout::new(subwidget!(Self.left)).boxed())
+            .with(SplitRule::Proportional(1.0f32), LeafLayout::new(subwidget!(Self.right)).boxed());
+
+        SplitLayout::new(SplitDirection::Vertical)
+            .with(SplitRule::Fixed(1), titles.boxed())
+            .with(SplitRule::Proportional(1.0f32), sides.boxed())
+            .boxed()
+    }
+
+    // right side is the "new" one, usually the buffer being worked on
+    fn get_default_focused(&self) -> SubwidgetPointer<Self> {
+        subwidget!(Self.right)
+    }
+
+    fn set_display_state(&mut self, display_state: DisplayState<Self>) {
+        self.display_state = Some(display_state);
+    }
+
+    fn get_display_state_op(&self) -> Option<&DisplayState<Self>> {
+        self.display_state.as_ref()
+    }
+
+    fn get_display_state_mut_op(&mut self) -> Option<&mut DisplayState<Self>> {
+        self.display_state.as_mut()
+    }
+}
@@ -0,0 +1,15 @@
+/*
+Side-by-side diff of two texts. Left side is considered "old", right one "new".
+ */
+
+mod aligned_diff;
+pub use aligned_diff::{AlignedDiff, AlignedHunk, AlignedSide, DiffLineKind};
+
+mod diff_source;
+pub use diff_source::{DiffSide, DiffSource};
+
+mod diff_view;
+pub use diff_view::DiffView;
+
+mod msg;
+pub use msg::DiffViewMsg;
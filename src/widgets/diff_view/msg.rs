@@ -0,0 +1,13 @@
+use crate::*;
+
+#[derive(Debug)]
+pub enum DiffViewMsg {
+    FocusUpdateMsg(FocusUpdate),
+
+    NextHunk,
+    PrevHunk,
+    // copies hunk under cursor from focused side to the other one
+    CopyHunk,
+}
+
+impl AnyMsg for DiffViewMsg {}
@@ -0,0 +1,40 @@
+use crate::*;
+
+const CANCEL_STRING: &'static str = "Cancel";
+const OVERWRITE_STRING: &'static str = "Overwrite";
+const OK_STRING: &'static str = "Ok";
+
+/*
+Asked on save, when file was changed by someone else since it was read or written.
+ */
+pub fn changed_on_disk_dialog<T: Printable>(filename: T) -> GenericDialog {
+    let mut text = "File \n\"".to_string();
+    for grapheme in filename.graphemes() {
+        text += grapheme;
+    }
+
+    text += "\"\n was changed on disk since\n it was opened. Overwrite it?";
+
+    GenericDialog::new(Box::new(text))
+        .with_border(&SINGLE_BORDER_STYLE)
+        .with_fitted_frame()
+        .with_option(ButtonWidget::new(Box::new(CANCEL_STRING)).with_on_hit(|_| EditorViewMsg::CancelOverwrite.someboxed()))
+        .with_option(ButtonWidget::new(Box::new(OVERWRITE_STRING)).with_on_hit(|_| EditorViewMsg::ConfirmOverwrite.someboxed()))
+}
+
+/*
+Shown when writing file (or its backup) failed, buffer stays modified.
+ */
+pub fn save_failed_dialog<T: Printable>(filename: T, error: &WriteError) -> GenericDialog {
+    let mut text = "Failed to save\n\"".to_string();
+    for grapheme in filename.graphemes() {
+        text += grapheme;
+    }
+
+    text += &format!("\":\n {}", error);
+
+    GenericDialog::new(Box::new(text))
+        .with_border(&SINGLE_BORDER_STYLE)
+        .with_fitted_frame()
+        .with_option(ButtonWidget::new(Box::new(OK_STRING)).with_on_hit(|_| EditorViewMsg::DismissSaveError.someboxed()))
+}
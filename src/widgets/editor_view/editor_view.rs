@@ -13,6 +13,14 @@ enum EditorViewState {
     FindReplace,
 }
 
+enum EditorHover {
+    SaveFile(Box<SaveFileDialogWidget>),
+    // asks whether to overwrite file that was changed on disk since it was read
+    ChangedOnDisk(GenericDialog),
+    // tells why file could not be saved
+    SaveFailed(GenericDialog),
+}
+
 // TODO join paths of saving file and set navcomp then in one place
 
 pub struct EditorView {
@@ -26,9 +34,12 @@ pub struct EditorView {
     find_label: TextWidget,
     replace_box: EditBoxWidget,
     replace_label: TextWidget,
+    // large file mode, encoding and line endings, shown below editor only if there's anything unusual about them
+    status_label: TextWidget,
+    show_status: bool,
 
     state: EditorViewState,
-    hover_dialog: Option<SaveFileDialogWidget>,
+    hover_dialog: Option<EditorHover>,
 
     /*
     This represents "where the save as dialog should start", but only in case the file_front on buffer_state is None.
@@ -62,15 +73,24 @@ impl EditorView {
             .with_clipboard(providers.clipboard().clone())
             .with_size_policy(SizePolicy::MATCH_LAYOUT);
 
+        let mut editor = WithScroll::new(ScrollDirection::Both, editor)
+            .with_line_no()
+            .with_first_line_no(|editor: &EditorWidget| editor.first_line());
+        if providers.git().is_some() {
+            editor = editor.with_line_markers(|editor: &EditorWidget, theme, line_idx| editor.get_line_marker(theme, line_idx));
+        }
+
         EditorView {
             wid: get_new_widget_id(),
             providers,
             display_state: None,
-            editor: WithScroll::new(ScrollDirection::Both, editor).with_line_no(),
+            editor,
             find_box,
             find_label,
             replace_box,
             replace_label,
+            status_label: TextWidget::new(Box::new("")),
+            show_status: false,
             state: EditorViewState::Simple,
             hover_dialog: None,
             start_path: None,
@@ -132,14 +152,58 @@ impl EditorView {
     /*
     This attempts to save current file, but in case that's not possible (filename unknown) proceeds to open_save_as_dialog() below
      */
-    fn save_or_save_as(&mut self, buffer: &BufferState) {
-        if let Some(ff) = buffer.get_path() {
-            ff.overwrite_with_stream(&mut buffer.streaming_iterator(), false);
+    fn save_or_save_as(&mut self, buffer: &mut BufferState) {
+        if let Some(ff) = buffer.get_path().cloned() {
+            if changed_on_disk(buffer).is_some() {
+                self.open_changed_on_disk_dialog_and_focus(&ff);
+            } else {
+                self.save(buffer, &ff);
+            }
         } else {
             self.open_save_as_dialog_and_focus(buffer)
         }
     }
 
+    /*
+    Backs up previous version of file (if configured), then writes buffer to it. Nothing is written if
+    backup fails. Failure is shown in a dialog.
+     */
+    fn write_buffer(&mut self, buffer: &mut BufferState, ff: &SPath) -> bool {
+        if buffer.clean_up_for_save() {
+            debug!("cleaned up {} before save", ff);
+            self.editor.internal().after_content_changed(buffer);
+        }
+
+        match ff
+            .backup(&self.providers.config().global.backup)
+            .and_then(|_| buffer.write_to_file(ff))
+        {
+            Ok(_) => true,
+            Err(e) => {
+                error!("failed to save {}: {}", ff, e);
+                self.hover_dialog = Some(EditorHover::SaveFailed(save_failed_dialog(ff.label().to_string(), &e)));
+                self.set_focused(self.get_hover_subwidget());
+                false
+            }
+        }
+    }
+
+    fn save(&mut self, buffer: &mut BufferState, ff: &SPath) {
+        if self.write_buffer(buffer, ff) {
+            buffer.set_disk_stamp(ff.stamp().ok());
+            buffer.mark_saved();
+            save_undo_history(&self.providers, ff, buffer);
+            // someone might have committed in the meantime, so HEAD version is re-read
+            self.editor.internal_mut().refresh_git_base(buffer);
+            self.providers.refresh_git_status();
+        }
+    }
+
+    fn open_changed_on_disk_dialog_and_focus(&mut self, ff: &SPath) {
+        self.hover_dialog = Some(EditorHover::ChangedOnDisk(changed_on_disk_dialog(ff.label().to_string())));
+        self.set_focused(self.get_hover_subwidget());
+    }
+
     fn open_save_as_dialog_and_focus(&mut self, buffer: &BufferState) {
         match self.state {
             EditorViewState::Simple => {}
@@ -153,13 +217,18 @@ impl EditorView {
             .with_on_save(|_, ff| EditorViewMsg::OnSaveAsHit { ff }.someboxed())
             .with_path(self.get_save_file_dialog_path(buffer));
 
-        self.hover_dialog = Some(save_file_dialog);
+        self.hover_dialog = Some(EditorHover::SaveFile(Box::new(save_file_dialog)));
         self.set_focused(self.get_hover_subwidget());
     }
 
     fn after_positive_save(&mut self, buffer_mut: &mut BufferState, path: &SPath) -> Option<MainViewMsg> {
         // setting the file path
         let set_path_result = self.set_file_name(buffer_mut, path);
+        buffer_mut.set_disk_stamp(path.stamp().ok());
+        buffer_mut.mark_saved();
+        save_undo_history(&self.providers, path, buffer_mut);
+        self.editor.internal_mut().refresh_git_base(buffer_mut);
+        self.providers.refresh_git_status();
 
         if set_path_result.path_changed {
             // updating the "save as dialog" starting position
@@ -193,11 +262,15 @@ impl EditorView {
 
     fn get_hover_subwidget(&self) -> SubwidgetPointer<Self> {
         SubwidgetPointer::new(
-            Box::new(|w: &Self| {
-                w.hover_dialog.as_ref().unwrap() // TODO
+            Box::new(|w: &Self| match w.hover_dialog.as_ref().unwrap() {
+                EditorHover::SaveFile(dialog) => dialog.as_ref() as &dyn Widget,
+                EditorHover::ChangedOnDisk(dialog) => dialog as &dyn Widget,
+                EditorHover::SaveFailed(dialog) => dialog as &dyn Widget,
             }),
-            Box::new(|w: &mut Self| {
-                w.hover_dialog.as_mut().unwrap() // TODO
+            Box::new(|w: &mut Self| match w.hover_dialog.as_mut().unwrap() {
+                EditorHover::SaveFile(dialog) => dialog.as_mut() as &mut dyn Widget,
+                EditorHover::ChangedOnDisk(dialog) => dialog as &mut dyn Widget,
+                EditorHover::SaveFailed(dialog) => dialog as &mut dyn Widget,
             }),
         )
     }
@@ -265,6 +338,21 @@ impl EditorView {
     pub fn get_internal_widget(&self) -> &EditorWidget {
         self.editor.internal()
     }
+
+    pub fn get_internal_widget_mut(&mut self) -> &mut EditorWidget {
+        self.editor.internal_mut()
+    }
+
+    pub fn scroll_offset(&self) -> XY {
+        self.editor.scroll().offset
+    }
+
+    /*
+    Next layout still moves it, if cursor would end up outside of view.
+     */
+    pub fn set_scroll_offset(&mut self, offset: XY) {
+        self.editor.scroll_mut().offset = offset;
+    }
 }
 
 impl Widget for EditorView {
@@ -327,7 +415,7 @@ impl Widget for EditorView {
                 if let Some(mut buffer_lock) = self.editor.internal_mut().get_buffer().clone().lock_rw() {
                     match msg {
                         EditorViewMsg::Save => {
-                            self.save_or_save_as(&buffer_lock);
+                            self.save_or_save_as(&mut buffer_lock);
                             None
                         }
                         EditorViewMsg::SaveAs => {
@@ -340,16 +428,35 @@ impl Widget for EditorView {
                             None
                         }
                         EditorViewMsg::OnSaveAsHit { ff } => {
-                            // TODO handle errors and add test that
                             // TODO add test that checks if effects of after_positive_save are achieved
-                            if ff.overwrite_with_stream(&mut buffer_lock.streaming_iterator(), false).is_ok() {
+                            self.hover_dialog = None;
+                            self.set_focused(subwidget!(Self.editor));
+
+                            // on failure, this opens save_failed_dialog
+                            if self.write_buffer(&mut buffer_lock, ff) {
                                 self.after_positive_save(&mut buffer_lock, ff);
                             }
-
+                            None
+                        }
+                        EditorViewMsg::CancelOverwrite => {
                             self.hover_dialog = None;
                             self.set_focused(subwidget!(Self.editor));
                             None
                         }
+                        EditorViewMsg::DismissSaveError => {
+                            self.hover_dialog = None;
+                            self.set_focused(subwidget!(Self.editor));
+                            None
+                        }
+                        EditorViewMsg::ConfirmOverwrite => {
+                            self.hover_dialog = None;
+                            self.set_focused(subwidget!(Self.editor));
+                            match buffer_lock.get_path().cloned() {
+                                Some(ff) => self.save(&mut buffer_lock, &ff),
+                                None => error!("confirmed overwrite of buffer without a file"),
+                            }
+                            None
+                        }
                         EditorViewMsg::FocusUpdateMsg(focus_update) => {
                             // warn!("updating focus");
                             self.update_focus(*focus_update);
@@ -431,7 +538,13 @@ impl Widget for EditorView {
 
 impl ComplexWidget for EditorView {
     fn get_layout(&self) -> Box<dyn Layout<Self>> {
-        let editor_layout = LeafLayout::new(subwidget!(Self.editor)).boxed();
+        let mut editor_layout = LeafLayout::new(subwidget!(Self.editor)).boxed();
+        if self.show_status {
+            editor_layout = SplitLayout::new(SplitDirection::Vertical)
+                .with(SplitRule::Proportional(1.0), editor_layout)
+                .with(SplitRule::Fixed(1), LeafLayout::new(subwidget!(Self.status_label)).boxed())
+                .boxed();
+        }
         let find_text_layout = LeafLayout::new(subwidget!(Self.find_label)).boxed();
         let find_box_layout = LeafLayout::new(subwidget!(Self.find_box)).boxed();
         let find_layout = SplitLayout::new(SplitDirection::Horizontal)
@@ -463,16 +576,30 @@ impl ComplexWidget for EditorView {
         if self.hover_dialog.is_none() {
             background
         } else {
-            let hover = LeafLayout::new(SubwidgetPointer::new(
-                Box::new(|s: &Self| s.hover_dialog.as_ref().unwrap()),
-                Box::new(|s: &mut Self| s.hover_dialog.as_mut().unwrap()),
-            ))
-            .boxed();
+            let hover = LeafLayout::new(self.get_hover_subwidget()).boxed();
 
             HoverLayout::new(background, hover, Box::new(Self::get_hover_rect), true).boxed()
         }
     }
 
+    fn internal_prelayout(&mut self) {
+        let parts_op = self.get_buffer_ref().lock().map(|buffer| {
+            let mut parts: Vec<String> = Vec::new();
+            if buffer.is_large_file() {
+                parts.push("large file".to_string());
+            }
+            if !buffer.file_format().is_default() {
+                parts.push(buffer.file_format().to_string());
+            }
+            parts
+        });
+
+        if let Some(parts) = parts_op {
+            self.show_status = !parts.is_empty();
+            self.status_label.set_text(Box::new(parts.join(", ")));
+        }
+    }
+
     fn get_default_focused(&self) -> SubwidgetPointer<EditorView> {
         subwidget!(Self.editor)
     }
@@ -1,3 +1,6 @@
+mod dialogs;
+pub use dialogs::{changed_on_disk_dialog, save_failed_dialog};
+
 mod editor_view;
 pub use editor_view::EditorView;
 
@@ -6,6 +6,11 @@ pub enum EditorViewMsg {
     SaveAs,
     OnSaveAsCancel,
     OnSaveAsHit { ff: SPath },
+    // answers to changed_on_disk_dialog
+    CancelOverwrite,
+    ConfirmOverwrite,
+    // closes save_failed_dialog
+    DismissSaveError,
 
     FocusUpdateMsg(FocusUpdate),
 
@@ -1,5 +1,5 @@
 use std::borrow::Cow;
-use std::fmt::Debug;
+use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
 
 use crate::*;
@@ -44,6 +44,25 @@ use crate::*;
 
 pub type Action = fn() -> Box<dyn AnyMsg>;
 
+/*
+Static actions are enough for "fixed" options, but some items (like user defined tasks) need to carry
+data, hence the dynamic variant.
+ */
+#[derive(Clone)]
+pub enum ContextBarAction {
+    Static(Action),
+    Dynamic(Rc<dyn Fn() -> Box<dyn AnyMsg>>),
+}
+
+impl Debug for ContextBarAction {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ContextBarAction::Static(_) => write!(f, "ContextBarAction::Static"),
+            ContextBarAction::Dynamic(_) => write!(f, "ContextBarAction::Dynamic"),
+        }
+    }
+}
+
 /*
 TODO
  I am not sure how this struct should look like inside, I just know how I want it to look in UI.
@@ -52,26 +71,138 @@ TODO
 #[derive(Debug, Clone)]
 pub struct ContextBarItem {
     title: Cow<'static, str>,
-    action: Action,
+    action: ContextBarAction,
 }
 
 impl ContextBarItem {
     pub const GO_TO_DEFINITION: ContextBarItem = ContextBarItem {
         title: Cow::Borrowed("go to definition"),
-        action: || EditorWidgetMsg::GoToDefinition.boxed(),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::GoToDefinition.boxed()),
     };
     pub const REFORMAT_FILE: ContextBarItem = ContextBarItem {
         title: Cow::Borrowed("reformat file"),
-        action: || EditorWidgetMsg::Reformat.boxed(),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::Reformat.boxed()),
     };
     pub const SHOW_USAGES: ContextBarItem = ContextBarItem {
         title: Cow::Borrowed("show usages"),
-        action: || EditorWidgetMsg::ShowUsages.boxed(),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::ShowUsages.boxed()),
+    };
+    pub const NEXT_CHANGE: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("next change"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::NextHunk.boxed()),
+    };
+    pub const PREV_CHANGE: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("previous change"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::PrevHunk.boxed()),
+    };
+    pub const REVERT_CHANGE: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("revert change"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::RevertHunk.boxed()),
+    };
+    pub const TOGGLE_BLAME: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("toggle blame"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::ToggleBlame.boxed()),
+    };
+    pub const JUMP_TO_MATCHING_BRACKET: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("jump to matching bracket"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::JumpToMatchingBracket.boxed()),
+    };
+    pub const ADD_NEXT_OCCURRENCE: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("add cursor at next occurrence"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::AddNextOccurrence { skip: false }.boxed()),
+    };
+    pub const SELECT_ALL_OCCURRENCES: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("select all occurrences"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::SelectAllOccurrences.boxed()),
+    };
+    pub const TOGGLE_COMMENT: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("toggle comment"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::EditMsg(CommonEditMsg::ToggleLineComment).boxed()),
+    };
+    pub const TOGGLE_BLOCK_COMMENT: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("toggle block comment"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::EditMsg(CommonEditMsg::ToggleBlockComment).boxed()),
+    };
+    pub const SORT_LINES: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("sort lines"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::EditMsg(CommonEditMsg::SortLines).boxed()),
+    };
+    pub const UNIQUE_LINES: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("remove duplicate lines"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::EditMsg(CommonEditMsg::UniqueLines).boxed()),
+    };
+    pub const REVERSE_LINES: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("reverse lines"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::EditMsg(CommonEditMsg::ReverseLines).boxed()),
+    };
+    pub const TAKE_OURS: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("take ours"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::ResolveConflict(ConflictResolution::Ours).boxed()),
+    };
+    pub const TAKE_THEIRS: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("take theirs"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::ResolveConflict(ConflictResolution::Theirs).boxed()),
+    };
+    pub const TAKE_BOTH: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("take both"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::ResolveConflict(ConflictResolution::Both).boxed()),
+    };
+    pub const NEXT_CONFLICT: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("next conflict"),
+        action: ContextBarAction::Static(|| EditorWidgetMsg::NextConflict.boxed()),
+    };
+    pub const COPY_HUNK: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("copy difference to other side"),
+        action: ContextBarAction::Static(|| DiffViewMsg::CopyHunk.boxed()),
+    };
+    pub const NEXT_DIFFERENCE: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("next difference"),
+        action: ContextBarAction::Static(|| DiffViewMsg::NextHunk.boxed()),
+    };
+    pub const PREV_DIFFERENCE: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("previous difference"),
+        action: ContextBarAction::Static(|| DiffViewMsg::PrevHunk.boxed()),
+    };
+    pub const FIND_CHANGED_FILE: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("find changed file"),
+        action: ContextBarAction::Static(|| {
+            MainViewMsg::OpenFuzzyFilesFiltered {
+                filter: GitFileFilter::ChangedOnly,
+            }
+            .boxed()
+        }),
+    };
+    pub const FIND_TRACKED_FILE: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("find tracked file"),
+        action: ContextBarAction::Static(|| {
+            MainViewMsg::OpenFuzzyFilesFiltered {
+                filter: GitFileFilter::TrackedOnly,
+            }
+            .boxed()
+        }),
+    };
+    pub const OPEN_TERMINAL: ContextBarItem = ContextBarItem {
+        title: Cow::Borrowed("open terminal"),
+        action: ContextBarAction::Static(|| MainViewMsg::OpenTerminal.boxed()),
     };
     // TODO add reformat selection
 
+    pub fn new_dynamic<F: Fn() -> Box<dyn AnyMsg> + 'static>(title: String, action: F) -> Self {
+        ContextBarItem {
+            title: Cow::Owned(title),
+            action: ContextBarAction::Dynamic(Rc::new(action)),
+        }
+    }
+
+    pub fn title(&self) -> &str {
+        &self.title
+    }
+
     pub fn msg(&self) -> Box<dyn AnyMsg> {
-        (self.action)()
+        match &self.action {
+            ContextBarAction::Static(action) => action(),
+            ContextBarAction::Dynamic(action) => action(),
+        }
     }
 }
 
@@ -2,7 +2,7 @@
 // widgets (escalating). But bottom of tree is Editor, so this is where I start.
 
 mod context_bar_item;
-pub use context_bar_item::ContextBarItem;
+pub use context_bar_item::{ContextBarAction, ContextBarItem};
 
 mod msg;
 pub use msg::ContextBarWidgetMsg;
@@ -5,6 +5,9 @@ use crate::*;
 pub struct ContextBarWidget {
     id: WID,
     list: ListWidget<ContextBarItem>,
+    // editor's hover by default
+    on_close: WidgetAction<Self>,
+    size_policy: SizePolicy,
 
     display_state: Option<DisplayState<Self>>,
 
@@ -23,11 +26,21 @@ impl ContextBarWidget {
                 .with_show_column_names(false)
                 .with_size_policy(SizePolicy::MATCH_LAYOUTS_WIDTH)
                 .with_on_hit(|_| ContextBarWidgetMsg::Hit.someboxed()),
+            on_close: |_| EditorWidgetMsg::HoverClose.someboxed(),
+            size_policy: SizePolicy::SELF_DETERMINED,
             display_state: None,
             query: BufferState::simplified_single_line(),
         }
     }
 
+    pub fn with_on_close(self, on_close: WidgetAction<Self>) -> Self {
+        Self { on_close, ..self }
+    }
+
+    pub fn with_size_policy(self, size_policy: SizePolicy) -> Self {
+        Self { size_policy, ..self }
+    }
+
     fn on_query_change(&mut self) {
         let query_str = self.query.to_string();
         if query_str.is_empty() {
@@ -57,6 +70,10 @@ impl Widget for ContextBarWidget {
         self.complex_prelayout();
     }
 
+    fn size_policy(&self) -> SizePolicy {
+        self.size_policy
+    }
+
     fn full_size(&self) -> XY {
         XY::new(1, 12) // TODO completely arbitrary
     }
@@ -88,6 +105,7 @@ impl Widget for ContextBarWidget {
                     CommonEditMsg::Delete => true,
                     CommonEditMsg::Copy => true,
                     CommonEditMsg::Paste => true,
+                    CommonEditMsg::PasteBlock => true,
                     CommonEditMsg::Undo => true,
                     CommonEditMsg::Redo => true,
                     CommonEditMsg::DeleteBlock { .. } => true,
@@ -95,6 +113,19 @@ impl Widget for ContextBarWidget {
                     CommonEditMsg::SubstituteBlock { .. } => true,
                     CommonEditMsg::Tab => true,
                     CommonEditMsg::ShiftTab => true,
+                    CommonEditMsg::ToggleLineComment => true,
+                    CommonEditMsg::ToggleBlockComment => true,
+                    CommonEditMsg::MoveLinesUp => true,
+                    CommonEditMsg::MoveLinesDown => true,
+                    CommonEditMsg::DuplicateLines => true,
+                    CommonEditMsg::DeleteLines => true,
+                    CommonEditMsg::JoinLines => true,
+                    CommonEditMsg::InsertLineAbove => true,
+                    CommonEditMsg::InsertLineBelow => true,
+                    CommonEditMsg::SortLines => true,
+                    CommonEditMsg::UniqueLines => true,
+                    CommonEditMsg::ReverseLines => true,
+                    CommonEditMsg::Transform(_) => true,
                 };
 
                 if !ignore {
@@ -118,7 +149,7 @@ impl Widget for ContextBarWidget {
                 None
             }
             Some(msg) => match msg {
-                ContextBarWidgetMsg::Close => EditorWidgetMsg::HoverClose.someboxed(),
+                ContextBarWidgetMsg::Close => (self.on_close)(self),
                 ContextBarWidgetMsg::Edit(cem) => {
                     if self.query.apply_cem(cem.clone(), self.id, 1, None) {
                         self.on_query_change();
@@ -52,3 +52,25 @@ pub fn get_context_options(
 
     results
 }
+
+/*
+Options that don't depend on what's focused: git file search, terminal and user defined tasks. Editor
+adds them to its own, and MainView shows them alone when no editor is there to ask.
+ */
+pub fn get_global_context_options(providers: &Providers) -> Vec<ContextBarItem> {
+    let mut results: Vec<ContextBarItem> = Vec::new();
+
+    if providers.git().is_some() {
+        results.push(ContextBarItem::FIND_CHANGED_FILE);
+        results.push(ContextBarItem::FIND_TRACKED_FILE);
+    }
+    results.push(ContextBarItem::OPEN_TERMINAL);
+    for task in providers.workspace_settings().tasks.iter() {
+        let name = task.name.clone();
+        results.push(ContextBarItem::new_dynamic(format!("run task: {}", task.name), move || {
+            MainViewMsg::RunTask { name: name.clone() }.boxed()
+        }));
+    }
+
+    results
+}
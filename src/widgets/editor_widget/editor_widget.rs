@@ -1,5 +1,6 @@
 use std::cmp::{max, min};
 use std::collections::BTreeMap;
+use std::ops::Range;
 use std::time::Duration;
 
 use log::{debug, error, warn};
@@ -16,6 +17,7 @@ const NEWLINE: &'static str = "⏎";
 const BEYOND: &'static str = "⇱";
 
 const DEFAULT_EDITOR_TIMEOUT: Duration = Duration::from_millis(500);
+const FORMATTER_TIMEOUT: Duration = Duration::from_secs(10);
 
 /*
 This is heart and soul of Gladius Editor.
@@ -118,6 +120,8 @@ pub struct EditorWidget {
     buffer: BufferSharedRef,
 
     kite: XY,
+    // line of buffer drawn at y = 0. Longer texts are shown LINE_WINDOW lines at a time, window follows cursors.
+    first_line: usize,
 
     // navcomp is to submit edit messages, suggestion display will probably be somewhere else
     navcomp: Option<NavCompRef>,
@@ -129,6 +133,25 @@ pub struct EditorWidget {
     // This is completion or navigation
     // Settings are calculated based on last_size, and entire hover will be discarded on resize.
     requested_hover: Option<(HoverSettings, EditorHover)>,
+
+    // markers of what changed since HEAD, drawn by WithScroll in line number margin
+    git_gutter: GitGutter,
+    // git blame labels, None when blame is toggled off
+    blame_op: Option<BlameAnnotations>,
+    // regions between <<<<<<< and >>>>>>> markers
+    merge_conflicts: MergeConflicts,
+
+    // extra backgrounds, used by diff view. Whole lines (by idx) first, then char ranges (sorted) on top.
+    line_backgrounds: Vec<Option<Color>>,
+    char_backgrounds: Vec<(Range<usize>, Color)>,
+    // items added to context bar by whoever owns the editor
+    extra_context_items: Vec<ContextBarItem>,
+    // column selection being spanned with column_select keys, (anchor, head). Any other message ends it.
+    column_selection: Option<(BlockCorner, BlockCorner)>,
+    // what was last copied from column selection, so pasting it back keeps the rectangular shape
+    block_clipboard: Option<String>,
+    // external formatter running in background, with the text it was given
+    formatter_run_op: Option<(FormatterRunRef, String)>,
     // These are label providers. Their order is important.
     // todo_lable_providers: Vec<LabelsProviderRef>, // moved to providers
 }
@@ -136,6 +159,10 @@ pub struct EditorWidget {
 impl EditorWidget {
     pub const TYPENAME: &'static str = "editor_widget";
 
+    // Widgets address lines with u16, and scroll adds its offset to positions within the widget, so
+    // only half of that range is shown at once.
+    pub const LINE_WINDOW: usize = (u16::MAX / 2) as usize;
+
     const MAX_HOVER_WIDTH: u16 = 45;
     const MIN_HOVER_WIDTH: u16 = 15;
 
@@ -154,9 +181,19 @@ impl EditorWidget {
             last_hover_rect: None,
             buffer: buffer.clone(),
             kite: XY::ZERO,
+            first_line: 0,
             state: EditorState::Editing,
             navcomp: None,
             requested_hover: None,
+            git_gutter: GitGutter::new(),
+            blame_op: None,
+            merge_conflicts: MergeConflicts::new(),
+            line_backgrounds: Vec::new(),
+            char_backgrounds: Vec::new(),
+            extra_context_items: Vec::new(),
+            column_selection: None,
+            block_clipboard: None,
+            formatter_run_op: None,
         };
 
         if buffer_named {
@@ -186,6 +223,19 @@ impl EditorWidget {
         }
     }
 
+    pub fn with_extra_context_items(self, extra_context_items: Vec<ContextBarItem>) -> Self {
+        Self {
+            extra_context_items,
+            ..self
+        }
+    }
+
+    pub fn set_backgrounds(&mut self, line_backgrounds: Vec<Option<Color>>, char_backgrounds: Vec<(Range<usize>, Color)>) {
+        debug_assert!(char_backgrounds.windows(2).all(|w| w[0].0.end <= w[1].0.start));
+        self.line_backgrounds = line_backgrounds;
+        self.char_backgrounds = char_backgrounds;
+    }
+
     fn after_path_change(&mut self) {
         self.update_navcomp();
     }
@@ -194,9 +244,274 @@ impl EditorWidget {
         &self.buffer
     }
 
+    pub fn first_line(&self) -> usize {
+        self.first_line
+    }
+
+    fn lines_shown(&self) -> Range<usize> {
+        self.first_line..self.first_line + Self::LINE_WINDOW
+    }
+
+    /*
+    Moves window of lines shown, keeping kite at the same line of buffer (if it's still within window).
+     */
+    fn set_first_line(&mut self, first_line: usize) {
+        let kite_line = self.first_line + self.kite.y as usize;
+        self.kite.y = min(kite_line.saturating_sub(first_line), Self::LINE_WINDOW - 1) as u16;
+        self.first_line = first_line;
+    }
+
+    /*
+    Moves window of lines shown so it contains the cursor line in direction of last move, centered on it.
+     */
+    fn update_first_line(&mut self, buffer: &BufferState, cursor_set: &CursorSet, last_move_direction: Arrow) {
+        let lines = cursor_set.iter().filter_map(|c| buffer.char_to_line(c.a));
+        let line = match last_move_direction {
+            Arrow::Up | Arrow::Left => lines.min(),
+            Arrow::Down | Arrow::Right => lines.max(),
+        };
+        let line = unpack_or!(line, ());
+
+        if !self.lines_shown().contains(&line) {
+            let last_first_line = buffer.len_lines().saturating_sub(Self::LINE_WINDOW);
+            self.set_first_line(min(line.saturating_sub(Self::LINE_WINDOW / 2), last_first_line));
+        }
+    }
+
+    pub fn get_line_marker(&self, theme: &Theme, line_idx: usize) -> Option<LineMarker> {
+        match self.git_gutter.hunk_kind_at(line_idx)? {
+            HunkKind::Added => Some(LineMarker {
+                text: "▎",
+                foreground: theme.ui.git.added,
+            }),
+            HunkKind::Modified => Some(LineMarker {
+                text: "▎",
+                foreground: theme.ui.git.modified,
+            }),
+            HunkKind::Deleted => Some(LineMarker {
+                text: "▔",
+                foreground: theme.ui.git.deleted,
+            }),
+        }
+    }
+
+    /*
+    To be called after save (or anything else that could have moved HEAD), so HEAD version is re-read.
+    Takes the buffer, because it's called while caller still holds the lock.
+     */
+    pub fn refresh_git_base(&mut self, buffer: &BufferState) {
+        self.git_gutter.invalidate_base();
+
+        // saved lines are now "not committed yet" instead of "whatever was there before"
+        if self.blame_op.is_some() {
+            self.blame_op = None;
+            self.toggle_blame(buffer);
+        }
+    }
+
+    pub fn is_blame_on(&self) -> bool {
+        self.blame_op.is_some()
+    }
+
+    fn toggle_blame(&mut self, buffer: &BufferState) {
+        if self.blame_op.take().is_some() {
+            return;
+        }
+
+        if buffer.is_large_file() {
+            debug!("not blaming large file {:?}", buffer.get_path());
+            return;
+        }
+
+        let git = unpack_or!(self.providers.git(), (), "no git, no blame");
+        let path = unpack_or!(buffer.get_path(), (), "can't blame a buffer without a file");
+
+        match BlameAnnotations::load(git, path) {
+            Ok(mut blame) => {
+                blame.update(buffer);
+                self.blame_op = Some(blame);
+            }
+            Err(e) => {
+                error!("failed to blame {}: {}", path, e);
+            }
+        }
+    }
+
+    fn cursor_line(&self, buffer: &BufferState) -> Option<usize> {
+        let cursor_set = buffer.cursors(self.wid)?;
+        buffer.char_to_line(cursor_set.supercursor().a)
+    }
+
+    // whether any selection covers more than one line, so there is something to sort
+    fn selection_spans_lines(&self, buffer: &BufferState) -> bool {
+        let cursor_set = unpack_or!(buffer.cursors(self.wid), false);
+        cursor_set.iter().any(|c| {
+            c.s.map(|sel| buffer.char_to_line(sel.b) != buffer.char_to_line(sel.e.saturating_sub(1)))
+                .unwrap_or(false)
+        })
+    }
+
+    fn jump_to_hunk(&mut self, buffer: &mut BufferState, forward: bool) {
+        let line_idx = unpack_or!(self.cursor_line(buffer), (), "no cursor line");
+        let target_line = if forward {
+            self.git_gutter.next_hunk_line(line_idx)
+        } else {
+            self.git_gutter.prev_hunk_line(line_idx)
+        };
+        let target_line = unpack_or!(target_line, (), "no hunks to jump to");
+        self.jump_to_line(buffer, line_idx, target_line);
+    }
+
+    fn jump_to_conflict(&mut self, buffer: &mut BufferState) {
+        let line_idx = unpack_or!(self.cursor_line(buffer), (), "no cursor line");
+        let target_line = unpack_or!(self.merge_conflicts.next_conflict_line(line_idx), (), "no conflicts to jump to");
+        self.jump_to_line(buffer, line_idx, target_line);
+    }
+
+    /*
+    Bracket next to the only cursor and the one it pairs with, see bracket_pairs.
+     */
+    fn matching_bracket_pair(&self, buffer: &BufferState) -> Option<(usize, usize)> {
+        let cursor = buffer.cursors(self.wid)?.as_single().filter(|cursor| cursor.is_simple())?;
+        matching_bracket_pair(buffer, cursor.a)
+    }
+
+    /*
+    Moves head of column selection by arrow and selects the block, starting one at the supercursor if
+    there is none.
+     */
+    fn column_select(&mut self, buffer: &mut BufferState, column_selection: Option<(BlockCorner, BlockCorner)>, arrow: Arrow) {
+        let (anchor, head) = match column_selection {
+            Some(corners) => corners,
+            None => {
+                let caret = unpack_or!(buffer.cursors(self.wid), (), "no cursors").supercursor().a;
+                let line_idx = unpack_or!(buffer.char_to_line(caret), (), "cursor {} beyond buffer", caret);
+                let column = unpack_or!(char_to_display_column(&*buffer, caret), (), "no column of cursor {}", caret);
+                let corner = BlockCorner::new(column, line_idx);
+                (corner, corner)
+            }
+        };
+
+        let last_line = buffer.len_lines().saturating_sub(1);
+        let head = match arrow {
+            Arrow::Up => BlockCorner::new(head.column, head.line.saturating_sub(1)),
+            Arrow::Down => BlockCorner::new(head.column, (head.line + 1).min(last_line)),
+            Arrow::Left => BlockCorner::new(head.column.saturating_sub(1), head.line),
+            Arrow::Right => BlockCorner::new(head.column.saturating_add(1), head.line),
+        };
+
+        let cursor_set = unpack_or!(block_to_cursor_set(&*buffer, anchor, head), (), "failed selecting block");
+        buffer.text_mut().set_cursor_set(self.wid, cursor_set);
+        self.column_selection = Some((anchor, head));
+        self.update_kite(buffer, arrow);
+    }
+
+    fn jump_to_matching_bracket(&mut self, buffer: &mut BufferState) {
+        let (bracket_idx, other_idx) = unpack_or!(self.matching_bracket_pair(buffer), (), "no bracket to jump from");
+        let cursor_idx = unpack_or!(buffer.cursors(self.wid).and_then(|cs| cs.as_single()), ()).a;
+
+        // cursor keeps its side of the bracket: before one goes before the other, after goes after
+        let target_idx = if cursor_idx == bracket_idx { other_idx } else { other_idx + 1 };
+
+        buffer
+            .text_mut()
+            .set_cursor_set(self.wid, CursorSet::singleton(Cursor::new(target_idx)));
+        self.update_kite(buffer, if target_idx < cursor_idx { Arrow::Up } else { Arrow::Down });
+    }
+
+    fn jump_to_line(&mut self, buffer: &mut BufferState, from_line: usize, target_line: usize) {
+        let char_idx = unpack_or_e!(buffer.line_to_char(target_line), (), "line {} beyond buffer", target_line);
+
+        buffer
+            .text_mut()
+            .set_cursor_set(self.wid, CursorSet::singleton(Cursor::new(char_idx)));
+        self.update_kite(buffer, if target_line < from_line { Arrow::Up } else { Arrow::Down });
+    }
+
+    /*
+    Moves buffer to given state of undo tree (picked in undo tree browser).
+     */
+    pub fn jump_to_history_node(&mut self, node_id: HistoryNodeId) -> bool {
+        let buffer_ref = self.buffer.clone();
+        let mut buffer = unpack_or_e!(buffer_ref.lock_rw(), false, "failed to lock buffer");
+
+        if !buffer.jump_to_history_node(node_id) {
+            return false;
+        }
+
+        self.after_content_changed(&buffer);
+        self.update_kite(&buffer, Arrow::Up);
+        self.update_kite(&buffer, Arrow::Down);
+        true
+    }
+
+    fn revert_hunk(&mut self, buffer: &mut BufferState) -> bool {
+        let line_idx = unpack_or!(self.cursor_line(buffer), false, "no cursor line");
+        let (char_range, old_text) = unpack_or!(self.git_gutter.revert_at(line_idx, buffer), false, "no hunk under cursor");
+        let cursor_pos = char_range.start;
+
+        let changed = buffer.apply_cem(
+            CommonEditMsg::SubstituteBlock {
+                char_range,
+                with_what: old_text,
+            },
+            self.wid,
+            self.page_height() as usize,
+            Some(self.providers.clipboard()),
+        );
+
+        if changed {
+            self.after_content_changed(buffer);
+            buffer
+                .text_mut()
+                .set_cursor_set(self.wid, CursorSet::singleton(Cursor::new(cursor_pos)));
+            self.update_kite(buffer, Arrow::Up);
+        }
+
+        changed
+    }
+
+    /*
+    Whole conflict region (markers included) is replaced in one go, so it's a single undo step.
+     */
+    fn resolve_conflict(&mut self, buffer: &mut BufferState, resolution: ConflictResolution) -> bool {
+        let line_idx = unpack_or!(self.cursor_line(buffer), false, "no cursor line");
+        let (char_range, with_what) = unpack_or!(
+            self.merge_conflicts.resolve_at(line_idx, resolution, buffer),
+            false,
+            "no conflict under cursor"
+        );
+        let cursor_pos = char_range.start;
+
+        let changed = buffer.apply_cem(
+            CommonEditMsg::SubstituteBlock { char_range, with_what },
+            self.wid,
+            self.page_height() as usize,
+            Some(self.providers.clipboard()),
+        );
+
+        if changed {
+            self.after_content_changed(buffer);
+            self.merge_conflicts.update(buffer);
+            buffer
+                .text_mut()
+                .set_cursor_set(self.wid, CursorSet::singleton(Cursor::new(cursor_pos)));
+            self.update_kite(buffer, Arrow::Up);
+        }
+
+        changed
+    }
+
     fn update_navcomp(&mut self) {
         let buffer = unpack_or_e!(self.buffer.lock(), (), "failed locking buffer");
 
+        // sending whole large file to language server on every keystroke is not an option
+        if buffer.is_large_file() {
+            debug!("not starting navigation for large file {:?}", buffer.get_path());
+            self.navcomp = None;
+            return;
+        }
+
         if self.navcomp.is_none() {
             let navcomp_group = unpack_or_e!(self.providers.navcomp_group().try_read().ok(), (), "failed to lock navcompgroup");
 
@@ -238,7 +553,8 @@ impl EditorWidget {
 
         let cursor_set = unpack_or_e!(buffer.text().get_cursor_set(self.wid), (), "failed to get cursor_set");
 
-        let cursor_rect = cursor_set_to_rect(cursor_set, &*buffer);
+        self.update_first_line(buffer, cursor_set, last_move_direction);
+        let cursor_rect = cursor_set_to_rect(cursor_set, &*buffer, self.lines_shown());
         match last_move_direction {
             Arrow::Up => {
                 if self.kite.y > cursor_rect.upper_left().y {
@@ -288,6 +604,14 @@ impl EditorWidget {
             "failed mapping cursor to lsp-cursor"
         );
         let lsp_cursor_xy = unpack_or_e!(lsp_cursor.to_xy(buffer), None, "lsp cursor beyond XY max");
+        let window_xy = XY::new(
+            lsp_cursor_xy.x,
+            unpack_or!(
+                (lsp_cursor_xy.y as usize).checked_sub(self.first_line),
+                None,
+                "cursor above lines shown"
+            ) as u16,
+        );
 
         let layout_res = unpack_or!(
             self.layout_res.as_ref(),
@@ -296,7 +620,7 @@ impl EditorWidget {
         );
         let visible_rect = layout_res.visible_rect();
 
-        if !visible_rect.contains(lsp_cursor_xy) {
+        if !visible_rect.contains(window_xy) {
             warn!("cursor seems to be outside visible hint {:?}", layout_res.visible_rect());
             return Some(CursorScreenPosition {
                 cursor,
@@ -305,7 +629,7 @@ impl EditorWidget {
             });
         }
 
-        let local_pos = lsp_cursor_xy - visible_rect.upper_left();
+        let local_pos = window_xy - visible_rect.upper_left();
 
         debug!("cursor {:?} converted to {:?} positioned at {:?}", cursor, lsp_cursor, local_pos);
         debug_assert!(local_pos >= XY::ZERO);
@@ -401,7 +725,7 @@ impl EditorWidget {
             .flatten()
             .map(|highlight_item| highlight_item.identifier);
 
-        let items = get_context_options(
+        let mut items = get_context_options(
             &self.state,
             single_cursor,
             &cursor_set,
@@ -409,6 +733,121 @@ impl EditorWidget {
             None,
             tree_sitter_highlight.as_ref().map(|c| c.as_str()),
         );
+        items.extend(self.extra_context_items.iter().cloned());
+
+        if !self.git_gutter.hunks().is_empty() {
+            items.push(ContextBarItem::NEXT_CHANGE);
+            items.push(ContextBarItem::PREV_CHANGE);
+            if self
+                .cursor_line(buffer)
+                .and_then(|line| self.git_gutter.hunk_idx_at(line))
+                .is_some()
+            {
+                items.push(ContextBarItem::REVERT_CHANGE);
+            }
+        }
+
+        if !self.merge_conflicts.conflicts().is_empty() {
+            if !self.readonly
+                && self
+                    .cursor_line(buffer)
+                    .and_then(|line| self.merge_conflicts.conflict_idx_at(line))
+                    .is_some()
+            {
+                items.push(ContextBarItem::TAKE_OURS);
+                items.push(ContextBarItem::TAKE_THEIRS);
+                items.push(ContextBarItem::TAKE_BOTH);
+            }
+            items.push(ContextBarItem::NEXT_CONFLICT);
+        }
+
+        if !self.readonly && !buffer.history().is_empty() {
+            let buffer_ref = self.buffer.clone();
+            items.push(ContextBarItem::new_dynamic("undo tree".to_string(), move || {
+                MainViewMsg::OpenUndoTree {
+                    buffer: buffer_ref.clone(),
+                }
+                .boxed()
+            }));
+        }
+
+        if self.matching_bracket_pair(buffer).is_some() {
+            items.push(ContextBarItem::JUMP_TO_MATCHING_BRACKET);
+        }
+
+        if buffer.cursors(self.wid).map(|cs| cs.iter().any(|c| c.s.is_some())).unwrap_or(false) {
+            items.push(ContextBarItem::ADD_NEXT_OCCURRENCE);
+            items.push(ContextBarItem::SELECT_ALL_OCCURRENCES);
+        }
+
+        if !self.readonly && buffer.get_lang_id().is_some() {
+            items.push(ContextBarItem::TOGGLE_COMMENT);
+            items.push(ContextBarItem::TOGGLE_BLOCK_COMMENT);
+        }
+
+        if !self.readonly && self.selection_spans_lines(buffer) {
+            items.push(ContextBarItem::SORT_LINES);
+            items.push(ContextBarItem::UNIQUE_LINES);
+            items.push(ContextBarItem::REVERSE_LINES);
+        }
+
+        if !self.readonly && !self.transform_options(buffer).is_empty() {
+            items.push(ContextBarItem::new_dynamic("transform text".to_string(), || {
+                EditorWidgetMsg::OpenContextSubmenu(ContextSubmenu::Transforms).boxed()
+            }));
+        }
+
+        if self.providers.git().is_some() && buffer.get_path().is_some() && !buffer.is_large_file() {
+            items.push(ContextBarItem::TOGGLE_BLAME);
+        }
+        if let Some(commit) = self
+            .blame_op
+            .as_ref()
+            .zip(self.cursor_line(buffer))
+            .and_then(|(blame, line)| blame.commit_at(line))
+        {
+            let hash = commit.hash.clone();
+            items.push(ContextBarItem::new_dynamic(
+                format!("show commit {}: {}", commit.short_hash(), commit.summary),
+                move || MainViewMsg::ShowCommit { hash: hash.clone() }.boxed(),
+            ));
+        }
+
+        if let Some(path) = buffer.get_path().filter(|_| !self.readonly) {
+            let disk_path = path.clone();
+            items.push(ContextBarItem::new_dynamic("diff with saved".to_string(), move || {
+                MainViewMsg::OpenDiff {
+                    source: DiffSource::BufferVsDisk { path: disk_path.clone() },
+                }
+                .boxed()
+            }));
+            if self.providers.git().is_some() {
+                let head_path = path.clone();
+                items.push(ContextBarItem::new_dynamic("diff with HEAD".to_string(), move || {
+                    MainViewMsg::OpenDiff {
+                        source: DiffSource::BufferVsHead { path: head_path.clone() },
+                    }
+                    .boxed()
+                }));
+            }
+            let compared_path = path.clone();
+            items.push(ContextBarItem::new_dynamic("compare with file".to_string(), move || {
+                MainViewMsg::CompareWithFile {
+                    path: compared_path.clone(),
+                }
+                .boxed()
+            }));
+        }
+
+        if !self.readonly {
+            items.push(ContextBarItem::new_dynamic(
+                format!("file format: {}", buffer.file_format()),
+                || EditorWidgetMsg::OpenContextSubmenu(ContextSubmenu::FileFormat).boxed(),
+            ));
+        }
+
+        // terminal, git file search and user defined tasks are available from anywhere
+        items.extend(get_global_context_options(&self.providers));
 
         if items.is_empty() {
             warn!("ignoring everything bar, no items");
@@ -423,11 +862,155 @@ impl EditorWidget {
         }
     }
 
+    fn file_format_options(&self, buffer: &BufferState) -> Vec<ContextBarItem> {
+        let format = buffer.file_format();
+        let mut items: Vec<ContextBarItem> = Vec::new();
+
+        for line_ending in [LineEnding::Lf, LineEnding::CrLf] {
+            if line_ending != format.line_ending {
+                items.push(ContextBarItem::new_dynamic(
+                    format!("line endings: {} → {}", format.line_ending, line_ending),
+                    move || EditorWidgetMsg::SetLineEnding(line_ending).boxed(),
+                ));
+            }
+        }
+        for encoding in Encoding::ALL {
+            if encoding != format.encoding {
+                items.push(ContextBarItem::new_dynamic(
+                    format!("encoding: {} → {}", format.encoding, encoding),
+                    move || EditorWidgetMsg::SetEncoding(encoding).boxed(),
+                ));
+            }
+        }
+
+        items
+    }
+
+    /*
+    Transforms work on selections, except for numbering, which also fills multiple cursors.
+     */
+    fn transform_options(&self, buffer: &BufferState) -> Vec<ContextBarItem> {
+        let cursor_set = buffer.cursors(self.wid);
+        let any_selection = cursor_set.map(|cs| cs.iter().any(|c| c.s.is_some())).unwrap_or(false);
+        let multiple_cursors = cursor_set.map(|cs| cs.len() > 1).unwrap_or(false);
+
+        TextTransform::ALL
+            .iter()
+            .copied()
+            .filter(|transform| any_selection || (*transform == TextTransform::NumberSequence && multiple_cursors))
+            .map(|transform| {
+                ContextBarItem::new_dynamic(transform.title().to_string(), move || {
+                    EditorWidgetMsg::EditMsg(CommonEditMsg::Transform(transform)).boxed()
+                })
+            })
+            .collect()
+    }
+
+    /*
+    Replaces context bar with one holding only options of given submenu, in the same place.
+     */
+    fn open_context_submenu(&mut self, buffer: &BufferState, submenu: ContextSubmenu) {
+        let items = match submenu {
+            ContextSubmenu::FileFormat => self.file_format_options(buffer),
+            ContextSubmenu::Transforms => self.transform_options(buffer),
+        };
+
+        let hover_settings_op = self.get_cursor_related_hover_settings(buffer, None);
+        self.requested_hover = hover_settings_op.map(|hs| (hs, EditorHover::Context(ContextBarWidget::new(items))));
+    }
+
     // TODO add test to reformat
     pub fn reformat(&mut self, buffer: &mut BufferState) -> bool {
+        let lang_id_op = buffer.get_lang_id().or_else(|| buffer.get_path().and_then(filename_to_language));
+        match self.providers.language_options(lang_id_op).formatter {
+            FormatterChoice::Navcomp => self.reformat_with_navcomp(buffer),
+            FormatterChoice::Disabled => {
+                debug!("not reformatting: formatter disabled in settings");
+                false
+            }
+            FormatterChoice::Command { command, args } => self.reformat_with_command(buffer, &command, &args),
+        }
+    }
+
+    /*
+    Starts piping entire buffer through external command in background (see run_formatter). Result is
+    applied in prelayout, see apply_finished_formatting.
+     */
+    fn reformat_with_command(&mut self, buffer: &BufferState, command: &str, args: &[String]) -> bool {
+        if self.formatter_run_op.is_some() {
+            debug!("not reformatting: formatter is already running");
+            return false;
+        }
+
+        let tick_sender = match self.providers.navcomp_group().try_read() {
+            Ok(lock) => lock.todo_sender().clone(),
+            Err(e) => {
+                error!(
+                    "failed to acquire navcomp group lock, formatting will be applied only on input: {}",
+                    e
+                );
+                crossbeam_channel::unbounded::<NavCompTick>().0
+            }
+        };
+
+        let input = buffer.to_string();
+        let cwd_op = buffer.get_path().and_then(|p| p.parent()).map(|parent| parent.absolute_path());
+        let run = run_formatter(command, args, cwd_op, input.clone(), FORMATTER_TIMEOUT, tick_sender);
+        self.formatter_run_op = Some((run, input));
+        true
+    }
+
+    /*
+    If formatter is done, substitutes buffer contents with its output as a single edit (so it's one step
+    in undo history). Output is dropped if buffer was changed in the meantime.
+     */
+    fn apply_finished_formatting(&mut self) {
+        let status = {
+            let (run, _) = unpack_or!(self.formatter_run_op.as_ref(), ());
+            unpack_or_e!(run.read().ok(), (), "failed to lock formatter status").clone()
+        };
+        let formatted = match status {
+            FormatterStatus::Running => return,
+            FormatterStatus::Finished(formatted) => formatted,
+            FormatterStatus::Failed(reason) => {
+                warn!("formatter failed: {}", reason);
+                self.formatter_run_op = None;
+                return;
+            }
+        };
+        let (_, input) = self.formatter_run_op.take().unwrap();
+
+        let mut buffer = unpack_or_e!(self.buffer.lock_rw(), (), "failed to lock buffer");
+        if buffer.to_string() != input {
+            warn!("buffer changed while formatting, dropping formatter output");
+            return;
+        }
+        if formatted == input {
+            return;
+        }
+
+        let page_height = self.page_height();
+        let buffer_end = buffer.len_chars();
+        buffer.apply_cem(
+            CommonEditMsg::SubstituteBlock {
+                char_range: 0..buffer_end,
+                with_what: formatted,
+            },
+            self.wid,
+            page_height as usize,
+            Some(self.providers.clipboard()),
+        );
+        self.after_content_changed(&buffer);
+    }
+
+    fn reformat_with_navcomp(&mut self, buffer: &mut BufferState) -> bool {
         let navcomp = unpack_or!(self.navcomp.as_ref(), false, "can't reformat: navcomp not available");
         let path = unpack_or!(buffer.get_path(), false, "can't reformat: unsaved file");
-        let mut promise = unpack_or!(navcomp.todo_reformat(path), false, "can't reformat: no promise for reformat");
+        let mut promise = unpack_or!(
+            navcomp.todo_reformat(path, &buffer.formatting_settings()),
+            false,
+            "can't reformat: no promise for reformat"
+        );
         let cursor_set = unpack_or!(buffer.cursors(self.wid), false, "no cursor for wid");
 
         if !cursor_set.are_simple() {
@@ -572,16 +1155,31 @@ impl EditorWidget {
 
         let visible_rect = output.visible_rect();
 
-        let char_range_op = buffer.get_visible_chars_range(output);
+        // ruler goes first, so text that is too long is drawn over it
+        if let Some(max_line_length) = buffer.editorconfig().max_line_length {
+            if visible_rect.upper_left().x as usize <= max_line_length && max_line_length < visible_rect.lower_right().x as usize {
+                let ruler_style = default.with_background(theme.ui.ruler_background);
+                for y in visible_rect.upper_left().y..visible_rect.lower_right().y {
+                    output.print_at(XY::new(max_line_length as u16, y), ruler_style, " ");
+                }
+            }
+        }
+
+        let bracket_pair_op = self.matching_bracket_pair(&buffer);
+
+        let char_range_op = buffer.get_visible_chars_range(output, self.first_line);
         // highlights are actually just code coloring
         let highlights = buffer.highlight(char_range_op.clone());
 
         let mut highlight_iter = highlights.iter().peekable();
-        let lines_to_skip = visible_rect.upper_left().y as usize;
+        let mut char_background_iter = self.char_backgrounds.iter().peekable();
+        // these are lines of buffer, widget's y = 0 is self.first_line
+        let first_visible_line = self.first_line + visible_rect.upper_left().y as usize;
+        let beyond_visible_lines = self.first_line + visible_rect.lower_right().y as usize;
 
-        let mut lines_it = buffer.lines().skip(lines_to_skip);
         // skipping lines that cannot be visible, because they are before hint()
-        let mut line_idx = lines_to_skip;
+        let mut lines_it = buffer.lines_from(first_visible_line);
+        let mut line_idx = first_visible_line;
 
         // preparing labels
         // Right now labels "chain" one after another. Provided priority does not change, they should not
@@ -590,22 +1188,27 @@ impl EditorWidget {
 
         // if we don't have a char_range, that means the "visible rect" is empty, so we don't draw anything
         if let Some(char_range) = char_range_op {
-            for label_provider in self.providers.todo_label_providers() {
-                for label in label_provider.query_for(buffer.get_path()) {
-                    if label
-                        .pos
-                        .maybe_should_draw(char_range.clone(), lines_to_skip..visible_rect.lower_right().y as usize)
-                    {
-                        if let Some(xy) = label.pos.into_position(&*buffer) {
-                            if (xy.y as usize) < lines_to_skip {
-                                continue;
-                            }
+            let provider_labels = self
+                .providers
+                .todo_label_providers()
+                .flat_map(|label_provider| label_provider.query_for(buffer.get_path()));
+            // blame goes last, so it's the one discarded on collision
+            let blame_labels = self.blame_op.iter().flat_map(|blame| blame.labels());
+
+            for label in provider_labels.chain(blame_labels) {
+                if label
+                    .pos
+                    .maybe_should_draw(char_range.clone(), first_visible_line..beyond_visible_lines)
+                {
+                    if let Some(xy) = label.pos.into_position(&*buffer, self.first_line) {
+                        if xy.y < visible_rect.upper_left().y {
+                            continue;
+                        }
 
-                            if Self::can_add_label(&mut labels, (xy, label)) {
-                                labels.insert(xy, label);
-                            } else {
-                                warn!("Discarding a label because of collision. This is an omission most likely.");
-                            }
+                        if Self::can_add_label(&mut labels, (xy, label)) {
+                            labels.insert(xy, label);
+                        } else {
+                            warn!("Discarding a label because of collision. This is an omission most likely.");
                         }
                     }
                 }
@@ -617,7 +1220,7 @@ impl EditorWidget {
         //     .skip(output.size_constraint().visible_hint().upper_left().y as usize)
         while let Some(line) = lines_it.next() {
             // skipping lines that cannot be visible, because the are after the hint()
-            if line_idx >= visible_rect.lower_right().y as usize {
+            if line_idx >= beyond_visible_lines {
                 // debug!("early exit 7");
                 break;
             }
@@ -626,12 +1229,13 @@ impl EditorWidget {
                 Some(begin) => begin,
                 None => continue,
             };
+            let y = (line_idx - self.first_line) as u16;
 
             // let's generate the "combined line" of actual file and labels.
             let mut filtered_labels: Vec<(XY, &Label)> = Vec::new();
 
             for (label_pos, label) in labels.iter() {
-                if label_pos.y as usize != line_idx {
+                if label_pos.y != y {
                     continue;
                 }
 
@@ -643,6 +1247,19 @@ impl EditorWidget {
                 filtered_labels.push((*label_pos, label));
             }
 
+            let line_background_op = self.line_backgrounds.get(line_idx).copied().flatten().or_else(|| {
+                self.merge_conflicts.part_at(line_idx).map(|part| match part {
+                    ConflictPart::Marker => theme.ui.git.conflict_marker_background,
+                    ConflictPart::Ours => theme.ui.git.conflict_ours_background,
+                    ConflictPart::Base => theme.ui.git.conflict_base_background,
+                    ConflictPart::Theirs => theme.ui.git.conflict_theirs_background,
+                })
+            });
+            let line_style = match line_background_op {
+                Some(background) => default.with_background(background),
+                None => default,
+            };
+
             let mut combined_line: Vec<(TextStyle, String)> = Vec::new();
 
             {
@@ -650,7 +1267,7 @@ impl EditorWidget {
                 let mut x_offset: usize = 0;
 
                 for (c_idx, c) in line.graphemes().into_iter().enumerate() {
-                    let text_pos = XY::new(x_offset as u16, line_idx as u16);
+                    let text_pos = XY::new(x_offset as u16, y);
 
                     while let Some((label_pos, label)) = label_it.peek() {
                         if *label_pos == text_pos {
@@ -679,7 +1296,20 @@ impl EditorWidget {
                     // TODO optimise
                     let tr = if c == "\n" { NEWLINE.to_string() } else { c.to_string() };
 
-                    let mut style = default;
+                    let mut style = line_style;
+
+                    while let Some((range, _)) = char_background_iter.peek() {
+                        if char_idx >= range.end {
+                            char_background_iter.next();
+                        } else {
+                            break;
+                        }
+                    }
+                    if let Some((range, background)) = char_background_iter.peek() {
+                        if range.contains(&char_idx) {
+                            style = style.with_background(*background);
+                        }
+                    }
 
                     if tr != NEWLINE {
                         // TODO cleanup
@@ -690,6 +1320,12 @@ impl EditorWidget {
                         }
                     }
 
+                    if let Some((bracket_idx, other_idx)) = bracket_pair_op {
+                        if char_idx == bracket_idx || char_idx == other_idx {
+                            style = style.with_background(theme.ui.matching_bracket_background);
+                        }
+                    }
+
                     self.pos_to_cursor(&cursor_set_copy, theme, char_idx).map(|mut bg| {
                         if !focused {
                             bg = bg.half();
@@ -733,15 +1369,22 @@ impl EditorWidget {
             // ok, at this point I consume the combined line
             let mut x_offset: usize = 0;
             for (style, grapheme) in combined_line.styled_graphemes() {
-                let pos = XY::new(x_offset as u16, line_idx as u16);
+                let pos = XY::new(x_offset as u16, y);
 
                 output.print_at(pos, *style, grapheme);
                 x_offset += grapheme.width();
             }
 
+            // line background spans the whole width, not just the text
+            if line_background_op.is_some() {
+                while x_offset < visible_rect.lower_right().x as usize {
+                    output.print_at(XY::new(x_offset as u16, y), line_style, " ");
+                    x_offset += 1;
+                }
+            }
+
             line_idx += 1;
-            // TODO u16 overflow
-            if line_idx as u16 >= visible_rect.lower_right().y {
+            if line_idx >= beyond_visible_lines {
                 // debug!("early exit 5 : osc : {:?}, output : {:?}", output.size_constraint(), output);
                 break;
             }
@@ -751,7 +1394,8 @@ impl EditorWidget {
         let last_line = buffer.char_to_line(one_beyond_limit).unwrap(); //TODO
         let x_beyond_last = one_beyond_limit - buffer.line_to_char(last_line).unwrap(); //TODO
 
-        let one_beyond_last_pos = XY::new(x_beyond_last as u16, last_line as u16);
+        // window of lines ends with text at latest, so last line is never above it
+        let one_beyond_last_pos = XY::new(x_beyond_last as u16, last_line.saturating_sub(self.first_line) as u16);
 
         if one_beyond_last_pos < visible_rect.lower_right() {
             let mut style = default;
@@ -979,7 +1623,10 @@ impl EditorWidget {
         // ).flatten();
     }
 
-    fn after_content_changed(&self, buffer: &BufferState) {
+    /*
+    Lets navcomp know about new contents. To be called after every change not made by the widget itself.
+     */
+    pub fn after_content_changed(&self, buffer: &BufferState) {
         match (&self.navcomp, buffer.get_path()) {
             (Some(navcomp), Some(path)) => {
                 let contents = buffer.text().rope().clone();
@@ -1132,13 +1779,38 @@ impl Widget for EditorWidget {
 
     fn full_size(&self) -> XY {
         if let Some(lock) = self.buffer.lock() {
-            lock.size()
+            let mut size = lock.size();
+            size.y = min(lock.len_lines().saturating_sub(self.first_line), Self::LINE_WINDOW) as u16;
+            size
         } else {
             error!("couldn't lock buffer to count");
             MIN_EDITOR_SIZE
         }
     }
 
+    fn prelayout(&mut self) {
+        self.apply_finished_formatting();
+
+        let buffer_ref = self.buffer.clone();
+        let buffer = unpack_or_e!(buffer_ref.lock(), (), "failed to lock buffer");
+
+        // text got shorter under the window, in some other way than through this widget
+        let last_first_line = buffer.len_lines().saturating_sub(Self::LINE_WINDOW);
+        if self.first_line > last_first_line {
+            self.set_first_line(last_first_line);
+        }
+
+        // all of these go through whole text on every change
+        if buffer.is_large_file() {
+            return;
+        }
+        if let Some(blame) = self.blame_op.as_mut() {
+            blame.update(&buffer);
+        }
+        self.git_gutter.update(self.providers.git(), &buffer);
+        self.merge_conflicts.update(&buffer);
+    }
+
     fn layout(&mut self, screenspace: Screenspace) {
         if self.layout_res != Some(screenspace) {
             debug!("changed size");
@@ -1179,6 +1851,44 @@ impl Widget for EditorWidget {
             (&EditorState::Editing, InputEvent::KeyInput(key)) if self.readonly == false && key == c.reformat => {
                 EditorWidgetMsg::Reformat.someboxed()
             }
+            // without hunks these keys are left for whoever is above (diff view uses them too)
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if key == c.next_hunk && !self.git_gutter.hunks().is_empty() => {
+                EditorWidgetMsg::NextHunk.someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if key == c.prev_hunk && !self.git_gutter.hunks().is_empty() => {
+                EditorWidgetMsg::PrevHunk.someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if key == c.toggle_blame => EditorWidgetMsg::ToggleBlame.someboxed(),
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if key == c.jump_to_matching_bracket => {
+                EditorWidgetMsg::JumpToMatchingBracket.someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if c.column_select_arrow(key).is_some() => {
+                EditorWidgetMsg::ColumnSelect(c.column_select_arrow(key).unwrap()).someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if key == c.add_next_occurrence => {
+                EditorWidgetMsg::AddNextOccurrence { skip: false }.someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if key == c.skip_occurrence => {
+                EditorWidgetMsg::AddNextOccurrence { skip: true }.someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if key == c.select_all_occurrences => {
+                EditorWidgetMsg::SelectAllOccurrences.someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if self.readonly == false && key == c.toggle_comment => {
+                EditorWidgetMsg::EditMsg(CommonEditMsg::ToggleLineComment).someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if self.readonly == false && key == c.toggle_block_comment => {
+                EditorWidgetMsg::EditMsg(CommonEditMsg::ToggleBlockComment).someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if self.readonly == false && c.line_edit_msg(key).is_some() => {
+                EditorWidgetMsg::EditMsg(c.line_edit_msg(key).unwrap()).someboxed()
+            }
+            (&EditorState::Editing, InputEvent::KeyInput(key)) if self.readonly == false && key == c.undo_tree => {
+                MainViewMsg::OpenUndoTree {
+                    buffer: self.buffer.clone(),
+                }
+                .someboxed()
+            }
             // TODO change to if let Some() when it's stabilized
             (&EditorState::DroppingCursor { .. }, InputEvent::KeyInput(key)) if key_to_edit_msg(key).is_some() => {
                 let cem = key_to_edit_msg(key).unwrap();
@@ -1206,16 +1916,36 @@ impl Widget for EditorWidget {
         return match msg.as_msg::<EditorWidgetMsg>() {
             None => {
                 debug!(target: "recursive_treat_views", "expected EditorWidgetMsg, got {:?}, passing through", msg);
+                // context bar item addressed to someone higher up was hit, so we're done with the bar.
+                if let Some((_, EditorHover::Context(_))) = &self.requested_hover {
+                    self.requested_hover = None;
+                }
                 Some(msg)
             }
             Some(msg) => {
+                let column_selection = self.column_selection.take();
+
                 let result = if let Some(mut buffer) = self.buffer.clone().lock_rw() {
                     match (&self.state, msg) {
                         (&EditorState::Editing, EditorWidgetMsg::EditMsg(cem)) => {
+                            let pasting_block = *cem == CommonEditMsg::Paste
+                                && self
+                                    .block_clipboard
+                                    .as_ref()
+                                    .map(|block| *block == self.providers.clipboard().get())
+                                    .unwrap_or(false);
+                            let cem = if pasting_block { &CommonEditMsg::PasteBlock } else { cem };
+
                             let page_height = self.page_height();
                             // page_height as usize is safe, since page_height is u16 and usize is larger.
                             let changed = buffer.apply_cem(cem.clone(), self.wid, page_height as usize, Some(self.providers.clipboard()));
 
+                            if *cem == CommonEditMsg::Copy {
+                                self.block_clipboard = column_selection.map(|_| self.providers.clipboard().get());
+                                // copying doesn't move cursors, so selection can still grow
+                                self.column_selection = column_selection;
+                            }
+
                             // TODO this needs to happen only if CONTENTS changed, not if cursor positions changed
                             if changed {
                                 self.after_content_changed(&buffer);
@@ -1298,6 +2028,7 @@ impl Widget for EditorWidget {
                         }
                         (&EditorState::Editing, EditorWidgetMsg::HoverClose) => {
                             self.requested_hover = None;
+                            self.column_selection = column_selection;
                             None
                         }
                         (&EditorState::Editing, EditorWidgetMsg::CompletionWidgetSelected(completion)) => {
@@ -1305,6 +2036,8 @@ impl Widget for EditorWidget {
                             None
                         }
                         (&EditorState::Editing, EditorWidgetMsg::RequestContextBar) => {
+                            // so "copy" from context bar still copies a block
+                            self.column_selection = column_selection;
                             self.todo_request_context_bar(&buffer);
                             None
                         }
@@ -1321,6 +2054,84 @@ impl Widget for EditorWidget {
                             self.todo_go_to_definition();
                             None
                         }
+                        (&EditorState::Editing, EditorWidgetMsg::NextHunk) => {
+                            self.requested_hover = None;
+                            self.jump_to_hunk(&mut buffer, true);
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::PrevHunk) => {
+                            self.requested_hover = None;
+                            self.jump_to_hunk(&mut buffer, false);
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::RevertHunk) => {
+                            self.requested_hover = None;
+                            if !self.readonly {
+                                self.revert_hunk(&mut buffer);
+                            }
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::ToggleBlame) => {
+                            self.requested_hover = None;
+                            self.toggle_blame(&buffer);
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::JumpToMatchingBracket) => {
+                            self.requested_hover = None;
+                            self.jump_to_matching_bracket(&mut buffer);
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::ColumnSelect(arrow)) => {
+                            self.requested_hover = None;
+                            self.column_select(&mut buffer, column_selection, *arrow);
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::AddNextOccurrence { skip }) => {
+                            self.requested_hover = None;
+                            match buffer.add_next_occurrence(self.wid, *skip) {
+                                Ok(true) => self.update_kite(&buffer, Arrow::Down),
+                                Ok(false) => debug!("no next occurrence"),
+                                Err(e) => warn!("failed looking for next occurrence: {:?}", e),
+                            }
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::SelectAllOccurrences) => {
+                            self.requested_hover = None;
+                            if let Err(e) = buffer.select_all_occurrences(self.wid) {
+                                warn!("failed selecting all occurrences: {:?}", e);
+                            }
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::NextConflict) => {
+                            self.requested_hover = None;
+                            self.jump_to_conflict(&mut buffer);
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::ResolveConflict(resolution)) => {
+                            self.requested_hover = None;
+                            if !self.readonly {
+                                self.resolve_conflict(&mut buffer, *resolution);
+                            }
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::SetEncoding(encoding)) => {
+                            self.requested_hover = None;
+                            if !self.readonly {
+                                buffer.set_encoding(*encoding);
+                            }
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::SetLineEnding(line_ending)) => {
+                            self.requested_hover = None;
+                            if !self.readonly {
+                                buffer.set_line_ending(*line_ending);
+                            }
+                            None
+                        }
+                        (&EditorState::Editing, EditorWidgetMsg::OpenContextSubmenu(submenu)) => {
+                            self.open_context_submenu(&buffer, *submenu);
+                            None
+                        }
                         (editor_state, msg) => {
                             error!("Unhandled combination of editor state {:?} and msg {:?}", editor_state, msg);
                             None
@@ -0,0 +1,163 @@
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::process::{Command, Stdio};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::{Duration, Instant};
+
+use log::{debug, error, warn};
+
+use crate::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum FormatterStatus {
+    Running,
+    // formatted text
+    Finished(String),
+    Failed(String),
+}
+
+pub type FormatterRunRef = Arc<RwLock<FormatterStatus>>;
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/*
+Pipes text through external formatter in background, the same way tasks are run (see run_task), so a
+slow formatter doesn't freeze the UI. One that doesn't finish within timeout gets killed. Once done, a
+tick wakes up the main loop, and editor picks the result up.
+ */
+pub fn run_formatter(
+    command: &str,
+    args: &[String],
+    cwd_op: Option<PathBuf>,
+    input: String,
+    timeout: Duration,
+    tick_sender: NavCompTickSender,
+) -> FormatterRunRef {
+    let status: FormatterRunRef = Arc::new(RwLock::new(FormatterStatus::Running));
+
+    debug!("starting formatter {} {:?} in {:?}", command, args, cwd_op);
+
+    let mut cmd = Command::new(command);
+    cmd.args(args).stdin(Stdio::piped()).stdout(Stdio::piped()).stderr(Stdio::piped());
+    if let Some(cwd) = cwd_op {
+        cmd.current_dir(cwd);
+    }
+
+    let command = command.to_string();
+    let status_clone = status.clone();
+    std::thread::spawn(move || {
+        let new_status = match format_with(cmd, &command, input, timeout) {
+            Ok(formatted) => FormatterStatus::Finished(formatted),
+            Err(reason) => FormatterStatus::Failed(reason),
+        };
+
+        match status_clone.write() {
+            Ok(mut lock) => *lock = new_status,
+            Err(e) => error!("failed to lock formatter status: {}", e),
+        }
+
+        if tick_sender.send(NavCompTick::TaskTick).is_err() {
+            warn!("failed sending tick");
+        }
+    });
+
+    status
+}
+
+fn format_with(mut cmd: Command, command: &str, input: String, timeout: Duration) -> Result<String, String> {
+    let mut child = cmd.spawn().map_err(|e| format!("failed to start {}: {}", command, e))?;
+
+    if let Some(mut stdin) = child.stdin.take() {
+        // writing in separate thread, so we don't deadlock on full stdout pipe
+        std::thread::spawn(move || {
+            if let Err(e) = stdin.write_all(input.as_bytes()) {
+                error!("failed writing to formatter stdin: {}", e);
+            }
+        });
+    }
+    let stdout_reader = child.stdout.take().map(spawn_reader);
+    let stderr_reader = child.stderr.take().map(spawn_reader);
+
+    let started = Instant::now();
+    let exit_status = loop {
+        match child.try_wait() {
+            Ok(Some(exit_status)) => break exit_status,
+            Ok(None) if started.elapsed() > timeout => {
+                if let Err(e) = child.kill() {
+                    error!("failed to kill formatter {}: {}", command, e);
+                }
+                let _ = child.wait();
+                return Err(format!("{} didn't finish within {:?}", command, timeout));
+            }
+            Ok(None) => std::thread::sleep(POLL_INTERVAL),
+            Err(e) => return Err(format!("failed to wait for {}: {}", command, e)),
+        }
+    };
+
+    let stdout = stdout_reader.and_then(|reader| reader.join().ok()).unwrap_or_default();
+    let stderr = stderr_reader.and_then(|reader| reader.join().ok()).unwrap_or_default();
+
+    if !exit_status.success() {
+        return Err(format!(
+            "{} failed with {}: {}",
+            command,
+            exit_status,
+            String::from_utf8_lossy(&stderr)
+        ));
+    }
+
+    String::from_utf8(stdout).map_err(|_| format!("{} returned non-utf8 output", command))
+}
+
+fn spawn_reader<R: Read + Send + 'static>(mut stream: R) -> JoinHandle<Vec<u8>> {
+    std::thread::spawn(move || {
+        let mut buf: Vec<u8> = Vec::new();
+        if let Err(e) = stream.read_to_end(&mut buf) {
+            error!("failed reading formatter output: {}", e);
+        }
+        buf
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run_and_wait(command: &str, args: &[&str], input: &str, timeout: Duration) -> FormatterStatus {
+        let (tick_sender, tick_receiver) = crossbeam_channel::unbounded::<NavCompTick>();
+        let args: Vec<String> = args.iter().map(|arg| String::from(*arg)).collect();
+        let run = run_formatter(command, &args, None, input.to_string(), timeout, tick_sender);
+
+        assert!(tick_receiver.recv_timeout(Duration::from_secs(5)).is_ok());
+        let status = run.read().unwrap().clone();
+        status
+    }
+
+    #[test]
+    fn test_output_of_formatter_is_returned() {
+        let status = run_and_wait("tr", &["a-z", "A-Z"], "fn main() {}\n", Duration::from_secs(5));
+        assert_eq!(status, FormatterStatus::Finished("FN MAIN() {}\n".to_string()));
+    }
+
+    #[test]
+    fn test_failing_formatter_reports_stderr() {
+        let status = run_and_wait("sh", &["-c", "echo broken >&2; exit 3"], "", Duration::from_secs(5));
+        match status {
+            FormatterStatus::Failed(reason) => assert!(reason.contains("broken"), "{}", reason),
+            other => panic!("expected failure, got {:?}", other),
+        }
+
+        let status = run_and_wait("no-such-formatter-for-sure", &[], "", Duration::from_secs(5));
+        assert!(matches!(status, FormatterStatus::Failed(_)));
+    }
+
+    #[test]
+    fn test_hanging_formatter_gets_killed() {
+        let started = Instant::now();
+        let status = run_and_wait("sleep", &["10"], "", Duration::from_millis(100));
+
+        assert!(matches!(status, FormatterStatus::Failed(_)));
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -41,41 +41,48 @@ impl LabelPos {
         }
     }
 
-    pub fn into_position(&self, text_buffer: &dyn TextBuffer) -> Option<XY> {
-        match self {
+    /*
+    Position relative to first_line, which is the line of buffer drawn at y = 0. None for lines before it.
+     */
+    pub fn into_position(&self, text_buffer: &dyn TextBuffer, first_line: usize) -> Option<XY> {
+        let (x, line_no_0b) = match self {
             LabelPos::Inline { char_idx } => {
-                if let Some(line_no_0b) = text_buffer.char_to_line(*char_idx) {
-                    debug_assert!(line_no_0b <= *char_idx);
-                    let line_begin_char_idx_0b = text_buffer.line_to_char(line_no_0b)?;
-                    let in_line_char_idx = char_idx - line_begin_char_idx_0b;
+                let line_no_0b = text_buffer.char_to_line(*char_idx)?;
+                debug_assert!(line_no_0b <= *char_idx);
+                let line_begin_char_idx_0b = text_buffer.line_to_char(line_no_0b)?;
+                let in_line_char_idx = char_idx - line_begin_char_idx_0b;
 
-                    debug_assert!(line_no_0b <= u16::MAX as usize);
-                    debug_assert!(in_line_char_idx <= u16::MAX as usize);
+                debug_assert!(in_line_char_idx <= u16::MAX as usize);
 
-                    Some(XY::new(in_line_char_idx as u16, line_no_0b as u16))
-                } else {
-                    None
-                }
+                (in_line_char_idx as u16, line_no_0b)
+            }
+            LabelPos::InlineStupid { stupid_cursor } => {
+                let xy = stupid_cursor.to_xy(text_buffer)?;
+                (xy.x, xy.y as usize)
             }
-            LabelPos::InlineStupid { stupid_cursor } => stupid_cursor.to_xy(text_buffer),
             LabelPos::LineAfter { line_no_1b } => {
                 debug_assert!(*line_no_1b >= 1);
-                if text_buffer.len_lines() + 1 > *line_no_1b {
-                    let line_no_0b = line_no_1b - 1;
-                    if line_no_0b > u16::MAX as usize {
-                        warn!("line too far");
-                        return None;
-                    }
-
-                    let line = unpack_or!(text_buffer.get_line(line_no_0b), None);
-
-                    // I add NEWLINE_WIDTH to cover the "⏎" char
-                    Some(XY::new(line.screen_width() + NEWLINE_WIDTH, line_no_0b as u16))
-                } else {
-                    None
+                if *line_no_1b > text_buffer.len_lines() {
+                    return None;
                 }
+                let line_no_0b = line_no_1b - 1;
+                if line_no_0b < first_line {
+                    return None;
+                }
+                let line = unpack_or!(text_buffer.get_line(line_no_0b), None);
+
+                // I add NEWLINE_WIDTH to cover the "⏎" char
+                (line.screen_width() + NEWLINE_WIDTH, line_no_0b)
             }
+        };
+
+        let y = line_no_0b.checked_sub(first_line)?;
+        if y > u16::MAX as usize {
+            warn!("line too far");
+            return None;
         }
+
+        Some(XY::new(x, y as u16))
     }
 }
 
@@ -5,11 +5,14 @@ mod context_bar;
 pub use context_bar::*;
 
 mod context_options_matrix;
-pub use context_options_matrix::get_context_options;
+pub use context_options_matrix::{get_context_options, get_global_context_options};
 
 mod editor_widget;
 pub use editor_widget::{EditorState, EditorWidget, HoverSettings};
 
+mod external_formatter;
+pub use external_formatter::{run_formatter, FormatterRunRef, FormatterStatus};
+
 mod helpers;
 pub use helpers::{find_trigger_and_substring, CursorScreenPosition};
 
@@ -17,7 +20,7 @@ mod label;
 pub use label::*;
 
 mod msg;
-pub use msg::EditorWidgetMsg;
+pub use msg::{ContextSubmenu, EditorWidgetMsg};
 
 #[cfg(test)]
 mod tests;
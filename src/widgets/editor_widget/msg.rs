@@ -25,6 +25,43 @@ pub enum EditorWidgetMsg {
     Reformat,
     GoToDefinition,
     ShowUsages,
+
+    // git hunks (changes since HEAD)
+    NextHunk,
+    PrevHunk,
+    RevertHunk,
+
+    // git blame labels on/off
+    ToggleBlame,
+
+    JumpToMatchingBracket,
+
+    // grows column (block) selection towards arrow
+    ColumnSelect(Arrow),
+
+    // multi-cursor: cursor at next occurrence of selection (or word under cursor), optionally dropping
+    // the last one, or at all of them
+    AddNextOccurrence { skip: bool },
+    SelectAllOccurrences,
+
+    // merge conflict regions (<<<<<<< ... >>>>>>>)
+    NextConflict,
+    ResolveConflict(ConflictResolution),
+
+    // format buffer is saved in
+    SetEncoding(Encoding),
+    SetLineEnding(LineEnding),
+
+    // context bar of its own, for options too many to put in the main one
+    OpenContextSubmenu(ContextSubmenu),
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ContextSubmenu {
+    // encodings and line endings
+    FileFormat,
+    // case changes, encodings and numbering of selections
+    Transforms,
 }
 
 impl AnyMsg for EditorWidgetMsg {}
@@ -1,4 +1,5 @@
 use std::fmt::Debug;
+use std::path::{Path, PathBuf};
 use std::rc::Rc;
 
 use crate::*;
@@ -9,6 +10,11 @@ pub type SPathToMsg = fn(&SPath) -> Box<dyn AnyMsg>;
 pub struct FsfProvider {
     fsf: FsfRef,
     consider_ignores: bool,
+    // relative to root
+    excluded_dirs: Vec<PathBuf>,
+    // if present, ignored files are skipped (with consider_ignores) and git_filter_op can be used
+    git_status_op: Option<GitStatusRef>,
+    git_filter_op: Option<GitFileFilter>,
 }
 
 impl FsfProvider {
@@ -16,6 +22,37 @@ impl FsfProvider {
         Self {
             fsf,
             consider_ignores: false,
+            excluded_dirs: Vec::new(),
+            git_status_op: None,
+            git_filter_op: None,
+        }
+    }
+
+    pub fn with_git_status(self, git_status: GitStatusRef) -> Self {
+        Self {
+            git_status_op: Some(git_status),
+            ..self
+        }
+    }
+
+    pub fn with_git_filter(self, git_filter: GitFileFilter) -> Self {
+        Self {
+            git_filter_op: Some(git_filter),
+            ..self
+        }
+    }
+
+    fn passes_git_filters(&self, relative_path: &Path) -> bool {
+        let git_status = unpack_or!(self.git_status_op.as_ref(), true);
+        let lock = unpack_or_e!(git_status.read().ok(), true, "failed to lock git status");
+
+        if self.consider_ignores && lock.is_ignored(relative_path) {
+            return false;
+        }
+
+        match self.git_filter_op {
+            Some(filter) => lock.matches(filter, relative_path),
+            None => true,
         }
     }
 
@@ -25,6 +62,10 @@ impl FsfProvider {
             ..self
         }
     }
+
+    pub fn with_excluded_dirs(self, excluded_dirs: Vec<PathBuf>) -> Self {
+        Self { excluded_dirs, ..self }
+    }
 }
 
 #[derive(Debug)]
@@ -48,10 +89,15 @@ impl Item for SPath {
     }
 }
 
-// TODO reintroduce ignoring of gitignores
+// TODO ignores are respected only if workspace is a git repository
 impl ItemsProvider for FsfProvider {
     fn context_name(&self) -> Rc<String> {
-        Rc::new("fs".to_string())
+        let name = match self.git_filter_op {
+            None => "fs",
+            Some(GitFileFilter::ChangedOnly) => "changed files",
+            Some(GitFileFilter::TrackedOnly) => "tracked files",
+        };
+        Rc::new(name.to_string())
     }
 
     fn items(&self, query: String, limit: usize) -> Box<dyn Iterator<Item = Box<dyn Item + '_>> + '_> {
@@ -59,8 +105,14 @@ impl ItemsProvider for FsfProvider {
             self.fsf
                 .root()
                 .recursive_iter()
+                .with_excluded_dirs(self.excluded_dirs.clone())
                 .filter(move |item| {
-                    let item_str = item.relative_path().to_string_lossy().to_string();
+                    let relative_path = item.relative_path();
+                    if !self.passes_git_filters(&relative_path) {
+                        return false;
+                    }
+
+                    let item_str = relative_path.to_string_lossy().to_string();
                     is_subsequence(&item_str, &query)
                 })
                 .map(|f| Box::new(f) as Box<dyn Item>)
@@ -20,6 +20,8 @@ pub struct GenericDialog {
     text_widget: TextWidget,
 
     with_border: Option<&'static BorderStyle>,
+    // if set, size accounts for frame margins and border is drawn in them, see with_fitted_frame
+    fitted_frame: bool,
 
     buttons: Vec<ButtonWidget>,
     keystroke: Option<Box<dyn KeyToMsg>>,
@@ -41,6 +43,7 @@ impl GenericDialog {
             display_state: None,
             text_widget: TextWidget::new(text),
             with_border: None,
+            fitted_frame: false,
             buttons: vec![],
             keystroke: None,
         }
@@ -71,6 +74,17 @@ impl GenericDialog {
         }
     }
 
+    /*
+    Makes room for the whole text and buttons within frame margins, and draws the border (if set) in
+    them. Meant for dialogs with several lines of text, that would be cut otherwise.
+     */
+    pub fn with_fitted_frame(self) -> Self {
+        Self {
+            fitted_frame: true,
+            ..self
+        }
+    }
+
     pub fn get_total_options_width(&self, interval: u16) -> u16 {
         let mut result: usize = 0;
         for (idx, button) in self.buttons.iter().enumerate() {
@@ -123,12 +137,17 @@ impl Widget for GenericDialog {
         if !self.buttons.is_empty() {
             let op_widths = self.get_total_options_width(DEFAULT_INTERVAL);
 
-            total_size.y += 2;
+            total_size.y += if self.fitted_frame { 1 } else { 2 };
             if total_size.x < op_widths {
                 total_size.x = op_widths;
             }
         }
 
+        if self.fitted_frame {
+            // margins of FrameLayout (see get_layout), border is drawn in them
+            return total_size + XY::new(4, 4);
+        }
+
         total_size + if self.with_border.is_some() { XY::new(2, 2) } else { XY::ZERO }
     }
 
@@ -176,7 +195,23 @@ impl Widget for GenericDialog {
     }
 
     fn render(&self, theme: &Theme, focused: bool, output: &mut dyn Output) {
-        self.complex_render(theme, focused, output)
+        #[cfg(test)]
+        {
+            let size = unpack_or_e!(self.display_state.as_ref(), (), "render before layout").total_size;
+            output.emit_metadata(Metadata {
+                id: self.id(),
+                typename: self.typename().to_string(),
+                rect: Rect::from_zero(size),
+                focused,
+            });
+        }
+
+        self.complex_render(theme, focused, output);
+        if self.fitted_frame {
+            if let Some(border_style) = self.with_border {
+                border_style.draw_edges(theme.default_text(focused), output);
+            }
+        }
     }
 
     fn get_focused(&self) -> Option<&dyn Widget> {
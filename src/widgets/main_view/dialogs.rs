@@ -0,0 +1,48 @@
+use crate::*;
+
+const RECOVER_STRING: &'static str = "Recover";
+const DIFF_STRING: &'static str = "Diff";
+const DISCARD_STRING: &'static str = "Discard";
+const RELOAD_STRING: &'static str = "Reload";
+const KEEP_STRING: &'static str = "Keep";
+
+/*
+Asks what to do with unsaved changes found in a swap file. Diff is offered only if there's a file to
+compare them with.
+ */
+pub fn swap_recovery_dialog(swap: &SwapFile, with_diff: bool) -> GenericDialog {
+    let name = match &swap.path {
+        Some(path) => format!("\"{}\"", path.to_string_lossy()),
+        None => "unnamed buffer".to_string(),
+    };
+    let text = format!("Unsaved changes of\n {}\n were left by previous session.", name);
+
+    let mut dialog = GenericDialog::new(Box::new(text))
+        .with_border(&SINGLE_BORDER_STYLE)
+        .with_fitted_frame()
+        .with_option(ButtonWidget::new(Box::new(RECOVER_STRING)).with_on_hit(|_| MainViewMsg::RecoverSwap.someboxed()));
+
+    if with_diff {
+        dialog.add_option(ButtonWidget::new(Box::new(DIFF_STRING)).with_on_hit(|_| MainViewMsg::DiffSwap.someboxed()));
+    }
+
+    dialog.with_option(ButtonWidget::new(Box::new(DISCARD_STRING)).with_on_hit(|_| MainViewMsg::DiscardSwap.someboxed()))
+}
+
+/*
+Asks what to do about file that was changed on disk, while its buffer has unsaved changes: reload it
+(losing them, though it can be undone), keep them, or compare them with the file.
+ */
+pub fn external_change_dialog(path: &SPath) -> GenericDialog {
+    let text = format!(
+        "File\n \"{}\"\n was changed on disk, but it has\n unsaved changes here.",
+        path.relative_path().to_string_lossy()
+    );
+
+    GenericDialog::new(Box::new(text))
+        .with_border(&SINGLE_BORDER_STYLE)
+        .with_fitted_frame()
+        .with_option(ButtonWidget::new(Box::new(RELOAD_STRING)).with_on_hit(|_| MainViewMsg::ReloadChanged.someboxed()))
+        .with_option(ButtonWidget::new(Box::new(KEEP_STRING)).with_on_hit(|_| MainViewMsg::KeepChanged.someboxed()))
+        .with_option(ButtonWidget::new(Box::new(DIFF_STRING)).with_on_hit(|_| MainViewMsg::DiffChanged.someboxed()))
+}
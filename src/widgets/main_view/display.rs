@@ -3,6 +3,9 @@ use crate::*;
 pub enum MainViewDisplay {
     Editor(EditorView),
     ResultsView(CodeResultsView),
+    TaskOutput(TaskOutputView),
+    Terminal(TerminalView),
+    Diff(DiffView),
 }
 
 impl MainViewDisplay {
@@ -10,6 +13,9 @@ impl MainViewDisplay {
         match self {
             MainViewDisplay::Editor(e) => e,
             MainViewDisplay::ResultsView(r) => r,
+            MainViewDisplay::TaskOutput(t) => t,
+            MainViewDisplay::Terminal(t) => t,
+            MainViewDisplay::Diff(d) => d,
         }
     }
 
@@ -17,6 +23,9 @@ impl MainViewDisplay {
         match self {
             MainViewDisplay::Editor(e) => e,
             MainViewDisplay::ResultsView(r) => r,
+            MainViewDisplay::TaskOutput(t) => t,
+            MainViewDisplay::Terminal(t) => t,
+            MainViewDisplay::Diff(d) => d,
         }
     }
 }
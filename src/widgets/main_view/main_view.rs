@@ -1,3 +1,4 @@
+use std::path::PathBuf;
 use std::rc::Rc;
 
 use log::{debug, error, warn};
@@ -9,6 +10,10 @@ pub type BufferId = Uuid;
 
 pub enum HoverItem {
     FuzzySearch(WithScroll<FuzzySearchWidget>),
+    UndoTree(UndoTreeWidget),
+    ContextBar(ContextBarWidget),
+    SwapRecovery(GenericDialog),
+    ExternalChange(GenericDialog),
 }
 
 // TODO start indexing documents with DocumentIdentifier as opposed to usize
@@ -52,6 +57,13 @@ pub struct MainView {
     display_idx: usize,
 
     hover: Option<HoverItem>,
+    // set while fuzzy file search picks a file to compare this one with
+    compare_with_op: Option<SPath>,
+    // swap files left by previous session, waiting for the user to decide about them. First one is shown.
+    swap_recoveries: Vec<(SPath, SwapFile)>,
+    // files changed on disk while their buffers had unsaved changes, waiting for the user to decide about them.
+    // First one is shown.
+    external_changes: Vec<SPath>,
 }
 
 impl MainView {
@@ -136,6 +148,17 @@ impl MainView {
 
                             DisplayItem::new(idx, text.into())
                         }
+                        MainViewDisplay::TaskOutput(task) => DisplayItem::new(idx, Rc::new(format!("task [{}]", task.get_task_name()))),
+                        MainViewDisplay::Diff(diff) => DisplayItem::new(idx, Rc::new(format!("diff [{}]", diff.get_title()))),
+                        MainViewDisplay::Terminal(terminal) => {
+                            let text = match (terminal.get_title(), terminal.has_exited()) {
+                                (_, true) => format!("terminal #{} (exited)", idx),
+                                (Some(title), false) => format!("terminal #{} [{}]", idx, title),
+                                (None, false) => format!("terminal #{}", idx),
+                            };
+
+                            DisplayItem::new(idx, Rc::new(text))
+                        }
                     }
                 })
                 .collect::<Vec<_>>(),
@@ -154,11 +177,445 @@ impl MainView {
                     }
                 }
                 MainViewDisplay::ResultsView(_) => {}
+                MainViewDisplay::TaskOutput(_) => {}
+                MainViewDisplay::Terminal(_) => {}
+                MainViewDisplay::Diff(_) => {}
             }
         }
         None
     }
 
+    /*
+    Editors have their own everything bar, this one is shown when input reaches MainView, so there's no
+    editor focused (or none is open).
+     */
+    fn open_context_bar_and_focus(&mut self) {
+        let items = get_global_context_options(&self.providers);
+        self.hover = Some(HoverItem::ContextBar(
+            ContextBarWidget::new(items)
+                .with_on_close(|_| MainViewMsg::CloseHover.someboxed())
+                .with_size_policy(SizePolicy::MATCH_LAYOUT),
+        ));
+        self.set_focus_to_hover();
+    }
+
+    fn run_task_and_focus(&mut self, name: &str) -> bool {
+        let task = unpack_or_e!(
+            self.providers.workspace_settings().get_task(name).cloned(),
+            false,
+            "no task named {}",
+            name
+        );
+
+        self.displays
+            .push(MainViewDisplay::TaskOutput(TaskOutputView::run(self.providers.clone(), &task)));
+        self.display_idx = self.displays.len() - 1;
+        self.set_focus_to_default();
+        true
+    }
+
+    fn open_terminal_and_focus(&mut self) -> bool {
+        let terminal = match TerminalView::new(self.providers.clone()) {
+            Ok(terminal) => terminal,
+            Err(e) => {
+                error!("failed to start terminal: {}", e);
+                return false;
+            }
+        };
+
+        self.displays.push(MainViewDisplay::Terminal(terminal));
+        self.display_idx = self.displays.len() - 1;
+        self.set_focus_to_default();
+        true
+    }
+
+    fn open_diff_and_focus(&mut self, source: &DiffSource) -> bool {
+        let diff_view = unpack_or_e!(
+            DiffView::from_source(self.providers.clone(), source),
+            false,
+            "failed to create diff view for {:?}",
+            source
+        );
+
+        self.displays.push(MainViewDisplay::Diff(diff_view));
+        self.display_idx = self.displays.len() - 1;
+        self.set_focus_to_default();
+        true
+    }
+
+    /*
+    Commit is shown in a fresh scratch buffer, so all the usual editor goodies (search, copy) work on it.
+     */
+    fn show_commit_and_focus(&mut self, hash: &str) -> bool {
+        let git = unpack_or_e!(self.providers.git(), false, "no git, no commits");
+        let contents = match git.show_commit(hash) {
+            Ok(contents) => contents,
+            Err(e) => {
+                error!("failed to show commit {}: {}", hash, e);
+                return false;
+            }
+        };
+
+        let buffer = {
+            let mut buffer_register = unpack_or_e!(
+                self.providers.buffer_register().try_write().ok(),
+                false,
+                "failed to acquire register lock"
+            );
+            buffer_register.open_new_file(&self.providers)
+        };
+
+        match buffer.lock_rw() {
+            Some(mut buffer_lock) => buffer_lock.set_text(contents),
+            None => {
+                error!("failed to lock new buffer");
+                return false;
+            }
+        }
+
+        self.displays
+            .push(MainViewDisplay::Editor(EditorView::new(self.providers.clone(), buffer)));
+        self.display_idx = self.displays.len() - 1;
+        self.set_focus_to_default();
+        true
+    }
+
+    /*
+    When terminal is focused, it gets all the keys, so run_gladius needs to know not to intercept them.
+     */
+    pub fn is_terminal_focused(&self) -> bool {
+        if self.hover.is_some() {
+            return false;
+        }
+
+        match (self.displays.get(self.display_idx), self.get_focused()) {
+            (Some(MainViewDisplay::Terminal(terminal)), Some(focused)) => terminal.id() == focused.id(),
+            _ => false,
+        }
+    }
+
+    /*
+    Opens file and moves cursor to position. If position is outside the file, the file is opened anyway.
+     */
+    pub fn open_file_at_position(&mut self, ff: SPath, position: StupidCursor) -> bool {
+        if !self.open_file(ff.clone()) {
+            return false;
+        }
+
+        let editor = match self.displays.get_mut(self.display_idx) {
+            Some(MainViewDisplay::Editor(editor)) => editor.get_internal_widget_mut(),
+            _ => {
+                error!("expected editor under display_idx after opening {}", ff);
+                return false;
+            }
+        };
+
+        let cursor_op = editor.get_buffer().lock().and_then(|buffer| {
+            position
+                .to_real_cursor(&*buffer)
+                .or_else(|| StupidCursor::new(0, position.line_0b).to_real_cursor(&*buffer))
+        });
+
+        match cursor_op {
+            Some(cursor) => editor.set_cursors(CursorSet::singleton(cursor)),
+            None => {
+                warn!("position {:?} not found in {}", position, ff);
+                true
+            }
+        }
+    }
+
+    /*
+    Editor displaying the buffer does the jump, so it can update navcomp and scroll to the change.
+     */
+    fn jump_to_history_node(&mut self, buffer: &BufferSharedRef, node_id: HistoryNodeId) -> bool {
+        for display in self.displays.iter_mut() {
+            if let MainViewDisplay::Editor(editor) = display {
+                if editor.get_buffer_ref() == buffer {
+                    return editor.get_internal_widget_mut().jump_to_history_node(node_id);
+                }
+            }
+        }
+
+        warn!("no editor for buffer, jumping in undo tree without one");
+        buffer.lock_rw().map(|mut lock| lock.jump_to_history_node(node_id)).unwrap_or(false)
+    }
+
+    /*
+    Asks about each of swap files left by previous session (see SwapWriter), one by one: recover (opens
+    buffer with unsaved changes), diff (the same, then compares it with file on disk) or discard.
+     */
+    pub fn offer_swap_recovery(&mut self, swaps: Vec<(SPath, SwapFile)>) {
+        self.swap_recoveries = swaps;
+        self.show_next_swap_recovery();
+    }
+
+    fn show_next_swap_recovery(&mut self) {
+        match self.swap_recoveries.first() {
+            Some((_, swap)) => {
+                let with_diff = self.swap_target_file(swap).is_some();
+                self.hover = Some(HoverItem::SwapRecovery(swap_recovery_dialog(swap, with_diff)));
+                self.set_focus_to_hover();
+            }
+            None => {
+                self.hover = None;
+                self.set_focus_to_default();
+            }
+        }
+    }
+
+    fn swap_target_file(&self, swap: &SwapFile) -> Option<SPath> {
+        swap.path
+            .as_ref()
+            .and_then(|path| self.providers.fsf().descendant_checked(path))
+            .filter(|path| path.is_file())
+    }
+
+    /*
+    Opens buffer swap was written for (or a new one, if it was unnamed or file is gone) and puts unsaved
+    contents in it, as an edit that can be undone.
+     */
+    fn recover_swap(&mut self, swap: &SwapFile) -> bool {
+        match self.swap_target_file(swap) {
+            Some(path) => {
+                if !self.open_file(path.clone()) {
+                    error!("failed to open {} to recover it", path);
+                    return false;
+                }
+            }
+            None => self.open_empty_editor_and_focus(),
+        }
+
+        let buffer = match self.displays.get(self.display_idx) {
+            Some(MainViewDisplay::Editor(editor)) => editor.get_buffer_ref().clone(),
+            _ => {
+                error!("expected editor under display_idx after opening buffer to recover");
+                return false;
+            }
+        };
+
+        let mut buffer_lock = unpack_or_e!(buffer.lock_rw(), false, "failed to lock buffer to recover");
+        if buffer_lock.get_path().is_none() {
+            if let Some(path) = swap.path.as_ref().and_then(|path| self.providers.fsf().descendant_unchecked(path)) {
+                buffer_lock.set_file_path(Some(path));
+            }
+        }
+        buffer_lock.replace_text(&swap.contents);
+        true
+    }
+
+    fn answer_swap_recovery(&mut self, recover: bool, diff: bool) {
+        if self.swap_recoveries.is_empty() {
+            error!("no swap file to answer about");
+            return;
+        }
+
+        let (swap_path, swap) = self.swap_recoveries.remove(0);
+        self.hover = None;
+
+        if recover && !self.recover_swap(&swap) {
+            error!("failed to recover {}, leaving it on disk", swap_path);
+            self.show_next_swap_recovery();
+            return;
+        }
+
+        if diff {
+            if let Some(path) = self.swap_target_file(&swap) {
+                let source = DiffSource::BufferVsDisk { path };
+                if !self.open_diff_and_focus(&source) {
+                    error!("failed to open diff {:?}", source);
+                }
+            }
+        }
+
+        if let Err(e) = swap_path.remove_file() {
+            error!("failed to remove swap file {}: {:?}", swap_path, e);
+        }
+
+        self.show_next_swap_recovery();
+    }
+
+    /*
+    Reloads buffers whose files were changed by someone else (see check_external_changes), and asks about
+    ones with unsaved changes, one by one: reload, keep unsaved changes, or compare them with the file.
+    Questions wait until other hovers are closed.
+     */
+    pub fn check_files_on_disk(&mut self) {
+        let conflicts = match self.providers.buffer_register().try_read() {
+            Ok(register) => check_external_changes(register.buffers()),
+            Err(e) => {
+                error!("failed to lock buffer register, not checking files on disk: {}", e);
+                return;
+            }
+        };
+
+        for path in conflicts.into_iter() {
+            if !self.external_changes.contains(&path) {
+                self.external_changes.push(path);
+            }
+        }
+
+        if self.hover.is_none() && !self.external_changes.is_empty() {
+            self.show_next_external_change();
+        }
+    }
+
+    fn show_next_external_change(&mut self) {
+        match self.external_changes.first() {
+            Some(path) => {
+                self.hover = Some(HoverItem::ExternalChange(external_change_dialog(path)));
+                self.set_focus_to_hover();
+            }
+            None => {
+                self.hover = None;
+                self.set_focus_to_default();
+            }
+        }
+    }
+
+    /*
+    Both keeping and comparing take current version of file as the one buffer was read from, so it's not
+    asked about again, and saving overwrites it without a warning.
+     */
+    fn answer_external_change(&mut self, reload: bool, diff: bool) {
+        if self.external_changes.is_empty() {
+            error!("no changed file to answer about");
+            return;
+        }
+
+        let path = self.external_changes.remove(0);
+        self.hover = None;
+
+        let buffer_op = match self.providers.buffer_register().try_read() {
+            Ok(register) => register.get_buffer_ref_from_path(&path),
+            Err(e) => {
+                error!("failed to lock buffer register: {}", e);
+                None
+            }
+        };
+
+        match buffer_op.as_ref().and_then(|buffer| buffer.lock_rw()) {
+            Some(mut buffer_lock) => {
+                if reload {
+                    if let Err(e) = reload_from_disk(&mut buffer_lock) {
+                        error!("failed to reload {}: {}", path, e);
+                    }
+                } else {
+                    buffer_lock.set_disk_stamp(path.stamp().ok());
+                }
+            }
+            None => {
+                error!("failed to get buffer of {}", path);
+            }
+        }
+
+        if diff {
+            let source = DiffSource::BufferVsDisk { path };
+            if !self.open_diff_and_focus(&source) {
+                error!("failed to open diff {:?}", source);
+            }
+        }
+
+        self.show_next_external_change();
+    }
+
+    /*
+    Open editors of files with their cursors and scroll, and expanded dirs of file tree. Other displays
+    are skipped, see Session.
+     */
+    pub fn session(&self) -> Session {
+        let mut editors: Vec<SessionEditor> = Vec::new();
+        let mut display_idx: Option<usize> = None;
+
+        for (idx, display) in self.displays.iter().enumerate() {
+            let (editor, path) = match display {
+                MainViewDisplay::Editor(editor) => match editor.get_path() {
+                    Some(path) => (editor, path),
+                    None => continue,
+                },
+                _ => continue,
+            };
+
+            let editor_widget = editor.get_internal_widget();
+            let cursors_op = editor_widget
+                .get_buffer()
+                .lock()
+                .and_then(|buffer| buffer.cursors(editor_widget.id()).cloned());
+            let cursors = match cursors_op {
+                Some(cursors) => cursors,
+                None => {
+                    error!("no cursors of editor of {}", path);
+                    continue;
+                }
+            };
+
+            if idx == self.display_idx {
+                display_idx = Some(editors.len());
+            }
+            editors.push(SessionEditor {
+                path: path.relative_path(),
+                cursors,
+                scroll_offset: editor.scroll_offset(),
+            });
+        }
+
+        let mut expanded: Vec<PathBuf> = self
+            .tree_widget
+            .internal()
+            .expanded()
+            .iter()
+            .map(|spath| spath.relative_path())
+            .collect();
+        expanded.sort();
+
+        Session {
+            editors,
+            display_idx,
+            expanded,
+        }
+    }
+
+    /*
+    Reopens what's left of session: files that are gone are skipped, cursors that don't fit the file
+    anymore are left at the beginning.
+     */
+    pub fn restore_session(&mut self, session: &Session) {
+        let root = self.providers.fsf().root();
+        let mut display_indices: Vec<Option<usize>> = Vec::new();
+
+        for session_editor in session.editors.iter() {
+            let path_op = root.descendant_checked(&session_editor.path).filter(|path| path.is_file());
+            let opened = match path_op {
+                Some(path) if self.open_file(path.clone()) => path,
+                _ => {
+                    debug!("failed to reopen {:?} from session", session_editor.path);
+                    display_indices.push(None);
+                    continue;
+                }
+            };
+            display_indices.push(Some(self.display_idx));
+
+            if let Some(MainViewDisplay::Editor(editor)) = self.displays.get_mut(self.display_idx) {
+                if !editor.get_internal_widget_mut().set_cursors(session_editor.cursors.clone()) {
+                    warn!("cursors from session don't fit {}", opened);
+                }
+                editor.set_scroll_offset(session_editor.scroll_offset);
+            }
+        }
+
+        // parents are expanded with their children, so the dirs are visible
+        for relative_path in session.expanded.iter() {
+            if let Some(dir) = root.descendant_checked(relative_path).filter(|path| path.is_dir()) {
+                self.tree_widget.internal_mut().expand_path(&dir);
+            }
+        }
+
+        if let Some(idx) = session.display_idx.and_then(|idx| display_indices.get(idx).copied().flatten()) {
+            self.display_idx = idx;
+            self.set_focus_to_default();
+        }
+    }
+
     fn get_hover_rect(screenspace: Screenspace) -> Option<Rect> {
         let output_size = screenspace.output_size();
         if output_size >= XY::new(10, 8) {
@@ -172,7 +629,12 @@ impl MainView {
 
     pub fn new(providers: Providers) -> MainView {
         let root = providers.fsf().root();
-        let tree = TreeViewWidget::new(FileTreeNode::new(root.clone()))
+        let mut root_node = FileTreeNode::new(root.clone()).with_workspace_settings(providers.workspace_settings().clone());
+        if providers.git().is_some() {
+            root_node = root_node.with_git_status(providers.git_status().clone());
+        }
+
+        let tree = TreeViewWidget::new(root_node)
             .with_on_flip_expand(|widget| {
                 let (_, item) = widget.get_highlighted();
 
@@ -197,6 +659,9 @@ impl MainView {
             no_editor: NoEditorWidget::default(),
             display_idx: 0,
             hover: None,
+            compare_with_op: None,
+            swap_recoveries: vec![],
+            external_changes: vec![],
         }
     }
 
@@ -220,13 +685,13 @@ impl MainView {
 
         if let Some(idx) = self.get_editor_idx_for(&ff) {
             self.display_idx = idx;
-            self.set_focused(self.get_default_focused());
+            self.set_focus_to_default();
             true
         } else {
             self.create_new_editor_for_file(&ff)
                 .map(|idx| {
                     self.display_idx = idx;
-                    self.set_focused(self.get_default_focused());
+                    self.set_focus_to_default();
                 })
                 .is_ok()
         }
@@ -245,14 +710,27 @@ impl MainView {
         self.set_focus_to_hover();
     }
 
-    fn open_fuzzy_search_in_files_and_focus(&mut self) {
+    fn open_fuzzy_search_in_files_and_focus(&mut self, git_filter_op: Option<GitFileFilter>) {
+        let mut provider = FsfProvider::new(self.providers.fsf().clone())
+            .with_ignores_filter()
+            .with_excluded_dirs(self.providers.workspace_settings().excluded_dirs.clone());
+
+        if self.providers.git().is_some() {
+            provider = provider.with_git_status(self.providers.git_status().clone());
+            if let Some(filter) = git_filter_op {
+                provider = provider.with_git_filter(filter);
+            }
+        } else if git_filter_op.is_some() {
+            warn!("git filter requested, but workspace is not a git repository, ignoring");
+        }
+
         self.hover = Some(HoverItem::FuzzySearch(WithScroll::new(
             ScrollDirection::Vertical,
             FuzzySearchWidget::new(
                 |_| Some(Box::new(MainViewMsg::CloseHover)),
                 Some(self.providers.clipboard().clone()),
             )
-            .with_provider(Box::new(FsfProvider::new(self.providers.fsf().clone()).with_ignores_filter()))
+            .with_provider(Box::new(provider))
             .with_draw_comment_setting(DrawComment::Highlighted),
         )));
         self.set_focus_to_hover();
@@ -263,17 +741,21 @@ impl MainView {
         self.set_focused(ptr);
     }
 
-    fn set_focus_to_hover(&mut self) {
-        let ptr_to_hover = SubwidgetPointer::<Self>::new(
+    fn get_hover_ptr(&self) -> SubwidgetPointer<Self> {
+        SubwidgetPointer::<Self>::new(
             Box::new(|s: &MainView| {
                 let hover_present = s.hover.is_some();
                 if hover_present {
                     match s.hover.as_ref().unwrap() {
                         HoverItem::FuzzySearch(fs) => fs as &dyn Widget,
+                        HoverItem::UndoTree(ut) => ut as &dyn Widget,
+                        HoverItem::ContextBar(cb) => cb as &dyn Widget,
+                        HoverItem::SwapRecovery(dialog) => dialog as &dyn Widget,
+                        HoverItem::ExternalChange(dialog) => dialog as &dyn Widget,
                     }
                 } else {
                     error!("failed to unwrap hover widget!");
-                    s.get_default_focused().get(s)
+                    s.get_curr_display_ptr().get(s)
                 }
             }),
             Box::new(|s: &mut MainView| {
@@ -281,15 +763,21 @@ impl MainView {
                 if hover_present {
                     match s.hover.as_mut().unwrap() {
                         HoverItem::FuzzySearch(fs) => fs as &mut dyn Widget,
+                        HoverItem::UndoTree(ut) => ut as &mut dyn Widget,
+                        HoverItem::ContextBar(cb) => cb as &mut dyn Widget,
+                        HoverItem::SwapRecovery(dialog) => dialog as &mut dyn Widget,
+                        HoverItem::ExternalChange(dialog) => dialog as &mut dyn Widget,
                     }
                 } else {
                     error!("failed to unwrap hover widget!");
-                    s.get_default_focused().get_mut(s)
+                    s.get_curr_display_ptr().get_mut(s)
                 }
             }),
-        );
+        )
+    }
 
-        self.set_focused(ptr_to_hover);
+    fn set_focus_to_hover(&mut self) {
+        self.set_focused(self.get_hover_ptr());
     }
 
     pub fn with_empty_editor(mut self) -> Self {
@@ -340,6 +828,7 @@ impl Widget for MainView {
             }
             InputEvent::KeyInput(key) if key == config.keyboard_config.global.new_buffer => MainViewMsg::OpenNewFile.someboxed(),
             InputEvent::KeyInput(key) if key == config.keyboard_config.global.fuzzy_file => MainViewMsg::OpenFuzzyFiles.someboxed(),
+            InputEvent::EverythingBarTrigger if self.hover.is_none() => MainViewMsg::OpenContextBar.someboxed(),
             InputEvent::KeyInput(key) if key == config.keyboard_config.global.browse_buffers => {
                 if self.displays.is_empty() {
                     debug!("ignoring browse_buffers request - no displays open.");
@@ -359,6 +848,14 @@ impl Widget for MainView {
         debug!("main_view.update {:?}", msg);
 
         if let Some(main_view_msg) = msg.as_msg_mut::<MainViewMsg>() {
+            // everything bar items are addressed to MainView, so anything but closing means one was picked
+            let picked_from_context_bar = matches!(self.hover, Some(HoverItem::ContextBar(_)))
+                && !matches!(main_view_msg, MainViewMsg::FocusUpdateMsg(_) | MainViewMsg::CloseHover);
+            if picked_from_context_bar {
+                self.hover = None;
+                self.set_focus_to_default();
+            }
+
             return match main_view_msg {
                 MainViewMsg::FocusUpdateMsg(focus_update) => {
                     if !self.update_focus(*focus_update) {
@@ -376,7 +873,11 @@ impl Widget for MainView {
                     None
                 }
                 MainViewMsg::OpenFuzzyFiles => {
-                    self.open_fuzzy_search_in_files_and_focus();
+                    self.open_fuzzy_search_in_files_and_focus(None);
+                    None
+                }
+                MainViewMsg::OpenFuzzyFilesFiltered { filter } => {
+                    self.open_fuzzy_search_in_files_and_focus(Some(*filter));
                     None
                 }
                 MainViewMsg::CloseHover => {
@@ -385,6 +886,7 @@ impl Widget for MainView {
                     }
 
                     self.hover = None;
+                    self.compare_with_op = None;
                     None
                 }
                 MainViewMsg::OpenFuzzyBuffers => {
@@ -427,6 +929,94 @@ impl Widget for MainView {
                     }
                     None
                 }
+                MainViewMsg::OpenContextBar => {
+                    self.open_context_bar_and_focus();
+                    None
+                }
+                MainViewMsg::RunTask { name } => {
+                    let name = name.clone();
+                    if !self.run_task_and_focus(&name) {
+                        error!("failed to run task {}", name);
+                    }
+                    None
+                }
+                MainViewMsg::OpenFileAtPosition { file, position } => {
+                    let (file, position) = (file.clone(), *position);
+                    if !self.open_file_at_position(file, position) {
+                        error!("failed open_file_at_position");
+                    }
+                    None
+                }
+                MainViewMsg::OpenTerminal => {
+                    if !self.open_terminal_and_focus() {
+                        error!("failed to open terminal");
+                    }
+                    None
+                }
+                MainViewMsg::ShowCommit { hash } => {
+                    let hash = hash.clone();
+                    if !self.show_commit_and_focus(&hash) {
+                        error!("failed to show commit {}", hash);
+                    }
+                    None
+                }
+                MainViewMsg::OpenDiff { source } => {
+                    let source = source.clone();
+                    if !self.open_diff_and_focus(&source) {
+                        error!("failed to open diff {:?}", source);
+                    }
+                    None
+                }
+                MainViewMsg::CompareWithFile { path } => {
+                    self.compare_with_op = Some(path.clone());
+                    self.open_fuzzy_search_in_files_and_focus(None);
+                    None
+                }
+                MainViewMsg::OpenUndoTree { buffer } => {
+                    self.hover = Some(HoverItem::UndoTree(UndoTreeWidget::new(
+                        buffer.clone(),
+                        Some(self.providers.clipboard().clone()),
+                    )));
+                    self.set_focus_to_hover();
+                    None
+                }
+                MainViewMsg::JumpToHistoryNode { buffer, node_id } => {
+                    let (buffer, node_id) = (buffer.clone(), *node_id);
+                    self.hover = None;
+                    self.set_focus_to_default();
+                    if !self.jump_to_history_node(&buffer, node_id) {
+                        debug!("jump to history node {} changed nothing", node_id);
+                    }
+                    None
+                }
+                MainViewMsg::FocusTree => {
+                    self.set_focused(subwidget!(Self.tree_widget));
+                    None
+                }
+                MainViewMsg::RecoverSwap => {
+                    self.answer_swap_recovery(true, false);
+                    None
+                }
+                MainViewMsg::DiffSwap => {
+                    self.answer_swap_recovery(true, true);
+                    None
+                }
+                MainViewMsg::DiscardSwap => {
+                    self.answer_swap_recovery(false, false);
+                    None
+                }
+                MainViewMsg::ReloadChanged => {
+                    self.answer_external_change(true, false);
+                    None
+                }
+                MainViewMsg::KeepChanged => {
+                    self.answer_external_change(false, false);
+                    None
+                }
+                MainViewMsg::DiffChanged => {
+                    self.answer_external_change(false, true);
+                    None
+                }
                 _ => {
                     warn!("unprocessed event {:?}", main_view_msg);
                     None
@@ -438,7 +1028,20 @@ impl Widget for MainView {
             return match fuzzy_file_msg {
                 SPathMsg::Hit(file_front) => {
                     if file_front.is_file() {
-                        self.open_file(file_front.clone());
+                        match self.compare_with_op.take() {
+                            Some(left) => {
+                                let source = DiffSource::Files {
+                                    left,
+                                    right: file_front.clone(),
+                                };
+                                if !self.open_diff_and_focus(&source) {
+                                    error!("failed to open diff {:?}", source);
+                                }
+                            }
+                            None => {
+                                self.open_file(file_front.clone());
+                            }
+                        }
                         self.hover = None;
                         None
                     } else if file_front.is_dir() {
@@ -483,13 +1086,25 @@ impl ComplexWidget for MainView {
 
         let res = if let Some(hover) = &self.hover {
             match hover {
-                HoverItem::FuzzySearch(_fuzzy) => {
+                HoverItem::FuzzySearch(_)
+                | HoverItem::UndoTree(_)
+                | HoverItem::ContextBar(_)
+                | HoverItem::SwapRecovery(_)
+                | HoverItem::ExternalChange(_) => {
                     let hover = LeafLayout::new(SubwidgetPointer::new(
                         Box::new(|s: &Self| match s.hover.as_ref().unwrap() {
-                            HoverItem::FuzzySearch(fs) => fs,
+                            HoverItem::FuzzySearch(fs) => fs as &dyn Widget,
+                            HoverItem::UndoTree(ut) => ut as &dyn Widget,
+                            HoverItem::ContextBar(cb) => cb as &dyn Widget,
+                            HoverItem::SwapRecovery(dialog) => dialog as &dyn Widget,
+                            HoverItem::ExternalChange(dialog) => dialog as &dyn Widget,
                         }),
                         Box::new(|s: &mut Self| match s.hover.as_mut().unwrap() {
-                            HoverItem::FuzzySearch(fs) => fs,
+                            HoverItem::FuzzySearch(fs) => fs as &mut dyn Widget,
+                            HoverItem::UndoTree(ut) => ut as &mut dyn Widget,
+                            HoverItem::ContextBar(cb) => cb as &mut dyn Widget,
+                            HoverItem::SwapRecovery(dialog) => dialog as &mut dyn Widget,
+                            HoverItem::ExternalChange(dialog) => dialog as &mut dyn Widget,
                         }),
                     ))
                     .boxed();
@@ -505,7 +1120,12 @@ impl ComplexWidget for MainView {
     }
 
     fn get_default_focused(&self) -> SubwidgetPointer<MainView> {
-        self.get_curr_display_ptr()
+        // hover can be opened before first layout (see offer_swap_recovery)
+        if self.hover.is_some() {
+            self.get_hover_ptr()
+        } else {
+            self.get_curr_display_ptr()
+        }
     }
 
     fn set_display_state(&mut self, display_state: DisplayState<MainView>) {
@@ -1,3 +1,6 @@
+mod dialogs;
+pub use dialogs::{external_change_dialog, swap_recovery_dialog};
+
 mod display;
 pub use display::MainViewDisplay;
 
@@ -15,6 +15,8 @@ pub enum MainViewMsg {
 
     // Open fuzzy files
     OpenFuzzyFiles,
+    // Same, but only files matching git status filter
+    OpenFuzzyFilesFiltered { filter: GitFileFilter },
     // Used by OpenOpenBuffers too
     CloseHover,
 
@@ -31,6 +33,44 @@ pub enum MainViewMsg {
     OpenFile { file: DocumentIdentifier, position_op: Cursor },
 
     BufferChangedName { updated_identifier: DocumentIdentifier },
+
+    // opens everything bar with options available without an editor (tasks, terminal etc.)
+    OpenContextBar,
+
+    // runs a task defined in workspace settings, by name
+    RunTask { name: String },
+
+    // opens file (or focuses already opened one) and moves cursor to given position
+    OpenFileAtPosition { file: SPath, position: StupidCursor },
+
+    // opens a new embedded terminal
+    OpenTerminal,
+
+    // moves focus to file tree, used to "leave" widgets that consume all input (like terminal)
+    FocusTree,
+
+    // opens full message and diff of a commit (by hash) in a new buffer
+    ShowCommit { hash: String },
+
+    // opens side-by-side diff
+    OpenDiff { source: DiffSource },
+    // opens fuzzy file search, picked file is compared with given one
+    CompareWithFile { path: SPath },
+
+    // opens browser of buffer's undo tree
+    OpenUndoTree { buffer: BufferSharedRef },
+    // moves buffer to a state picked in undo tree browser, closing it
+    JumpToHistoryNode { buffer: BufferSharedRef, node_id: HistoryNodeId },
+
+    // answers to swap recovery dialog, about the swap file it shows
+    RecoverSwap,
+    DiffSwap,
+    DiscardSwap,
+
+    // answers to external change dialog, about the file it shows
+    ReloadChanged,
+    KeepChanged,
+    DiffChanged,
 }
 
 impl AnyMsg for MainViewMsg {}
@@ -15,6 +15,9 @@ pub use code_results_view::*;
 
 mod dir_tree_view;
 
+mod diff_view;
+pub use diff_view::*;
+
 mod dump_visualizer_widget;
 pub use dump_visualizer_widget::DumpVisualizerWidget;
 
@@ -52,14 +55,23 @@ mod spath_list_widget_item;
 mod spath_tree_view_node;
 pub use spath_tree_view_node::{DirTreeNode, FileTreeNode};
 
+mod task_output_view;
+pub use task_output_view::*;
+
+mod terminal_view;
+pub use terminal_view::*;
+
 mod text_widget;
 pub use text_widget::TextWidget;
 
 mod tree_view;
 pub use tree_view::*;
 
+mod undo_tree_view;
+pub use undo_tree_view::*;
+
 mod with_scroll;
-pub use with_scroll::WithScroll;
+pub use with_scroll::{LineMarker, WithScroll};
 
 // TODO: rename/move, includes test helpers only
 #[cfg(test)]
@@ -23,11 +23,44 @@ impl DirTreeNode {
 #[derive(Debug, Clone)]
 pub struct FileTreeNode {
     sp: SPath,
+    // if set, labels are colored according to git status
+    git_status_op: Option<GitStatusRef>,
+    // if set, excluded_dirs are not listed
+    workspace_settings_op: Option<WorkspaceSettingsRef>,
 }
 
 impl FileTreeNode {
     pub fn new(sp: SPath) -> Self {
-        FileTreeNode { sp }
+        FileTreeNode {
+            sp,
+            git_status_op: None,
+            workspace_settings_op: None,
+        }
+    }
+
+    pub fn with_git_status(self, git_status: GitStatusRef) -> Self {
+        FileTreeNode {
+            git_status_op: Some(git_status),
+            ..self
+        }
+    }
+
+    pub fn with_workspace_settings(self, workspace_settings: WorkspaceSettingsRef) -> Self {
+        FileTreeNode {
+            workspace_settings_op: Some(workspace_settings),
+            ..self
+        }
+    }
+
+    pub fn git_status(&self) -> Option<GitFileStatus> {
+        let git_status = self.git_status_op.as_ref()?;
+        let lock = unpack_or_e!(git_status.read().ok(), None, "failed to lock git status");
+        let relative_path = self.sp.relative_path();
+        if self.sp.is_file() {
+            lock.file_status(&relative_path)
+        } else {
+            lock.dir_status(&relative_path)
+        }
     }
 
     pub fn spath(&self) -> &SPath {
@@ -50,7 +83,23 @@ impl TreeViewNode<SPath> for FileTreeNode {
 
     fn child_iter(&self) -> Box<dyn Iterator<Item = Self>> {
         match self.sp.blocking_list() {
-            Ok(items) => Box::new(items.into_iter().map(|item| FileTreeNode::new(item))) as Box<dyn Iterator<Item = Self>>,
+            Ok(items) => {
+                let children: Vec<FileTreeNode> = items
+                    .into_iter()
+                    .filter(|item| {
+                        self.workspace_settings_op
+                            .as_ref()
+                            .map(|settings| !settings.is_excluded(item))
+                            .unwrap_or(true)
+                    })
+                    .map(|item| FileTreeNode {
+                        sp: item,
+                        git_status_op: self.git_status_op.clone(),
+                        workspace_settings_op: self.workspace_settings_op.clone(),
+                    })
+                    .collect();
+                Box::new(children.into_iter()) as Box<dyn Iterator<Item = Self>>
+            }
             Err(e) => {
                 error!("fail to call blocking_list {:?}", e);
                 Box::new(std::iter::empty()) as Box<dyn Iterator<Item = Self>>
@@ -61,6 +110,10 @@ impl TreeViewNode<SPath> for FileTreeNode {
     fn is_complete(&self) -> bool {
         true //TODO
     }
+
+    fn label_color(&self, theme: &Theme) -> Option<Color> {
+        self.git_status().map(|status| theme.ui.git.file_status_color(status))
+    }
 }
 
 impl TreeViewNode<SPath> for DirTreeNode {
@@ -0,0 +1,248 @@
+use crate::*;
+
+/*
+A tiny interpreter of ANSI escape sequences, just enough to show colored output of compilers, linters
+and similar tools. Only SGR (ESC [ ... m) sequences are interpreted, everything else is stripped.
+ */
+
+#[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
+pub struct AnsiStyle {
+    pub foreground: Option<Color>,
+    pub background: Option<Color>,
+    pub effect: Effect,
+}
+
+impl AnsiStyle {
+    pub fn to_text_style(&self, base: TextStyle) -> TextStyle {
+        TextStyle {
+            foreground: self.foreground.unwrap_or(base.foreground),
+            background: self.background.unwrap_or(base.background),
+            effect: if self.effect != Effect::None { self.effect } else { base.effect },
+        }
+    }
+}
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct StyledSegment {
+    pub style: AnsiStyle,
+    pub text: String,
+}
+
+// xterm defaults
+const BASIC_COLORS: [Color; 16] = [
+    Color::new(0, 0, 0),
+    Color::new(205, 0, 0),
+    Color::new(0, 205, 0),
+    Color::new(205, 205, 0),
+    Color::new(0, 0, 238),
+    Color::new(205, 0, 205),
+    Color::new(0, 205, 205),
+    Color::new(229, 229, 229),
+    Color::new(127, 127, 127),
+    Color::new(255, 0, 0),
+    Color::new(0, 255, 0),
+    Color::new(255, 255, 0),
+    Color::new(92, 92, 255),
+    Color::new(255, 0, 255),
+    Color::new(0, 255, 255),
+    Color::new(255, 255, 255),
+];
+
+pub fn ansi_256_to_color(idx: u8) -> Color {
+    match idx {
+        0..=15 => BASIC_COLORS[idx as usize],
+        16..=231 => {
+            let idx = idx - 16;
+            let level = |v: u8| if v == 0 { 0 } else { 55 + v * 40 };
+            Color::new(level(idx / 36), level((idx / 6) % 6), level(idx % 6))
+        }
+        _ => {
+            let grey = 8 + (idx - 232) * 10;
+            Color::new(grey, grey, grey)
+        }
+    }
+}
+
+/*
+Style "leaks" between lines (tools often set color in one line and reset it a few lines later),
+that's why parser is stateful and there should be one per stream.
+ */
+#[derive(Clone, Debug, Default)]
+pub struct AnsiParser {
+    style: AnsiStyle,
+}
+
+impl AnsiParser {
+    pub fn new() -> Self {
+        AnsiParser::default()
+    }
+
+    /*
+    Parses a single line (trailing newline is ignored). Carriage return "rewinds" the line, so only
+    what follows the last one is kept (that's how progress bars are drawn).
+     */
+    pub fn parse_line(&mut self, line: &str) -> Vec<StyledSegment> {
+        let line = line.trim_end_matches(['\n', '\r']);
+        let line = match line.rfind('\r') {
+            Some(idx) => &line[idx + 1..],
+            None => line,
+        };
+
+        let mut result: Vec<StyledSegment> = Vec::new();
+        let mut current = String::new();
+
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match c {
+                '\x1b' => {
+                    match chars.peek() {
+                        Some('[') => {
+                            chars.next();
+                            let mut params = String::new();
+                            let mut final_char: Option<char> = None;
+                            for c in chars.by_ref() {
+                                if ('\x40'..='\x7e').contains(&c) {
+                                    final_char = Some(c);
+                                    break;
+                                }
+                                params.push(c);
+                            }
+
+                            if final_char == Some('m') {
+                                if !current.is_empty() {
+                                    result.push(StyledSegment {
+                                        style: self.style,
+                                        text: std::mem::take(&mut current),
+                                    });
+                                }
+                                self.apply_sgr(&params);
+                            }
+                        }
+                        Some(']') => {
+                            // OSC, terminated by BEL or ESC \
+                            chars.next();
+                            while let Some(c) = chars.next() {
+                                if c == '\x07' {
+                                    break;
+                                }
+                                if c == '\x1b' && chars.peek() == Some(&'\\') {
+                                    chars.next();
+                                    break;
+                                }
+                            }
+                        }
+                        _ => {
+                            // two character sequence, skipping
+                            chars.next();
+                        }
+                    }
+                }
+                '\t' => current.push_str("    "),
+                c if c.is_control() => {}
+                c => current.push(c),
+            }
+        }
+
+        if !current.is_empty() {
+            result.push(StyledSegment {
+                style: self.style,
+                text: current,
+            });
+        }
+
+        result
+    }
+
+    fn apply_sgr(&mut self, params: &str) {
+        let codes: Vec<u32> = if params.is_empty() {
+            vec![0]
+        } else {
+            params.split(';').map(|p| p.parse::<u32>().unwrap_or(0)).collect()
+        };
+
+        let mut it = codes.into_iter();
+        while let Some(code) = it.next() {
+            match code {
+                0 => self.style = AnsiStyle::default(),
+                1 => self.style.effect = Effect::Bold,
+                3 => self.style.effect = Effect::Italic,
+                4 => self.style.effect = Effect::Underline,
+                22..=24 => self.style.effect = Effect::None,
+                30..=37 => self.style.foreground = Some(BASIC_COLORS[(code - 30) as usize]),
+                39 => self.style.foreground = None,
+                40..=47 => self.style.background = Some(BASIC_COLORS[(code - 40) as usize]),
+                49 => self.style.background = None,
+                90..=97 => self.style.foreground = Some(BASIC_COLORS[(code - 90 + 8) as usize]),
+                100..=107 => self.style.background = Some(BASIC_COLORS[(code - 100 + 8) as usize]),
+                38 | 48 => {
+                    let color = match it.next() {
+                        Some(5) => it.next().map(|idx| ansi_256_to_color(idx.min(255) as u8)),
+                        Some(2) => {
+                            let r = it.next().unwrap_or(0).min(255) as u8;
+                            let g = it.next().unwrap_or(0).min(255) as u8;
+                            let b = it.next().unwrap_or(0).min(255) as u8;
+                            Some(Color::new(r, g, b))
+                        }
+                        _ => None,
+                    };
+
+                    if code == 38 {
+                        self.style.foreground = color;
+                    } else {
+                        self.style.background = color;
+                    }
+                }
+                _ => {}
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_line() {
+        let mut parser = AnsiParser::new();
+        let segments = parser.parse_line("hello world");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text, "hello world");
+        assert_eq!(segments[0].style, AnsiStyle::default());
+    }
+
+    #[test]
+    fn test_colors_and_reset() {
+        let mut parser = AnsiParser::new();
+        let segments = parser.parse_line("\x1b[1;31merror\x1b[0m: something");
+        assert_eq!(segments.len(), 2);
+        assert_eq!(segments[0].text, "error");
+        assert_eq!(segments[0].style.foreground, Some(BASIC_COLORS[1]));
+        assert_eq!(segments[0].style.effect, Effect::Bold);
+        assert_eq!(segments[1].text, ": something");
+        assert_eq!(segments[1].style, AnsiStyle::default());
+    }
+
+    #[test]
+    fn test_style_carries_over_lines() {
+        let mut parser = AnsiParser::new();
+        parser.parse_line("\x1b[38;2;1;2;3mfirst");
+        let segments = parser.parse_line("second");
+        assert_eq!(segments[0].style.foreground, Some(Color::new(1, 2, 3)));
+    }
+
+    #[test]
+    fn test_strips_other_sequences() {
+        let mut parser = AnsiParser::new();
+        let segments = parser.parse_line("\x1b]0;title\x07a\x1b[2Kb\r\n");
+        assert_eq!(segments.len(), 1);
+        assert_eq!(segments[0].text, "ab");
+    }
+
+    #[test]
+    fn test_carriage_return_rewinds() {
+        let mut parser = AnsiParser::new();
+        let segments = parser.parse_line("10%\r50%\r100%");
+        assert_eq!(segments[0].text, "100%");
+    }
+}
@@ -0,0 +1,80 @@
+use lazy_static::lazy_static;
+use regex::Regex;
+
+use crate::*;
+
+/*
+Position found in output of a tool, like "src/main.rs:12:5" (rustc, gcc, go, eslint...) or
+"src/main.rs:12". Line and column are 1-based, as that's what tools print.
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct FilePosition {
+    pub path: String,
+    pub line_1b: usize,
+    pub column_1b: Option<usize>,
+    // range of characters (not bytes) within the line where the pattern was found
+    pub char_range: std::ops::Range<usize>,
+}
+
+lazy_static! {
+    static ref FILE_POSITION_REGEX: Regex = Regex::new(r#"(?:^|[\s(\[<'"`])([^\s:()\[\]<>'"`]+):(\d+)(?::(\d+))?"#).unwrap();
+}
+
+pub fn find_file_position(line: &str) -> Option<FilePosition> {
+    for captures in FILE_POSITION_REGEX.captures_iter(line) {
+        let path_match = unpack_or!(captures.get(1), None);
+        let path = path_match.as_str();
+        // filters out things like timestamps
+        if !path.chars().any(|c| c.is_alphabetic()) {
+            continue;
+        }
+
+        let line_1b = unpack_or!(captures.get(2).and_then(|c| c.as_str().parse::<usize>().ok()), None);
+        if line_1b == 0 {
+            continue;
+        }
+        let column_1b = captures.get(3).and_then(|c| c.as_str().parse::<usize>().ok());
+        let end = captures.get(3).or(captures.get(2)).map(|c| c.end()).unwrap_or(path_match.end());
+
+        let char_begin = line[..path_match.start()].chars().count();
+        let char_end = char_begin + line[path_match.start()..end].chars().count();
+
+        return Some(FilePosition {
+            path: path.to_string(),
+            line_1b,
+            column_1b,
+            char_range: char_begin..char_end,
+        });
+    }
+
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rustc_style() {
+        let pos = find_file_position("  --> src/main.rs:12:5").unwrap();
+        assert_eq!(pos.path, "src/main.rs");
+        assert_eq!(pos.line_1b, 12);
+        assert_eq!(pos.column_1b, Some(5));
+        assert_eq!(pos.char_range, 6..22);
+    }
+
+    #[test]
+    fn test_no_column() {
+        let pos = find_file_position("Makefile:3: *** missing separator.  Stop.").unwrap();
+        assert_eq!(pos.path, "Makefile");
+        assert_eq!(pos.line_1b, 3);
+        assert_eq!(pos.column_1b, None);
+    }
+
+    #[test]
+    fn test_no_position() {
+        assert_eq!(find_file_position("Compiling bernardo v0.1.0"), None);
+        assert_eq!(find_file_position("time: 12:30"), None);
+        assert_eq!(find_file_position("http://localhost:8080"), None);
+    }
+}
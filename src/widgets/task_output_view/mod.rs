@@ -0,0 +1,18 @@
+/*
+Output of user defined tasks (see TaskDefinition in workspace settings).
+ */
+
+mod ansi;
+pub use ansi::{ansi_256_to_color, AnsiParser, AnsiStyle, StyledSegment};
+
+mod file_position;
+pub use file_position::{find_file_position, FilePosition};
+
+mod task_output_view;
+pub use task_output_view::TaskOutputView;
+
+mod task_output_widget;
+pub use task_output_widget::{TaskOutputWidget, TaskOutputWidgetMsg};
+
+mod task_run;
+pub use task_run::{run_task, TaskOutput, TaskOutputLine, TaskOutputRef, TaskStatus};
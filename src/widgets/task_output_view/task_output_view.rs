@@ -0,0 +1,180 @@
+use std::path::PathBuf;
+
+use log::{debug, error};
+
+use crate::*;
+
+/*
+Full-page display of a running (or finished) user defined task: a status line and scrollable output.
+ */
+pub struct TaskOutputView {
+    wid: WID,
+
+    task_name: String,
+    label: TextWidget,
+    output: WithScroll<TaskOutputWidget>,
+
+    display_state: Option<DisplayState<TaskOutputView>>,
+}
+
+impl TaskOutputView {
+    pub const TYPENAME: &'static str = "task_output_view";
+    pub const MIN_WIDTH: u16 = 20;
+
+    pub fn new(providers: Providers, task_name: String, output: TaskOutputRef, cwd: PathBuf) -> Self {
+        TaskOutputView {
+            wid: get_new_widget_id(),
+            label: TextWidget::new(Box::new(format!("task [{}]", task_name))).with_size_policy(SizePolicy::MATCH_LAYOUTS_WIDTH),
+            task_name,
+            output: WithScroll::new(ScrollDirection::Both, TaskOutputWidget::new(providers, output, cwd)),
+            display_state: None,
+        }
+    }
+
+    /*
+    Starts the task and creates a view of it.
+     */
+    pub fn run(providers: Providers, task: &TaskDefinition) -> Self {
+        let root = providers.fsf().root_path_buf().clone();
+        let cwd = task.cwd.as_ref().map(|cwd| root.join(cwd)).unwrap_or(root);
+
+        let tick_sender = match providers.navcomp_group().try_read() {
+            Ok(lock) => lock.todo_sender().clone(),
+            Err(e) => {
+                error!(
+                    "failed to acquire navcomp group lock, task output will be refreshed only on input: {}",
+                    e
+                );
+                crossbeam_channel::unbounded::<NavCompTick>().0
+            }
+        };
+
+        let output = run_task(task, cwd.clone(), tick_sender);
+        Self::new(providers, task.name.clone(), output, cwd)
+    }
+
+    pub fn get_task_name(&self) -> &String {
+        &self.task_name
+    }
+
+    pub fn get_text(&self) -> String {
+        self.label.get_text()
+    }
+
+    pub fn get_output_widget(&self) -> &TaskOutputWidget {
+        self.output.internal()
+    }
+
+    fn status_text(&self) -> String {
+        let lock = unpack_or_e!(
+            self.output.internal().output().read().ok(),
+            format!("task [{}]", self.task_name),
+            "failed to lock task output"
+        );
+
+        let status = match lock.status() {
+            TaskStatus::Running => "running".to_string(),
+            TaskStatus::Finished { exit_code: Some(code) } => format!("exited with code {}", code),
+            TaskStatus::Finished { exit_code: None } => "terminated".to_string(),
+            TaskStatus::Stopped => "stopped".to_string(),
+            TaskStatus::FailedToStart(reason) => format!("failed to start: {}", reason),
+        };
+
+        let truncated = if lock.is_truncated() { " (output truncated)" } else { "" };
+
+        format!("task [{}] {}{}", self.task_name, status, truncated)
+    }
+}
+
+impl Widget for TaskOutputView {
+    fn id(&self) -> WID {
+        self.wid
+    }
+
+    fn static_typename() -> &'static str
+    where
+        Self: Sized,
+    {
+        Self::TYPENAME
+    }
+
+    fn typename(&self) -> &'static str {
+        Self::TYPENAME
+    }
+
+    fn prelayout(&mut self) {
+        let text = self.status_text();
+        self.label.set_text(Box::new(text));
+
+        self.complex_prelayout();
+    }
+
+    fn size_policy(&self) -> SizePolicy {
+        SizePolicy::MATCH_LAYOUT
+    }
+
+    fn full_size(&self) -> XY {
+        XY::new(Self::MIN_WIDTH, 2)
+    }
+
+    fn layout(&mut self, screenspace: Screenspace) {
+        self.complex_layout(screenspace)
+    }
+
+    fn on_input(&self, input_event: InputEvent) -> Option<Box<dyn AnyMsg>> {
+        debug!("{} input {:?}", self.typename(), input_event);
+        None
+    }
+
+    fn update(&mut self, msg: Box<dyn AnyMsg>) -> Option<Box<dyn AnyMsg>> {
+        // everything meaningful comes from output widget and is addressed to MainView
+        Some(msg)
+    }
+
+    fn get_focused(&self) -> Option<&dyn Widget> {
+        self.complex_get_focused()
+    }
+
+    fn get_focused_mut(&mut self) -> Option<&mut dyn Widget> {
+        self.complex_get_focused_mut()
+    }
+
+    fn render(&self, theme: &Theme, focused: bool, output: &mut dyn Output) {
+        #[cfg(test)]
+        {
+            output.emit_metadata(Metadata {
+                id: self.wid,
+                typename: self.typename().to_string(),
+                rect: Rect::from_zero(output.size()),
+                focused,
+            });
+        }
+
+        self.complex_render(theme, focused, output)
+    }
+}
+
+impl ComplexWidget for TaskOutputView {
+    fn get_layout(&self) -> Box<dyn Layout<Self>> {
+        SplitLayout::new(SplitDirection::Vertical)
+            .with(SplitRule::Fixed(1), LeafLayout::new(subwidget!(Self.label)).boxed())
+            .with(SplitRule::Proportional(1.0f32), LeafLayout::new(subwidget!(Self.output)).boxed())
+            .boxed()
+    }
+
+    fn get_default_focused(&self) -> SubwidgetPointer<Self> {
+        subwidget!(Self.output)
+    }
+
+    fn set_display_state(&mut self, display_state: DisplayState<Self>) {
+        self.display_state = Some(display_state);
+    }
+
+    fn get_display_state_op(&self) -> Option<&DisplayState<Self>> {
+        self.display_state.as_ref()
+    }
+
+    fn get_display_state_mut_op(&mut self) -> Option<&mut DisplayState<Self>> {
+        self.display_state.as_mut()
+    }
+}
@@ -0,0 +1,277 @@
+use std::path::PathBuf;
+
+use log::{debug, error, warn};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::*;
+
+/*
+Read-only view of the output of a task. Lines can be selected, and if the selected line contains
+something that looks like "file:line:col", hitting enter opens that file at that position.
+ */
+pub struct TaskOutputWidget {
+    wid: WID,
+    providers: Providers,
+
+    output: TaskOutputRef,
+    // paths in the output are resolved relative to this first, then relative to workspace root
+    cwd: PathBuf,
+
+    selected: usize,
+    // if true, selection follows the last line as new output arrives
+    follow: bool,
+
+    last_size: Option<Screenspace>,
+}
+
+impl TaskOutputWidget {
+    pub const TYPENAME: &'static str = "task_output_widget";
+
+    pub fn new(providers: Providers, output: TaskOutputRef, cwd: PathBuf) -> Self {
+        TaskOutputWidget {
+            wid: get_new_widget_id(),
+            providers,
+            output,
+            cwd,
+            selected: 0,
+            follow: true,
+            last_size: None,
+        }
+    }
+
+    pub fn output(&self) -> &TaskOutputRef {
+        &self.output
+    }
+
+    pub fn selected(&self) -> usize {
+        self.selected
+    }
+
+    fn lines_count(&self) -> usize {
+        self.output.read().map(|lock| lock.lines().len()).unwrap_or(0)
+    }
+
+    fn resolve_path(&self, path: &str) -> Option<SPath> {
+        let fsf = self.providers.fsf();
+        let path = PathBuf::from(path);
+
+        if path.is_absolute() {
+            let relative = unpack_or!(
+                path.strip_prefix(fsf.root_path_buf()).ok(),
+                None,
+                "path {:?} outside workspace",
+                path
+            );
+            return fsf.descendant_checked(relative);
+        }
+
+        let from_cwd = self
+            .cwd
+            .join(&path)
+            .strip_prefix(fsf.root_path_buf())
+            .ok()
+            .and_then(|relative| fsf.descendant_checked(relative));
+
+        from_cwd.or_else(|| fsf.descendant_checked(&path))
+    }
+
+    fn on_hit(&self) -> Option<Box<dyn AnyMsg>> {
+        let text = {
+            let lock = unpack_or_e!(self.output.read().ok(), None, "failed to lock task output");
+            unpack_or!(lock.lines().get(self.selected), None, "no line selected").text()
+        };
+
+        let position = unpack_or!(find_file_position(&text), None, "no file position in line");
+        let file = unpack_or!(self.resolve_path(&position.path), None, "failed to resolve {}", position.path);
+
+        debug!("jumping to {} line {} col {:?}", file, position.line_1b, position.column_1b);
+
+        MainViewMsg::OpenFileAtPosition {
+            file,
+            position: StupidCursor::new(
+                position.column_1b.unwrap_or(1).saturating_sub(1) as u32,
+                position.line_1b.saturating_sub(1) as u32,
+            ),
+        }
+        .someboxed()
+    }
+}
+
+#[derive(Clone, Copy, Debug)]
+pub enum TaskOutputWidgetMsg {
+    Up,
+    Down,
+    PageUp,
+    PageDown,
+    Home,
+    End,
+    Hit,
+    Stop,
+}
+
+impl AnyMsg for TaskOutputWidgetMsg {}
+
+/*
+Closing the output stops the task, reader threads keep TaskOutput alive until the task exits, so
+dropping it wouldn't be enough.
+ */
+impl Drop for TaskOutputWidget {
+    fn drop(&mut self) {
+        match self.output.write() {
+            Ok(mut lock) => {
+                lock.stop();
+            }
+            Err(e) => error!("failed to lock task output, task may be left running: {}", e),
+        }
+    }
+}
+
+impl Widget for TaskOutputWidget {
+    fn id(&self) -> WID {
+        self.wid
+    }
+
+    fn static_typename() -> &'static str
+    where
+        Self: Sized,
+    {
+        Self::TYPENAME
+    }
+
+    fn typename(&self) -> &'static str {
+        Self::TYPENAME
+    }
+
+    fn prelayout(&mut self) {
+        let count = self.lines_count();
+        if self.follow && count > 0 {
+            self.selected = count - 1;
+        }
+    }
+
+    fn full_size(&self) -> XY {
+        let lock = unpack_or_e!(self.output.read().ok(), XY::new(1, 1), "failed to lock task output");
+        XY::new(
+            lock.max_width().min(u16::MAX as usize).max(1) as u16,
+            lock.lines().len().min(u16::MAX as usize).max(1) as u16,
+        )
+    }
+
+    fn layout(&mut self, screenspace: Screenspace) {
+        self.last_size = Some(screenspace);
+    }
+
+    fn on_input(&self, input_event: InputEvent) -> Option<Box<dyn AnyMsg>> {
+        match input_event {
+            InputEvent::KeyInput(key) if key == self.providers.config().keyboard_config.task.stop => TaskOutputWidgetMsg::Stop.someboxed(),
+            InputEvent::KeyInput(key) if key.modifiers.is_empty() => match key.keycode {
+                Keycode::ArrowUp => TaskOutputWidgetMsg::Up.someboxed(),
+                Keycode::ArrowDown => TaskOutputWidgetMsg::Down.someboxed(),
+                Keycode::PageUp => TaskOutputWidgetMsg::PageUp.someboxed(),
+                Keycode::PageDown => TaskOutputWidgetMsg::PageDown.someboxed(),
+                Keycode::Home => TaskOutputWidgetMsg::Home.someboxed(),
+                Keycode::End => TaskOutputWidgetMsg::End.someboxed(),
+                Keycode::Enter => TaskOutputWidgetMsg::Hit.someboxed(),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, msg: Box<dyn AnyMsg>) -> Option<Box<dyn AnyMsg>> {
+        let our_msg = unpack_or_e!(
+            msg.as_msg::<TaskOutputWidgetMsg>(),
+            None,
+            "expected TaskOutputWidgetMsg, got {:?}",
+            msg
+        );
+
+        let count = self.lines_count();
+        let last = count.saturating_sub(1);
+        let page_height = self.last_size.map(|ls| ls.page_height()).unwrap_or(1).max(1) as usize;
+
+        match our_msg {
+            TaskOutputWidgetMsg::Up => self.selected = self.selected.saturating_sub(1),
+            TaskOutputWidgetMsg::Down => self.selected = (self.selected + 1).min(last),
+            TaskOutputWidgetMsg::PageUp => self.selected = self.selected.saturating_sub(page_height),
+            TaskOutputWidgetMsg::PageDown => self.selected = (self.selected + page_height).min(last),
+            TaskOutputWidgetMsg::Home => self.selected = 0,
+            TaskOutputWidgetMsg::End => self.selected = last,
+            TaskOutputWidgetMsg::Hit => return self.on_hit(),
+            TaskOutputWidgetMsg::Stop => {
+                match self.output.write() {
+                    Ok(mut lock) => {
+                        if !lock.stop() {
+                            debug!("task is not running, nothing to stop");
+                        }
+                    }
+                    Err(e) => error!("failed to lock task output: {}", e),
+                }
+                return None;
+            }
+        }
+
+        self.follow = self.selected == last;
+        None
+    }
+
+    fn render(&self, theme: &Theme, focused: bool, output: &mut dyn Output) {
+        #[cfg(test)]
+        output.emit_metadata(Metadata {
+            id: self.wid,
+            typename: self.typename().to_string(),
+            rect: Rect::from_zero(output.size()),
+            focused,
+        });
+
+        if self.last_size.is_none() {
+            error!("render before layout");
+            return;
+        }
+
+        let default_style = theme.default_text(focused);
+        fill_output(default_style.background, output);
+
+        let lock = unpack_or_e!(self.output.read().ok(), (), "failed to lock task output");
+        let visible_rect = output.visible_rect();
+        let first_line = visible_rect.upper_left().y as usize;
+        let last_line = visible_rect.lower_right().y as usize;
+
+        for (line_idx, line) in lock.lines().iter().enumerate().skip(first_line).take(last_line - first_line) {
+            let base_style = if line_idx == self.selected {
+                theme.highlighted(focused)
+            } else {
+                default_style
+            };
+
+            let jump_range = find_file_position(&line.text()).map(|pos| pos.char_range);
+
+            let mut x: usize = 0;
+            let mut char_idx: usize = 0;
+            for segment in line.segments.iter() {
+                let segment_style = segment.style.to_text_style(base_style);
+                for grapheme in UnicodeSegmentation::graphemes(segment.text.as_str(), true) {
+                    if x >= u16::MAX as usize {
+                        warn!("line {} too long, cutting", line_idx);
+                        break;
+                    }
+
+                    let style = if jump_range.as_ref().map(|r| r.contains(&char_idx)).unwrap_or(false) {
+                        segment_style.with_effect(Effect::Underline)
+                    } else {
+                        segment_style
+                    };
+
+                    output.print_at(XY::new(x as u16, line_idx as u16), style, grapheme);
+                    x += grapheme.width();
+                    char_idx += grapheme.chars().count();
+                }
+            }
+        }
+    }
+
+    fn kite(&self) -> XY {
+        XY::new(0, self.selected.min(u16::MAX as usize) as u16)
+    }
+}
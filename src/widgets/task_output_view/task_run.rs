@@ -0,0 +1,291 @@
+use std::cmp::max;
+use std::io::{BufRead, BufReader, Read};
+use std::path::PathBuf;
+use std::process::{Child, Command, Stdio};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+
+use log::{debug, error, warn};
+use unicode_width::UnicodeWidthStr;
+
+use crate::*;
+
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum TaskStatus {
+    Running,
+    Finished { exit_code: Option<i32> },
+    // killed on user request
+    Stopped,
+    FailedToStart(String),
+}
+
+#[derive(Clone, Debug)]
+pub struct TaskOutputLine {
+    pub segments: Vec<StyledSegment>,
+    pub is_stderr: bool,
+}
+
+impl TaskOutputLine {
+    pub fn text(&self) -> String {
+        self.segments.iter().map(|s| s.text.as_str()).collect()
+    }
+}
+
+/*
+This is shared between reader threads (writing) and the widget (reading). It also owns the process
+while it runs, so it can be stopped from the UI.
+ */
+#[derive(Debug)]
+pub struct TaskOutput {
+    lines: Vec<TaskOutputLine>,
+    max_width: usize,
+    truncated: bool,
+    status: TaskStatus,
+    // present while the task runs
+    child_op: Option<Child>,
+}
+
+pub type TaskOutputRef = Arc<RwLock<TaskOutput>>;
+
+impl Default for TaskOutput {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TaskOutput {
+    // widgets are addressed with u16, so we can't display more anyway
+    pub const MAX_LINES: usize = u16::MAX as usize - 1;
+
+    pub fn new() -> Self {
+        TaskOutput {
+            lines: Vec::new(),
+            max_width: 0,
+            truncated: false,
+            status: TaskStatus::Running,
+            child_op: None,
+        }
+    }
+
+    pub fn lines(&self) -> &Vec<TaskOutputLine> {
+        &self.lines
+    }
+
+    pub fn status(&self) -> &TaskStatus {
+        &self.status
+    }
+
+    pub fn max_width(&self) -> usize {
+        self.max_width
+    }
+
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    pub fn push_line(&mut self, line: TaskOutputLine) {
+        if self.lines.len() >= Self::MAX_LINES {
+            if !self.truncated {
+                warn!("task output exceeded {} lines, ignoring the rest", Self::MAX_LINES);
+                self.truncated = true;
+            }
+            return;
+        }
+
+        self.max_width = max(self.max_width, line.text().width());
+        self.lines.push(line);
+    }
+
+    pub fn set_status(&mut self, status: TaskStatus) {
+        self.status = status;
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.child_op.is_some()
+    }
+
+    /*
+    Kills the task and waits for it, so no zombie is left behind. Returns false if it wasn't running.
+     */
+    pub fn stop(&mut self) -> bool {
+        let mut child = unpack_or!(self.child_op.take(), false);
+
+        if let Err(e) = child.kill() {
+            warn!("failed to kill task: {}", e);
+        }
+        if let Err(e) = child.wait() {
+            error!("failed to wait for killed task: {}", e);
+        }
+
+        self.status = TaskStatus::Stopped;
+        true
+    }
+
+    /*
+    Checks if the task finished, and if so, reaps it and sets status.
+     */
+    fn poll_finished(&mut self) -> bool {
+        let child = unpack_or!(self.child_op.as_mut(), true);
+
+        let status = match child.try_wait() {
+            Ok(None) => return false,
+            Ok(Some(exit_status)) => TaskStatus::Finished {
+                exit_code: exit_status.code(),
+            },
+            Err(e) => {
+                error!("failed to wait for task: {}", e);
+                TaskStatus::Finished { exit_code: None }
+            }
+        };
+
+        self.child_op = None;
+        self.status = status;
+        true
+    }
+}
+
+impl Drop for TaskOutput {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+const POLL_INTERVAL: Duration = Duration::from_millis(10);
+
+/*
+Spawns the task in background. Stdout and stderr are read line by line in separate threads, and every
+new line results in a tick, so the main loop redraws.
+ */
+pub fn run_task(task: &TaskDefinition, cwd: PathBuf, tick_sender: NavCompTickSender) -> TaskOutputRef {
+    debug!("starting task [{}]: {} {:?} in {:?}", task.name, task.command, task.args, cwd);
+
+    let mut child = match Command::new(&task.command)
+        .args(&task.args)
+        .current_dir(&cwd)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => child,
+        Err(e) => {
+            error!("failed to start task [{}]: {}", task.name, e);
+            let mut output = TaskOutput::new();
+            output.set_status(TaskStatus::FailedToStart(e.to_string()));
+            return Arc::new(RwLock::new(output));
+        }
+    };
+
+    let stdout_op = child.stdout.take();
+    let stderr_op = child.stderr.take();
+    let mut output = TaskOutput::new();
+    output.child_op = Some(child);
+    let output: TaskOutputRef = Arc::new(RwLock::new(output));
+
+    let mut readers = Vec::new();
+    if let Some(stdout) = stdout_op {
+        readers.push(spawn_reader(stdout, false, output.clone(), tick_sender.clone()));
+    }
+    if let Some(stderr) = stderr_op {
+        readers.push(spawn_reader(stderr, true, output.clone(), tick_sender.clone()));
+    }
+
+    let output_clone = output.clone();
+    std::thread::spawn(move || {
+        for reader in readers.into_iter() {
+            if reader.join().is_err() {
+                error!("task output reader panicked");
+            }
+        }
+
+        // the child is not waited for under lock, so it can be stopped meanwhile
+        loop {
+            match output_clone.write() {
+                Ok(mut lock) => {
+                    if lock.poll_finished() {
+                        break;
+                    }
+                }
+                Err(e) => {
+                    error!("failed to lock task output: {}", e);
+                    break;
+                }
+            }
+            std::thread::sleep(POLL_INTERVAL);
+        }
+
+        if tick_sender.send(NavCompTick::TaskTick).is_err() {
+            warn!("failed sending tick");
+        }
+    });
+
+    output
+}
+
+fn spawn_reader<R: Read + Send + 'static>(
+    stream: R,
+    is_stderr: bool,
+    output: TaskOutputRef,
+    tick_sender: NavCompTickSender,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut parser = AnsiParser::new();
+        let mut reader = BufReader::new(stream);
+        let mut buf: Vec<u8> = Vec::new();
+
+        loop {
+            buf.clear();
+            match reader.read_until(b'\n', &mut buf) {
+                Ok(0) => break,
+                Ok(_) => {
+                    let line = String::from_utf8_lossy(&buf);
+                    let segments = parser.parse_line(&line);
+
+                    match output.write() {
+                        Ok(mut lock) => lock.push_line(TaskOutputLine { segments, is_stderr }),
+                        Err(e) => {
+                            error!("failed to lock task output: {}", e);
+                            break;
+                        }
+                    }
+
+                    // it's only a "wake up" signal, it's fine if some are lost
+                    let _ = tick_sender.try_send(NavCompTick::TaskTick);
+                }
+                Err(e) => {
+                    error!("failed reading task output: {}", e);
+                    break;
+                }
+            }
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::Instant;
+
+    use super::*;
+
+    #[test]
+    fn test_stopped_task_is_killed_and_reaped() {
+        let task = TaskDefinition {
+            name: "forever".to_string(),
+            command: "sleep".to_string(),
+            args: vec!["10".to_string()],
+            cwd: None,
+        };
+        let (tick_sender, _tick_receiver) = crossbeam_channel::unbounded::<NavCompTick>();
+        let started = Instant::now();
+        let output = run_task(&task, std::env::temp_dir(), tick_sender);
+
+        assert!(output.read().unwrap().is_running());
+        assert!(output.write().unwrap().stop());
+        assert!(!output.write().unwrap().stop());
+
+        let lock = output.read().unwrap();
+        assert!(!lock.is_running());
+        assert_eq!(lock.status(), &TaskStatus::Stopped);
+        assert!(started.elapsed() < Duration::from_secs(5));
+    }
+}
@@ -0,0 +1,139 @@
+use crate::*;
+
+/*
+Translates our Key back to bytes a program running in terminal expects (xterm flavour).
+application_cursor is a mode set by programs like vim or less, it changes how arrows are encoded.
+ */
+pub fn key_to_bytes(key: &Key, application_cursor: bool) -> Option<Vec<u8>> {
+    // xterm modifier parameter: 1 + shift + 2*alt + 4*ctrl
+    let modifier_param = 1 + (key.modifiers.shift as u8) + 2 * (key.modifiers.alt as u8) + 4 * (key.modifiers.ctrl as u8);
+
+    let cursor_key = |letter: char| -> Vec<u8> {
+        if modifier_param > 1 {
+            format!("\x1b[1;{}{}", modifier_param, letter).into_bytes()
+        } else if application_cursor {
+            format!("\x1bO{}", letter).into_bytes()
+        } else {
+            format!("\x1b[{}", letter).into_bytes()
+        }
+    };
+
+    let tilde_key = |code: u8| -> Vec<u8> {
+        if modifier_param > 1 {
+            format!("\x1b[{};{}~", code, modifier_param).into_bytes()
+        } else {
+            format!("\x1b[{}~", code).into_bytes()
+        }
+    };
+
+    let alt_prefixed = |bytes: Vec<u8>| -> Vec<u8> {
+        if key.modifiers.alt {
+            let mut res = vec![0x1b];
+            res.extend(bytes);
+            res
+        } else {
+            bytes
+        }
+    };
+
+    let res = match key.keycode {
+        Keycode::Char(c) => {
+            let bytes = if key.modifiers.ctrl {
+                vec![ctrl_char(c)?]
+            } else {
+                c.to_string().into_bytes()
+            };
+            alt_prefixed(bytes)
+        }
+        Keycode::Space => alt_prefixed(if key.modifiers.ctrl { vec![0] } else { vec![b' '] }),
+        Keycode::Enter => alt_prefixed(vec![b'\r']),
+        Keycode::Backspace => alt_prefixed(if key.modifiers.ctrl { vec![0x08] } else { vec![0x7f] }),
+        Keycode::Tab => {
+            if key.modifiers.shift {
+                b"\x1b[Z".to_vec()
+            } else {
+                alt_prefixed(vec![b'\t'])
+            }
+        }
+        Keycode::Esc => alt_prefixed(vec![0x1b]),
+        Keycode::ArrowUp => cursor_key('A'),
+        Keycode::ArrowDown => cursor_key('B'),
+        Keycode::ArrowRight => cursor_key('C'),
+        Keycode::ArrowLeft => cursor_key('D'),
+        Keycode::Home => cursor_key('H'),
+        Keycode::End => cursor_key('F'),
+        Keycode::Insert => tilde_key(2),
+        Keycode::Delete => tilde_key(3),
+        Keycode::PageUp => tilde_key(5),
+        Keycode::PageDown => tilde_key(6),
+        Keycode::F(n) => match n {
+            1..=4 => {
+                let letter = (b'P' + n - 1) as char;
+                if modifier_param > 1 {
+                    format!("\x1b[1;{}{}", modifier_param, letter).into_bytes()
+                } else {
+                    format!("\x1bO{}", letter).into_bytes()
+                }
+            }
+            5 => tilde_key(15),
+            6..=10 => tilde_key(n + 11),
+            11..=12 => tilde_key(n + 12),
+            _ => return None,
+        },
+        Keycode::LeftAlt | Keycode::RightAlt | Keycode::LeftCtrl | Keycode::RightCtrl | Keycode::Null | Keycode::Unhandled => return None,
+    };
+
+    Some(res)
+}
+
+fn ctrl_char(c: char) -> Option<u8> {
+    match c {
+        'a'..='z' => Some(c as u8 - b'a' + 1),
+        'A'..='Z' => Some(c as u8 - b'A' + 1),
+        '@' | ' ' | '2' => Some(0),
+        '[' | '3' => Some(0x1b),
+        // crossterm reports ctrl+\ ctrl+] ctrl+^ ctrl+_ as ctrl+4..7
+        '\\' | '4' => Some(0x1c),
+        ']' | '5' => Some(0x1d),
+        '^' | '6' => Some(0x1e),
+        '_' | '7' => Some(0x1f),
+        '?' | '8' => Some(0x7f),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plain_chars() {
+        assert_eq!(key_to_bytes(&Keycode::Char('a').to_key(), false), Some(b"a".to_vec()));
+        assert_eq!(key_to_bytes(&Keycode::Char('ż').to_key(), false), Some("ż".as_bytes().to_vec()));
+        assert_eq!(key_to_bytes(&Keycode::Enter.to_key(), false), Some(b"\r".to_vec()));
+        assert_eq!(key_to_bytes(&Keycode::Backspace.to_key(), false), Some(vec![0x7f]));
+    }
+
+    #[test]
+    fn test_ctrl_and_alt() {
+        assert_eq!(key_to_bytes(&Keycode::Char('c').to_key().with_ctrl(), false), Some(vec![3]));
+        assert_eq!(
+            key_to_bytes(&Keycode::Char('b').to_key().with_alt(), false),
+            Some(b"\x1bb".to_vec())
+        );
+        assert_eq!(key_to_bytes(&Keycode::Char('%').to_key().with_ctrl(), false), None);
+    }
+
+    #[test]
+    fn test_arrows() {
+        assert_eq!(key_to_bytes(&Keycode::ArrowUp.to_key(), false), Some(b"\x1b[A".to_vec()));
+        assert_eq!(key_to_bytes(&Keycode::ArrowUp.to_key(), true), Some(b"\x1bOA".to_vec()));
+        assert_eq!(
+            key_to_bytes(&Keycode::ArrowLeft.to_key().with_ctrl(), true),
+            Some(b"\x1b[1;5D".to_vec())
+        );
+        assert_eq!(key_to_bytes(&Keycode::PageDown.to_key(), false), Some(b"\x1b[6~".to_vec()));
+        assert_eq!(key_to_bytes(&Keycode::F(5).to_key(), false), Some(b"\x1b[15~".to_vec()));
+        assert_eq!(key_to_bytes(&Keycode::F(12).to_key(), false), Some(b"\x1b[24~".to_vec()));
+    }
+}
@@ -0,0 +1,12 @@
+/*
+Embedded terminal emulator. Actual emulation is done by vt100 crate, we just feed it and render the screen.
+ */
+
+mod key_encoding;
+pub use key_encoding::key_to_bytes;
+
+mod terminal_session;
+pub use terminal_session::{TerminalError, TerminalScreenRef, TerminalSession};
+
+mod terminal_view;
+pub use terminal_view::{TerminalView, TerminalViewMsg};
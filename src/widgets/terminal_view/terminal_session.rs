@@ -0,0 +1,252 @@
+use std::fmt::{Display, Formatter};
+use std::io::{Read, Write};
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+
+use log::{debug, error, warn};
+use portable_pty::{native_pty_system, Child, CommandBuilder, MasterPty, PtySize};
+
+use crate::*;
+
+#[derive(Debug)]
+pub enum TerminalError {
+    PtyError(String),
+    IoError(std::io::Error),
+}
+
+impl Display for TerminalError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            TerminalError::PtyError(e) => write!(f, "pty error: {}", e),
+            TerminalError::IoError(e) => write!(f, "io error: {}", e),
+        }
+    }
+}
+
+impl From<std::io::Error> for TerminalError {
+    fn from(e: std::io::Error) -> Self {
+        TerminalError::IoError(e)
+    }
+}
+
+pub type TerminalScreenRef = Arc<RwLock<vt100::Parser>>;
+
+/*
+A shell running in a pseudo terminal. Output is read in a background thread and fed into vt100 parser,
+which holds the "screen" we render. Every chunk of output results in a tick, so the main loop redraws.
+ */
+pub struct TerminalSession {
+    screen: TerminalScreenRef,
+    master: Box<dyn MasterPty + Send>,
+    writer: Box<dyn Write + Send>,
+    child: Box<dyn Child + Send + Sync>,
+    exited: Arc<AtomicBool>,
+    size: XY,
+}
+
+impl TerminalSession {
+    // vt100 keeps this many lines above the screen
+    pub const SCROLLBACK_LEN: usize = 1000;
+
+    pub fn spawn(cwd: &Path, size: XY, tick_sender: NavCompTickSender) -> Result<Self, TerminalError> {
+        let size = XY::new(size.x.max(1), size.y.max(1));
+        let pty_system = native_pty_system();
+        let pair = pty_system
+            .openpty(Self::pty_size(size))
+            .map_err(|e| TerminalError::PtyError(e.to_string()))?;
+
+        // uses $SHELL, falls back to /bin/sh
+        let mut cmd = CommandBuilder::new_default_prog();
+        cmd.cwd(cwd);
+        cmd.env("TERM", "xterm-256color");
+
+        debug!("starting shell in {:?}", cwd);
+
+        let child = pair.slave.spawn_command(cmd).map_err(|e| TerminalError::PtyError(e.to_string()))?;
+        // we don't need slave end anymore, and keeping it open would prevent us from getting EOF
+        drop(pair.slave);
+
+        let reader = pair.master.try_clone_reader().map_err(|e| TerminalError::PtyError(e.to_string()))?;
+        let writer = pair.master.take_writer().map_err(|e| TerminalError::PtyError(e.to_string()))?;
+
+        let screen: TerminalScreenRef = Arc::new(RwLock::new(vt100::Parser::new(size.y, size.x, Self::SCROLLBACK_LEN)));
+        let exited = Arc::new(AtomicBool::new(false));
+
+        spawn_reader(reader, screen.clone(), exited.clone(), tick_sender);
+
+        Ok(TerminalSession {
+            screen,
+            master: pair.master,
+            writer,
+            child,
+            exited,
+            size,
+        })
+    }
+
+    fn pty_size(size: XY) -> PtySize {
+        PtySize {
+            rows: size.y,
+            cols: size.x,
+            pixel_width: 0,
+            pixel_height: 0,
+        }
+    }
+
+    pub fn screen(&self) -> &TerminalScreenRef {
+        &self.screen
+    }
+
+    pub fn size(&self) -> XY {
+        self.size
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.exited.load(Ordering::Relaxed)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) -> Result<(), TerminalError> {
+        self.writer.write_all(bytes)?;
+        self.writer.flush()?;
+        Ok(())
+    }
+
+    /*
+    Lines scrolled back from the bottom, 0 means current screen is shown.
+     */
+    pub fn scrollback(&self) -> usize {
+        self.screen.read().map(|lock| lock.screen().scrollback()).unwrap_or(0)
+    }
+
+    /*
+    Scrolls up (positive) or down (negative) by given number of lines, clamped to what vt100 kept.
+     */
+    pub fn scroll_by(&mut self, lines: isize) {
+        match self.screen.write() {
+            Ok(mut lock) => {
+                let scrollback = lock.screen().scrollback().saturating_add_signed(lines);
+                lock.set_scrollback(scrollback);
+            }
+            Err(e) => error!("failed to lock terminal screen: {}", e),
+        }
+    }
+
+    pub fn resize(&mut self, size: XY) {
+        let size = XY::new(size.x.max(1), size.y.max(1));
+        if size == self.size {
+            return;
+        }
+
+        debug!("resizing terminal from {} to {}", self.size, size);
+        self.size = size;
+
+        match self.screen.write() {
+            Ok(mut lock) => lock.set_size(size.y, size.x),
+            Err(e) => error!("failed to lock terminal screen: {}", e),
+        }
+
+        if let Err(e) = self.master.resize(Self::pty_size(size)) {
+            error!("failed to resize pty: {}", e);
+        }
+    }
+}
+
+impl Drop for TerminalSession {
+    fn drop(&mut self) {
+        if !self.has_exited() {
+            if let Err(e) = self.child.kill() {
+                // waiting for it would hang
+                warn!("failed to kill shell: {}", e);
+                return;
+            }
+        }
+
+        // reaping, so the shell does not linger as a zombie
+        if let Err(e) = self.child.wait() {
+            warn!("failed to wait for shell: {}", e);
+        }
+    }
+}
+
+fn spawn_reader(
+    mut reader: Box<dyn Read + Send>,
+    screen: TerminalScreenRef,
+    exited: Arc<AtomicBool>,
+    tick_sender: NavCompTickSender,
+) -> std::thread::JoinHandle<()> {
+    std::thread::spawn(move || {
+        let mut buf = [0u8; 4096];
+
+        loop {
+            match reader.read(&mut buf) {
+                Ok(0) => break,
+                Ok(n) => {
+                    match screen.write() {
+                        Ok(mut lock) => lock.process(&buf[..n]),
+                        Err(e) => {
+                            error!("failed to lock terminal screen: {}", e);
+                            break;
+                        }
+                    }
+
+                    // it's only a "wake up" signal, it's fine if some are lost
+                    let _ = tick_sender.try_send(NavCompTick::TaskTick);
+                }
+                Err(e) => {
+                    // on linux reading from pty after child exited results in EIO, that's how we learn it's over
+                    debug!("terminal reader finished: {}", e);
+                    break;
+                }
+            }
+        }
+
+        exited.store(true, Ordering::Relaxed);
+        if tick_sender.send(NavCompTick::TaskTick).is_err() {
+            warn!("failed sending tick");
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::time::{Duration, Instant};
+
+    use super::*;
+
+    #[test]
+    fn test_shell_echoes() {
+        let (sender, _receiver) = crossbeam_channel::unbounded::<NavCompTick>();
+        let cwd = std::env::temp_dir();
+        let mut session = TerminalSession::spawn(&cwd, XY::new(40, 10), sender).unwrap();
+
+        session.write(b"echo bernardo$((20+3))\r").unwrap();
+
+        let deadline = Instant::now() + Duration::from_secs(5);
+        let mut found = false;
+        while Instant::now() < deadline {
+            if session.screen().read().unwrap().screen().contents().contains("bernardo23") {
+                found = true;
+                break;
+            }
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert!(found);
+
+        for _ in 0..10 {
+            session.write(b"echo line\r").unwrap();
+        }
+        // first lines go off screen once output arrives
+        let deadline = Instant::now() + Duration::from_secs(5);
+        while session.scrollback() < 3 && Instant::now() < deadline {
+            session.scroll_by(3);
+            std::thread::sleep(Duration::from_millis(20));
+        }
+        assert_eq!(session.scrollback(), 3);
+        session.scroll_by(-10);
+        assert_eq!(session.scrollback(), 0);
+
+        session.resize(XY::new(20, 5));
+        assert_eq!(session.screen().read().unwrap().screen().size(), (5, 20));
+    }
+}
@@ -0,0 +1,267 @@
+use log::{debug, error, warn};
+
+use crate::*;
+
+/*
+Embedded terminal, running a shell in workspace root.
+While it's focused, it consumes ALL keys (run_gladius does not translate global shortcuts), except for
+the terminal_escape chord, which returns focus to Gladius, and terminal_scroll_up/down ones, which scroll
+through lines that went off screen. Any other key scrolls back to the bottom.
+ */
+pub struct TerminalView {
+    wid: WID,
+    providers: Providers,
+
+    session: TerminalSession,
+}
+
+#[derive(Debug)]
+pub enum TerminalViewMsg {
+    Input(Vec<u8>),
+    Escape,
+    ScrollUp,
+    ScrollDown,
+}
+
+impl AnyMsg for TerminalViewMsg {}
+
+impl TerminalView {
+    pub const TYPENAME: &'static str = "terminal_view";
+    pub const MIN_SIZE: XY = XY::new(10, 3);
+    // it will be resized to whatever space we get on first layout
+    pub const INITIAL_SIZE: XY = XY::new(80, 24);
+
+    pub fn new(providers: Providers) -> Result<Self, TerminalError> {
+        let tick_sender = match providers.navcomp_group().try_read() {
+            Ok(lock) => lock.todo_sender().clone(),
+            Err(e) => {
+                error!(
+                    "failed to acquire navcomp group lock, terminal will be refreshed only on input: {}",
+                    e
+                );
+                crossbeam_channel::unbounded::<NavCompTick>().0
+            }
+        };
+
+        let session = TerminalSession::spawn(providers.fsf().root_path_buf(), Self::INITIAL_SIZE, tick_sender)?;
+
+        Ok(TerminalView {
+            wid: get_new_widget_id(),
+            providers,
+            session,
+        })
+    }
+
+    pub fn has_exited(&self) -> bool {
+        self.session.has_exited()
+    }
+
+    /*
+    Title set by the shell (most shells put cwd or running command there), if any.
+     */
+    pub fn get_title(&self) -> Option<String> {
+        let lock = unpack_or_e!(self.session.screen().read().ok(), None, "failed to lock terminal screen");
+        let title = lock.screen().title();
+        if title.is_empty() {
+            None
+        } else {
+            Some(title.to_string())
+        }
+    }
+
+    pub fn scrollback(&self) -> usize {
+        self.session.scrollback()
+    }
+
+    pub fn get_screen_contents(&self) -> String {
+        let lock = unpack_or_e!(self.session.screen().read().ok(), String::new(), "failed to lock terminal screen");
+        lock.screen().contents()
+    }
+
+    fn to_color(color: vt100::Color, default: Color) -> Color {
+        match color {
+            vt100::Color::Default => default,
+            vt100::Color::Idx(idx) => ansi_256_to_color(idx),
+            vt100::Color::Rgb(r, g, b) => Color::new(r, g, b),
+        }
+    }
+
+    fn cell_style(cell: &vt100::Cell, base: TextStyle) -> TextStyle {
+        let mut foreground = Self::to_color(cell.fgcolor(), base.foreground);
+        let mut background = Self::to_color(cell.bgcolor(), base.background);
+        if cell.inverse() {
+            std::mem::swap(&mut foreground, &mut background);
+        }
+
+        // we support only one effect at a time
+        let effect = if cell.bold() {
+            Effect::Bold
+        } else if cell.underline() {
+            Effect::Underline
+        } else if cell.italic() {
+            Effect::Italic
+        } else {
+            Effect::None
+        };
+
+        TextStyle::new(foreground, background, effect)
+    }
+}
+
+impl Widget for TerminalView {
+    fn id(&self) -> WID {
+        self.wid
+    }
+
+    fn static_typename() -> &'static str
+    where
+        Self: Sized,
+    {
+        Self::TYPENAME
+    }
+
+    fn typename(&self) -> &'static str {
+        Self::TYPENAME
+    }
+
+    fn size_policy(&self) -> SizePolicy {
+        SizePolicy::MATCH_LAYOUT
+    }
+
+    fn full_size(&self) -> XY {
+        Self::MIN_SIZE
+    }
+
+    fn layout(&mut self, screenspace: Screenspace) {
+        self.session.resize(screenspace.output_size());
+    }
+
+    fn on_input(&self, input_event: InputEvent) -> Option<Box<dyn AnyMsg>> {
+        debug!("{} input {:?}", self.typename(), input_event);
+
+        match input_event {
+            InputEvent::KeyInput(key) if key == self.providers.config().keyboard_config.global.terminal_escape => {
+                TerminalViewMsg::Escape.someboxed()
+            }
+            InputEvent::KeyInput(key) if key == self.providers.config().keyboard_config.global.terminal_scroll_up => {
+                TerminalViewMsg::ScrollUp.someboxed()
+            }
+            InputEvent::KeyInput(key) if key == self.providers.config().keyboard_config.global.terminal_scroll_down => {
+                TerminalViewMsg::ScrollDown.someboxed()
+            }
+            InputEvent::KeyInput(key) => {
+                let application_cursor = self
+                    .session
+                    .screen()
+                    .read()
+                    .map(|lock| lock.screen().application_cursor())
+                    .unwrap_or(false);
+                let bytes = unpack_or!(
+                    key_to_bytes(&key, application_cursor),
+                    None,
+                    "key {:?} not supported in terminal",
+                    key
+                );
+                TerminalViewMsg::Input(bytes).someboxed()
+            }
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, msg: Box<dyn AnyMsg>) -> Option<Box<dyn AnyMsg>> {
+        let our_msg = unpack_or_e!(msg.as_msg::<TerminalViewMsg>(), None, "expected TerminalViewMsg, got {:?}", msg);
+
+        match our_msg {
+            TerminalViewMsg::Input(bytes) => {
+                // typing is about current screen
+                if self.session.scrollback() > 0 {
+                    self.session.scroll_by(isize::MIN);
+                }
+
+                if self.session.has_exited() {
+                    debug!("ignoring input, shell exited");
+                } else if let Err(e) = self.session.write(bytes) {
+                    error!("failed writing to terminal: {}", e);
+                }
+                None
+            }
+            TerminalViewMsg::Escape => MainViewMsg::FocusTree.someboxed(),
+            TerminalViewMsg::ScrollUp => {
+                self.session.scroll_by(self.session.size().y as isize);
+                None
+            }
+            TerminalViewMsg::ScrollDown => {
+                self.session.scroll_by(-(self.session.size().y as isize));
+                None
+            }
+        }
+    }
+
+    fn render(&self, theme: &Theme, focused: bool, output: &mut dyn Output) {
+        #[cfg(test)]
+        output.emit_metadata(Metadata {
+            id: self.wid,
+            typename: self.typename().to_string(),
+            rect: Rect::from_zero(output.size()),
+            focused,
+        });
+
+        let default_style = theme.default_text(focused);
+        fill_output(default_style.background, output);
+
+        let lock = unpack_or_e!(self.session.screen().read().ok(), (), "failed to lock terminal screen");
+        let screen = lock.screen();
+        let (rows, cols) = screen.size();
+        let visible_rect = output.visible_rect();
+
+        for y in visible_rect.upper_left().y..visible_rect.lower_right().y.min(rows) {
+            for x in visible_rect.upper_left().x..visible_rect.lower_right().x.min(cols) {
+                let cell = unpack_or!(screen.cell(y, x), (), "no cell at {}:{}", x, y);
+                if cell.is_wide_continuation() {
+                    continue;
+                }
+
+                let style = Self::cell_style(cell, default_style);
+                let contents = cell.contents();
+                let text = if contents.is_empty() { " " } else { contents.as_str() };
+                output.print_at(XY::new(x, y), style, text);
+            }
+        }
+
+        // cursor is on current screen, not in scrollback
+        if focused && !screen.hide_cursor() && screen.scrollback() == 0 {
+            let (cursor_row, cursor_col) = screen.cursor_position();
+            let pos = XY::new(cursor_col, cursor_row);
+            if visible_rect.contains(pos) {
+                let cell_style = screen
+                    .cell(cursor_row, cursor_col)
+                    .map(|c| Self::cell_style(c, default_style))
+                    .unwrap_or(default_style);
+                let contents = screen.cell(cursor_row, cursor_col).map(|c| c.contents()).unwrap_or_default();
+                let text = if contents.is_empty() { " " } else { contents.as_str() };
+                let cursor_style = TextStyle::new(cell_style.background, cell_style.foreground, cell_style.effect);
+                output.print_at(pos, cursor_style, text);
+            }
+        }
+
+        if self.session.has_exited() && rows > 0 {
+            let msg = "[shell exited]";
+            if visible_rect.lower_right().y > 0 && (msg.len() as u16) <= visible_rect.lower_right().x {
+                output.print_at(XY::new(0, visible_rect.lower_right().y - 1), theme.editor_label_warning(), msg);
+            } else {
+                warn!("no space to show that shell exited");
+            }
+        }
+    }
+
+    fn kite(&self) -> XY {
+        self.session
+            .screen()
+            .read()
+            .map(|lock| {
+                let (row, col) = lock.screen().cursor_position();
+                XY::new(col, row)
+            })
+            .unwrap_or(XY::ZERO)
+    }
+}
@@ -342,11 +342,14 @@ impl<K: Hash + Eq + Debug + Clone + 'static, I: TreeViewNode<K> + 'static> Widge
                 break;
             }
 
-            let style = if item_idx == self.highlighted {
+            let mut style = if item_idx == self.highlighted {
                 cursor_style
             } else {
                 primary_style
             };
+            if let Some(color) = node.label_color(theme) {
+                style = style.with_foreground(color);
+            }
 
             let prefix = if node.is_leaf() {
                 " "
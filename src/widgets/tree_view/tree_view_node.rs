@@ -2,6 +2,8 @@ use std::borrow::Cow;
 use std::fmt::Debug;
 use std::hash::Hash;
 
+use crate::*;
+
 // TODO(XXX): remove, this should probably just be an Option<bool>
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum MaybeBool {
@@ -20,6 +22,13 @@ pub trait TreeViewNode<Key: Hash + Eq + Debug>: Clone + Debug {
 
     fn is_complete(&self) -> bool;
 
+    /*
+    Overrides foreground of the label, used to decorate nodes (like git status of files).
+     */
+    fn label_color(&self, _theme: &Theme) -> Option<Color> {
+        None
+    }
+
     /*
     the answer is true, false, or "we don't know yet"
      */
@@ -0,0 +1,12 @@
+/*
+Browser of buffer's undo tree: branches with timestamps, preview of highlighted state, and jumping to it.
+ */
+
+mod msg;
+pub use msg::UndoTreeMsg;
+
+mod undo_tree_rows;
+pub use undo_tree_rows::{build_undo_tree_rows, nearest_shown_node, parse_time_ago, UndoTreeRow};
+
+mod undo_tree_widget;
+pub use undo_tree_widget::UndoTreeWidget;
@@ -0,0 +1,13 @@
+use crate::*;
+
+#[derive(Clone, Debug)]
+pub enum UndoTreeMsg {
+    // edits "as of" query, like "10m"
+    EditMsg(CommonEditMsg),
+    Navigation(Navigation),
+    // moves buffer to highlighted state
+    Hit,
+    Close,
+}
+
+impl AnyMsg for UndoTreeMsg {}
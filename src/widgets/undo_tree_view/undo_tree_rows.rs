@@ -0,0 +1,196 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, SystemTime};
+
+use crate::*;
+
+/*
+One line of undo tree browser. States are listed newest first, each branch has its own column of
+"graph", forking from its parent state like this:
+
+    ●   #4 just now +1 -0
+  @ │   #3 1 min ago +3 -0
+  ● │   #2 5 min ago +0 -2
+  ●─╯   #1 6 min ago +4 -0
+  ●     #0 start
+ */
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct UndoTreeRow {
+    pub node_id: HistoryNodeId,
+    pub graph: String,
+    pub label: String,
+    pub is_current: bool,
+}
+
+/*
+States that only moved cursors are not worth a row, they are folded into the closest state shown above
+them.
+ */
+fn is_shown(history: &EditHistory, node_id: HistoryNodeId, node: &HistoryNode) -> bool {
+    node_id == history.root() || node_id == history.current() || !node.entry.edits.is_empty()
+}
+
+/*
+Given state or closest ancestor that has a row.
+ */
+pub fn nearest_shown_node(history: &EditHistory, node_id: HistoryNodeId) -> Option<HistoryNodeId> {
+    let mut id = node_id;
+    loop {
+        let node = history.node(id)?;
+        if is_shown(history, id, node) {
+            return Some(id);
+        }
+        id = node.parent?;
+    }
+}
+
+fn time_ago(now: SystemTime, time: SystemTime) -> String {
+    let secs = now.duration_since(time).unwrap_or(Duration::ZERO).as_secs();
+
+    match secs {
+        0..=4 => "just now".to_string(),
+        5..=59 => format!("{}s ago", secs),
+        60..=3599 => format!("{} min ago", secs / 60),
+        3600..=86399 => format!("{} h ago", secs / 3600),
+        _ => format!("{} d ago", secs / 86400),
+    }
+}
+
+/*
+Parses how far back to go, like "10m", "90 s", "2 hours ago". Number without unit means minutes.
+ */
+pub fn parse_time_ago(query: &str) -> Option<Duration> {
+    let query = query.trim();
+    let query = query.strip_suffix("ago").unwrap_or(query).trim();
+
+    let digits_end = query.find(|c: char| !c.is_ascii_digit()).unwrap_or(query.len());
+    let number: u64 = query[..digits_end].parse().ok()?;
+
+    let unit_secs = match query[digits_end..].trim() {
+        "s" | "sec" | "secs" | "second" | "seconds" => 1,
+        "" | "m" | "min" | "mins" | "minute" | "minutes" => 60,
+        "h" | "hour" | "hours" => 3600,
+        "d" | "day" | "days" => 86400,
+        _ => return None,
+    };
+
+    Some(Duration::from_secs(number.checked_mul(unit_secs)?))
+}
+
+pub fn build_undo_tree_rows(history: &EditHistory, now: SystemTime) -> Vec<UndoTreeRow> {
+    let shown: Vec<HistoryNodeId> = history
+        .nodes()
+        .filter(|(id, node)| is_shown(history, *id, node))
+        .map(|(id, _)| id)
+        .collect();
+
+    // first (oldest) child continues parent's column, every other one opens a new column
+    let mut column: HashMap<HistoryNodeId, usize> = HashMap::new();
+    let mut continued: HashSet<HistoryNodeId> = HashSet::new();
+    // per column: state it forked from and the newest state in it
+    let mut columns: Vec<(Option<HistoryNodeId>, HistoryNodeId)> = Vec::new();
+
+    for id in shown.iter() {
+        let parent_op = history
+            .node(*id)
+            .and_then(|node| node.parent)
+            .and_then(|parent| nearest_shown_node(history, parent));
+
+        let col = match parent_op {
+            Some(parent) if continued.insert(parent) => column[&parent],
+            _ => {
+                columns.push((parent_op, *id));
+                columns.len() - 1
+            }
+        };
+        column.insert(*id, col);
+        columns[col].1 = *id;
+    }
+
+    let row_of: HashMap<HistoryNodeId, usize> = shown.iter().rev().enumerate().map(|(row, id)| (*id, row)).collect();
+
+    shown
+        .iter()
+        .rev()
+        .enumerate()
+        .map(|(row, id)| {
+            // two cells per column: the column itself and a space after it
+            let mut cells: Vec<char> = vec![' '; columns.len() * 2];
+
+            for (col, (fork_op, newest)) in columns.iter().enumerate() {
+                let top = row_of[newest];
+                let bottom = fork_op.map(|fork| row_of[&fork]);
+
+                if row > top && bottom.map(|b| row < b).unwrap_or(true) {
+                    cells[col * 2] = '│';
+                }
+
+                if bottom == Some(row) {
+                    cells[col * 2] = '╯';
+                    for cell in cells.iter_mut().take(col * 2).skip(column[id] * 2 + 1) {
+                        if *cell == ' ' {
+                            *cell = '─';
+                        }
+                    }
+                }
+            }
+
+            let is_current = *id == history.current();
+            cells[column[id] * 2] = if is_current { '@' } else { '●' };
+
+            let node = history.node(*id).unwrap();
+            let label = if *id == history.root() {
+                format!("#{} start", id)
+            } else {
+                let (inserted, removed) = node.entry.chars_changed();
+                format!("#{} {} +{} -{}", id, time_ago(now, node.created), inserted, removed)
+            };
+
+            UndoTreeRow {
+                node_id: *id,
+                graph: cells.into_iter().collect::<String>().trim_end().to_string(),
+                label,
+                is_current,
+            }
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_time_ago() {
+        assert_eq!(parse_time_ago("10m"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_time_ago("10"), Some(Duration::from_secs(600)));
+        assert_eq!(parse_time_ago(" 2 hours ago"), Some(Duration::from_secs(7200)));
+        assert_eq!(parse_time_ago("30s"), Some(Duration::from_secs(30)));
+        assert_eq!(parse_time_ago("m"), None);
+        assert_eq!(parse_time_ago("10 parsecs"), None);
+    }
+
+    #[test]
+    fn test_rows_of_branched_history() {
+        let mut buffer = BufferState::full(None, DocumentIdentifier::new_unique());
+        let wid = get_new_widget_id();
+        buffer.initialize_for_widget(wid, None);
+
+        for c in ['a', 'b'] {
+            buffer.apply_cem(CommonEditMsg::Char(c), wid, 10, None);
+        }
+        buffer.apply_cem(CommonEditMsg::Undo, wid, 10, None);
+        buffer.apply_cem(CommonEditMsg::Char('c'), wid, 10, None);
+        // cursor only step, not shown
+        buffer.apply_cem(CommonEditMsg::CursorLeft { selecting: false }, wid, 10, None);
+        buffer.apply_cem(CommonEditMsg::Undo, wid, 10, None);
+        buffer.apply_cem(CommonEditMsg::Undo, wid, 10, None);
+
+        let rows = build_undo_tree_rows(buffer.history(), SystemTime::now());
+        let graphs: Vec<&str> = rows.iter().map(|r| r.graph.as_str()).collect();
+
+        assert_eq!(graphs, vec!["  ●", "● │", "@─╯", "●"]);
+        assert!(rows[2].is_current);
+        assert!(rows[0].label.ends_with("just now +1 -0"));
+        assert_eq!(rows[3].label, format!("#{} start", buffer.history().root()));
+    }
+}
@@ -0,0 +1,321 @@
+use std::cmp::{max, min};
+use std::time::SystemTime;
+
+use log::{debug, error, warn};
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthStr;
+
+use crate::*;
+
+/*
+Lists states of buffer's undo tree on the left and a preview of highlighted one on the right. Typing
+"as of" query (like "10m") highlights the state from that time, enter moves buffer to highlighted state.
+The widget never modifies the buffer itself.
+ */
+pub struct UndoTreeWidget {
+    id: WID,
+    buffer: BufferSharedRef,
+
+    query: EditBoxWidget,
+    rows: Vec<UndoTreeRow>,
+    highlighted: usize,
+
+    // text around the last change of highlighted state, and which of these lines it's on
+    preview: Vec<String>,
+    preview_focus: usize,
+
+    last_size: Option<Screenspace>,
+}
+
+impl UndoTreeWidget {
+    pub const TYPENAME: &'static str = "undo_tree";
+    const QUERY_LABEL: &'static str = "as of: ";
+    // lines of preview above and below the change
+    const PREVIEW_CONTEXT: usize = 40;
+
+    pub fn new(buffer: BufferSharedRef, clipboard_op: Option<ClipboardRef>) -> Self {
+        let mut query = EditBoxWidget::new().with_fill_x();
+        if let Some(clipboard) = clipboard_op {
+            query = query.with_clipboard(clipboard);
+        }
+
+        let mut res = UndoTreeWidget {
+            id: get_new_widget_id(),
+            buffer,
+            query,
+            rows: Vec::new(),
+            highlighted: 0,
+            preview: Vec::new(),
+            preview_focus: 0,
+            last_size: None,
+        };
+
+        res.refresh_rows();
+        res
+    }
+
+    pub fn rows(&self) -> &Vec<UndoTreeRow> {
+        &self.rows
+    }
+
+    pub fn highlighted_node(&self) -> Option<HistoryNodeId> {
+        self.rows.get(self.highlighted).map(|row| row.node_id)
+    }
+
+    pub fn preview(&self) -> &Vec<String> {
+        &self.preview
+    }
+
+    fn refresh_rows(&mut self) {
+        self.rows = match self.buffer.lock() {
+            Some(buffer) => build_undo_tree_rows(buffer.history(), SystemTime::now()),
+            None => {
+                error!("failed to lock buffer for undo tree");
+                Vec::new()
+            }
+        };
+
+        self.highlighted = self.rows.iter().position(|row| row.is_current).unwrap_or(0);
+        self.refresh_preview();
+    }
+
+    fn highlight_node(&mut self, node_id: HistoryNodeId) {
+        match self.rows.iter().position(|row| row.node_id == node_id) {
+            Some(idx) => {
+                self.highlighted = idx;
+                self.refresh_preview();
+            }
+            None => warn!("no row for history node {}", node_id),
+        }
+    }
+
+    fn refresh_preview(&mut self) {
+        self.preview.clear();
+        self.preview_focus = 0;
+
+        let node_id = unpack_or!(self.highlighted_node(), ());
+        let buffer = unpack_or_e!(self.buffer.lock(), (), "failed to lock buffer for preview");
+        let rope = unpack_or_e!(buffer.history_text_at(node_id), (), "failed to compute text of state {}", node_id);
+
+        let focus_char = buffer
+            .history()
+            .node(node_id)
+            .and_then(|node| node.entry.edits.last())
+            .map(|edit| min(edit.char_idx(), rope.len_chars()))
+            .unwrap_or(0);
+        let focus_line = rope.char_to_line(focus_char);
+
+        let first_line = focus_line.saturating_sub(Self::PREVIEW_CONTEXT);
+        let last_line = min(focus_line + Self::PREVIEW_CONTEXT + 1, rope.len_lines());
+        for line_idx in first_line..last_line {
+            let line = rope.line(line_idx).to_string();
+            self.preview.push(line.trim_end_matches(['\n', '\r']).to_string());
+        }
+        self.preview_focus = focus_line - first_line;
+    }
+
+    /*
+    Highlights state from the time given by query, if it parses.
+     */
+    fn on_query_changed(&mut self) {
+        let query = self.query.get_text();
+        let ago = unpack_or!(parse_time_ago(&query), (), "not a time query: {}", query);
+        let time = unpack_or!(SystemTime::now().checked_sub(ago), ());
+
+        let node_op = self.buffer.lock().and_then(|buffer| {
+            let history = buffer.history();
+            nearest_shown_node(history, history.node_at_time(time))
+        });
+
+        if let Some(node_id) = node_op {
+            self.highlight_node(node_id);
+        }
+    }
+
+    fn rows_width(&self, total_width: u16) -> u16 {
+        let widest = self
+            .rows
+            .iter()
+            .map(|row| row.graph.width() + 1 + row.label.width())
+            .max()
+            .unwrap_or(0) as u16;
+
+        min(widest + 2, total_width / 2)
+    }
+
+    // prints text clipped to width, and fills the rest of width with style
+    fn print_line(output: &mut dyn Output, pos: XY, width: u16, style: TextStyle, text: &str) {
+        let mut x: u16 = 0;
+        for g in text.graphemes(true) {
+            let w = g.width() as u16;
+            if x + w > width {
+                break;
+            }
+            output.print_at(XY::new(pos.x + x, pos.y), style, g);
+            x += w;
+        }
+        while x < width {
+            output.print_at(XY::new(pos.x + x, pos.y), style, " ");
+            x += 1;
+        }
+    }
+}
+
+impl Widget for UndoTreeWidget {
+    fn id(&self) -> WID {
+        self.id
+    }
+
+    fn typename(&self) -> &'static str {
+        Self::TYPENAME
+    }
+
+    fn static_typename() -> &'static str
+    where
+        Self: Sized,
+    {
+        Self::TYPENAME
+    }
+
+    fn full_size(&self) -> XY {
+        let rows_width = self
+            .rows
+            .iter()
+            .map(|row| row.graph.width() + 1 + row.label.width())
+            .max()
+            .unwrap_or(0) as u16;
+
+        XY::new(max(rows_width * 2, 40), max(self.rows.len() as u16 + 1, 10))
+    }
+
+    fn layout(&mut self, screenspace: Screenspace) {
+        let query_width = screenspace.output_size().x.saturating_sub(Self::QUERY_LABEL.len() as u16);
+        self.query.layout(Screenspace::full_output(XY::new(query_width, 1)));
+        self.last_size = Some(screenspace);
+    }
+
+    fn on_input(&self, input_event: InputEvent) -> Option<Box<dyn AnyMsg>> {
+        match input_event {
+            InputEvent::KeyInput(ki) => {
+                let msg = match ki.keycode {
+                    Keycode::Esc => UndoTreeMsg::Close,
+                    Keycode::Enter => UndoTreeMsg::Hit,
+                    Keycode::ArrowUp => UndoTreeMsg::Navigation(Navigation::ArrowUp),
+                    Keycode::ArrowDown => UndoTreeMsg::Navigation(Navigation::ArrowDown),
+                    Keycode::PageUp => UndoTreeMsg::Navigation(Navigation::PageUp),
+                    Keycode::PageDown => UndoTreeMsg::Navigation(Navigation::PageDown),
+                    _ => UndoTreeMsg::EditMsg(key_to_edit_msg(ki)?),
+                };
+
+                Some(Box::new(msg))
+            }
+            _ => None,
+        }
+    }
+
+    fn update(&mut self, msg: Box<dyn AnyMsg>) -> Option<Box<dyn AnyMsg>> {
+        debug!("undo_tree.update {:?}", msg);
+
+        let our_msg = unpack_or_e!(msg.as_msg::<UndoTreeMsg>(), None, "expected UndoTreeMsg, got {:?}", msg);
+
+        match our_msg {
+            UndoTreeMsg::EditMsg(cem) => {
+                self.query.update(Box::new(EditBoxWidgetMsg::CommonEditMsg(cem.clone())));
+                self.on_query_changed();
+                None
+            }
+            UndoTreeMsg::Navigation(nav) => {
+                let page = self.last_size.map(|ss| ss.output_size().y.saturating_sub(1) as usize).unwrap_or(1);
+                let last = self.rows.len().saturating_sub(1);
+
+                self.highlighted = match nav {
+                    Navigation::ArrowUp => self.highlighted.saturating_sub(1),
+                    Navigation::ArrowDown => min(self.highlighted + 1, last),
+                    Navigation::PageUp => self.highlighted.saturating_sub(page),
+                    Navigation::PageDown => min(self.highlighted + page, last),
+                };
+                self.refresh_preview();
+                None
+            }
+            // jump goes through main view, so editor of the buffer learns about it
+            UndoTreeMsg::Hit => match self.highlighted_node() {
+                Some(node_id) => MainViewMsg::JumpToHistoryNode {
+                    buffer: self.buffer.clone(),
+                    node_id,
+                }
+                .someboxed(),
+                None => MainViewMsg::CloseHover.someboxed(),
+            },
+            UndoTreeMsg::Close => MainViewMsg::CloseHover.someboxed(),
+        }
+    }
+
+    fn render(&self, theme: &Theme, focused: bool, output: &mut dyn Output) {
+        let size = unpack_or_e!(self.last_size, (), "render before layout").output_size();
+
+        #[cfg(test)]
+        {
+            output.emit_metadata(Metadata {
+                id: self.id,
+                typename: self.typename().to_string(),
+                rect: Rect::from_zero(output.size()),
+                focused,
+            });
+        }
+
+        let label_width = Self::QUERY_LABEL.len() as u16;
+        Self::print_line(output, XY::ZERO, label_width, theme.header(focused), Self::QUERY_LABEL);
+        {
+            let mut suboutput = SubOutput::new(
+                output,
+                Rect::new(XY::new(label_width, 0), XY::new(size.x.saturating_sub(label_width), 1)),
+            );
+            self.query.render(theme, focused, &mut suboutput);
+        }
+
+        let visible_rows = size.y.saturating_sub(1) as usize;
+        let rows_width = self.rows_width(size.x);
+        let first_row = (self.highlighted + 1).saturating_sub(visible_rows);
+
+        for y in 0..visible_rows {
+            let pos = XY::new(0, y as u16 + 1);
+            match self.rows.get(first_row + y) {
+                Some(row) => {
+                    let style = if first_row + y == self.highlighted {
+                        theme.highlighted(focused)
+                    } else {
+                        theme.default_text(focused)
+                    };
+                    Self::print_line(output, pos, rows_width, style, &format!("{} {}", row.graph, row.label));
+                }
+                None => Self::print_line(output, pos, rows_width, theme.default_text(focused), ""),
+            }
+        }
+
+        // preview, change line in the middle
+        let preview_width = size.x.saturating_sub(rows_width + 1);
+        let first_line = self.preview_focus.saturating_sub(visible_rows / 2);
+        for y in 0..visible_rows {
+            let pos = XY::new(rows_width + 1, y as u16 + 1);
+            let line_idx = first_line + y;
+            let style = if line_idx == self.preview_focus && !self.preview.is_empty() {
+                theme.highlighted(false)
+            } else {
+                theme.default_text(false)
+            };
+
+            output.print_at(XY::new(rows_width, y as u16 + 1), theme.header(focused), "│");
+            Self::print_line(
+                output,
+                pos,
+                preview_width,
+                style,
+                self.preview.get(line_idx).map(|s| s.as_str()).unwrap_or(""),
+            );
+        }
+    }
+
+    fn kite(&self) -> XY {
+        XY::new(0, self.highlighted as u16 + 1)
+    }
+}
@@ -14,12 +14,32 @@ struct LayoutRes {
     child_space_visible_rect: Rect,
 }
 
+/*
+Single-column marker drawn between line numbers and the contents (git gutter, for example).
+ */
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct LineMarker {
+    pub text: &'static str,
+    pub foreground: Color,
+}
+
+type LineMarkersFn<W> = Box<dyn Fn(&W, &Theme, usize) -> Option<LineMarker>>;
+type FirstLineNoFn<W> = Box<dyn Fn(&W) -> usize>;
+
 pub struct WithScroll<W: Widget> {
     id: WID,
     child_widget: W,
     scroll: Scroll,
     line_no: bool,
 
+    // called with index (0-based) of every visible line, works only together with line_no
+    line_markers: Option<LineMarkersFn<W>>,
+
+    // index (0-based) of line drawn at child's y = 0, for children showing a window of their lines
+    first_line_no: Option<FirstLineNoFn<W>>,
+    // as of last layout, so scroll can move along when window does
+    last_first_line_no: usize,
+
     // TODO I guess that was for something but I forgot what was that
     fill_non_free_axis: bool,
 
@@ -45,6 +65,9 @@ impl<W: Widget> WithScroll<W> {
             child_widget: widget,
             scroll: Scroll::new(scroll_direction),
             line_no: false,
+            line_markers: None,
+            first_line_no: None,
+            last_first_line_no: 0,
             fill_non_free_axis: true,
             layout_res: None,
         }
@@ -54,10 +77,44 @@ impl<W: Widget> WithScroll<W> {
         Self { line_no: true, ..self }
     }
 
+    pub fn with_line_markers<F: Fn(&W, &Theme, usize) -> Option<LineMarker> + 'static>(self, line_markers: F) -> Self {
+        debug_assert!(self.line_no, "line markers are drawn in line number margin");
+        Self {
+            line_markers: Some(Box::new(line_markers)),
+            ..self
+        }
+    }
+
+    pub fn with_first_line_no<F: Fn(&W) -> usize + 'static>(self, first_line_no: F) -> Self {
+        Self {
+            first_line_no: Some(Box::new(first_line_no)),
+            ..self
+        }
+    }
+
+    fn first_line_no(&self) -> usize {
+        self.first_line_no
+            .as_ref()
+            .map(|first_line_no| first_line_no(&self.child_widget))
+            .unwrap_or(0)
+    }
+
+    fn markers_width(&self) -> u16 {
+        if self.line_markers.is_some() {
+            1
+        } else {
+            0
+        }
+    }
+
     pub fn scroll(&self) -> &Scroll {
         &self.scroll
     }
 
+    pub fn scroll_mut(&mut self) -> &mut Scroll {
+        &mut self.scroll
+    }
+
     pub fn internal_mut(&mut self) -> &mut W {
         &mut self.child_widget
     }
@@ -75,27 +132,32 @@ impl<W: Widget> WithScroll<W> {
 
     fn render_line_no(&self, margin_width: u16, theme: &Theme, focused: bool, output: &mut dyn Output) {
         let _layout_res = unpack_or!(self.layout_res.as_ref(), (), "render before layout");
+
+        // markers column is not part of "numbers" margin
+        let markers_width = self.markers_width();
+        let numbers_width = margin_width - markers_width;
+
         #[cfg(test)]
         {
             output.emit_metadata(Metadata {
                 id: self.id(),
                 typename: Self::TYPENAME_FOR_MARGIN.to_string(),
-                rect: Rect::from_zero(XY::new(margin_width, output.size().y)),
+                rect: Rect::from_zero(XY::new(numbers_width, output.size().y)),
                 focused,
             });
         }
 
         debug_assert!(self.line_no);
-        let start_idx = self.scroll.offset.y;
+        let start_idx = self.first_line_no() + self.scroll.offset.y as usize;
 
         let style = if focused { theme.ui.header } else { theme.ui.header.half() }.with_background(theme.default_text(focused).background);
 
         for idx in output.visible_rect().pos.y..output.visible_rect().lower_right().y {
-            let line_no_base_0 = start_idx + idx;
+            let line_no_base_0 = start_idx + idx as usize;
             let item = format!("{}", line_no_base_0 + 1);
             let num_digits = item.len() as u16;
-            let offset = if num_digits <= margin_width {
-                margin_width - num_digits
+            let offset = if num_digits <= numbers_width {
+                numbers_width - num_digits
             } else {
                 error!("num_digits > margin_width, hardcoding safe fix");
                 0
@@ -110,10 +172,17 @@ impl<W: Widget> WithScroll<W> {
             }
 
             output.print_at(XY::new(offset, idx), style, &item);
+
+            if let Some(line_markers) = self.line_markers.as_ref() {
+                match line_markers(&self.child_widget, theme, line_no_base_0) {
+                    Some(marker) => output.print_at(XY::new(numbers_width, idx), style.with_foreground(marker.foreground), marker.text),
+                    None => output.print_at(XY::new(numbers_width, idx), style, " "),
+                }
+            }
         }
     }
 
-    fn get_margin_width_for_height(height: u16) -> u16 {
+    fn get_margin_width_for_height(height: usize) -> u16 {
         format!("{}", height).width() as u16 + 2 // TODO logarithm? Never heard of it.
     }
 
@@ -152,7 +221,8 @@ impl<W: Widget> WithScroll<W> {
 
         // now that we know height y, we can see what's our final width.
         let (margin_width, max_output_width) = if self.line_no {
-            let margin_width = Self::get_margin_width_for_height(internal_output_size.y);
+            let highest_line_no = self.first_line_no() + internal_output_size.y as usize;
+            let margin_width = Self::get_margin_width_for_height(highest_line_no) + self.markers_width();
             debug!(
                 "having {} lines to count, I need {} width for the numbers.",
                 internal_output_size.y, margin_width
@@ -238,6 +308,14 @@ impl<W: Widget> Widget for WithScroll<W> {
     fn layout(&mut self, screenspace: Screenspace) {
         self.layout_res = None; // erasing old layout_res
 
+        // child moved its window of lines, scroll moves along, so the same lines stay where they were
+        let first_line_no = self.first_line_no();
+        if first_line_no != self.last_first_line_no {
+            let offset_y = self.scroll.offset.y as isize + self.last_first_line_no as isize - first_line_no as isize;
+            self.scroll.offset.y = offset_y.clamp(0, u16::MAX as isize) as u16;
+            self.last_first_line_no = first_line_no;
+        }
+
         let child_output = self.get_output_size_that_will_be_offered_to_child(screenspace.output_size());
 
         if child_output.child_size_in_its_output.x == 0 || child_output.child_size_in_its_output.y == 0 {
@@ -0,0 +1,4 @@
+fn main() {
+    let s = "}";
+    println!("{}", s);
+}
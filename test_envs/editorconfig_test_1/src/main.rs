@@ -0,0 +1,3 @@
+fn main() {   
+  println!("hi");	
+}
\ No newline at end of file
@@ -0,0 +1,2 @@
+// generated, not to be edited
+pub fn bindings() {}
@@ -0,0 +1,13 @@
+fn main() {
+<<<<<<< HEAD
+    let a = 1;
+=======
+    let a = 2;
+>>>>>>> feature
+    let b = 3;
+<<<<<<< HEAD
+    println!("{}", a);
+=======
+    println!("{}", b);
+>>>>>>> feature
+}
@@ -0,0 +1,5 @@
+pub fn first() {}
+
+pub fn second() {}
+
+pub fn third() {}
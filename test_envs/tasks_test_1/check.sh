@@ -0,0 +1,4 @@
+printf 'checking...\n'
+printf '\033[31merror\033[0m: src/main.rs:2:5: unused variable\n'
+sleep 2
+printf 'done\n'
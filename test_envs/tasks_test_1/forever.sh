@@ -0,0 +1,3 @@
+echo "started"
+# exec, so stopping the task kills sleep itself
+exec sleep 100